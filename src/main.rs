@@ -1,18 +1,70 @@
 #![feature(byte_slice_trim_ascii)]
 
-use clap::Parser;
-use duckdb::{params, Connection, DropBehavior, Error};
-use humantime::format_duration;
-use std::fs::File;
-use std::io::{stdin, stdout, BufRead, BufReader, Read, Write};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use duckdb::{AccessMode, Config, Connection};
+use glob::glob;
+use std::collections::{HashMap, HashSet};
+use std::fs::{remove_file, File};
+use std::io::{stdin, stdout, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::path::Path;
-use std::time::{Duration, Instant};
-use wikidata::{Entity, Rank};
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::Duration;
+use wikidata::{Lang, Pid, Qid};
 
-use wikidata_rs::id::Id;
+use strum::IntoEnumIterator;
+use wikidata_rs::archive;
+use wikidata_rs::backfill;
+use wikidata_rs::budget::{parse_size, SizeBudget};
+use wikidata_rs::checkpoint::Checkpoint;
+use wikidata_rs::checksum::{Checksums, Sha1Reader};
+use wikidata_rs::columns::SchemaConfig;
+use wikidata_rs::compat::DumpFormat;
+use wikidata_rs::converter::{
+    create_tables, entity_id, entity_type, parse_entity, parse_line, split_entities, store_entity,
+    Converter, ConverterConfig,
+};
+use wikidata_rs::dead_letter::DeadLetters;
+use wikidata_rs::dedup::{dedup_vertices, Deduplicator, OnConflict};
+use wikidata_rs::derived::Derived;
+use wikidata_rs::enrich::{Enricher, Enrichment};
+use wikidata_rs::export::{ExportFormat, JsonExporter};
+use wikidata_rs::fetch::Fetcher;
+use wikidata_rs::generate::Generator;
+use wikidata_rs::globe;
+use wikidata_rs::http::{self, HttpReader};
+use wikidata_rs::id::{parse_item, parse_property, Id, IdEncoding};
+use wikidata_rs::labels::{parse_language, ALIAS_TABLE, LABEL_TABLE};
+use wikidata_rs::load_log::LoadLog;
+use wikidata_rs::macros::Macro;
+use wikidata_rs::memory::MemoryWatchdog;
+use wikidata_rs::metadata;
+use wikidata_rs::motherduck;
+use wikidata_rs::ntriples::entity_triples;
+use wikidata_rs::output::{export_parquet, parquet_path, OutputFormat};
+use wikidata_rs::partition::{self, Partition};
+use wikidata_rs::path::PropertyPath;
+use wikidata_rs::pipeline::Pipeline;
+use wikidata_rs::pragma::{self, Pragma};
+use wikidata_rs::preset::Preset;
+use wikidata_rs::progress::{Metered, Progress};
+use wikidata_rs::remote::{self, RemoteReader, RemoteWriter};
+use wikidata_rs::sample::{ClassQuota, ClassSampler, Sampler};
+use wikidata_rs::schema;
+use wikidata_rs::snapshot::{parse_duration, SnapshotFormat, Snapshotter};
+use wikidata_rs::stats::{self, DatabaseStats, StatsFormat};
+use wikidata_rs::throttle::Throttled;
+use wikidata_rs::udf;
+use wikidata_rs::validation::{Failure, LineError, Report};
 use wikidata_rs::value::AppenderHelper;
 use wikidata_rs::value::Table;
-use wikidata_rs::{INSERTS_PER_TRANSACTION, LANG};
+use wikidata_rs::verify::Verifier;
+use wikidata_rs::watch::DirectoryWatcher;
+use wikidata_rs::xml::InputFormat;
+use wikidata_rs::LANG;
 
 #[cfg(not(target_env = "msvc"))]
 #[global_allocator]
@@ -20,253 +72,1990 @@ static ALLOCATOR: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    /// Input JSON file
-    #[arg(short, long)]
-    json: String,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// File of the output database
-    #[arg(short, long)]
-    database: String,
+    // The options of the import are also accepted with no subcommand at all, so the command lines
+    // written before the subcommands were introduced keep on working :D
+    #[command(flatten)]
+    import: ImportArgs,
 }
 
-/// The function creates tables in a database connection using SQL queries.
+/// The `ImportArgs` struct gathers the options of the `import` subcommand, which
+/// loads the dumps into a database.
+#[derive(clap::Args, Debug)]
+struct ImportArgs {
+    /// Input JSON file. It can be repeated, or be a glob pattern, for loading several files at once
+    #[arg(short, long, required_unless_present_any = ["watch", "upload"])]
+    json: Vec<String>,
+
+    /// Directory to watch for the chunks of the dump, which are loaded as soon as they arrive
+    #[arg(long, conflicts_with = "json")]
+    watch: Option<String>,
+
+    /// Seconds without any new chunk after which the watch is over, unless a DONE file is created
+    #[arg(long, default_value_t = 600, requires = "watch")]
+    watch_timeout: u64,
+
+    /// File of the output database, or MotherDuck database, such as md:my_db
+    #[arg(short, long, required_unless_present = "dry_run")]
+    database: Option<String>,
+
+    /// Upload the local copy of a MotherDuck database kept after a failed upload, loading nothing
+    #[arg(long, conflicts_with_all = ["json", "watch", "update", "resume", "dry_run"])]
+    upload: bool,
+
+    /// Parse and validate every line of the inputs, counting the valid entities of each type,
+    /// without creating any database
+    #[arg(long, conflicts_with_all = ["update", "resume", "watch"])]
+    dry_run: bool,
+
+    /// Format of the output: duckdb, or parquet for a Parquet file per table next to the database
+    #[arg(long, default_value = "duckdb")]
+    output_format: OutputFormat,
+
+    /// Optional tables computed once the load has finished: quantity-si, quantity-stats,
+    /// datatype-mismatch, property-cooccurrence
+    #[arg(long, value_delimiter = ',')]
+    derive: Vec<Derived>,
+
+    /// Disable automatic checkpoints while loading. A crash means re-running
+    #[arg(long)]
+    unsafe_load: bool,
+
+    /// Do not create the indices over src_id, dst_id and statement_id at all
+    #[arg(long, conflicts_with = "indices_after_load")]
+    no_indices: bool,
+
+    /// Create the indices once every entity is stored, which is much faster for full dumps
+    #[arg(long)]
+    indices_after_load: bool,
+
+    /// Do not report the progress of the load, as in batch jobs
+    #[arg(long, short)]
+    quiet: bool,
+
+    /// Format of the dump: current or legacy (archived dumps prior to 2020)
+    #[arg(long, default_value = "current")]
+    dump_format: DumpFormat,
+
+    /// Format of the input: json (entity dumps), xml (pages-articles dumps), stream-json
+    /// (pretty-printed or concatenated entities) or ntriples (RDF dumps, such as the truthy ones)
+    #[arg(long, default_value = "json")]
+    input_format: InputFormat,
+
+    /// Store the qualifiers of each claim in the qualifier_* tables, which is the default
+    /// unless a preset reducing the size of the database is given
+    #[arg(long, overrides_with = "no_qualifiers")]
+    qualifiers: bool,
+
+    /// Drop the qualifiers of the claims, leaving the qualifier_* tables empty
+    #[arg(long, overrides_with = "qualifiers")]
+    no_qualifiers: bool,
+
+    /// Store the references of each claim in the reference_* tables, which is the default
+    /// unless a preset reducing the size of the database is given
+    #[arg(long, overrides_with = "no_references")]
+    references: bool,
+
+    /// Drop the references of the claims, leaving the reference_* tables empty
+    #[arg(long, overrides_with = "references")]
+    no_references: bool,
+
+    /// Properties whose claims are also stored in tables of their own, such as P31
+    #[arg(long, value_delimiter = ',')]
+    partition: Vec<Partition>,
+
+    /// Bundle of options reducing the size of the database: minimal, graph-only or full
+    #[arg(long)]
+    preset: Option<Preset>,
+
+    /// Store only the best ranked claims of each property, as in the truthy dumps
+    #[arg(long, visible_alias = "best-rank-only")]
+    truthy_only: bool,
+
+    /// Store the deprecated claims as well, whose rank column is 0, instead of skipping them
+    #[arg(long)]
+    keep_deprecated: bool,
+
+    /// Drop the claims holding external identifiers; that is, the external_id table
+    #[arg(long)]
+    drop_external_ids: bool,
+
+    /// Properties whose claims are stored, such as P31,P279. The rest of the claims are dropped
+    #[arg(long, value_delimiter = ',', value_parser = parse_property)]
+    properties: Vec<Pid>,
+
+    /// Tables to be populated, such as vertex,edge,time. The rest of them are left empty
+    #[arg(long, value_delimiter = ',')]
+    tables: Vec<String>,
+
+    /// JSON file declaring extra columns computed during the load, such as vertex.alias_count
+    #[arg(long)]
+    schema_config: Option<String>,
+
+    /// Keep the original strings of the amounts and bounds of the quantities in text columns
+    #[arg(long)]
+    quantity_strings: bool,
+
+    /// Languages whose labels are stored in columns of their own, such as en,de,fr
+    #[arg(long, value_delimiter = ',')]
+    label_columns: Vec<String>,
+
+    /// Languages whose labels, descriptions and aliases are stored in the label and alias tables,
+    /// such as en,de,es
+    #[arg(long, value_delimiter = ',', value_parser = parse_language)]
+    languages: Vec<String>,
+
+    /// Store the lemmas, forms and senses of the lexemes in the lexeme, lemma, form, form_feature
+    /// and sense tables
+    #[arg(long)]
+    lexemes: bool,
+
+    /// Leave the label and description columns out of the vertex table, for purely structural
+    /// analyses
+    #[arg(long, conflicts_with_all = ["languages", "label_columns"])]
+    no_text: bool,
+
+    /// Leave the string, text, external_id and url tables empty, as well as their counterparts
+    #[arg(long)]
+    no_strings: bool,
+
+    /// What to do with entities already stored from another input: skip or replace
+    #[arg(long)]
+    on_conflict: Option<OnConflict>,
+
+    /// Remove the duplicated vertices once the load has finished, keeping the latest revision
+    #[arg(long)]
+    dedup_vertices: bool,
+
+    /// Load over an already created database, storing only the entities whose revision changed
+    #[arg(long, visible_alias = "append")]
+    update: bool,
+
+    /// Resume an interrupted load right after the last line committed by --checkpoint-every
+    #[arg(long, conflicts_with_all = ["update", "watch", "sample_per_class"])]
+    resume: bool,
+
+    /// Commit and checkpoint every N entities, bounding the growth of the WAL and memory. The line
+    /// committed last is only recorded, for --resume, with --threads 1 or --preserve-order
+    #[arg(long)]
+    checkpoint_every: Option<NonZeroUsize>,
+
+    /// Flush the rows buffered by the appenders every N entities, trading throughput for memory.
+    /// Nothing is committed, as opposed to --checkpoint-every, which also flushes the appenders
+    #[arg(long)]
+    batch_size: Option<NonZeroUsize>,
+
+    /// Take a consistent copy of the database every so often, such as `1h`, to explore it meanwhile
+    #[arg(long, value_parser = parse_duration)]
+    snapshot_every: Option<Duration>,
+
+    /// Format of the snapshots: duckdb (a copy of the database file) or parquet
+    #[arg(long, default_value = "duckdb", requires = "snapshot_every")]
+    snapshot_format: SnapshotFormat,
+
+    /// Probability of keeping each entity, for building a random sample of the dump
+    #[arg(long)]
+    sample: Option<f64>,
+
+    /// Maximum number of instances (P31) kept of each class, such as `Q5=10000,Q11424=5000`
+    #[arg(long, value_delimiter = ',')]
+    sample_per_class: Vec<ClassQuota>,
+
+    /// Classes whose instances (P31) are the only entities stored, such as Q5,Q7187
+    #[arg(long, value_delimiter = ',', value_parser = parse_item)]
+    class: Vec<Qid>,
+
+    /// Seed of the random sample, or of the one per class, so the same entities are kept between runs
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Memory limit, such as `8GB`. Batches are committed, and shrunk, as it is approached, while
+    /// DuckDB is given half of it, spilling to the temporary directory once reached
+    #[arg(long, value_parser = parse_size)]
+    memory_limit: Option<u64>,
+
+    /// Temporary directory DuckDB spills to, which is next to the database if not provided
+    #[arg(long)]
+    temp_dir: Option<String>,
+
+    /// Number of threads DuckDB runs on, such as when creating the indices
+    #[arg(long)]
+    duckdb_threads: Option<NonZeroUsize>,
+
+    /// Setting of DuckDB applied once the database is opened, such as `preserve_insertion_order=false`.
+    /// It can be repeated
+    #[arg(long)]
+    pragma: Vec<Pragma>,
+
+    /// Maximum size of the database, such as `50GB`. The load stops gracefully once it is reached
+    #[arg(long, value_parser = parse_size)]
+    max_db_size: Option<u64>,
+
+    /// Maximum rate, in megabytes per second, at which the input JSON file is read
+    #[arg(long)]
+    max_read_mbps: Option<f64>,
+
+    /// Maximum length of a line, in megabytes. Longer lines are skipped and reported
+    #[arg(long, default_value = "256")]
+    max_line_mb: NonZeroUsize,
+
+    /// Replace invalid UTF-8 sequences, logging the line, instead of halting the load
+    #[arg(long)]
+    lossy_utf8: bool,
+
+    /// Number of threads parsing the entities, which are still stored by a single one
+    #[arg(long, default_value = "1")]
+    threads: NonZeroUsize,
+
+    /// Store the entities in the order of the input, even if those are parsed on several threads
+    #[arg(long)]
+    preserve_order: bool,
+
+    /// Scan the inputs once more for the units and globes that are referenced but were not stored
+    #[arg(long, conflicts_with = "watch")]
+    backfill_labels: bool,
+
+    /// Encoding of the identifiers: `v2`, `v1`, or the offsets of the namespaces, such as `property=1000000000`
+    #[arg(long, default_value = "v2")]
+    id_encoding: IdEncoding,
+
+    /// Fill the gaps of the database out of the Wikidata API once loaded, such as `labels`
+    #[arg(long, value_delimiter = ',')]
+    enrich: Vec<Enrichment>,
+
+    /// Maximum number of requests per second sent to the Wikidata API
+    #[arg(long, default_value_t = 1.0)]
+    enrich_rate: f64,
+
+    /// Record the input file, line and byte offset each entity comes from in the provenance table
+    #[arg(long)]
+    provenance: bool,
+
+    /// Abort the load, checkpointing what has been stored, once more than N lines have failed
+    #[arg(long)]
+    abort_after: Option<usize>,
+
+    /// Write every line that could not be stored, with its error, to this JSON Lines file
+    #[arg(long)]
+    errors: Option<String>,
+
+    /// Record the throughput of the load every N seconds into the _load_log table
+    #[arg(long)]
+    load_log: Option<NonZeroU64>,
+}
+
+/// The `Dump` type represents a buffered reader over the contents of a dump, once
+/// it has been unpacked.
+type Dump = BufReader<Box<dyn Read + Send>>;
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Load the dumps into a database, which is also done when no subcommand is given
+    Import(Box<ImportArgs>),
+    /// Load an incremental dump over a database, replacing the entities that changed
+    Update(Box<ImportArgs>),
+    /// Run a SQL query against a database, printing the results as tab-separated values
+    Query {
+        /// SQL query to be run, such as `SELECT * FROM vertex LIMIT 10`
+        sql: String,
+
+        /// Database the query is run against
+        #[arg(short, long)]
+        database: String,
+    },
+    /// Serve SQL queries against a database over HTTP, answering those sent in the body of POST
+    /// requests with their results as tab-separated values
+    Serve {
+        /// Database the queries are run against
+        #[arg(short, long)]
+        database: String,
+
+        /// Address the server listens on
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        address: String,
+    },
+    /// Print the number of rows of each table of a database, the entities of each type, the most
+    /// used properties, its size and how it was built
+    Stats {
+        /// Database the statistics are computed for
+        #[arg(value_name = "DATABASE", required_unless_present = "database")]
+        file: Option<String>,
+
+        /// Database the statistics are computed for, as an option
+        #[arg(short, long, conflicts_with = "file")]
+        database: Option<String>,
+
+        /// Format of the statistics: table or json
+        #[arg(long, default_value = "table")]
+        format: StatsFormat,
+
+        /// Number of the most used properties listed
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Compile a property path, such as `P31/P279*`, into SQL over the edge table
+    Path {
+        /// Property path to be compiled
+        path: String,
+
+        /// Entity the path starts from, such as `Q42`
+        #[arg(short, long)]
+        from: Option<String>,
+
+        /// Database the query is run against. If not provided, the SQL is printed
+        #[arg(short, long)]
+        database: Option<String>,
+    },
+    /// Parse and convert a single line of a dump, printing verbose diagnostics
+    ReparseLine {
+        /// Input JSON file
+        #[arg(short, long)]
+        json: String,
+
+        /// Number of the line, as reported in the error log
+        #[arg(
+            short,
+            long,
+            required_unless_present = "entity",
+            conflicts_with = "entity"
+        )]
+        line: Option<usize>,
+
+        /// Identifier of the entity, such as `Q42`
+        #[arg(short, long)]
+        entity: Option<String>,
+
+        /// Format of the dump: current or legacy (archived dumps prior to 2020)
+        #[arg(long, default_value = "current")]
+        dump_format: DumpFormat,
+    },
+    /// Print the SQL statements creating the schema of the database
+    Schema {
+        /// Optional tables computed once the load has finished: quantity-si, quantity-stats,
+        /// datatype-mismatch, property-cooccurrence
+        #[arg(long, value_delimiter = ',')]
+        derive: Vec<Derived>,
+
+        /// Properties whose claims are also stored in tables of their own, such as P31
+        #[arg(long, value_delimiter = ',')]
+        partition: Vec<Partition>,
+
+        /// JSON file declaring extra columns computed during the load
+        #[arg(long)]
+        schema_config: Option<String>,
+
+        /// Keep the original strings of the amounts and bounds of the quantities in text columns
+        #[arg(long)]
+        quantity_strings: bool,
+
+        /// Languages whose labels are stored in columns of their own, such as en,de,fr
+        #[arg(long, value_delimiter = ',')]
+        label_columns: Vec<String>,
+
+        /// Encoding of the identifiers: `v2`, `v1`, or the offsets of the namespaces
+        #[arg(long, default_value = "v2")]
+        id_encoding: IdEncoding,
+    },
+    /// Generate a small, yet realistic, fake dump, for testing pipelines without a real one
+    GenerateSample {
+        /// Number of items and lexemes generated, besides the properties
+        #[arg(short, long, default_value_t = 1000)]
+        entities: u64,
+
+        /// Output JSON file. If not provided, it is written to the standard output
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Seed of the sample, so the same dump is generated between runs
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+    /// Export the entities of a database back to the format of the dumps
+    Export {
+        /// Database the entities are exported from
+        #[arg(short, long)]
+        database: String,
+
+        /// Format of the export: wikidata-json or ntriples (truthy triples, as the RDF dumps)
+        #[arg(short, long, default_value = "wikidata-json")]
+        format: ExportFormat,
+
+        /// Output file, or object store URL. If not provided, it is written to the standard output
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Entities to be exported, such as `Q42`. If not provided, every entity is exported
+        #[arg(short, long, value_delimiter = ',')]
+        entities: Vec<String>,
+
+        /// Property path the entities exported are reached through, such as `P279*`
+        #[arg(short, long, requires = "entities")]
+        path: Option<String>,
+    },
+    /// Compare random entities of a database with their current revision in Wikidata
+    Verify {
+        /// Database the entities are picked from
+        #[arg(short, long)]
+        database: String,
+
+        /// Number of entities verified
+        #[arg(short, long, default_value_t = 100)]
+        sample: usize,
+
+        /// Maximum number of requests per second sent to the Wikidata API
+        #[arg(long, default_value_t = 1.0)]
+        rate: f64,
+    },
+    /// Retrieve single entities from the Wikidata API and store them as if loaded from a dump
+    Fetch {
+        /// Entities to be retrieved, such as `Q42,Q64,P31`
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        entities: Vec<String>,
+
+        /// Database the entities are stored in, which is created
+        #[arg(short, long)]
+        database: String,
+
+        /// Maximum number of requests per second sent to the Wikidata API
+        #[arg(long, default_value_t = 1.0)]
+        rate: f64,
+    },
+}
+
+/// This function prints the SQL statements the database is created with, in the
+/// very same order those are executed, so the schema can be reviewed, or an
+/// external database provisioned, without running a load.
 ///
 /// Arguments:
 ///
-/// * `connection`: The `connection` parameter is a reference to a
-/// `PooledConnection` object from the `DuckdbConnectionManager` type. This object
-/// represents a connection to a DuckDB database and is used to execute SQL queries
-/// and commands on that database. The `create_tables` function uses this connection
-/// to create the tables according to the Wikidata entity data model.
+/// * `config`: The `SchemaConfig` declaring the extra columns of the tables.
+///
+/// * `derived`: A slice of the derived tables to be created.
+///
+/// * `partitions`: A slice of the properties whose claims are to be stored in
+///   tables of their own.
+///
+/// * `encoding`: The `IdEncoding` the identifiers would be stored with.
 ///
 /// Returns:
 ///
-/// The function `create_tables` is returning a `Result` with an empty tuple `()` as
-/// the success value and an `Error` as the error value.
-fn create_tables(connection: &mut Connection) -> Result<(), Error> {
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case any of the partitioned
+/// properties does not fit the range of its namespace.
+fn print_schema(
+    config: &SchemaConfig,
+    derived: &[Derived],
+    partitions: &[Partition],
+    encoding: &IdEncoding,
+) -> Result<(), String> {
+    // Several variants of the values are stored in the same table, so the statements are only
+    // printed the first time those are found :D
+    let mut statements: Vec<String> = Vec::new();
+    for table in Table::iterator() {
+        for statement in table.create_table_sql(config) {
+            if !statements.contains(&statement) {
+                statements.push(statement);
+            }
+        }
+    }
+    for table in Table::iterator() {
+        for statement in table.create_indices_sql() {
+            if !statements.contains(&statement) {
+                statements.push(statement);
+            }
+        }
+    }
+    for statement in statements {
+        println!("{}", statement);
+    }
+
+    println!("\n-- Once the load has finished");
+    for statement in globe::create_globe_sql(!config.no_text, encoding) {
+        println!("{}", statement);
+    }
+    println!("{}", stats::create_class_stats_sql(encoding));
+    for table in derived {
+        println!("{}", table.create_table_sql(encoding));
+    }
+    for partition in partitions {
+        let property_id = encoding.encode(Id::Pid(partition.property))?;
+        for table_name in Partition::table_names() {
+            println!(
+                "-- Only in case the property has any claim in {}\n{}",
+                table_name,
+                partition.create_table_sql(table_name, property_id)
+            );
+        }
+    }
+    if !partitions.is_empty() {
+        println!("-- The macros read the partitions created too, joined with the generic tables");
+    }
+    // Databases built with --no-text have no labels on the vertex table to be read :(
+    for helper in Macro::iter().filter(|helper| !(config.no_text && *helper == Macro::Label)) {
+        println!("{}", helper.create_macro_sql(encoding));
+    }
+
+    Ok(())
+}
+
+/// The function stores the vertices of the units and globes that are referenced
+/// by the quantities and coordinates, but were not stored during the load; for
+/// example, because those were left out of the sample. The inputs are scanned a
+/// second time, just for them, so their labels resolve in queries.
+///
+/// Arguments:
+///
+/// * `connection`: A mutable reference to the connection to the database, once the
+///   load has finished.
+///
+/// * `inputs`: The paths to the inputs the entities were loaded from.
+///
+/// * `options`: A reference to the `ConverterConfig` of the load, such as the
+///   format of the inputs.
+///
+/// Returns:
+///
+/// a `Result` type with the `Ok` variant containing the number of vertices stored
+/// and the number of those still missing, and the `Err` variant containing a
+/// `String` with an error message if the database or the inputs cannot be read.
+fn backfill_labels(
+    connection: &mut Connection,
+    inputs: &[String],
+    options: &ConverterConfig,
+) -> Result<(usize, usize), String> {
+    let mut missing = match backfill::missing_ids(connection) {
+        Ok(missing) => missing,
+        Err(error) => return Err(format!("Error retrieving missing labels. {}", error)),
+    };
+    if missing.is_empty() {
+        return Ok((0, 0));
+    }
+
+    let transaction = match connection.transaction() {
+        Ok(transaction) => transaction,
+        Err(error) => return Err(format!("Error opening transaction. {}", error)),
+    };
+
+    // Only the vertices are stored, so the rest of the tables are skipped altogether. Notice that
+    // the entities are neither sampled nor deduplicated, as those are missing anyway :D
+    let mut appender_helper = AppenderHelper::new(&transaction, options.id_encoding);
+    appender_helper.skipped = AppenderHelper::unselected(&["vertex".to_string()])?;
+    if !options.languages.is_empty() {
+        for table in [LABEL_TABLE, ALIAS_TABLE] {
+            match transaction.appender(table) {
+                Ok(appender) => {
+                    appender_helper.appenders.insert(table, appender);
+                }
+                Err(error) => return Err(format!("Error appending to {}. {}", table, error)),
+            }
+        }
+    }
+
+    let mut stored = 0;
+    for json in inputs {
+        if json == "-" {
+            eprintln!("Cannot backfill labels from the standard input. Skipping it");
+            continue;
+        }
+        let reader = open_json(json, None, None, None)?;
+        let lines = split_entities(reader, &options.input_format, options.max_line_length);
+        for (line_number, line) in lines.enumerate() {
+            if missing.is_empty() {
+                break;
+            }
+            let line = match line {
+                Ok(line) => String::from_utf8_lossy(&line).into_owned(),
+                Err(_) => continue, // the line has been already reported during the load
+            };
+            match backfill::header_item_id(&line, &options.id_encoding) {
+                Some(id) if missing.remove(&id) => (),
+                _ => continue,
+            }
+            match parse_entity(line, line_number as u32, options) {
+                Ok(Some(parsed)) => {
+                    if let Err(error) = store_entity(&mut appender_helper, parsed, options) {
+                        return Err(format!("Error backfilling label. {}", error));
+                    }
+                    stored += 1;
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    drop(appender_helper);
+    if let Err(error) = transaction.commit() {
+        return Err(format!("Error committing transaction. {}", error));
+    }
+
+    Ok((stored, missing.len()))
+}
+
+/// The function opens the JSON dump provided by the user, which can be either a file
+/// in the file system, an object in an object store, a file served over HTTP or the
+/// standard input.
+///
+/// Arguments:
+///
+/// * `json`: The path to the JSON file, the URL of an object stored in an object
+///   store, such as `s3://bucket/key`, the URL of a file served over HTTP, or `-` for
+///   reading from the standard input.
+///
+/// * `read`: The counter of the bytes read from the storage, in case those have to
+///   be reported in the progress of the load.
+///
+/// * `max_read_mbps`: The maximum rate, in megabytes per second, at which the dump
+///   is read. In case it is not provided, the dump is read as fast as possible.
+///
+/// * `checksums`: The checksums the one of the dump is recorded in, once it has
+///   been read from beginning to end, in case those have to be stored.
+///
+/// Returns:
+///
+/// a `Result` type with the `Ok` variant containing a buffered reader over the
+/// dump, and the `Err` variant containing a `String` with an error message if the
+/// file could not be opened.
+fn open_json(
+    json: &str,
+    read: Option<&Arc<AtomicU64>>,
+    max_read_mbps: Option<f64>,
+    checksums: Option<&Checksums>,
+) -> Result<Dump, String> {
+    // We open the JSON file. Notice that some error handling has to be performed as errors may
+    // occur in the process of opening the file provided by the user. More in more, we have to
+    // check if the file is the standard input or a file in the file system. In the first case, we
+    // use the standard input as the reader; otherwise, we use the file provided by the user :D
+    let reader: Box<dyn Read + Send> = if json == "-" {
+        Box::new(stdin())
+    } else if remote::is_remote(json) {
+        // Dumps staged in object stores are streamed, so those do not have to be downloaded first
+        Box::new(match RemoteReader::open(json) {
+            Ok(reader) => reader,
+            Err(error) => return Err(format!("Error opening remote JSON file. {}", error)),
+        })
+    } else if http::is_http(json) {
+        // The same goes for the dumps served over HTTP, which are converted as those are downloaded
+        Box::new(match HttpReader::open(json) {
+            Ok(reader) => reader,
+            Err(error) => return Err(format!("Error downloading JSON file. {}", error)),
+        })
+    } else {
+        Box::new(match File::open(json) {
+            Ok(file) => file,
+            Err(error) => return Err(format!("Error opening JSON file. {}", error)),
+        })
+    };
+
+    // The bytes are counted before unpacking them as well, as the size of the inputs is the one of
+    // the bytes stored, so the progress of the load can be estimated out of it :D
+    let reader: Box<dyn Read + Send> = match read {
+        Some(read) => Box::new(Metered::new(reader, Arc::clone(read))),
+        None => reader,
+    };
+
+    // The same goes for the checksum, which is the one of the file as it is stored, so it can be
+    // compared with the ones published next to the dumps :D
+    let reader: Box<dyn Read + Send> = match checksums {
+        Some(checksums) => Box::new(Sha1Reader::new(reader, json, checksums.clone())),
+        None => reader,
+    };
+
+    // In case the user has bounded the read rate, the reader is throttled before unpacking it.
+    // Hence, the rate holds for the bytes actually read from the storage :D
+    let reader: Box<dyn Read + Send> = match max_read_mbps {
+        Some(max_read_mbps) if max_read_mbps > 0.0 => {
+            Box::new(Throttled::new(reader, max_read_mbps))
+        }
+        Some(_) => return Err("The maximum read rate must be positive".to_string()),
+        None => reader,
+    };
+
+    // Dumps may be shipped in archives, such as multistream bz2 files or tarred chunks, which are
+    // unpacked on the fly. Hence, users do not need to extract hundreds of GB beforehand :D
+    let reader = archive::unpack(json, reader);
+    Ok(BufReader::new(reader))
+}
+
+/// The function returns the size of an input, in bytes, in case it is known; that
+/// is, for the local files and the files served over HTTP whose size the server
+/// tells.
+fn input_size(json: &str) -> Option<u64> {
+    match http::is_http(json) {
+        true => http::content_length(json),
+        false => std::fs::metadata(json).ok().map(|metadata| metadata.len()),
+    }
+}
+
+/// The function expands the inputs given by the user, so those containing glob
+/// patterns, such as `chunks/*.json.gz`, are replaced by the files they match, in
+/// alphabetical order. This allows processing a dump that has been pre-split into
+/// several chunks at once.
+///
+/// Arguments:
+///
+/// * `inputs`: The inputs given by the user, which are either paths, glob patterns
+///   or `-` for reading from the standard input.
+///
+/// Returns:
+///
+/// a `Result` type with the `Ok` variant containing the paths to the files to be
+/// processed, and the `Err` variant containing a `String` with an error message if
+/// any of the patterns is not valid or matches no file at all.
+fn expand_inputs(inputs: &[String]) -> Result<Vec<String>, String> {
+    let mut expanded = Vec::new();
+
+    for input in inputs {
+        // Only the inputs containing any of the special characters of the patterns are expanded.
+        // Hence, paths to existing files, remote objects and the standard input are left untouched
+        if input == "-"
+            || remote::is_remote(input)
+            || http::is_http(input)
+            || !input.contains(['*', '?', '['])
+        {
+            expanded.push(input.to_owned());
+            continue;
+        }
+
+        let paths = match glob(input) {
+            Ok(paths) => paths,
+            Err(error) => return Err(format!("Not valid pattern {}. {}", input, error)),
+        };
+
+        let mut matched = paths
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .map(|path| path.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        if matched.is_empty() {
+            return Err(format!("No file matches the pattern {}", input));
+        }
+        matched.sort();
+        expanded.append(&mut matched);
+    }
+
+    Ok(expanded)
+}
+
+/// The function reads the encoding of the identifiers a database was built with,
+/// so those are converted in the very same manner. Databases recording no encoding
+/// are either empty or built before it was recorded, with the default one.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` with the `IdEncoding` of the database, or a `String` value containing
+/// an error message in case of failure.
+fn read_encoding(connection: &Connection) -> Result<IdEncoding, String> {
+    match IdEncoding::of(connection) {
+        Ok(encoding) => Ok(encoding),
+        Err(error) => Err(format!("Error reading metadata. {}", error)),
+    }
+}
+
+/// The function compiles a property path into SQL and either prints it or runs it
+/// against an already created database, printing the pairs of vertices found.
+///
+/// Arguments:
+///
+/// * `path`: A string representing the property path, such as `P31/P279*`.
+///
+/// * `from`: An optional identifier of the entity the path starts from.
+///
+/// * `database`: An optional path to the database the query is run against. In
+///   case it is not provided, the SQL query is printed instead.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case of failure.
+fn run_path(path: &str, from: Option<&str>, database: Option<&str>) -> Result<(), String> {
+    if let Some(database) = database {
+        if !Path::new(database).exists() {
+            return Err("Cannot open a database that does not exist".to_string());
+        }
+    }
+
+    // The database is opened beforehand, so the identifiers are encoded as those stored in it.
+    // Otherwise, those are encoded with the default encoding :D
+    let connection = match database.map(Connection::open) {
+        Some(Ok(connection)) => {
+            metadata::check_schema_version(&connection)?;
+            Some(connection)
+        }
+        Some(Err(error)) => return Err(format!("Error opening connection. {}", error)),
+        None => None,
+    };
+    let encoding = match &connection {
+        Some(connection) => read_encoding(connection)?,
+        None => IdEncoding::default(),
+    };
+
+    let from = from
+        .map(|id| id.parse::<Id>().and_then(|id| encoding.encode(id)))
+        .transpose()?;
+    // The claims of the partitioned properties are no longer in the edge table, so those are read
+    // from their partitions as well :D
+    let edge = match &connection {
+        Some(connection) => match partition::source(connection, "edge") {
+            Ok(edge) => edge,
+            Err(error) => return Err(format!("Error reading partitions. {}", error)),
+        },
+        None => "edge".to_string(),
+    };
+    let sql = PropertyPath::parse(path, &encoding)?.to_sql(from, &edge);
+
+    let connection = match connection {
+        Some(connection) => connection,
+        None => {
+            println!("{}", sql);
+            return Ok(());
+        }
+    };
+
+    let mut statement = match connection.prepare(&sql) {
+        Ok(statement) => statement,
+        Err(error) => return Err(format!("Error preparing query. {}", error)),
+    };
+
+    let rows =
+        match statement.query_map([], |row| Ok((row.get::<_, u64>(0)?, row.get::<_, u64>(1)?))) {
+            Ok(rows) => rows,
+            Err(error) => return Err(format!("Error running query. {}", error)),
+        };
+
+    for row in rows {
+        match row {
+            Ok((src_id, dst_id)) => println!("{}\t{}", src_id, dst_id),
+            Err(error) => return Err(format!("Error reading row. {}", error)),
+        }
+    }
+
+    Ok(())
+}
+
+/// The function opens an already created database to be queried, as the `query`
+/// and `serve` subcommands do, registering the scalar functions written in Rust on
+/// the connection, such as `wd_decode_id`.
+///
+/// Arguments:
+///
+/// * `database`: A string slice with the path to the database.
+///
+/// * `config`: The configuration the database is opened with, which restricts what
+///   the queries are allowed to do for the `serve` subcommand.
+///
+/// Returns:
+///
+/// a `Result` with the `Connection` to the database, or a `String` value containing
+/// an error message in case it cannot be opened or queried by this version.
+fn open_queried(database: &str, config: Config) -> Result<Connection, String> {
+    if !Path::new(database).exists() {
+        return Err("Cannot open a database that does not exist".to_string());
+    }
+
+    let connection = match Connection::open_with_flags(database, config) {
+        Ok(connection) => connection,
+        Err(error) => return Err(format!("Error opening connection. {}", error)),
+    };
+    metadata::check_schema_version(&connection)?;
+
+    // The functions decode the identifiers with the encoding of the database, which they read as
+    // those are registered :D
+    if let Err(error) = udf::register(&connection) {
+        return Err(format!("Error registering functions. {}", error));
+    }
+
+    Ok(connection)
+}
+
+/// The function runs a SQL query against an already created database, printing the
+/// results as tab-separated values, headed by the names of the columns.
+///
+/// Arguments:
+///
+/// * `sql`: A string slice with the SQL query, such as `SELECT * FROM vertex`.
+///
+/// * `database`: A string slice with the path to the database.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case of failure.
+fn run_query(sql: &str, database: &str) -> Result<(), String> {
+    let connection = open_queried(database, Config::default())?;
+    write_results(&connection, sql, &mut stdout().lock())
+}
+
+/// The function runs a SQL query, writing the results as tab-separated values,
+/// headed by the names of the columns.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection the query is run through.
+///
+/// * `sql`: A string slice with the SQL query, such as `SELECT * FROM vertex`.
+///
+/// * `writer`: Where the results are written to.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case of failure.
+fn write_results(connection: &Connection, sql: &str, writer: &mut dyn Write) -> Result<(), String> {
+    // The columns of the query may be of any type, so those are described beforehand, and then
+    // cast to text, which is how DuckDB itself prints them :D
+    let sql = sql.trim().trim_end_matches(';');
+    let mut statement = match connection.prepare(&format!("DESCRIBE {}", sql)) {
+        Ok(statement) => statement,
+        Err(error) => return Err(format!("Error preparing query. {}", error)),
+    };
+    let columns = match statement
+        .query_map([], |row| row.get::<_, String>(0))
+        .and_then(|columns| columns.collect::<Result<Vec<_>, _>>())
+    {
+        Ok(columns) => columns,
+        Err(error) => return Err(format!("Error running query. {}", error)),
+    };
+
+    let mut statement = match connection.prepare(&format!("SELECT COLUMNS(*)::TEXT FROM ({})", sql))
+    {
+        Ok(statement) => statement,
+        Err(error) => return Err(format!("Error preparing query. {}", error)),
+    };
+    let rows = match statement.query_map([], |row| {
+        (0..columns.len())
+            .map(|index| row.get::<_, Option<String>>(index))
+            .collect::<Result<Vec<_>, _>>()
+    }) {
+        Ok(rows) => rows,
+        Err(error) => return Err(format!("Error running query. {}", error)),
+    };
+
+    if let Err(error) = writeln!(writer, "{}", columns.join("\t")) {
+        return Err(format!("Error writing results. {}", error));
+    }
+    for row in rows {
+        let values = match row {
+            Ok(values) => values,
+            Err(error) => return Err(format!("Error reading row. {}", error)),
+        };
+        let line = values
+            .into_iter()
+            .map(|value| value.unwrap_or_else(|| "NULL".to_string()))
+            .collect::<Vec<_>>()
+            .join("\t");
+        if let Err(error) = writeln!(writer, "{}", line) {
+            return Err(format!("Error writing results. {}", error));
+        }
+    }
+
+    Ok(())
+}
+
+/// The greatest length of the body of the requests of the `serve` subcommand, in
+/// bytes, so a single request cannot take the memory of the server.
+const MAX_QUERY_LENGTH: usize = 1024 * 1024;
+
+/// The time the `serve` subcommand waits for a client to send or read anything,
+/// as requests are answered one after the other, and an idle client would block
+/// the rest of them otherwise.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The function serves SQL queries against an already created database over HTTP.
+/// Queries are sent in the body of POST requests, and answered with their results
+/// as tab-separated values, as the `query` subcommand prints them. Requests are
+/// answered one after the other, over the same connection. The database is opened
+/// read-only and with no access to files nor to other databases, so the clients
+/// can neither modify it nor reach anything else through it.
+///
+/// Arguments:
+///
+/// * `database`: A string slice with the path to the database.
+///
+/// * `address`: A string slice with the address the server listens on, such as
+///   `127.0.0.1:8080`.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value, which is never returned as
+/// the server runs until it is killed, or a `String` value containing an error
+/// message in case the database cannot be opened or the address bound.
+fn serve(database: &str, address: &str) -> Result<(), String> {
+    let config = match Config::default()
+        .access_mode(AccessMode::ReadOnly)
+        .and_then(|config| config.enable_external_access(false))
+    {
+        Ok(config) => config,
+        Err(error) => return Err(format!("Error configuring database. {}", error)),
+    };
+    let connection = open_queried(database, config)?;
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(error) => return Err(format!("Error listening on {}. {}", address, error)),
+    };
+    eprintln!("Serving {} at http://{}", database, address);
+
+    // A failed request is no reason to stop answering the rest of them :D
+    for stream in listener.incoming() {
+        let result = match stream {
+            Ok(stream) => answer(&connection, stream),
+            Err(error) => Err(format!("Error accepting connection. {}", error)),
+        };
+        if let Err(error) = result {
+            eprintln!("{}", error);
+        }
+    }
+
+    Ok(())
+}
+
+/// The function answers a single HTTP request of the `serve` subcommand, running
+/// the SQL query in its body.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection the query is run through.
+///
+/// * `stream`: The TCP stream of the request, where the response is written to.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value indicating the response has
+/// been sent, or a `String` value containing an error message otherwise.
+fn answer(connection: &Connection, stream: TcpStream) -> Result<(), String> {
+    if let Err(error) = stream
+        .set_read_timeout(Some(CLIENT_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(CLIENT_TIMEOUT)))
+    {
+        return Err(format!("Error configuring connection. {}", error));
+    }
+
+    // The headers are bounded as well, so no line of those can grow past the length of a query :D
+    let mut reader = BufReader::new((&stream).take(2 * MAX_QUERY_LENGTH as u64));
+    let mut request_line = String::new();
+    if let Err(error) = reader.read_line(&mut request_line) {
+        return Err(format!("Error reading request. {}", error));
+    }
+
+    // Only the length of the body is needed out of the headers, so the rest are skipped :D
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        match reader.read_line(&mut header) {
+            Ok(0) => break,
+            Ok(_) if header.trim().is_empty() => break,
+            Ok(_) => {
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.trim().eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+            }
+            Err(error) => return Err(format!("Error reading request. {}", error)),
+        }
+    }
+
+    if request_line.split_whitespace().next() != Some("POST") {
+        return respond(
+            &stream,
+            "405 Method Not Allowed",
+            b"Queries are sent in the body of POST requests\n",
+        );
+    }
+
+    if length > MAX_QUERY_LENGTH {
+        return respond(
+            &stream,
+            "413 Payload Too Large",
+            format!("Queries are at most {} bytes long\n", MAX_QUERY_LENGTH).as_bytes(),
+        );
+    }
+
+    let mut body = vec![0; length];
+    if let Err(error) = reader.read_exact(&mut body) {
+        return Err(format!("Error reading request. {}", error));
+    }
+    let sql = match String::from_utf8(body) {
+        Ok(sql) => sql,
+        Err(_) => return respond(&stream, "400 Bad Request", b"Queries are UTF-8 text\n"),
+    };
+
+    let mut results = Vec::new();
+    match write_results(connection, &sql, &mut results) {
+        Ok(()) => respond(&stream, "200 OK", &results),
+        Err(error) => respond(
+            &stream,
+            "400 Bad Request",
+            format!("{}\n", error).as_bytes(),
+        ),
+    }
+}
+
+/// The function writes an HTTP response, closing the connection afterwards.
+///
+/// Arguments:
+///
+/// * `stream`: The TCP stream the response is written to.
+///
+/// * `status`: A string slice with the status of the response, such as `200 OK`.
+///
+/// * `body`: The body of the response, which holds the results of the query or
+///   the error message.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case of failure.
+fn respond(mut stream: &TcpStream, status: &str, body: &[u8]) -> Result<(), String> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/tab-separated-values; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    match stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(body))
+    {
+        Ok(()) => Ok(()),
+        Err(error) => Err(format!("Error writing response. {}", error)),
+    }
+}
+
+/// The function prints the statistics of an already created database: the number
+/// of rows of each of its tables, the entities of each type, the most used
+/// properties and its size, followed by its metadata; that is, how it was built.
+///
+/// Arguments:
+///
+/// * `database`: A string slice with the path to the database.
+///
+/// * `format`: A reference to the `StatsFormat` the statistics are printed in.
+///
+/// * `top`: The number of the most used properties listed.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case of failure.
+fn print_stats(database: &str, format: &StatsFormat, top: usize) -> Result<(), String> {
+    if !Path::new(database).exists() {
+        return Err("Cannot open a database that does not exist".to_string());
+    }
+
+    let connection = match Connection::open(database) {
+        Ok(connection) => connection,
+        Err(error) => return Err(format!("Error opening connection. {}", error)),
+    };
+    metadata::check_schema_version(&connection)?;
+
+    let stats = match DatabaseStats::collect(&connection, Path::new(database)) {
+        Ok(stats) => stats,
+        Err(error) => return Err(format!("Error computing statistics. {}", error)),
+    };
+    match format {
+        StatsFormat::Table => print!("{}", stats.to_table(top)),
+        StatsFormat::Json => println!("{}", stats.to_json(top)),
+    }
+
+    Ok(())
+}
+
+/// The function checks whether a line of the dump holds the entity with the given
+/// identifier. As the identifier of the entity is placed before its claims, which
+/// may reference the entity as well, only the beginning of the line is considered.
+///
+/// Arguments:
+///
+/// * `line`: A string slice with the line of the dump.
+///
+/// * `entity_id`: The identifier of the entity, such as `Q42`.
+///
+/// Returns:
+///
+/// `true` if the line holds the entity, `false` otherwise.
+fn holds_entity(line: &str, entity_id: &str) -> bool {
+    let header = match line.find("\"claims\"") {
+        Some(position) => &line[..position],
+        None => line,
+    };
+    header.contains(&format!("\"id\":\"{}\"", entity_id))
+}
+
+/// The function extracts a single line from a dump, either by its number or by the
+/// identifier of the entity it holds, and runs the parse and convert path on it,
+/// printing verbose diagnostics of each of the steps. The entity is stored in an
+/// in-memory database, so storage errors are reproduced as well.
+///
+/// Arguments:
+///
+/// * `json`: The path to the JSON file, or `-` for reading from the standard input.
+///
+/// * `line_number`: The number of the line, as reported in the error log.
+///
+/// * `entity_id`: The identifier of the entity, used when no line number is given.
+///
+/// * `dump_format`: The format of the dump.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case of failure.
+fn reparse_line(
+    json: &str,
+    line_number: Option<usize>,
+    entity_id: Option<&str>,
+    dump_format: &DumpFormat,
+) -> Result<(), String> {
+    let found = open_json(json, None, None, None)?
+        .lines()
+        .enumerate()
+        .find_map(|(number, line)| {
+            let line = line.ok()?;
+            let matches = match (line_number, entity_id) {
+                (Some(line_number), _) => number == line_number,
+                (None, Some(entity_id)) => holds_entity(&line, entity_id),
+                (None, None) => false,
+            };
+            matches.then_some((number, line))
+        });
+
+    let (number, line) = match found {
+        Some(found) => found,
+        None => return Err("The line could not be found in the dump".to_string()),
+    };
+
+    println!("Line {} ({} bytes)", number, line.len());
+    println!("  {}", line.chars().take(200).collect::<String>());
+
+    let options = ConverterConfig {
+        dump_format: dump_format.clone(),
+        keep_deprecated: true,
+        lexemes: true,
+        ..ConverterConfig::default()
+    };
+
+    let parsed = match parse_entity(line, number as u32, &options) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => {
+            println!("The line is a delimiter of the dump, so it is skipped");
+            return Ok(());
+        }
+        Err(error) => {
+            println!("Parsing failed: {}", error);
+            return Ok(());
+        }
+    };
+
+    let entity = &parsed.entity;
+    println!(
+        "Parsed {:?} with {} labels, {} descriptions and {} claims",
+        entity.id,
+        entity.labels.len(),
+        entity.descriptions.len(),
+        entity.claims.len()
+    );
+
+    for raw_claim in &parsed.raw_claims {
+        println!(
+            "  P{}: cannot be converted, stored in the raw_claim table",
+            raw_claim.property.0
+        );
+    }
+
+    if let Some(lexeme) = &parsed.lexeme {
+        println!(
+            "  Lexeme with {} lemmas, {} forms and {} senses",
+            lexeme.lemmas.len(),
+            lexeme.forms.len(),
+            lexeme.senses.len()
+        );
+    }
+
+    // A claim that cannot be converted is reported as the rest are, so the ones after it are still
+    // diagnosed, and the entity is stored anyway to find out how the load handles it :D
+    for (property_id, claim_value) in &entity.claims {
+        let table = match Table::from_claim(claim_value.data.clone(), &options.id_encoding) {
+            Ok(table) => table,
+            Err(error) => {
+                println!(
+                    "  P{} ({:?}): conversion failed: {}",
+                    property_id.0, claim_value.rank, error
+                );
+                continue;
+            }
+        };
+        println!(
+            "  P{} ({:?}): {} table, datatype {:?}, {} qualifiers, {} references",
+            property_id.0,
+            claim_value.rank,
+            table.as_ref(),
+            table.data_type(),
+            claim_value.qualifiers.len(),
+            claim_value.references.len()
+        );
+    }
+
+    // Lastly, the entity is stored in an in-memory database with the very same schema, so that
+    // errors raised by the database are reproduced without touching any file on disk :D
+    let mut connection = match Connection::open_in_memory() {
+        Ok(connection) => connection,
+        Err(error) => return Err(format!("Error opening connection. {}", error)),
+    };
+    if let Err(error) = create_tables(&mut connection, &options.schema) {
+        return Err(format!("Error creating tables. {}", error));
+    }
     let transaction = match connection.transaction() {
         Ok(transaction) => transaction,
-        Err(_) => return Err(Error::AppendError),
+        Err(error) => return Err(format!("Error opening transaction. {}", error)),
     };
+    let mut appender_helper = AppenderHelper::new(&transaction, options.id_encoding);
 
-    for table in Table::iterator() {
-        table.create_table(&transaction)?;
+    match store_entity(&mut appender_helper, parsed, &options) {
+        Ok(()) => println!("Stored successfully"),
+        Err(error) => println!("Storing failed: {}", error),
     }
 
-    transaction.commit()
+    Ok(())
 }
 
-/// This function creates indices for the id column in the vertices table.
+/// The function compares random entities of a database with their current revision
+/// in Wikidata, printing the discrepancies found for each of them.
 ///
 /// Arguments:
 ///
-/// * `transaction`: A reference to a Transaction object, which is used to perform
-/// database operations.
+/// * `database`: The path to the database the entities are picked from.
+///
+/// * `sample`: The number of entities verified.
+///
+/// * `rate`: The maximum number of requests per second sent to the API.
 ///
 /// Returns:
 ///
-/// The function `create_indices` returns a `Result` enum with either an `Ok(())`
-/// value indicating that the function executed successfully, or an `Err` value
-/// containing an `Error` object if an error occurred during execution.
-fn create_indices(connection: &Connection) -> Result<(), Error> {
-    // We are interested only in creating an index for the id column in the vertices table, as we
-    // will only query over it. The rest of the data that is stored just extends the knowledge that
-    // we store, but has no relevance in regards with future processing :D
-    for table in Table::iterator() {
-        table.create_indices(connection)?;
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case of failure.
+fn verify(database: &str, sample: usize, rate: f64) -> Result<(), String> {
+    if !Path::new(database).exists() {
+        return Err("Cannot open a database that does not exist".to_string());
+    }
+
+    let connection = match Connection::open(database) {
+        Ok(connection) => connection,
+        Err(error) => return Err(format!("Error opening connection. {}", error)),
+    };
+    metadata::check_schema_version(&connection)?;
+
+    let report = Verifier::new(rate)?.verify(&connection, sample)?;
+    let mismatched = report
+        .iter()
+        .filter(|(_, discrepancies)| !discrepancies.is_empty())
+        .count();
+    for (entity, discrepancies) in &report {
+        for discrepancy in discrepancies {
+            println!("{}: {}", entity, discrepancy);
+        }
     }
+    println!(
+        "{} of {} entities match their current revision in Wikidata",
+        report.len() - mismatched,
+        report.len()
+    );
+
     Ok(())
 }
 
-/// The function parses and stores Wikidata entities from a JSON dump file.
+/// The function retrieves the given entities from the Wikidata API and stores them
+/// in a new database, with the very same schema of a dump import. Notice that the
+/// entities are written to a temporary dump, which is then imported as any other.
 ///
 /// Arguments:
 ///
-/// * `appender_helper`: A mutable reference to an AppenderHelper struct, which is
-/// used to append entities to a storage backend.
+/// * `entities`: The identifiers of the entities, such as `Q42`.
 ///
-/// * `line`: A string representing a line of JSON data from a Wikidata dump file.
+/// * `database`: The path to the database the entities are stored in.
 ///
-/// * `line_number`: The line number of the current line being processed in the
-/// input file.
+/// * `rate`: The maximum number of requests per second sent to the API.
 ///
 /// Returns:
 ///
-/// a `Result` type with the `Ok` variant containing an empty tuple `()` if the
-/// function executes successfully, and the `Err` variant containing a `String` with
-/// an error message if an error occurs during execution.
-fn insert_entity(
-    appender_helper: &mut AppenderHelper,
-    mut line: String,
-    line_number: u32,
-) -> Result<(), String> {
-    // We have to remove the delimiters so the JSON parsing is performed in a safe environment. For
-    // us to do so, we remove possible blanks both at the end and at the beginning of each line.
-    // After such, we check if the line is empty or any of the possible delimiters ('[' or ']').
-    // Hence, what we are ensuring is that the JSON line is as safe as possible
-    line = line.trim().parse().unwrap(); //
-    if line.is_empty() || line == "[" || line == "]" {
-        return Ok(()); // we just skip the line. It is not needed :D
-    }
-
-    // Remove the trailing comma and newline character. This is extremely important for serde_json to
-    // process the lines properly. In general, a processing of the lines is required for serde_json
-    // to work. We are making sure that the last character is a closing bracket; that is, the line
-    // is a valid JSON
-    if line.ends_with(',') {
-        line.truncate(line.len() - 1);
-    }
-
-    // By using simd_json we parse the string to a Value. In this regard, the line has to be a valid
-    // JSON by itself. As we are sure that Wikidata dumps are an enumeration of JSON objects: one
-    // per line in the document, we can use this algorithm for retrieving each entity in the dump
-    let value = match unsafe { simd_json::from_str(&mut line) } {
-        Ok(value) => value,
-        Err(error) => {
-            return Err(format!(
-                "Error parsing JSON at line {}: {}",
-                line_number, error
-            ))
-        }
-    };
+/// a `Result` type with the `Ok` variant containing an empty tuple `()` and the
+/// `Err` variant containing a `String` with an error message if the entities
+/// cannot be retrieved or stored.
+fn fetch(entities: &[String], database: &str, rate: f64) -> Result<(), String> {
+    // The database is checked beforehand, so no request is sent just to find out that the entities
+    // cannot be stored anyway :(
+    if Path::new(database).exists() {
+        return Err("Cannot open an already created database".to_string());
+    }
 
-    // Once we have the JSON value parsed, we try to transform it into a Wikidata entity, that will
-    // be stored later. This is basically the same object as before, but arranged in a better manner
-    let entity = match Entity::from_json(value) {
-        Ok(entity) => entity,
-        Err(error) => {
-            return Err(format!(
-                "Error parsing Entity at line {}: {:?}",
-                line_number, error
-            ))
-        }
+    let dump = std::env::temp_dir().join(format!("wd2duckdb-fetch-{}.json", std::process::id()));
+    let mut writer = match File::create(&dump) {
+        Ok(file) => BufWriter::new(file),
+        Err(error) => return Err(format!("Error creating {}. {}", dump.display(), error)),
     };
+    // The buffer is flushed as the writer is unwrapped, so an entity that could not be written is
+    // reported instead of being silently lost :(
+    let fetched = Fetcher::new(rate)?
+        .write_dump(entities, &mut writer)
+        .and_then(|fetched| match writer.into_inner() {
+            Ok(_) => Ok(fetched),
+            Err(error) => Err(format!("Error writing {}. {}", dump.display(), error.error())),
+        });
 
-    if let Err(error) = store_entity(appender_helper, entity) {
-        return Err(format!(
-            "Error storing entity at line {}: {}",
-            line_number, error
-        ));
-    }
-
-    Ok(())
+    // The temporary dump is loaded with the default options, so every table is built just as it
+    // is out of a real dump :D
+    let result = fetched.and_then(|(fetched, missing)| {
+        for id in &missing {
+            eprintln!("{} could not be found. Skipping it", id);
+        }
+        if fetched == 0 {
+            return Err("None of the entities could be found".to_string());
+        }
+        let reader = match File::open(&dump) {
+            Ok(file) => BufReader::new(file),
+            Err(error) => return Err(format!("Error opening {}. {}", dump.display(), error)),
+        };
+        let mut connection = match Connection::open(database) {
+            Ok(connection) => connection,
+            Err(error) => return Err(format!("Error opening connection. {}", error)),
+        };
+        let report = Converter::new(ConverterConfig::default()).run(reader, &mut connection)?;
+        if report.total() > 0 {
+            eprintln!("{}", report);
+        }
+        Ok(())
+    });
+    let _ = remove_file(&dump);
+    result
 }
 
-/// This function stores entity information in a table, ignoring deprecated
-/// information.
+/// The function exports every entity of a database back to the format of the dumps,
+/// so the database (or a filtered subset of it) can be fed to tools that only
+/// understand such format.
 ///
 /// Arguments:
 ///
-/// * `appender_helper`: A mutable reference to an AppenderHelper struct, which is
-/// used to append data to a database table.
+/// * `database`: The path to the database the entities are exported from.
+///
+/// * `format`: The `ExportFormat` the entities are exported to.
+///
+/// * `output`: An optional path to the file the export is written to, which may be
+///   the URL of an object store. In case it is not provided, the export is written
+///   to the standard output.
 ///
-/// * `entity`: An object representing a Wikidata entity, which can be an item,
-/// property, or lexeme. It contains information such as the entity's ID, labels,
-/// descriptions, and claims (which are statements about the entity, such as its
-/// properties and values).
+/// * `entities`: The identifiers of the entities to be exported. In case none is
+///   provided, every entity in the database is exported.
+///
+/// * `path`: An optional property path, such as `P279*`. In case it is provided,
+///   the entities exported are the ones reached through the path from any of the
+///   given entities, so a whole subgraph can be extracted.
 ///
 /// Returns:
 ///
 /// a `Result` type with either an empty `Ok(())` value indicating success or a
 /// `String` value containing an error message in case of failure.
-fn store_entity(appender_helper: &mut AppenderHelper, entity: Entity) -> Result<(), String> {
-    use wikidata::WikiId::*;
+fn export(
+    database: &str,
+    format: &ExportFormat,
+    output: Option<&str>,
+    entities: &[String],
+    path: Option<&str>,
+) -> Result<(), String> {
+    if !Path::new(database).exists() {
+        return Err("Cannot open a database that does not exist".to_string());
+    }
+
+    let connection = match Connection::open(database) {
+        Ok(connection) => connection,
+        Err(error) => return Err(format!("Error opening connection. {}", error)),
+    };
+    metadata::check_schema_version(&connection)?;
+    let encoding = read_encoding(&connection)?;
+
+    let ids = entities
+        .iter()
+        .map(|id| id.parse::<Id>().and_then(|id| encoding.encode(id)))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let src_id = u32::from(match entity.id {
-        EntityId(id) => Id::Qid(id),
-        PropertyId(id) => Id::Pid(id),
-        LexemeId(id) => Id::Lid(id),
+    // The entities exported are either the ones given by the user, or the ones reached from them
+    // through the property path; that is, the subgraph hanging from them. Notice that the path is
+    // compiled once per entity, as the compiled query starts from a single vertex :D
+    let selection = match path {
+        Some(path) => {
+            let path = PropertyPath::parse(path, &encoding)?;
+            let edge = match partition::source(&connection, "edge") {
+                Ok(edge) => edge,
+                Err(error) => return Err(format!("Error reading partitions. {}", error)),
+            };
+            format!(
+                " WHERE id IN ({})",
+                ids.iter()
+                    .map(|id| format!("SELECT dst_id FROM ({})", path.to_sql(Some(*id), &edge)))
+                    .collect::<Vec<_>>()
+                    .join(" UNION ")
+            )
+        }
+        None if !ids.is_empty() => format!(
+            " WHERE id IN ({})",
+            ids.iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => String::new(),
+    };
+
+    // Exports can be uploaded straight to an object store, in which case the upload is completed
+    // once the writer is flushed; that is, once every entity has been written :D
+    let mut writer: BufWriter<Box<dyn Write>> = BufWriter::new(match output {
+        Some(output) if remote::is_remote(output) => match RemoteWriter::create(output) {
+            Ok(writer) => Box::new(writer),
+            Err(error) => return Err(format!("Error creating remote output file. {}", error)),
+        },
+        Some(output) => match File::create(output) {
+            Ok(file) => Box::new(file),
+            Err(error) => return Err(format!("Error creating output file. {}", error)),
+        },
+        None => Box::new(stdout()),
     });
 
-    // We are only interested in the English label and description of the entity. This is because
-    // the rest of the information is not relevant for the processing that we are going to perform
-    // in the future. In this regard, we are only storing the English label and description of the
-    // entity in the vertices table of the database :D
-    if appender_helper
-        .appenders
-        .get_mut("vertex")
-        .unwrap()
-        .append_row(params![
-            src_id,
-            entity.labels.get(&LANG),
-            entity.descriptions.get(&LANG)
-        ])
-        .is_err()
-    {
-        return Err(format!("Error inserting into VERTEX: {:?}", entity.id));
-    }
-
-    for (property_id, claim_value) in entity.claims {
-        // In case the claim value stores some outdated or wrong information, we ignore it. The
-        // deprecated annotation indicates that this piece of information should be ignored
-        if claim_value.rank != Rank::Deprecated {
-            if let Err(error) = Table::from(claim_value.data).insert(
-                appender_helper,
-                src_id, // identifier of the entity
-                u32::from(Id::Pid(property_id)),
-            ) {
-                return Err(format!("Error inserting into TABLE: {:?}", error));
+    // The triples are written out of the JSON of the entities, so both formats hold the very same
+    // claims :D
+    let mut exporter = match JsonExporter::new(&connection) {
+        Ok(exporter) => exporter,
+        Err(error) => return Err(format!("Error preparing export. {}", error)),
+    };
+
+    // Databases built with --no-text store neither labels nor descriptions, so none are exported :(
+    let texts = match schema::has_column(&connection, "vertex", "label") {
+        Ok(true) => "label, description",
+        Ok(false) => "NULL::TEXT, NULL::TEXT",
+        Err(error) => return Err(format!("Error reading schema. {}", error)),
+    };
+    let mut statement = match connection.prepare(&format!(
+        "SELECT id, {} FROM vertex{} ORDER BY id",
+        texts, selection
+    )) {
+        Ok(statement) => statement,
+        Err(error) => return Err(format!("Error preparing query. {}", error)),
+    };
+
+    let vertices = match statement.query_map([], |row| {
+        Ok((
+            row.get::<_, u64>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, Option<String>>(2)?,
+        ))
+    }) {
+        Ok(vertices) => vertices,
+        Err(error) => return Err(format!("Error running query. {}", error)),
+    };
+
+    // The JSON dumps are a JSON array with one entity per line, so every entity but the last one
+    // is followed by a comma. Hence, we write the separator before each entity but the first one.
+    // On the contrary, the triples of N-Triples are just written one after the other :D
+    let mut exported = 0;
+    let mut write = |text: &str| match writer.write_all(text.as_bytes()) {
+        Ok(()) => Ok(()),
+        Err(error) => Err(format!("Error writing export. {}", error)),
+    };
+    if *format == ExportFormat::WikidataJson {
+        write("[\n")?;
+    }
+    for vertex in vertices {
+        let (id, label, description) = match vertex {
+            Ok(vertex) => vertex,
+            Err(error) => return Err(format!("Error reading row. {}", error)),
+        };
+        let entity = match exporter.entity(id, label, description) {
+            Ok(Some(entity)) => entity,
+            Ok(None) => continue, // the vertex is not an entity of the dump :(
+            Err(error) => return Err(format!("Error exporting entity {}. {}", id, error)),
+        };
+        match format {
+            ExportFormat::WikidataJson => {
+                if exported > 0 {
+                    write(",\n")?;
+                }
+                write(&entity.to_string())?;
             }
+            ExportFormat::Ntriples => write(&entity_triples(&entity))?,
         }
+        exported += 1;
+    }
+    if *format == ExportFormat::WikidataJson {
+        write("\n]\n")?;
+    }
+
+    if let Err(error) = writer.flush() {
+        return Err(format!("Error writing export. {}", error));
     }
 
+    eprintln!("{} entities exported", exported);
+
     Ok(())
 }
 
-/// The function prints the progress of entity processing with the line number and
-/// elapsed time.
+/// The function parses the command line and runs the subcommand given by the user,
+/// or the import in case no subcommand is given at all.
 ///
-/// Arguments:
+/// Returns:
 ///
-/// * `line_number`: An unsigned 32-bit integer representing the current line number
-/// being processed.
-///
-/// * `start_time`: The `start_time` parameter is an instance of the `Instant`
-/// struct, which represents a point in time. It is used to calculate the duration
-/// of time that has elapsed since a certain point in time, which is typically the
-/// start of a process or operation. In this case, it is used
-fn print_progress(line_number: u32, start_time: Instant) {
-    print!(
-        "\x1B[2K\r{} entities processed in {}.",
-        line_number,
-        format_duration(Duration::new(start_time.elapsed().as_secs(), 0))
-    );
-    let _ = stdout().flush();
+/// a `Result` type with the `Ok` variant containing an empty tuple `()` and the
+/// `Err` variant containing a `String` with an error message if any error occurs
+/// during the execution of the subcommand.
+fn main() -> Result<(), String> {
+    let args: Args = Args::parse();
+
+    // The import is run either as a subcommand or with no subcommand at all, as it used to be :D
+    match args.command {
+        None => import(args.import),
+        Some(Command::Import(args)) => import(*args),
+        // An update is an import over an already created database, where every row of the
+        // entities that changed is deleted before storing them again :D
+        Some(Command::Update(mut args)) => {
+            if args.resume {
+                return Err("Cannot resume an update; run it again instead".to_string());
+            }
+            args.update = true;
+            import(*args)
+        }
+        Some(Command::Query { sql, database }) => run_query(&sql, &database),
+        Some(Command::Serve { database, address }) => serve(&database, &address),
+        Some(Command::Stats {
+            file,
+            database,
+            format,
+            top,
+        }) => match file.or(database) {
+            Some(database) => print_stats(&database, &format, top),
+            None => Args::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "the database is required, either as an argument or through --database",
+                )
+                .exit(),
+        },
+        Some(Command::Path {
+            path,
+            from,
+            database,
+        }) => run_path(&path, from.as_deref(), database.as_deref()),
+        Some(Command::ReparseLine {
+            json,
+            line,
+            entity,
+            dump_format,
+        }) => reparse_line(&json, line, entity.as_deref(), &dump_format),
+        Some(Command::Schema {
+            derive,
+            partition,
+            schema_config,
+            quantity_strings,
+            label_columns,
+            id_encoding,
+        }) => {
+            let mut config = match schema_config {
+                Some(schema_config) => SchemaConfig::from_file(&schema_config)?,
+                None => SchemaConfig::default(),
+            };
+            if quantity_strings {
+                config.add_quantity_strings();
+            }
+            config.add_label_columns(&label_columns)?;
+            print_schema(&config, &derive, &partition, &id_encoding)
+        }
+        Some(Command::GenerateSample {
+            entities,
+            output,
+            seed,
+        }) => {
+            let mut writer: BufWriter<Box<dyn Write>> = BufWriter::new(match output {
+                Some(output) => match File::create(output) {
+                    Ok(file) => Box::new(file),
+                    Err(error) => return Err(format!("Error creating output file. {}", error)),
+                },
+                None => Box::new(stdout()),
+            });
+            match Generator::new(entities, seed).write_dump(&mut writer) {
+                Ok(()) => Ok(()),
+                Err(error) => Err(format!("Error writing sample. {}", error)),
+            }
+        }
+        Some(Command::Export {
+            database,
+            format,
+            output,
+            entities,
+            path,
+        }) => export(
+            &database,
+            &format,
+            output.as_deref(),
+            &entities,
+            path.as_deref(),
+        ),
+        Some(Command::Verify {
+            database,
+            sample,
+            rate,
+        }) => verify(&database, sample, rate),
+        Some(Command::Fetch {
+            entities,
+            database,
+            rate,
+        }) => fetch(&entities, &database, rate),
+    }
 }
 
 /// This function reads a JSON file, creates a new DuckDB database, and inserts the
 /// data from the JSON file into the database in parallel.
 ///
+/// Arguments:
+///
+/// * `args`: The `ImportArgs` given by the user.
+///
 /// Returns:
 ///
 /// a `Result` type with the `Ok` variant containing an empty tuple `()` and the
 /// `Err` variant containing a `String` with an error message if any error occurs
 /// during the execution of the function.
-fn main() -> Result<(), String> {
-    let args: Args = Args::parse();
+fn import(args: ImportArgs) -> Result<(), String> {
+    // Entities parsed on several threads are stored out of the order of the inputs, so the line
+    // committed last is not known, and no checkpoint could be recorded, nor resumed. Clap cannot
+    // tell whether the threads are more than one, so the options are rejected here instead :(
+    if args.threads.get() > 1
+        && !args.preserve_order
+        && (args.checkpoint_every.is_some() || args.resume)
+    {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--checkpoint-every and --resume require --threads 1 or --preserve-order",
+            )
+            .exit();
+    }
+
+    // The vertices retrieved out of the API would have nowhere to store their labels :(
+    if args.no_text && args.enrich.contains(&Enrichment::Labels) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--enrich labels cannot be used with --no-text",
+            )
+            .exit();
+    }
+
+    // Dumps are rather validated before spending hours loading them, in which case no database is
+    // given, nor created, at all :D
+    if args.dry_run {
+        return dry_run(args);
+    }
+
+    // Clap ensures that the database is provided unless the dump is just validated, which has been
+    // handled above :D
+    let database = match args.database.clone() {
+        Some(database) => database,
+        None => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--database is required unless --dry-run is given",
+            )
+            .exit(),
+    };
+
+    // The extra columns declared by the user are read beforehand, so any mistake in their
+    // definition is reported before creating the database :D
+    let mut schema = match &args.schema_config {
+        Some(schema_config) => SchemaConfig::from_file(schema_config)?,
+        None => SchemaConfig::default(),
+    };
+
+    // The original strings of the quantities are computed as any other extra column; that is, out
+    // of the JSON of the statement, as the parser only keeps their numeric value :(
+    if args.quantity_strings {
+        schema.add_quantity_strings();
+    }
+
+    // The same goes for the labels in other languages than English, which are just dropped by the
+    // parser, as we are only interested in the English ones :(
+    schema.add_label_columns(&args.label_columns)?;
+
+    // Structural analyses need no text at all, so the columns of the labels and the descriptions
+    // are not even created :D
+    schema.no_text = args.no_text;
+
+    // The tables the user is not interested in are created anyway, so every query, macro or derived
+    // table over them still works, but nothing is appended to them :D
+    // Presets are just bundles of the rest of the options, so the ones given by the user are still
+    // honored. In case no preset is given, everything is stored, qualifiers included :D
+    let preset = args.preset.as_ref();
+    let tables = match (args.tables.is_empty(), preset) {
+        (true, Some(preset)) => preset.tables(),
+        _ => args.tables.clone(),
+    };
+    let mut skipped = match tables.is_empty() {
+        true => HashSet::new(),
+        false => AppenderHelper::unselected(&tables)?,
+    };
+
+    // The same goes for the string-valued tables, which take much of the database while being of
+    // no use for structural analyses of the graph, so those are left empty as well :D
+    if args.no_strings {
+        for table in Table::iterator().filter(|table| table.is_textual()) {
+            skipped.extend(
+                table
+                    .scopes()
+                    .into_iter()
+                    .map(|scope| table.table_name(scope)),
+            );
+        }
+    }
+
+    // MotherDuck databases, such as md:my_db, are built locally and uploaded once finished, as
+    // loading them over the network would take forever :(
+    let motherduck = motherduck::database_name(&database);
+    let staging_path = motherduck.map(motherduck::staging_path);
+    if database.starts_with("md:") && motherduck.is_none() {
+        return Err(format!("Not valid MotherDuck database {}", database));
+    }
+    if args.upload {
+        return match (motherduck, &staging_path) {
+            (Some(name), Some(staging_path)) if staging_path.exists() => {
+                upload(staging_path, name)
+            }
+            (Some(_), _) => Err(format!("There is no local copy of {} to upload", database)),
+            _ => Err("Only MotherDuck databases can be uploaded".to_string()),
+        };
+    }
 
     // We have to check if the database already exists; that is, if the file given by the user is
     // an already existing file, an error is prompted in screen and execution is halted; otherwise,
     // execution is resumed :D
-    let database_path: &Path = Path::new(&args.database);
-    if database_path.exists() {
-        return Err("Cannot open an already created database".to_string());
+    let database_path: &Path = match &staging_path {
+        Some(staging_path) => staging_path,
+        None => Path::new(&database),
+    };
+    if database_path.exists() && !args.update && !args.resume {
+        return match motherduck {
+            // A failed upload keeps the local copy, which can be uploaded again, or completed :D
+            Some(_) => Err(format!(
+                "The local copy of {} is kept at {}. Pass --upload to upload it, --resume or \
+                 --update to carry on loading it, or remove it to start over",
+                database,
+                database_path.display()
+            )),
+            None => Err("Cannot open an already created database".to_string()),
+        };
+    }
+    if (args.update || args.resume) && !database_path.exists() {
+        return Err("Cannot update a database that does not exist locally".to_string());
+    }
+    if motherduck.is_some() && args.output_format == OutputFormat::Parquet {
+        return Err("Cannot write Parquet files of a MotherDuck database".to_string());
     }
 
-    // We open the JSON file. Notice that some error handling has to be performed as errors may
-    // occur in the process of opening the file provided by the user. More in more, we have to
-    // check if the file is the standard input or a file in the file system. In the first case, we
-    // use the standard input as the reader; otherwise, we use the file provided by the user :D
-    let reader: Box<dyn Read + Send> = if args.json == "-" {
-        Box::new(stdin())
-    } else {
-        Box::new(match File::open(&args.json) {
-            Ok(file) => file,
-            Err(error) => return Err(format!("Error opening JSON file. {}", error)),
-        })
+    // Building the database may take hours, so MotherDuck is checked to accept it beforehand :D
+    if let Some(name) = motherduck {
+        motherduck::check(name)?;
+    }
+
+    // Interrupted loads are resumed right after the last line committed, so the inputs before the
+    // one it comes from are not even opened :D
+    let checkpoint = match args.resume {
+        true => match Connection::open(database_path)
+            .and_then(|connection| Checkpoint::read(&connection))
+        {
+            Ok(Some(checkpoint)) => Some(checkpoint),
+            Ok(None) => return Err("The database records no checkpoint to resume from".to_string()),
+            Err(error) => return Err(format!("Error reading checkpoint. {}", error)),
+        },
+        false => None,
+    };
+
+    // The inputs given by the user are opened beforehand, so any error is reported before creating
+    // the database. Instead, the chunks of a watched directory are opened as soon as they arrive :D
+    let max_read_mbps = args.max_read_mbps;
+    let read = Arc::new(AtomicU64::new(0));
+    let checksums = Checksums::default();
+    let mut several = true;
+    let mut paths = Vec::new();
+    let mut total = None;
+    let inputs: Box<dyn Iterator<Item = (Arc<str>, Dump)>> = match &args.watch {
+        Some(directory) => {
+            if !Path::new(directory).is_dir() {
+                return Err(format!("Cannot watch {}. It is not a directory", directory));
+            }
+            let watcher = DirectoryWatcher::new(
+                Path::new(directory),
+                Duration::from_secs(1),
+                Duration::from_secs(args.watch_timeout),
+            );
+            // In case the directory cannot be read anymore, the watch is over, as no chunk would
+            // arrive. Instead, a chunk that cannot be opened is just skipped :D
+            let paths = watcher.map_while(|path| match path {
+                Ok(path) => Some(path.to_string_lossy().to_string()),
+                Err(error) => {
+                    eprintln!("Error watching directory. {}", error);
+                    None
+                }
+            });
+            let read = Arc::clone(&read);
+            let checksums = checksums.clone();
+            Box::new(paths.filter_map(move |json| {
+                match open_json(&json, Some(&read), max_read_mbps, Some(&checksums)) {
+                    Ok(reader) => Some((Arc::from(json), reader)),
+                    Err(error) => {
+                        eprintln!("Error opening {}. {}", json, error);
+                        None
+                    }
+                }
+            }))
+        }
+        None => {
+            let mut inputs = expand_inputs(&args.json)?;
+            paths = inputs.clone();
+            several = inputs.len() > 1;
+            if let Some(checkpoint) = &checkpoint {
+                match inputs.iter().position(|json| *json == checkpoint.input) {
+                    Some(position) => drop(inputs.drain(..position)),
+                    None => {
+                        return Err(format!(
+                            "Cannot resume from {}, as it is not among the inputs",
+                            checkpoint.input
+                        ))
+                    }
+                }
+            }
+            // The size of the inputs is only known whenever all of them are local files, or files
+            // served over HTTP, so the time left can be estimated. The standard input and the
+            // remote objects are of unknown size
+            total = inputs
+                .iter()
+                .map(|json| input_size(json))
+                .sum::<Option<u64>>();
+            let mut readers = inputs
+                .iter()
+                .map(|json| open_json(json, Some(&read), max_read_mbps, Some(&checksums)))
+                .collect::<Result<Vec<_>, String>>()?;
+            // Whenever a load is resumed, the first input is the one of the checkpoint, which is
+            // skipped up to the line committed last beforehand. Otherwise, the load would go on
+            // from wherever the reader stopped, storing shifted or duplicated entities :(
+            if let (Some(checkpoint), Some(reader)) = (&checkpoint, readers.first_mut()) {
+                if let Err(error) = checkpoint.seek(reader) {
+                    return Err(format!("Error resuming {}. {}", checkpoint.input, error));
+                }
+            }
+            Box::new(inputs.into_iter().map(Arc::from).zip(readers))
+        }
     };
-    let reader = BufReader::new(reader);
 
     // We open a database connection. We are attempting to put the outcome of the JSON processing
     // into a .duckdb file. As a result, the data must be saved to disk. In fact, the result will be
@@ -276,56 +2065,565 @@ fn main() -> Result<(), String> {
         Err(error) => return Err(format!("Error opening connection. {}", error)),
     };
 
+    // The settings are applied before anything is written, so even the creation of the tables runs
+    // within the limits given by the user. The generic ones go last, so those override the rest :D
+    let mut pragmas = Vec::new();
+    if let Some(temp_dir) = &args.temp_dir {
+        pragmas.push(Pragma::new("temp_directory", temp_dir));
+    }
+    if let Some(threads) = args.duckdb_threads {
+        pragmas.push(Pragma::new("threads", threads));
+    }
+    pragmas.extend(args.pragma.iter().cloned());
+    if let Err(error) = pragma::apply(&connection, &pragmas) {
+        return Err(format!("Error applying pragmas. {}", error));
+    }
+
+    // Databases built by a version of the tool with another schema cannot be appended to, as the
+    // rows would not fit their tables :(
+    if args.update || args.resume {
+        metadata::check_schema_version(&connection)?;
+    }
+
+    // The identifiers are encoded as the user has asked, unless the database was built with another
+    // encoding. The `Converter` records it as well, but it is checked beforehand, so nothing is
+    // written to a database built with another one :(
+    args.id_encoding.record(&connection)?;
+
+    // Databases built before the revisions were stored cannot tell which entities have changed,
+    // so those cannot be updated :(
+    if args.update {
+        match connection.query_row(
+            "SELECT count(*) FROM information_schema.columns \
+             WHERE table_name = 'vertex' AND column_name = 'lastrevid'",
+            [],
+            |row| row.get::<_, i64>(0),
+        ) {
+            Ok(0) => return Err("The database records no revisions to update".to_string()),
+            Ok(_) => (),
+            Err(error) => return Err(format!("Error reading schema. {}", error)),
+        }
+    }
+
+    // The vertices of a database built with --no-text have no room for the labels, and the other
+    // way around, so both loads must agree on it :(
+    match (
+        schema::has_column(&connection, "vertex", "id"),
+        schema::has_column(&connection, "vertex", "label"),
+    ) {
+        (Ok(true), Ok(labels)) if labels == args.no_text => {
+            return Err(match labels {
+                true => "The database stores the labels, so it cannot be loaded with --no-text",
+                false => "The database was built with --no-text, so it must be loaded with it too",
+            }
+            .to_string())
+        }
+        (Err(error), _) | (_, Err(error)) => {
+            return Err(format!("Error reading schema. {}", error))
+        }
+        _ => (),
+    }
+
     // -*- JSON to .DUCKDB ALGORITHM Starts here -*-
 
-    // We start computing the initial time at which it starts the execution of the algorithm
-    let start_time = Instant::now();
+    // We start reporting the progress right away, so the throughput is computed since the very
+    // beginning of the load, unless the user asks for nothing to be printed
+    let progress = Progress::new(read, total, args.quiet);
 
-    // We create the tables of the database so the elements can be inserted. For us to do so, we
-    // are creating one table per each primitive type that can be stored in Wikidata. For more
-    // details, refer to value.rs file in this same directory
-    if let Err(error) = create_tables(&mut connection) {
-        return Err(format!("Error creating tables. {}", error));
+    // The throughput is recorded together with the command line of the load, so the records of
+    // different runs, or machines, can be compared once those are gathered :D
+    let load_log = args.load_log.map(|seconds| {
+        LoadLog::new(
+            Duration::from_secs(seconds.get()),
+            std::env::args().collect::<Vec<_>>().join(" "),
+        )
+    });
+
+    // DuckDB is given half of the memory, so it spills to disk instead of growing over the limit,
+    // while the rest is left for the rows buffered by the appenders. Unless its memory limit has
+    // been given by the user through `--pragma`, in which case it is kept as it is :D
+    let watchdog = args.memory_limit.map(MemoryWatchdog::new);
+    let duckdb_limited = pragmas.iter().any(|pragma| pragma.key == "memory_limit");
+    if let Some(watchdog) = watchdog.as_ref().filter(|_| !duckdb_limited) {
+        if let Err(error) = connection.execute_batch(&format!(
+            "SET memory_limit = '{}B';",
+            watchdog.duckdb_limit()
+        )) {
+            return Err(format!("Error setting memory limit. {}", error));
+        }
     }
 
-    if let Err(error) = create_indices(&connection) {
-        return Err(format!("Error creating indices. {}", error));
+    // The lines that could not be stored are written to the sidecar file, if asked for, instead
+    // of just being reported, so those are not lost :D
+    let dead_letters = match &args.errors {
+        Some(path) => match DeadLetters::create(Path::new(path)) {
+            Ok(dead_letters) => Some(dead_letters),
+            Err(error) => return Err(format!("Error creating {}. {}", path, error)),
+        },
+        None => None,
+    };
+    let options = ConverterConfig {
+        dump_format: args.dump_format.clone(),
+        qualifiers: match (args.qualifiers, args.no_qualifiers) {
+            (true, _) => true,
+            (_, true) => false,
+            _ => preset.is_none_or(Preset::qualifiers),
+        },
+        references: match (args.references, args.no_references) {
+            (true, _) => true,
+            (_, true) => false,
+            _ => preset.is_none_or(Preset::references),
+        },
+        sampler: match args.sample {
+            Some(probability) => Some(Sampler::new(probability, args.seed)?),
+            None => None,
+        },
+        schema,
+        truthy_only: args.truthy_only || preset.is_some_and(Preset::truthy_only),
+        keep_deprecated: args.keep_deprecated,
+        external_ids: !args.drop_external_ids && !preset.is_some_and(Preset::drops_external_ids),
+        properties: args.properties.iter().copied().collect(),
+        classes: args
+            .class
+            .iter()
+            .filter_map(|class| args.id_encoding.encode(Id::Qid(*class)).ok())
+            .collect(),
+        languages: args.languages.iter().cloned().map(Lang).collect(),
+        lexemes: args.lexemes,
+        input_format: args.input_format.clone(),
+        max_line_length: args.max_line_mb.get() * 1_000_000,
+        lossy_utf8: args.lossy_utf8,
+        threads: args.threads,
+        preserve_order: args.preserve_order,
+        provenance: args.provenance,
+        skipped: skipped.clone(),
+        batch_size: args.batch_size,
+        checkpoint_every: args.checkpoint_every,
+        abort_after: args.abort_after,
+        no_indices: args.no_indices,
+        indices_after_load: args.indices_after_load,
+        unsafe_load: args.unsafe_load,
+        derived: args.derive.clone(),
+        partitions: args.partition.clone(),
+        id_encoding: args.id_encoding,
+    };
+
+    // How the database is built is recorded together with the entities, so it is known which dump
+    // it holds, and which part of it, without keeping the command line of the load around :D
+    let inputs_given = match &args.watch {
+        Some(directory) => directory.clone(),
+        None => paths.join(", "),
+    };
+    for (key, value) in build_metadata(&args, &options, &tables, &inputs_given) {
+        if let Err(error) = metadata::set(&connection, key, &value) {
+            return Err(format!("Error writing metadata. {}", error));
+        }
+    }
+    if let Err(error) = metadata::set_schema_version(&connection) {
+        return Err(format!("Error writing metadata. {}", error));
     }
 
-    // Transactions can improve performance by reducing the number of disk
-    // writes and network round trips. When you wrap multiple inserts within a transaction,
-    // the database can optimize the write operations by batching them together and
-    // committing them as a single unit. This can reduce the overhead of repeated disk I/O
-    // operations and improve overall insert speed.
-    let mut transaction = match connection.transaction() {
-        Ok(transaction) => transaction,
-        Err(error) => return Err(format!("Error opening transaction. {}", error)),
+    // Entities are only tracked in case they may be found more than once; that is, whenever several
+    // inputs are given or watched, or the user has explicitly asked for a policy. Otherwise, we would
+    // be keeping the revision of every entity in memory for nothing :(
+    let class_sampler = match args.sample_per_class.is_empty() {
+        true => None,
+        false => Some(ClassSampler::new(
+            &args.sample_per_class,
+            args.seed,
+            &args.id_encoding,
+        )),
+    };
+
+    // Whenever a database is updated, the entities stored are known beforehand, so the unchanged
+    // ones are skipped, while the rest are replaced, unless the user asks otherwise :D
+    let policy = match (&args.on_conflict, several) {
+        _ if args.update => Some(args.on_conflict.clone().unwrap_or(OnConflict::Replace)),
+        (Some(policy), _) => Some(policy.clone()),
+        (None, true) => Some(OnConflict::default()),
+        (None, false) => None,
     };
 
-    // We set the drop behavior to commit so that the transaction is committed when it is dropped.
-    transaction.set_drop_behavior(DropBehavior::Commit);
-
-    // Appenders also allow inserting entities in a better fashion. This allows a faster
-    // performance and an easier implementation of the algorithm
-    let mut appender_helper = AppenderHelper::new(&transaction);
-    reader
-        .lines() // we retrieve the iterator over the lines in the
-        .enumerate() // we enumerate the iterator so we can know the line number
-        .for_each(|(line_number, line)| {
-            // try to insert the entity in the database and handle errors appropriately
-            if let Err(error) =
-                insert_entity(&mut appender_helper, line.unwrap(), line_number as u32)
-            {
-                // do not halt execution in case an error happens, just warn the user :D
-                eprintln!("Error inserting entity. {}", error);
+    // The same goes for a resumed load, as the entities stored before the interruption are only
+    // known by the database itself :D
+    let deduplicator = match policy {
+        Some(policy) if args.update || args.resume => {
+            match Deduplicator::from_database(policy, &connection) {
+                Ok(deduplicator) => Some(deduplicator),
+                Err(error) => return Err(format!("Error reading revisions. {}", error)),
             }
+        }
+        Some(policy) => Some(Deduplicator::new(policy)),
+        None => None,
+    };
 
-            if line_number > 0 && line_number % INSERTS_PER_TRANSACTION.to_owned() == 0 {
-                print_progress(line_number as u32, start_time);
+    // The load itself is run by the very same `Converter` other programs embed, so the tool is
+    // just the one plugging in the optional parts of it, as given by the user :D
+    let mut converter = Converter::new(options);
+    converter.checkpoint = checkpoint;
+    converter.deduplicator = deduplicator;
+    converter.class_sampler = class_sampler;
+    converter.dead_letters = dead_letters;
+    converter.progress = Some(progress);
+    converter.load_log = load_log;
+    converter.watchdog = watchdog;
+    converter.budget = args
+        .max_db_size
+        .map(|limit| SizeBudget::new(limit, database_path));
+    converter.snapshotter = args
+        .snapshot_every
+        .map(|interval| Snapshotter::new(interval, args.snapshot_format.clone(), database_path));
+
+    // Once every entity has been stored, the gaps left by a filtered dump are filled, before the
+    // indices are built and the database is finalized :D
+    let backfill = args.backfill_labels;
+    let enrich = args.enrich.contains(&Enrichment::Labels);
+    let enrich_rate = args.enrich_rate;
+    let remove_duplicates = args.dedup_vertices;
+    let after_load = move |connection: &mut Connection, options: &ConverterConfig| {
+        // The checksums are only known for the inputs read from beginning to end, which are all of
+        // them unless the load has been stopped before, such as by the size budget :D
+        if !checksums.is_empty() {
+            if let Err(error) = metadata::set(connection, "dump_sha1", &checksums.to_sha1sums()) {
+                return Err(format!("Error writing metadata. {}", error));
             }
-        });
+        }
+
+        // Units and globes are usually left out whenever just a part of the dump is stored, such
+        // as a sample of it. Hence, their labels are looked for in a second pass over the inputs :D
+        if backfill && !skipped.contains("vertex") {
+            let (stored, missing) = backfill_labels(connection, &paths, options)?;
+            println!("\nBackfilled {} labels of units and globes", stored);
+            if missing > 0 {
+                eprintln!(
+                    "{} units and globes could not be found in the inputs",
+                    missing
+                );
+            }
+        }
+
+        // The entities that are still missing, as those are not in the inputs at all, may be
+        // retrieved from the Wikidata API, which is way slower, but fills the gaps left by a
+        // filtered dump :D
+        if enrich && !skipped.contains("vertex") {
+            let (stored, missing) = Enricher::new(enrich_rate)?.enrich_labels(connection)?;
+            println!("\nRetrieved {} labels from the Wikidata API", stored);
+            if missing > 0 {
+                eprintln!("{} entities could not be found in Wikidata", missing);
+            }
+        }
+
+        // Each entity is given a single vertex as it is stored, but the inputs may hold it several
+        // times whenever no conflict policy is applied. Hence, those can be removed once the load
+        // is over :D
+        if remove_duplicates && !skipped.contains("vertex") {
+            match dedup_vertices(connection) {
+                Ok(removed) => println!("\nRemoved {} duplicated vertices", removed),
+                Err(error) => return Err(format!("Error removing duplicated vertices. {}", error)),
+            }
+        }
+
+        Ok(())
+    };
+    converter.after_load = Some(Box::new(after_load));
+
+    converter.run_inputs(inputs, &mut connection)?;
 
     // -*- JSON to .DUCKDB ALGORITHM Ends here -*-
 
+    // Downstream tools, such as Spark or Polars, may rather read Parquet, so each of the tables is
+    // written to a file of its own once the database is finalized :D
+    if args.output_format == OutputFormat::Parquet {
+        let directory = parquet_path(database_path);
+        let tables = export_parquet(&connection, &directory)?;
+        println!("\nWritten {} tables to {}", tables, directory.display());
+    }
+
+    // The local database has to be closed before uploading it, so every change is on disk :D
+    if let Some(name) = motherduck {
+        if let Err((_, error)) = connection.close() {
+            return Err(format!("Error closing connection. {}", error));
+        }
+        upload(database_path, name)?;
+    }
+
+    Ok(())
+}
+
+/// The function uploads the local copy of a MotherDuck database, which is removed
+/// once uploaded, as it is of no use anymore. Otherwise, it is kept, so the upload
+/// can be retried through `--upload`.
+///
+/// Arguments:
+///
+/// * `database_path`: The path to the local copy of the database.
+/// * `name`: The name of the MotherDuck database.
+///
+/// Returns:
+///
+/// a `Result` with either an empty `Ok(())` or a `String` with the error.
+fn upload(database_path: &Path, name: &str) -> Result<(), String> {
+    if let Err(error) = motherduck::upload(database_path, name) {
+        return Err(format!(
+            "Error uploading database to MotherDuck. The local copy is kept at {}, which can \
+             be uploaded again through --upload. {}",
+            database_path.display(),
+            error
+        ));
+    }
+    if let Err(error) = remove_file(database_path) {
+        eprintln!("Error removing local copy of the database. {}", error);
+    }
+
+    Ok(())
+}
+
+/// The function describes how a database is built, as pairs of keys and values
+/// stored in its metadata: the version of the tool, the time of the import, the
+/// inputs, the languages of the texts stored and the filters applied, which are
+/// written as the options of the command line applying them.
+///
+/// Arguments:
+///
+/// * `args`: A reference to the `ImportArgs` given by the user.
+///
+/// * `options`: A reference to the `ConverterConfig` of the load, which holds the
+///   options implied by the preset, if any.
+///
+/// * `tables`: The tables populated, or an empty slice in case every table is.
+///
+/// * `inputs`: The inputs the entities are loaded from, as given by the user.
+///
+/// Returns:
+///
+/// A vector with the pairs of keys and values describing the build.
+fn build_metadata(
+    args: &ImportArgs,
+    options: &ConverterConfig,
+    tables: &[String],
+    inputs: &str,
+) -> Vec<(&'static str, String)> {
+    let mut languages = vec![format!(
+        "vertex={}",
+        if !options.schema.no_text {
+            LANG.0.as_str()
+        } else {
+            "none"
+        }
+    )];
+    if !args.languages.is_empty() {
+        languages.push(format!("label={}", args.languages.join(",")));
+    }
+    if !args.label_columns.is_empty() {
+        languages.push(format!("label_columns={}", args.label_columns.join(",")));
+    }
+
+    let mut filters = Vec::new();
+    if let Some(preset) = &args.preset {
+        filters.push(format!("--preset {}", preset));
+    }
+    for (applied, flag) in [
+        (options.truthy_only, "--truthy-only"),
+        (options.keep_deprecated, "--keep-deprecated"),
+        (!options.external_ids, "--drop-external-ids"),
+        (!options.qualifiers, "--no-qualifiers"),
+        (!options.references, "--no-references"),
+        (args.no_text, "--no-text"),
+        (args.no_strings, "--no-strings"),
+    ] {
+        if applied {
+            filters.push(flag.to_string());
+        }
+    }
+    if !args.properties.is_empty() {
+        let properties = args.properties.iter().map(|property| Id::Pid(*property));
+        filters.push(format!("--properties {}", join_ids(properties)));
+    }
+    if !args.class.is_empty() {
+        let classes = args.class.iter().map(|class| Id::Qid(*class));
+        filters.push(format!("--class {}", join_ids(classes)));
+    }
+    if let Some(sample) = args.sample {
+        filters.push(format!("--sample {} --seed {}", sample, args.seed));
+    }
+    if !args.sample_per_class.is_empty() {
+        let quotas = args
+            .sample_per_class
+            .iter()
+            .map(|quota| format!("{}={}", Id::Qid(quota.class), quota.limit))
+            .collect::<Vec<_>>();
+        filters.push(format!("--sample-per-class {}", quotas.join(",")));
+    }
+    if !tables.is_empty() {
+        filters.push(format!("--tables {}", tables.join(",")));
+    }
+
+    vec![
+        ("tool_version", env!("CARGO_PKG_VERSION").to_string()),
+        ("imported_at", chrono::Utc::now().to_rfc3339()),
+        ("dump", inputs.to_string()),
+        ("languages", languages.join(" ")),
+        (
+            "filters",
+            match filters.is_empty() {
+                true => "none".to_string(),
+                false => filters.join(" "),
+            },
+        ),
+    ]
+}
+
+/// The function joins the given identifiers with commas, such as `P31,P279`.
+fn join_ids(ids: impl Iterator<Item = Id>) -> String {
+    ids.map(|id| id.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// This function parses the inputs from beginning to end, validating every line
+/// just as a load would, but without opening, nor writing, any database. Hence, a
+/// freshly downloaded dump can be checked before spending hours loading it. The
+/// entities that would be stored are counted per type, while the rest of the lines
+/// are reported, and written to the sidecar file if asked for, as in a load.
+///
+/// Arguments:
+///
+/// * `args`: The `ImportArgs` given by the user, of which only the ones related to
+///   the inputs and their parsing are taken into account.
+///
+/// Returns:
+///
+/// a `Result` type with the `Ok` variant containing an empty tuple `()` and the
+/// `Err` variant containing a `String` with an error message in case the inputs
+/// cannot be read.
+fn dry_run(args: ImportArgs) -> Result<(), String> {
+    let inputs = expand_inputs(&args.json)?;
+    let read = Arc::new(AtomicU64::new(0));
+    let total = inputs
+        .iter()
+        .map(|json| input_size(json))
+        .sum::<Option<u64>>();
+    let readers = inputs
+        .iter()
+        .map(|json| open_json(json, Some(&read), args.max_read_mbps, None))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut dead_letters = match &args.errors {
+        Some(path) => match DeadLetters::create(Path::new(path)) {
+            Ok(dead_letters) => Some(dead_letters),
+            Err(error) => return Err(format!("Error creating {}. {}", path, error)),
+        },
+        None => None,
+    };
+
+    let options = Arc::new(ConverterConfig {
+        dump_format: args.dump_format.clone(),
+        input_format: args.input_format.clone(),
+        max_line_length: args.max_line_mb.get() * 1_000_000,
+        lossy_utf8: args.lossy_utf8,
+        // The identifiers are checked against the ranges of the encoding the database would be
+        // built with, as those out of range could not be stored :D
+        id_encoding: args.id_encoding,
+        ..ConverterConfig::default()
+    });
+
+    let input_format = options.input_format.clone();
+    let max_line_length = options.max_line_length;
+    let lines = inputs
+        .into_iter()
+        .map(Arc::<str>::from)
+        .zip(readers)
+        .flat_map(|(json, reader)| {
+            split_entities(reader, &input_format, max_line_length)
+                .enumerate()
+                .map(move |(line_number, line)| (json.clone(), line_number, line))
+        });
+
+    // Lines are parsed on as many threads as a load would, so validating a dump takes as long as
+    // parsing it does, which is most of the time of the load anyway :D
+    let keep_raw = dead_letters.is_some();
+    let parse = {
+        let options = Arc::clone(&options);
+        move |(json, line_number, line): (Arc<str>, usize, std::io::Result<Vec<u8>>)| {
+            let raw = match keep_raw {
+                true => line.as_ref().ok().cloned(),
+                false => None,
+            };
+            let parsed = parse_line(&json, line_number, line, &options);
+            (json, line_number, raw, parsed)
+        }
+    };
+    let lines: Box<dyn Iterator<Item = _>> = match args.threads.get() {
+        1 => Box::new(lines.map(parse).map(Ok)),
+        _ => Box::new(Pipeline::new(
+            lines,
+            args.threads,
+            args.preserve_order,
+            parse,
+        )),
+    };
+
+    let mut progress = Progress::new(read, total, args.quiet);
+    let mut valid: HashMap<&str, u64> = HashMap::new();
+    let mut report = Report::default();
+    let mut processed = 0;
+    for mapped in lines {
+        let (json, line_number, raw, parsed) = mapped?;
+        // Entities are only valid in case those could be stored as well; that is, whenever their
+        // identifier fits the range of its namespace :D
+        let outcome = parsed?.and_then(|parsed| match parsed {
+            Some(parsed) => match entity_id(&parsed.entity, &options.id_encoding) {
+                Ok(_) => Ok(Some(entity_type(&parsed.entity))),
+                Err(error) => Err(LineError::new(
+                    Failure::IdOutOfRange,
+                    format!("Error storing entity at line {}: {}", line_number, error),
+                )),
+            },
+            None => Ok(None),
+        });
+
+        match outcome {
+            Ok(Some(entity_type)) => *valid.entry(entity_type).or_insert(0) += 1,
+            Ok(None) => (),
+            Err(error) => {
+                eprintln!("Invalid entity in {}. {}", json, error);
+                if let Some(dead_letters) = dead_letters.as_mut() {
+                    if let Err(error) =
+                        dead_letters.write(&json, line_number, raw.as_deref(), &error)
+                    {
+                        return Err(format!("Error writing failed line. {}", error));
+                    }
+                }
+                report.record(error.failure);
+            }
+        }
+
+        processed += 1;
+        progress.tick(processed, &valid);
+    }
+    progress.finish(processed, &valid);
+
+    let count = |entity_type: &str| valid.get(entity_type).copied().unwrap_or_default();
+    println!(
+        "{} valid entities: {} items, {} properties and {} lexemes",
+        valid.values().sum::<u64>(),
+        count("item"),
+        count("property"),
+        count("lexeme")
+    );
+    if report.total() > 0 {
+        eprintln!("{}", report);
+    }
+    if let Some(dead_letters) = dead_letters.as_mut() {
+        if let Err(error) = dead_letters.flush() {
+            return Err(format!("Error writing failed lines. {}", error));
+        }
+        if dead_letters.written() > 0 {
+            eprintln!(
+                "{} failed lines written to {}",
+                dead_letters.written(),
+                args.errors.as_deref().unwrap_or_default()
+            );
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,172 @@
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::id::{Id, IdEncoding};
+use crate::LANG;
+
+/// The endpoint of the Wikidata API.
+const API: &str = "https://www.wikidata.org/w/api.php";
+
+/// The User-Agent the requests are sent with, as the Wikimedia policy requires
+/// every client to identify itself.
+pub(crate) const USER_AGENT: &str = concat!(
+    "wd2duckdb/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/angelip2303/wd2duckdb)"
+);
+
+/// The maximum number of entities `wbgetentities` returns per request.
+pub const BATCH_SIZE: usize = 50;
+
+/// The number of times a request is retried whenever the API asks us to slow down.
+const MAX_RETRIES: u32 = 5;
+
+/// The seconds waited before retrying a request, in case the API does not tell.
+const DEFAULT_RETRY_AFTER: u64 = 5;
+
+/// The function returns the identifier of an item or a property, as it is known
+/// by the API, such as `Q42`, or `None` for the rest of the entities.
+///
+/// Arguments:
+///
+/// * `encoding`: The `IdEncoding` of the database the identifier comes from.
+/// * `id`: The numeric identifier, as stored in the database.
+pub fn api_id(encoding: &IdEncoding, id: u64) -> Option<String> {
+    match encoding.decode(id).ok()? {
+        id @ (Id::Qid(_) | Id::Pid(_)) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// The `WikidataApi` struct is the client of the Wikidata API. The entities are
+/// requested in batches through `wbgetentities`, one request at a time and no
+/// faster than the given rate, and the requests are retried whenever the API asks
+/// us to slow down, as its etiquette requires.
+///
+/// Properties:
+///
+/// * `client`: The HTTP client the requests are sent with.
+/// * `runtime`: The runtime the requests are run on.
+/// * `interval`: The minimum time between two requests.
+/// * `last_request`: The instant the last request was sent, if any.
+pub struct WikidataApi {
+    client: Client,
+    runtime: Runtime,
+    interval: Duration,
+    last_request: Option<Instant>,
+}
+
+impl WikidataApi {
+    /// The function creates a client of the API sending, at most, the given number of
+    /// requests per second.
+    ///
+    /// Arguments:
+    ///
+    /// * `requests_per_second`: The maximum rate of the requests.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `WikidataApi`, or a `String` with an error message in case
+    /// the rate is not positive or the HTTP client cannot be built.
+    pub fn new(requests_per_second: f64) -> Result<Self, String> {
+        if requests_per_second.is_nan() || requests_per_second <= 0.0 {
+            return Err(format!("Not valid rate: {}", requests_per_second));
+        }
+
+        let client = match Client::builder().user_agent(USER_AGENT).build() {
+            Ok(client) => client,
+            Err(error) => return Err(format!("Error building HTTP client. {}", error)),
+        };
+        let runtime = match Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime,
+            Err(error) => return Err(format!("Error starting runtime. {}", error)),
+        };
+
+        Ok(Self {
+            client,
+            runtime,
+            interval: Duration::from_secs_f64(1.0 / requests_per_second),
+            last_request: None,
+        })
+    }
+
+    /// The function requests a batch of entities from the API, waiting as long as
+    /// needed to respect the rate, and retrying whenever the API is lagged or the
+    /// rate limit has been hit.
+    ///
+    /// Arguments:
+    ///
+    /// * `ids`: The identifiers of the entities, such as `Q42`, up to `BATCH_SIZE`.
+    /// * `props`: The properties of the entities requested, such as `labels|claims`.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `entities` object of the response, or a `String` with an
+    /// error message in case it could not be retrieved.
+    pub fn get_entities(&mut self, ids: &[&str], props: &str) -> Result<Value, String> {
+        let ids = ids.join("|");
+
+        for _ in 0..=MAX_RETRIES {
+            if let Some(elapsed) = self.last_request.map(|instant| instant.elapsed()) {
+                if elapsed < self.interval {
+                    sleep(self.interval - elapsed);
+                }
+            }
+            self.last_request = Some(Instant::now());
+
+            // The maxlag parameter asks the API to refuse the request whenever its replicas are
+            // lagged, which is how bots are expected to back off from an overloaded server :D
+            let request = self.client.get(API).query(&[
+                ("action", "wbgetentities"),
+                ("format", "json"),
+                ("ids", ids.as_str()),
+                ("props", props),
+                ("languages", LANG.0.as_str()),
+                ("maxlag", "5"),
+            ]);
+            let (status, retry_after, body) = match self.runtime.block_on(async {
+                let response = request.send().await?;
+                let status = response.status();
+                let retry_after = response
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok());
+                let body = response.json::<Value>().await.ok();
+                Ok::<_, reqwest::Error>((status, retry_after, body))
+            }) {
+                Ok(response) => response,
+                Err(error) => return Err(format!("Error requesting {}. {}", ids, error)),
+            };
+
+            let lagged = body
+                .as_ref()
+                .and_then(|body| body.get("error"))
+                .and_then(|error| error.get("code"))
+                .is_some_and(|code| code == "maxlag");
+            if lagged
+                || status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::SERVICE_UNAVAILABLE
+            {
+                sleep(Duration::from_secs(
+                    retry_after.unwrap_or(DEFAULT_RETRY_AFTER),
+                ));
+                continue;
+            }
+
+            return match body.and_then(|mut body| body.get_mut("entities").map(Value::take)) {
+                Some(entities) if status.is_success() => Ok(entities),
+                _ => Err(format!(
+                    "Unexpected response requesting {}: {}",
+                    ids, status
+                )),
+            };
+        }
+
+        Err(format!("The API kept refusing the request of {}", ids))
+    }
+}
@@ -0,0 +1,488 @@
+use std::io::{copy, sink, BufRead, BufReader, Error, ErrorKind, Read, Result, Take};
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+/// The `Compression` enum defines the formats a dump, or a chunk of it, may be
+/// compressed with: the official dumps are distributed as `.bz2` and `.gz` files,
+/// while `.zst` is common among the chunks prepared by third parties.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    Bzip2,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The function returns the compression of a file out of its extension, together
+    /// with the path once the extension has been removed, so the archive it may hold
+    /// is told apart as well; for example, `chunks.tar.gz`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the file.
+    ///
+    /// Returns:
+    ///
+    /// A tuple with the path without the extension of the compression, and the
+    /// `Compression`, or `None` in case the extension is not a known one.
+    pub fn from_extension(path: &str) -> (&str, Option<Compression>) {
+        for (extension, compression) in [
+            (".bz2", Compression::Bzip2),
+            (".gz", Compression::Gzip),
+            (".zst", Compression::Zstd),
+        ] {
+            if let Some(path) = path.strip_suffix(extension) {
+                return (path, Some(compression));
+            }
+        }
+        (path, None)
+    }
+
+    /// The function returns the compression of some contents out of their first
+    /// bytes, which allows decompressing files with no extension at all, or the
+    /// standard input, transparently.
+    ///
+    /// Arguments:
+    ///
+    /// * `magic`: The first bytes of the contents.
+    ///
+    /// Returns:
+    ///
+    /// An `Option` with the `Compression`, or `None` in case the contents do not
+    /// start as any of those.
+    pub fn from_magic(magic: &[u8]) -> Option<Compression> {
+        if magic.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// The function unpacks the archive a dump is shipped in, so the rest of the
+/// pipeline reads the plain JSON. The compression is told by the extension of the
+/// file or, if it has none, by the first bytes of its contents. Compressed files
+/// made of several concatenated streams, as the multistream bz2 dumps, are
+/// decompressed stream after stream, while tarred dumps are read member after
+/// member. Notice that both can be combined; for example, `chunks.tar.gz` or a tar
+/// made of `.json.bz2` chunks.
+///
+/// Arguments:
+///
+/// * `path`: The path to the dump, whose extension determines how it is unpacked.
+/// * `reader`: The reader over the contents of the file.
+///
+/// Returns:
+///
+/// A reader over the unpacked contents of the dump.
+pub fn unpack(path: &str, reader: Box<dyn Read + Send>) -> Box<dyn Read + Send> {
+    let (path, compression) = Compression::from_extension(path);
+
+    // The first bytes are only peeked, so those are read again by the decoder, or by the parser
+    // whenever the contents turn out to be plain. A read error is left for the latter :D
+    let mut reader = BufReader::new(reader);
+    let compression =
+        compression.or_else(|| Compression::from_magic(reader.fill_buf().unwrap_or_default()));
+
+    let reader: Box<dyn Read + Send> = match compression {
+        Some(Compression::Bzip2) => Box::new(MultiBzDecoder::new(reader)),
+        Some(Compression::Gzip) => Box::new(MultiGzDecoder::new(reader)),
+        Some(Compression::Zstd) => match ZstdDecoder::with_buffer(reader) {
+            Ok(decoder) => Box::new(decoder),
+            Err(error) => Box::new(Failed(Some(error))),
+        },
+        None => Box::new(reader),
+    };
+
+    if path.ends_with(".tar") {
+        Box::new(TarMembers::new(reader))
+    } else {
+        reader
+    }
+}
+
+/// The `Failed` struct is a reader whose first read returns the error a decoder
+/// could not be created with, so it is reported as any other error reading the
+/// input, and which is empty afterwards.
+struct Failed(Option<std::io::Error>);
+
+impl Read for Failed {
+    fn read(&mut self, _: &mut [u8]) -> Result<usize> {
+        match self.0.take() {
+            Some(error) => Err(error),
+            None => Ok(0),
+        }
+    }
+}
+
+/// The `Member` enum represents the contents of the member of a tar archive being
+/// read, which may be compressed on its own.
+enum Member<R: Read> {
+    Plain(Take<R>),
+    Bzip2(MultiBzDecoder<Take<R>>),
+    Gzip(Box<MultiGzDecoder<Take<R>>>),
+    Zstd(Box<ZstdDecoder<'static, BufReader<Take<R>>>>),
+}
+
+impl<R: Read> Member<R> {
+    /// The function returns the member holding the given contents, which are
+    /// decompressed according to the extension of its name.
+    fn new(name: &str, take: Take<R>) -> Result<Self> {
+        Ok(match Compression::from_extension(name).1 {
+            Some(Compression::Bzip2) => Member::Bzip2(MultiBzDecoder::new(take)),
+            Some(Compression::Gzip) => Member::Gzip(Box::new(MultiGzDecoder::new(take))),
+            Some(Compression::Zstd) => Member::Zstd(Box::new(ZstdDecoder::new(take)?)),
+            None => Member::Plain(take),
+        })
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match self {
+            Member::Plain(take) => take.read(buf),
+            Member::Bzip2(decoder) => decoder.read(buf),
+            Member::Gzip(decoder) => decoder.read(buf),
+            Member::Zstd(decoder) => decoder.read(buf),
+        }
+    }
+
+    /// The function skips whatever is left of the member, so the reader is placed
+    /// at the end of its contents, and returns the reader back. Notice that the
+    /// bytes buffered by the decoders belong to the member, so those can be
+    /// dropped.
+    fn finish(self) -> Result<R> {
+        let mut take = match self {
+            Member::Plain(take) => take,
+            Member::Bzip2(decoder) => decoder.into_inner(),
+            Member::Gzip(decoder) => decoder.into_inner(),
+            Member::Zstd(decoder) => decoder.finish().into_inner(),
+        };
+        copy(&mut take, &mut sink())?;
+        if take.limit() > 0 {
+            return Err(truncated());
+        }
+        Ok(take.into_inner())
+    }
+}
+
+/// The `State` enum represents where the reader of a tar archive is placed: either
+/// before the header of a member, or within its contents, which are followed by
+/// some padding up to the next block of the archive.
+enum State<R: Read> {
+    Header(R),
+    Member(Member<R>, u64),
+}
+
+/// The `TarMembers` struct reads the regular files stored in a tar archive one after
+/// the other, as if those were concatenated; hence, a dump split into several
+/// chunks can be processed without extracting it first. Each member is followed
+/// by a line break, so the last line of a member is never joined with the first
+/// one of the next.
+///
+/// Properties:
+///
+/// * `state`: The `State` of the reader, which is `None` once the end of the
+/// archive has been reached.
+/// * `long_name`: The name of the next member, in case it is too long to fit into
+/// its header, as GNU tar and the PAX format store such names in a member of their
+/// own.
+pub struct TarMembers<R: Read> {
+    state: Option<State<R>>,
+    long_name: Option<String>,
+}
+
+impl<R: Read> TarMembers<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            state: Some(State::Header(reader)),
+            long_name: None,
+        }
+    }
+}
+
+impl<R: Read> Read for TarMembers<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            match self.state.take() {
+                None => return Ok(0),
+                Some(State::Header(mut reader)) => {
+                    let mut header = [0u8; 512];
+                    let filled = fill(&mut reader, &mut header)?;
+                    if filled == 0 || header.iter().all(|b| *b == 0) {
+                        return Ok(0); // the archive is over, so is the reading :D
+                    } else if filled < header.len() {
+                        return Err(truncated());
+                    }
+
+                    let size = member_size(&header)?;
+                    let padding = (512 - size % 512) % 512;
+                    let mut take = reader.take(size);
+
+                    match header[156] {
+                        b'0' | b'\0' => {
+                            let name = self
+                                .long_name
+                                .take()
+                                .unwrap_or_else(|| member_name(&header));
+                            let member = Member::new(&name, take)?;
+                            self.state = Some(State::Member(member, padding));
+                        }
+                        b'L' | b'x' => {
+                            let mut contents = Vec::new();
+                            take.read_to_end(&mut contents)?;
+                            if (contents.len() as u64) < size {
+                                return Err(truncated());
+                            }
+                            let name = match header[156] {
+                                b'L' => Some(
+                                    String::from_utf8_lossy(&contents)
+                                        .trim_end_matches('\0')
+                                        .to_string(),
+                                ),
+                                _ => pax_path(&contents)?,
+                            };
+                            if name.is_some() {
+                                self.long_name = name;
+                            }
+                            let mut reader = take.into_inner();
+                            skip(&mut reader, padding)?;
+                            self.state = Some(State::Header(reader));
+                        }
+                        _ => {
+                            // Directories, links and global headers hold no entity at all :(
+                            let mut reader = Member::Plain(take).finish()?;
+                            skip(&mut reader, padding)?;
+                            self.state = Some(State::Header(reader));
+                        }
+                    }
+                }
+                Some(State::Member(mut member, padding)) => {
+                    let read = member.read(buf)?;
+                    if read > 0 {
+                        self.state = Some(State::Member(member, padding));
+                        return Ok(read);
+                    }
+
+                    let mut reader = member.finish()?;
+                    skip(&mut reader, padding)?;
+                    self.state = Some(State::Header(reader));
+
+                    buf[0] = b'\n';
+                    return Ok(1);
+                }
+            }
+        }
+    }
+}
+
+/// The error returned whenever the archive ends in the middle of a header, or of
+/// the contents of a member, which would otherwise be taken for its end.
+fn truncated() -> Error {
+    Error::new(ErrorKind::InvalidData, "The tar archive is truncated")
+}
+
+/// The function reads as many bytes as fit into the buffer, unless the reader ends
+/// before, and returns how many were read. Unlike `read_exact`, this tells an
+/// archive ending right after a member apart from one ending within a header.
+fn fill<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(filled)
+}
+
+/// The function skips the given amount of bytes of the reader, failing in case it
+/// ends before.
+fn skip<R: Read>(reader: &mut R, bytes: u64) -> Result<()> {
+    if copy(&mut reader.take(bytes), &mut sink())? < bytes {
+        return Err(truncated());
+    }
+    Ok(())
+}
+
+/// The function returns the path stored in the records of a PAX extended header,
+/// each of which is written as `<length> <key>=<value>\n`, the length counting
+/// the whole record.
+fn pax_path(mut records: &[u8]) -> Result<Option<String>> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "Invalid PAX extended header");
+    let mut path = None;
+
+    while !records.is_empty() && records[0] != 0 {
+        let space = records.iter().position(|b| *b == b' ').ok_or_else(invalid)?;
+        let length = std::str::from_utf8(&records[..space])
+            .ok()
+            .and_then(|length| length.parse::<usize>().ok())
+            .filter(|length| *length > space + 1 && *length <= records.len())
+            .ok_or_else(invalid)?;
+        let record = &records[space + 1..length];
+        let record = record.strip_suffix(b"\n").ok_or_else(invalid)?;
+        if let Some(value) = record.strip_prefix(b"path=") {
+            path = Some(String::from_utf8_lossy(value).to_string());
+        }
+        records = &records[length..];
+    }
+
+    Ok(path)
+}
+
+/// The function returns the name of a member out of its header, including the
+/// prefix of the name in case of the ustar format.
+fn member_name(header: &[u8; 512]) -> String {
+    let field = |bytes: &[u8]| {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).to_string()
+    };
+
+    let name = field(&header[0..100]);
+    match &header[257..262] {
+        b"ustar" if header[345] != 0 => format!("{}/{}", field(&header[345..500]), name),
+        _ => name,
+    }
+}
+
+/// The function returns the size of a member out of its header. The size is stored
+/// as an octal number, or as a big-endian binary number for members larger than
+/// 8 GB, in which case the highest bit of the field is set. A size which cannot be
+/// parsed is an error, as the contents could not be told apart from the next header.
+fn member_size(header: &[u8; 512]) -> Result<u64> {
+    let field = &header[124..136];
+    if field[0] & 0x80 != 0 {
+        Ok(field[1..]
+            .iter()
+            .fold(0, |size, byte| (size << 8) | *byte as u64))
+    } else {
+        let octal = String::from_utf8_lossy(field);
+        u64::from_str_radix(octal.trim_matches(|c: char| c == '\0' || c == ' '), 8).map_err(
+            |error| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid size of a tar member. {}", error),
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+
+    use super::*;
+
+    /// The function appends a member to a tar archive, with a ustar header holding
+    /// its name, size, type and checksum.
+    fn append(archive: &mut Vec<u8>, name: &str, kind: u8, contents: &[u8]) {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[124..136].copy_from_slice(format!("{:011o}\0", contents.len()).as_bytes());
+        header[156] = kind;
+        header[257..265].copy_from_slice(b"ustar\x0000");
+        header[148..156].copy_from_slice(b"        ");
+        let checksum = header.iter().map(|byte| *byte as u32).sum::<u32>();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(contents);
+        archive.resize(archive.len().div_ceil(512) * 512, 0);
+    }
+
+    /// The function builds a tar archive out of the given members, whose names are
+    /// written as GNU tar does, so the long ones are stored in members of their own.
+    fn archive(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for (name, contents) in members {
+            if name.len() > 100 {
+                append(&mut archive, "././@LongLink", b'L', format!("{}\0", name).as_bytes());
+            }
+            append(&mut archive, &name[..name.len().min(100)], b'0', contents);
+        }
+        archive.extend_from_slice(&[0; 1024]);
+        archive
+    }
+
+    fn gzip(contents: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(contents).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn unpacked(path: &str, contents: Vec<u8>) -> Result<String> {
+        let mut unpacked = String::new();
+        unpack(path, Box::new(std::io::Cursor::new(contents))).read_to_string(&mut unpacked)?;
+        Ok(unpacked)
+    }
+
+    #[test]
+    fn reads_the_members_of_a_tar() {
+        let tar = archive(&[("a.json", b"{\"id\":\"Q1\"}"), ("b.json", b"{\"id\":\"Q2\"}\n")]);
+        assert_eq!(
+            unpacked("chunks.tar", tar).unwrap(),
+            "{\"id\":\"Q1\"}\n{\"id\":\"Q2\"}\n\n"
+        );
+    }
+
+    #[test]
+    fn reads_the_members_of_a_gzipped_tar() {
+        let tar = gzip(&archive(&[("a.json", b"{\"id\":\"Q1\"}")]));
+        assert_eq!(unpacked("chunks.tar.gz", tar).unwrap(), "{\"id\":\"Q1\"}\n");
+    }
+
+    #[test]
+    fn decompresses_the_members_by_their_long_names() {
+        let name = format!("{}/chunk.json.gz", "directory".repeat(20));
+        let tar = archive(&[(&name, &gzip(b"{\"id\":\"Q1\"}"))]);
+        assert_eq!(unpacked("chunks.tar", tar).unwrap(), "{\"id\":\"Q1\"}\n");
+    }
+
+    #[test]
+    fn decompresses_the_members_by_their_pax_paths() {
+        // The length of the record counts its own digits: 3 of them, plus a space, `path=`
+        // and the line break :D
+        let name = format!("{}/chunk.json.gz", "directory".repeat(20));
+        let record = format!("{} path={}\n", name.len() + 10, name);
+        let mut tar = Vec::new();
+        append(&mut tar, "PaxHeader", b'x', record.as_bytes());
+        append(&mut tar, "chunk", b'0', &gzip(b"{\"id\":\"Q1\"}"));
+        tar.extend_from_slice(&[0; 1024]);
+
+        assert_eq!(unpacked("chunks.tar", tar).unwrap(), "{\"id\":\"Q1\"}\n");
+    }
+
+    #[test]
+    fn fails_on_a_truncated_tar() {
+        let tar = archive(&[("a.json", &[b'1'; 1000])]);
+
+        // Both within the contents of the member and within its header :D
+        for length in [700, 100] {
+            let error = unpacked("chunks.tar", tar[..length].to_vec()).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", length);
+        }
+    }
+
+    #[test]
+    fn fails_on_an_invalid_size() {
+        let mut tar = archive(&[("a.json", b"{}")]);
+        tar[124..136].copy_from_slice(b"not a size\0\0");
+
+        let error = unpacked("chunks.tar", tar).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+use duckdb::{Connection, Error};
+use wikidata::Qid;
+
+use crate::id::{Id, IdEncoding};
+
+/// This function returns the query retrieving the units and globes referenced by
+/// the quantities and coordinates, but missing from the `vertex` table. Those are
+/// usually missing because they were filtered out, for example, by `--sample`, so
+/// their names do not resolve in queries.
+fn missing_ids_sql() -> String {
+    "SELECT DISTINCT id FROM (\
+        SELECT unit_id AS id FROM quantity \
+        UNION ALL SELECT unit_id FROM qualifier_quantity \
+        UNION ALL SELECT unit_id FROM reference_quantity \
+        UNION ALL SELECT globe_id FROM coordinates \
+        UNION ALL SELECT globe_id FROM qualifier_coordinates \
+        UNION ALL SELECT globe_id FROM reference_coordinates\
+     ) WHERE id IS NOT NULL AND id NOT IN (SELECT id FROM vertex)"
+        .to_string()
+}
+
+/// The function retrieves the identifiers of the units and globes that are
+/// referenced, but whose vertex has not been stored.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database, once the load
+/// has finished.
+///
+/// Returns:
+///
+/// a `Result` with the set of the numeric identifiers missing, or an `Error` if the
+/// query could not be run.
+pub fn missing_ids(connection: &Connection) -> Result<HashSet<u64>, Error> {
+    let mut statement = connection.prepare(&missing_ids_sql())?;
+    let ids = statement
+        .query_map([], |row| row.get::<_, u64>(0))?
+        .collect::<Result<HashSet<_>, _>>()?;
+    Ok(ids)
+}
+
+/// The function returns the numeric identifier of the item a line of the dump
+/// holds, without parsing the whole line, so the dump can be scanned fast for a
+/// few entities. As the identifier of the entity is placed before its claims,
+/// which may reference other items, only the beginning of the line is considered.
+///
+/// Arguments:
+///
+/// * `line`: A string slice with the line of the dump.
+///
+/// * `encoding`: The `IdEncoding` of the database the item is looked up in.
+///
+/// Returns:
+///
+/// The numeric identifier of the item, or `None` in case the line does not hold
+/// an item, such as the lines holding properties or lexemes.
+pub fn header_item_id(line: &str, encoding: &IdEncoding) -> Option<u64> {
+    let header = match line.find("\"claims\"") {
+        Some(position) => &line[..position],
+        None => line,
+    };
+    let start = header.find("\"id\":\"Q")? + "\"id\":\"Q".len();
+    let digits = header[start..].split('"').next()?;
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|id| encoding.encode(Id::Qid(Qid(id))).ok())
+}
@@ -0,0 +1,125 @@
+use std::fs::metadata;
+use std::path::{Path, PathBuf};
+
+/// The number of entities processed between two measures of the size of the
+/// database. The rows only reach the file once those are committed and
+/// checkpointed, so each measure ends the batch, which is not worth doing often.
+const CHECK_EVERY: usize = 100_000;
+
+/// The `SizeBudget` struct keeps the database file under a size. Every now and
+/// then, the batch is ended, so it is committed and checkpointed, and the size of
+/// the file is measured. The load is stopped as soon as the next batch, which is
+/// expected to grow the file as much as the last one did, would not fit in the
+/// budget anymore.
+///
+/// Properties:
+///
+/// * `limit`: The maximum size of the database, in bytes.
+/// * `paths`: The database file and its write-ahead log.
+/// * `last_size`: The size measured at the end of the previous batch.
+/// * `in_batch`: The number of entities processed in the current batch.
+pub struct SizeBudget {
+    limit: u64,
+    paths: [PathBuf; 2],
+    last_size: u64,
+    in_batch: usize,
+}
+
+impl SizeBudget {
+    /// The function creates a budget keeping the given database under a size.
+    ///
+    /// Arguments:
+    ///
+    /// * `limit`: The maximum size of the database, in bytes.
+    /// * `database`: A reference to the path of the database.
+    ///
+    /// Returns:
+    ///
+    /// The `SizeBudget`.
+    pub fn new(limit: u64, database: &Path) -> Self {
+        let mut wal = database.as_os_str().to_owned();
+        wal.push(".wal");
+        Self {
+            limit,
+            paths: [database.to_path_buf(), PathBuf::from(wal)],
+            last_size: 0,
+            in_batch: 0,
+        }
+    }
+
+    /// The function returns the size of the database on disk, in bytes; that is, the
+    /// size of the file and its write-ahead log, if any.
+    pub fn size(&self) -> u64 {
+        self.paths
+            .iter()
+            .filter_map(|path| metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
+    /// The function is called once per entity processed, and returns whether the
+    /// batch has to be ended, so it is committed and checkpointed before the size of
+    /// the database is measured.
+    pub fn should_commit(&mut self) -> bool {
+        self.in_batch += 1;
+        if self.in_batch < CHECK_EVERY {
+            return false;
+        }
+        self.in_batch = 0;
+        true
+    }
+
+    /// The function measures the database, once the batch has been checkpointed, and
+    /// returns whether the load has to be stopped.
+    ///
+    /// Returns:
+    ///
+    /// A `bool` which is `true` whenever the database has reached the limit, or the
+    /// next batch would not fit in what is left of it.
+    pub fn is_exhausted(&mut self) -> bool {
+        let size = self.size();
+        let growth = size.saturating_sub(self.last_size);
+        self.last_size = size;
+        size.saturating_add(growth) > self.limit
+    }
+}
+
+/// The function parses a size out of a number followed by its unit, such as `50GB`
+/// or `512MiB`. Both decimal (`KB`, `MB`, `GB`, `TB`) and binary (`KiB`, `MiB`,
+/// `GiB`, `TiB`) units are understood, and numbers with no unit are taken as bytes.
+///
+/// Arguments:
+///
+/// * `size`: A string slice with the size.
+///
+/// Returns:
+///
+/// a `Result` with the number of bytes, or a `String` with an error message in case
+/// it is not valid, or it is zero.
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        "KIB" => 1 << 10,
+        "MIB" => 1 << 20,
+        "GIB" => 1 << 30,
+        "TIB" => 1 << 40,
+        _ => return Err(format!("Not valid size: {}", size)),
+    };
+
+    match number.parse::<f64>() {
+        Ok(number) if number > 0.0 && (number * multiplier as f64) >= 1.0 => {
+            Ok((number * multiplier as f64) as u64)
+        }
+        _ => Err(format!("Not valid size: {}", size)),
+    }
+}
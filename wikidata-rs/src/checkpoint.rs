@@ -0,0 +1,114 @@
+use std::io::{copy, sink, Error as IoError, ErrorKind, Read};
+
+use duckdb::{Connection, Error};
+
+use crate::metadata;
+
+/// The key the checkpoint is stored under in the metadata of the database.
+const CHECKPOINT_KEY: &str = "checkpoint";
+
+/// The `Checkpoint` struct records the last line of the dump whose entity has
+/// been committed to the database, so an interrupted load can be resumed from it
+/// instead of starting over. It is stored in the `wd2duckdb_meta` table within
+/// the very same transaction as the entities, so both of them are always
+/// consistent.
+///
+/// Properties:
+///
+/// * `input`: The input file the line comes from.
+/// * `line`: The number of the line within the input.
+/// * `offset`: The byte offset the line starts at, within the decompressed input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub input: String,
+    pub line: usize,
+    pub offset: u64,
+}
+
+impl Checkpoint {
+    /// The function retrieves the checkpoint stored in the database, if any.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Checkpoint`, or `None` in case the database records no
+    /// checkpoint at all, as it happens once the load has finished.
+    pub fn read(connection: &Connection) -> Result<Option<Self>, Error> {
+        // The input goes last, as it may contain colons itself, such as s3://bucket/dump.json :D
+        let checkpoint = metadata::get(connection, CHECKPOINT_KEY)?.and_then(|value| {
+            let mut fields = value.splitn(3, ':');
+            Some(Self {
+                offset: fields.next()?.parse().ok()?,
+                line: fields.next()?.parse().ok()?,
+                input: fields.next()?.to_string(),
+            })
+        });
+        Ok(checkpoint)
+    }
+
+    /// The function stores the checkpoint in the database, replacing the previous
+    /// one.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database, which should
+    /// be the transaction the entities up to the checkpoint are committed with.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// checkpoint is stored, or an `Error` object otherwise.
+    pub fn write(&self, connection: &Connection) -> Result<(), Error> {
+        metadata::set(
+            connection,
+            CHECKPOINT_KEY,
+            &format!("{}:{}:{}", self.offset, self.line, self.input),
+        )
+    }
+
+    /// The function removes the checkpoint from the database, once the load has
+    /// finished, so there is nothing left to resume.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// checkpoint is removed, or an `Error` object otherwise.
+    pub fn clear(connection: &Connection) -> Result<(), Error> {
+        metadata::remove(connection, CHECKPOINT_KEY)
+    }
+
+    /// The function skips the part of the input that precedes the line of the
+    /// checkpoint. Compressed inputs cannot be seeked, so the bytes are decompressed
+    /// and discarded; yet, those are neither split nor parsed, which is what takes
+    /// most of the time of the load.
+    ///
+    /// Arguments:
+    ///
+    /// * `reader`: A mutable reference to the reader over the decompressed input.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// reader is placed at the beginning of the line, or an `Error` otherwise; for
+    /// example, because the input is shorter than the offset of the checkpoint.
+    pub fn seek<R: Read>(&self, reader: &mut R) -> Result<(), IoError> {
+        let skipped = copy(&mut reader.take(self.offset), &mut sink())?;
+        if skipped < self.offset {
+            return Err(IoError::new(
+                ErrorKind::UnexpectedEof,
+                format!(
+                    "{} is {} bytes long, but the checkpoint is at byte {}",
+                    self.input, skipped, self.offset
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::{Read, Result};
+use std::sync::{Arc, Mutex};
+
+use sha1::{Digest, Sha1};
+
+/// The `Checksums` struct gathers the SHA-1 checksums of the inputs read from
+/// beginning to end, which are the ones published next to the dumps, such as in
+/// `wikidatawiki-*-sha1sums.txt`. Hence, it can be told later on which dump a
+/// database was built from. It is shared by the readers of every input.
+///
+/// Properties:
+///
+/// * `checksums`: The pairs of input and checksum, in the order those were read.
+#[derive(Clone, Default)]
+pub struct Checksums {
+    checksums: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl Checksums {
+    /// The function returns the checksums gathered so far in the format of the
+    /// `sha1sum` tool; that is, a line per input with its checksum and its name.
+    pub fn to_sha1sums(&self) -> String {
+        self.checksums
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(input, checksum)| format!("{}  {}", checksum, input))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The function returns whether no input has been read from beginning to end.
+    pub fn is_empty(&self) -> bool {
+        self.checksums.lock().unwrap().is_empty()
+    }
+}
+
+/// The `Sha1Reader` struct wraps a reader so the SHA-1 checksum of the bytes read
+/// from it is computed as those are read, without reading the input twice. Readers
+/// are wrapped before unpacking them, so the checksum is the one of the file as it
+/// is stored. Notice that it is only recorded once the whole input is read, as the
+/// checksum of a part of it is of no use.
+///
+/// Properties:
+///
+/// * `inner`: The reader being hashed.
+/// * `input`: The name of the input, as given by the user.
+/// * `hasher`: The state of the checksum, until the input is over.
+/// * `checksums`: The checksums the one of the input is recorded in.
+pub struct Sha1Reader<R> {
+    inner: R,
+    input: String,
+    hasher: Option<Sha1>,
+    checksums: Checksums,
+}
+
+impl<R: Read> Sha1Reader<R> {
+    pub fn new(inner: R, input: &str, checksums: Checksums) -> Self {
+        Self {
+            inner,
+            input: input.to_string(),
+            hasher: Some(Sha1::new()),
+            checksums,
+        }
+    }
+}
+
+impl<R: Read> Read for Sha1Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let read = self.inner.read(buf)?;
+        match read {
+            // Reading past the end of the input, which decompressors do, does not record it twice :D
+            0 if !buf.is_empty() => {
+                if let Some(hasher) = self.hasher.take() {
+                    let checksum = hasher
+                        .finalize()
+                        .iter()
+                        .map(|byte| format!("{:02x}", byte))
+                        .collect();
+                    self.checksums
+                        .checksums
+                        .lock()
+                        .unwrap()
+                        .push((self.input.clone(), checksum));
+                }
+            }
+            _ => {
+                if let Some(hasher) = self.hasher.as_mut() {
+                    hasher.update(&buf[..read]);
+                }
+            }
+        }
+        Ok(read)
+    }
+}
@@ -0,0 +1,312 @@
+use std::fs::read_to_string;
+use std::str::FromStr;
+
+use duckdb::ToSql;
+use serde_json::Value;
+
+use crate::labels::parse_language;
+
+/// The tables extra columns can be added to; that is, the one storing the vertices
+/// and the ones storing the main values of the claims.
+const TABLES: [&str; 9] = [
+    "vertex",
+    "string",
+    "text",
+    "external_id",
+    "url",
+    "edge",
+    "coordinates",
+    "quantity",
+    "time",
+];
+
+/// The `Expression` enum represents the simple expressions the extra columns are
+/// computed with. Those are evaluated during the load over the JSON of the entity,
+/// for the columns of the `vertex` table, or over the JSON of the statement, for
+/// the columns of the tables storing the claims. Paths are dot-separated keys, such
+/// as `sitelinks` or `mainsnak.datatype`.
+///
+/// Variants:
+///
+/// * `Count`: `count(<path>)`, the number of elements of an array or an object.
+/// * `Field`: `<path>`, the value found, as text.
+/// * `Equals`: `<path> = <literal>`, whether the value found is the literal.
+/// * `NotEquals`: `<path> != <literal>`, whether the value found is not the literal.
+/// * `Truthy`: `truthy`, whether the claim is among the best ranked ones of its
+/// property, as in the truthy dumps. It is only available for the claims.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Count(Vec<String>),
+    Field(Vec<String>),
+    Equals(Vec<String>, String),
+    NotEquals(Vec<String>, String),
+    Truthy,
+}
+
+impl Expression {
+    /// The function returns the type of the column storing the expression.
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            Expression::Count(_) => "UINTEGER",
+            Expression::Field(_) => "TEXT",
+            Expression::Equals(..) | Expression::NotEquals(..) | Expression::Truthy => "BOOLEAN",
+        }
+    }
+
+    /// The function evaluates the expression over a JSON value.
+    ///
+    /// Arguments:
+    ///
+    /// * `json`: The JSON of the entity or the statement the row stems from.
+    /// * `truthy`: Whether the claim is among the best ranked ones of its property.
+    ///
+    /// Returns:
+    ///
+    /// The value to be stored in the column. In case the path is not found in the
+    /// JSON value, it is `NULL`, but for the counts, which are 0.
+    pub fn evaluate(&self, json: &Value, truthy: bool) -> Box<dyn ToSql> {
+        match self {
+            Expression::Count(path) => {
+                Box::new(lookup(json, path).map_or(0, |value| match value {
+                    Value::Array(array) => array.len() as u32,
+                    Value::Object(object) => object.len() as u32,
+                    Value::Null => 0,
+                    _ => 1,
+                }))
+            }
+            Expression::Field(path) => Box::new(lookup(json, path).map(text)),
+            Expression::Equals(path, literal) => {
+                Box::new(lookup(json, path).map(text).as_ref() == Some(literal))
+            }
+            Expression::NotEquals(path, literal) => {
+                Box::new(lookup(json, path).map(text).as_ref() != Some(literal))
+            }
+            Expression::Truthy => Box::new(truthy),
+        }
+    }
+}
+
+/// This code parses an expression out of the definition of an extra column. An error
+/// message is returned in case it is not one of the supported expressions.
+impl FromStr for Expression {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let expression = expression.trim();
+
+        let path = |path: &str| -> Result<Vec<String>, String> {
+            let path = path.trim();
+            if path.is_empty() || path.split('.').any(str::is_empty) {
+                return Err(format!("Not valid path: {}", path));
+            }
+            Ok(path.split('.').map(str::to_string).collect())
+        };
+        let literal = |literal: &str| {
+            let literal = literal.trim();
+            literal
+                .strip_prefix('\'')
+                .and_then(|literal| literal.strip_suffix('\''))
+                .unwrap_or(literal)
+                .to_string()
+        };
+
+        if expression == "truthy" {
+            Ok(Expression::Truthy)
+        } else if let Some(inner) = expression
+            .strip_prefix("count(")
+            .and_then(|inner| inner.strip_suffix(')'))
+        {
+            Ok(Expression::Count(path(inner)?))
+        } else if let Some((left, right)) = expression.split_once("!=") {
+            Ok(Expression::NotEquals(path(left)?, literal(right)))
+        } else if let Some((left, right)) = expression.split_once('=') {
+            Ok(Expression::Equals(path(left)?, literal(right)))
+        } else {
+            Ok(Expression::Field(path(expression)?))
+        }
+    }
+}
+
+/// The `ExtraColumn` struct represents a column added by the user to any of the
+/// tables of the database.
+///
+/// Properties:
+///
+/// * `table`: The name of the table the column is added to, such as `vertex`.
+/// * `name`: The name of the column, such as `alias_count`.
+/// * `expression`: The `Expression` the values of the column are computed with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtraColumn {
+    pub table: String,
+    pub name: String,
+    pub expression: Expression,
+}
+
+/// The `SchemaConfig` struct contains the customizations of the schema declared by
+/// the user, so downstream teams can extend the database without forking the tool.
+/// It is read out of a JSON file, such as the following:
+///
+/// ```json
+/// {
+///   "columns": {
+///     "vertex.alias_count": "count(aliases.en)",
+///     "edge.is_truthy": "truthy"
+///   }
+/// }
+/// ```
+///
+/// Properties:
+///
+/// * `columns`: The extra columns, in the order those are added to the tables.
+/// * `no_text`: Whether the `label` and `description` columns are left out of the
+/// `vertex` table, which are of no use for purely structural analyses of the graph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaConfig {
+    pub columns: Vec<ExtraColumn>,
+    pub no_text: bool,
+}
+
+impl SchemaConfig {
+    /// The function reads the configuration of the schema out of a JSON file.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the JSON file.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `SchemaConfig`, or a `String` with an error message in case
+    /// the file cannot be read or declares any column that is not valid.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = match read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) => return Err(format!("Error reading schema config. {}", error)),
+        };
+        let config: Value = match serde_json::from_str(&contents) {
+            Ok(config) => config,
+            Err(error) => return Err(format!("Error parsing schema config. {}", error)),
+        };
+
+        let mut columns = Vec::new();
+        if let Some(declared) = config.get("columns").and_then(Value::as_object) {
+            for (column, expression) in declared {
+                let (table, name) = match column.split_once('.') {
+                    Some((table, name)) if TABLES.contains(&table) => (table, name),
+                    _ => return Err(format!("Not valid column: {}", column)),
+                };
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(format!("Not valid column name: {}", name));
+                }
+
+                let expression = match expression.as_str() {
+                    Some(expression) => expression.parse::<Expression>()?,
+                    None => return Err(format!("Not valid expression for {}", column)),
+                };
+                if expression == Expression::Truthy && table == "vertex" {
+                    return Err("Vertices cannot be truthy, only claims can".to_string());
+                }
+
+                columns.push(ExtraColumn {
+                    table: table.to_string(),
+                    name: name.to_string(),
+                    expression,
+                });
+            }
+        }
+
+        Ok(Self {
+            columns,
+            ..Default::default()
+        })
+    }
+
+    /// The function returns the extra columns of a table, in the order those are
+    /// added to it.
+    pub fn columns_of<'a>(&'a self, table: &'a str) -> impl Iterator<Item = &'a ExtraColumn> {
+        self.columns
+            .iter()
+            .filter(move |column| column.table == table)
+    }
+
+    /// The function returns whether a column created by the load is left out of a
+    /// table; that is, the `label` and `description` of the `vertex` table, in case
+    /// the labels and descriptions are not stored at all.
+    pub fn omits(&self, table: &str, column: &str) -> bool {
+        self.no_text && table == "vertex" && matches!(column, "label" | "description")
+    }
+
+    /// The function adds the columns keeping the original strings of the quantities;
+    /// that is, the `amount`, `lowerBound` and `upperBound` as found in the dump,
+    /// with their signs and exponents. The numeric columns are parsed as doubles,
+    /// which may not represent them exactly, so these allow lossless round-trips.
+    pub fn add_quantity_strings(&mut self) {
+        for (name, key) in [
+            ("amount_text", "amount"),
+            ("lower_bound_text", "lowerBound"),
+            ("upper_bound_text", "upperBound"),
+        ] {
+            self.columns.push(ExtraColumn {
+                table: "quantity".to_string(),
+                name: name.to_string(),
+                expression: Expression::Field(
+                    ["mainsnak", "datavalue", "value", key]
+                        .iter()
+                        .map(|key| key.to_string())
+                        .collect(),
+                ),
+            });
+        }
+    }
+
+    /// The function adds a column to the `vertex` table for the label of the entity
+    /// in each of the given languages, such as `label_de`, so simple queries do not
+    /// have to deal with the labels in other languages. Hyphens in the codes of the
+    /// languages are replaced by underscores; that is, `zh-hans` is `label_zh_hans`.
+    ///
+    /// Arguments:
+    ///
+    /// * `languages`: The codes of the languages, such as `de` or `fr`.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with either an empty `Ok(())` or a `String` with an error message
+    /// in case any of the codes is not valid.
+    pub fn add_label_columns(&mut self, languages: &[String]) -> Result<(), String> {
+        for language in languages {
+            let language = parse_language(language)?;
+            self.columns.push(ExtraColumn {
+                table: "vertex".to_string(),
+                name: format!("label_{}", language.replace('-', "_")),
+                expression: Expression::Field(vec![
+                    "labels".to_string(),
+                    language,
+                    "value".to_string(),
+                ]),
+            });
+        }
+        Ok(())
+    }
+
+    /// The function returns whether any extra column has been declared, in which
+    /// case the JSON of the entities has to be kept until they are stored.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+/// The function looks a dot-separated path up in a JSON value.
+fn lookup<'a>(json: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(json, |value, key| match value {
+        Value::Array(array) => key.parse::<usize>().ok().and_then(|index| array.get(index)),
+        _ => value.get(key),
+    })
+}
+
+/// The function returns the textual representation of a JSON value; that is, the
+/// string itself for strings, and the JSON otherwise.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.to_owned(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,199 @@
+use serde_json::{Map, Value};
+use strum_macros::EnumString;
+
+/// The `DumpFormat` enum defines the variants of the JSON dumps that can be
+/// processed. `Current` stands for the dumps as they are published nowadays, while
+/// `Legacy` stands for the archived dumps (pre-2020) whose encoding of entities,
+/// claims and snaks slightly differs from the current one.
+#[derive(Clone, Debug, Default, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum DumpFormat {
+    #[default]
+    Current,
+    Legacy,
+}
+
+impl DumpFormat {
+    /// The function normalizes a JSON entity so it follows the current encoding of
+    /// the dumps, and hence, it can be parsed as any other entity. Entities coming
+    /// from current dumps are left untouched.
+    ///
+    /// Arguments:
+    ///
+    /// * `entity`: A mutable reference to the JSON value of the entity.
+    pub fn normalize(&self, entity: &mut Value) {
+        if *self == DumpFormat::Legacy {
+            normalize_entity(entity);
+        }
+    }
+}
+
+/// The function normalizes the top-level fields of a legacy entity. Older dumps
+/// omitted the `type` of the entity, and serialized empty maps as empty arrays,
+/// which is how PHP encodes an empty associative array.
+///
+/// Arguments:
+///
+/// * `entity`: A mutable reference to the JSON value of the entity.
+fn normalize_entity(entity: &mut Value) {
+    let entity = match entity.as_object_mut() {
+        Some(entity) => entity,
+        None => return,
+    };
+
+    if !entity.contains_key("type") {
+        let entity_type = match entity
+            .get("id")
+            .and_then(Value::as_str)
+            .and_then(|id| id.get(0..1))
+        {
+            Some("P") => "property",
+            Some("L") => "lexeme",
+            _ => "item",
+        };
+        entity.insert("type".to_string(), Value::from(entity_type));
+    }
+
+    for key in ["labels", "descriptions", "aliases", "claims", "sitelinks"] {
+        empty_array_to_object(entity, key);
+    }
+
+    if let Some(Value::Object(claims)) = entity.get_mut("claims") {
+        for statements in claims.values_mut() {
+            for statement in statements.as_array_mut().into_iter().flatten() {
+                normalize_statement(statement);
+            }
+        }
+    }
+}
+
+/// The function normalizes a legacy statement, filling its `type` and `rank` when
+/// those are missing, and normalizing each of the snaks it contains; that is, the
+/// main snak, the qualifiers and the snaks of the references.
+///
+/// Arguments:
+///
+/// * `statement`: A mutable reference to the JSON value of the statement.
+fn normalize_statement(statement: &mut Value) {
+    let statement = match statement.as_object_mut() {
+        Some(statement) => statement,
+        None => return,
+    };
+
+    if !statement.contains_key("type") {
+        statement.insert("type".to_string(), Value::from("statement"));
+    }
+    if !statement.contains_key("rank") {
+        statement.insert("rank".to_string(), Value::from("normal"));
+    }
+
+    if let Some(snak) = statement.get_mut("mainsnak") {
+        normalize_snak(snak);
+    }
+
+    empty_array_to_object(statement, "qualifiers");
+    if let Some(Value::Object(qualifiers)) = statement.get_mut("qualifiers") {
+        qualifiers.values_mut().for_each(normalize_snaks);
+    }
+
+    if let Some(Value::Array(references)) = statement.get_mut("references") {
+        for reference in references {
+            if let Some(reference) = reference.as_object_mut() {
+                empty_array_to_object(reference, "snaks");
+                if let Some(Value::Object(snaks)) = reference.get_mut("snaks") {
+                    snaks.values_mut().for_each(normalize_snaks);
+                }
+            }
+        }
+    }
+}
+
+/// The function normalizes every snak in an array of snaks.
+///
+/// Arguments:
+///
+/// * `snaks`: A mutable reference to the JSON array of snaks.
+fn normalize_snaks(snaks: &mut Value) {
+    if let Some(snaks) = snaks.as_array_mut() {
+        snaks.iter_mut().for_each(normalize_snak);
+    }
+}
+
+/// The function normalizes a legacy snak. Older dumps included neither the `datatype`
+/// of the snak, which is inferred from the type of its value, nor the `id` of the
+/// entities referenced, which is built out of the `numeric-id`.
+///
+/// Arguments:
+///
+/// * `snak`: A mutable reference to the JSON value of the snak.
+fn normalize_snak(snak: &mut Value) {
+    let snak = match snak.as_object_mut() {
+        Some(snak) => snak,
+        None => return,
+    };
+
+    let datavalue = match snak.get_mut("datavalue").and_then(Value::as_object_mut) {
+        Some(datavalue) => datavalue,
+        None => return,
+    };
+
+    let value_type = datavalue
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    let mut entity_type = None;
+    if value_type == "wikibase-entityid" {
+        if let Some(value) = datavalue.get_mut("value").and_then(Value::as_object_mut) {
+            let kind = value
+                .get("entity-type")
+                .and_then(Value::as_str)
+                .unwrap_or("item")
+                .to_string();
+            if !value.contains_key("id") {
+                if let Some(numeric_id) = value.get("numeric-id").and_then(Value::as_u64) {
+                    let prefix = match kind.as_str() {
+                        "property" => "P",
+                        "lexeme" => "L",
+                        _ => "Q",
+                    };
+                    value.insert(
+                        "id".to_string(),
+                        Value::from(format!("{}{}", prefix, numeric_id)),
+                    );
+                }
+            }
+            entity_type = Some(kind);
+        }
+    }
+
+    if !snak.contains_key("datatype") {
+        let datatype = match (value_type.as_str(), entity_type.as_deref()) {
+            ("wikibase-entityid", Some("property")) => "wikibase-property",
+            ("wikibase-entityid", Some("lexeme")) => "wikibase-lexeme",
+            ("wikibase-entityid", _) => "wikibase-item",
+            ("globecoordinate", _) => "globe-coordinate",
+            ("monolingualtext", _) => "monolingualtext",
+            ("quantity", _) => "quantity",
+            ("time", _) => "time",
+            _ => "string",
+        };
+        snak.insert("datatype".to_string(), Value::from(datatype));
+    }
+}
+
+/// The function replaces an empty array stored under `key` by an empty object, as
+/// older dumps serialized empty maps as empty arrays.
+///
+/// Arguments:
+///
+/// * `object`: A mutable reference to the JSON object containing the key.
+/// * `key`: The key whose value is to be replaced.
+fn empty_array_to_object(object: &mut Map<String, Value>, key: &str) {
+    if let Some(Value::Array(array)) = object.get(key) {
+        if array.is_empty() {
+            object.insert(key.to_string(), Value::Object(Map::new()));
+        }
+    }
+}
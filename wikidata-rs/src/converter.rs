@@ -0,0 +1,1995 @@
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use duckdb::{params, Connection, DropBehavior, Error, ToSql};
+use serde_json::Value;
+use strum::IntoEnumIterator;
+use wikidata::{ClaimValueData, Entity, Lang, Pid, Rank};
+
+use crate::budget::SizeBudget;
+use crate::checkpoint::Checkpoint;
+use crate::columns::SchemaConfig;
+use crate::compat::DumpFormat;
+use crate::dead_letter::DeadLetters;
+use crate::dedup::{self, Admission, Deduplicator, Replacement};
+use crate::derived::Derived;
+use crate::dtype::{rank_code, DataType};
+use crate::globe;
+use crate::id::{parse_property, Id, IdEncoding, IdKind};
+use crate::labels::{self, ALIAS_TABLE, LABEL_TABLE};
+use crate::lexeme::{self, Lexeme, LEXEME_TABLES};
+use crate::lines::{line_too_long, BoundedLines, Counted, JsonDocuments};
+use crate::load_log::LoadLog;
+use crate::macros::Macro;
+use crate::memory::MemoryWatchdog;
+use crate::metadata;
+use crate::ntriples::{self, NTriplesEntities};
+use crate::partition::{self, Partition};
+use crate::pipeline::Pipeline;
+use crate::progress::Progress;
+use crate::provenance::{self, PROVENANCE_TABLE};
+use crate::raw::{salvage, RawClaim};
+use crate::sample::{ClassSampler, Sampler};
+use crate::snapshot::Snapshotter;
+use crate::stats;
+use crate::validation::{Failure, LineError, Report};
+use crate::value::{AppenderHelper, ExactAmounts, ExactDate, Table};
+use crate::xml::{InputFormat, XmlEntities};
+use crate::LANG;
+
+/// The `ConverterConfig` struct gathers the options of the load that affect how each
+/// of the entities in the dump is read, parsed and stored, and how often the rows
+/// stored are committed. Its default stores every entity and claim of a JSON dump,
+/// qualifiers and references included, in a single transaction.
+///
+/// Properties:
+///
+/// * `dump_format`: The format of the dump, according to which the JSON value of
+/// each entity is normalized before parsing it.
+/// * `qualifiers`: Whether the qualifiers of the claims have to be stored or not.
+/// * `references`: Whether the references of the claims have to be stored or not.
+/// * `sampler`: The `Sampler` deciding which entities are kept, in case only a
+/// sample of the dump is to be stored.
+/// * `schema`: The `SchemaConfig` declaring the extra columns of the tables.
+/// * `truthy_only`: Whether only the best ranked claims of each property are stored.
+/// * `keep_deprecated`: Whether the deprecated claims are stored as well.
+/// * `external_ids`: Whether the claims holding external identifiers are stored.
+/// * `properties`: The properties whose claims are stored, or an empty set in case
+/// the claims of every property are.
+/// * `classes`: The numeric identifiers of the classes whose instances are the only
+/// entities stored, or an empty set in case every entity is.
+/// * `languages`: The languages whose labels and descriptions are stored in the
+/// `label` table, and whose aliases are stored in the `alias` table.
+/// * `lexemes`: Whether the lexical details of the lexemes are stored.
+/// * `input_format`: The format of the input, according to which it is split into
+/// the entities it holds.
+/// * `max_line_length`: The maximum length of a line, in bytes. Longer lines are
+/// skipped and reported.
+/// * `lossy_utf8`: Whether invalid UTF-8 sequences are replaced, instead of halting
+/// the load.
+/// * `threads`: The number of threads the lines are parsed on.
+/// * `preserve_order`: Whether the entities parsed on several threads are stored in
+/// the order of the input.
+/// * `provenance`: Whether the input and the offset each entity comes from are stored.
+/// * `skipped`: The tables the user is not interested in, whose rows are dropped.
+/// * `batch_size`: The number of entities after which the appenders are flushed.
+/// * `checkpoint_every`: The number of entities after which the transaction is
+/// committed, recording the line the load can be resumed from.
+/// * `abort_after`: The number of failed lines after which the load is halted.
+/// * `no_indices`: Whether the indices are not created at all.
+/// * `indices_after_load`: Whether the indices are created once every entity has
+/// been stored, instead of before.
+/// * `unsafe_load`: Whether the automatic checkpoints of DuckDB are disabled while
+/// loading, so the WAL is only written into the database file once committed.
+/// * `derived`: The derived tables created once the load has finished.
+/// * `partitions`: The properties whose claims are moved to tables of their own once
+/// the load has finished.
+/// * `id_encoding`: The `IdEncoding` the identifiers are stored with, which is
+/// recorded in the database, so it can be told once loaded.
+pub struct ConverterConfig {
+    pub dump_format: DumpFormat,
+    pub qualifiers: bool,
+    pub references: bool,
+    pub sampler: Option<Sampler>,
+    pub schema: SchemaConfig,
+    pub truthy_only: bool,
+    pub keep_deprecated: bool,
+    pub external_ids: bool,
+    pub properties: HashSet<Pid>,
+    pub classes: HashSet<u64>,
+    pub languages: Vec<Lang>,
+    pub lexemes: bool,
+    pub input_format: InputFormat,
+    pub max_line_length: usize,
+    pub lossy_utf8: bool,
+    pub threads: NonZeroUsize,
+    pub preserve_order: bool,
+    pub provenance: bool,
+    pub skipped: HashSet<&'static str>,
+    pub batch_size: Option<NonZeroUsize>,
+    pub checkpoint_every: Option<NonZeroUsize>,
+    pub abort_after: Option<usize>,
+    pub no_indices: bool,
+    pub indices_after_load: bool,
+    pub unsafe_load: bool,
+    pub derived: Vec<Derived>,
+    pub partitions: Vec<Partition>,
+    pub id_encoding: IdEncoding,
+}
+
+impl ConverterConfig {
+    /// The function returns whether the claims of a property have to be stored, as
+    /// those of every property are unless the user has given a list of them.
+    pub fn stores_property(&self, property: &Pid) -> bool {
+        self.properties.is_empty() || self.properties.contains(property)
+    }
+
+    /// The function returns whether an entity has to be stored according to the
+    /// classes it is an instance of, as every entity is unless the user has given a
+    /// list of classes.
+    pub fn stores_instance_of(&self, entity: &Entity) -> bool {
+        self.classes.is_empty()
+            || instance_of(entity, &self.id_encoding)
+                .iter()
+                .any(|class| self.classes.contains(class))
+    }
+
+    /// The function returns whether the entities are stored in the order of the
+    /// input, which is only known for sure whenever the lines are parsed on a single
+    /// thread, unless the user asks for it. Otherwise, the line committed last is
+    /// not known, so no checkpoint can be recorded.
+    pub fn is_ordered(&self) -> bool {
+        self.threads.get() == 1 || self.preserve_order
+    }
+}
+
+impl Default for ConverterConfig {
+    fn default() -> Self {
+        Self {
+            dump_format: DumpFormat::default(),
+            qualifiers: true,
+            references: true,
+            sampler: None,
+            schema: SchemaConfig::default(),
+            truthy_only: false,
+            keep_deprecated: false,
+            external_ids: true,
+            properties: HashSet::new(),
+            classes: HashSet::new(),
+            languages: Vec::new(),
+            lexemes: false,
+            input_format: InputFormat::default(),
+            max_line_length: 256 * 1_000_000,
+            lossy_utf8: false,
+            threads: NonZeroUsize::MIN,
+            preserve_order: false,
+            provenance: false,
+            skipped: HashSet::new(),
+            batch_size: None,
+            checkpoint_every: None,
+            abort_after: None,
+            no_indices: false,
+            indices_after_load: false,
+            unsafe_load: false,
+            derived: Vec::new(),
+            partitions: Vec::new(),
+            id_encoding: IdEncoding::default(),
+        }
+    }
+}
+
+/// The `Parsed` struct contains an entity parsed out of a line of the dump, together
+/// with the fields of its JSON value that are not kept by the parser.
+///
+/// Properties:
+///
+/// * `entity`: The Wikidata entity.
+/// * `revision`: The revision of the entity; that is, its `lastrevid`.
+/// * `datatype`: The datatype declared by the entity, in case it is a property.
+/// * `sitelinks`: The number of sitelinks of the entity.
+/// * `raw_claims`: The claims of the entity whose value could not be converted.
+/// * `json`: The JSON value of the entity, which is only kept in case any extra
+/// column has to be computed out of it.
+/// * `lexeme`: The lexical details of the entity, in case it is a lexeme and those
+/// have to be stored.
+/// * `quantities`: The exact amounts of the quantities of the snaks, keyed by the
+/// identifier of their claim and their `SnakPosition` in it.
+/// * `times`: The exact dates of the times of the snaks, keyed by the identifier of
+/// their claim and their `SnakPosition` in it.
+/// * `datatypes`: The type of the values declared by each property of the snaks of
+/// the entity, whether those are main values, qualifiers or references.
+pub struct Parsed {
+    pub entity: Entity,
+    pub revision: u64,
+    pub datatype: Option<String>,
+    pub sitelinks: u32,
+    pub raw_claims: Vec<RawClaim>,
+    pub json: Option<Value>,
+    pub lexeme: Option<Lexeme>,
+    pub quantities: HashMap<(String, SnakPosition), ExactAmounts>,
+    pub times: HashMap<(String, SnakPosition), ExactDate>,
+    pub datatypes: HashMap<Pid, DataType>,
+}
+
+/// The `SnakPosition` enum tells apart the snaks of a claim; that is, its main value,
+/// each of its qualifiers, and each of the snaks of each of its references, which
+/// are numbered in the very order the parser reads them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SnakPosition {
+    Main,
+    Qualifier(usize),
+    Reference(usize, usize),
+}
+
+/// The function creates tables in a database connection using SQL queries.
+///
+/// Arguments:
+///
+/// * `connection`: The `connection` parameter is a reference to a
+/// `PooledConnection` object from the `DuckdbConnectionManager` type. This object
+/// represents a connection to a DuckDB database and is used to execute SQL queries
+/// and commands on that database. The `create_tables` function uses this connection
+/// to create the tables according to the Wikidata entity data model.
+///
+/// * `config`: The `SchemaConfig` declaring the extra columns of the tables.
+///
+/// Notice that the claims of the partitions of a database already loaded are moved
+/// back to the generic tables, as those are created again once the load finishes.
+///
+/// Returns:
+///
+/// The function `create_tables` is returning a `Result` with an empty tuple `()` as
+/// the success value and an `Error` as the error value.
+pub fn create_tables(connection: &mut Connection, config: &SchemaConfig) -> Result<(), Error> {
+    let transaction = match connection.transaction() {
+        Ok(transaction) => transaction,
+        Err(_) => return Err(Error::AppendError),
+    };
+
+    for table in Table::iterator() {
+        table.create_table(&transaction, config)?;
+    }
+
+    transaction.commit()?;
+
+    // The entities replaced during the load may have claims in the partitions, so those are moved
+    // back, and every claim is seen by the derived tables and the statistics computed at the end :D
+    partition::merge(connection)
+}
+
+/// This function creates indices for the id column in the vertices table.
+///
+/// Arguments:
+///
+/// * `transaction`: A reference to a Transaction object, which is used to perform
+/// database operations.
+///
+/// Returns:
+///
+/// The function `create_indices` returns a `Result` enum with either an `Ok(())`
+/// value indicating that the function executed successfully, or an `Err` value
+/// containing an `Error` object if an error occurred during execution.
+pub fn create_indices(connection: &Connection) -> Result<(), Error> {
+    // We are interested only in creating an index for the id column in the vertices table, as we
+    // will only query over it. The rest of the data that is stored just extends the knowledge that
+    // we store, but has no relevance in regards with future processing :D
+    for table in Table::iterator() {
+        table.create_indices(connection)?;
+    }
+    Ok(())
+}
+
+/// This function finalizes the load by creating the dimension table of the globes
+/// and the statistics of the classes, by computing the derived tables requested by
+/// the user, by moving the claims of the partitions to tables of their own, and by
+/// installing the helper macros in the database, so the most common queries over
+/// the graph become one-liners.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database, once every
+/// entity has been inserted and the transaction has been committed.
+///
+/// * `derived`: A slice of the derived tables to be created.
+///
+/// * `partitions`: A slice of the properties whose claims are to be stored in
+/// tables of their own.
+///
+/// Returns:
+///
+/// The function `finalize` returns a `Result` enum with either an `Ok(())`
+/// value indicating that the function executed successfully, or an `Err` value
+/// containing an `Error` object if an error occurred during execution.
+pub fn finalize(
+    connection: &Connection,
+    derived: &[Derived],
+    partitions: &[Partition],
+) -> Result<(), Error> {
+    globe::create_globe(connection)?;
+    stats::create_class_stats(connection)?;
+    for table in derived {
+        table.create_table(connection)?;
+    }
+    for partition in partitions {
+        partition.create_tables(connection)?;
+    }
+    // The macros read the partitions too, so those are installed once the claims have been moved :D
+    for helper in Macro::iter() {
+        helper.create_macro(connection)?;
+    }
+    Ok(())
+}
+
+/// The function parses a line of a JSON dump file into a Wikidata entity.
+///
+/// Arguments:
+///
+/// * `line`: A string representing a line of JSON data from a Wikidata dump file.
+///
+/// * `line_number`: The line number of the current line being processed in the
+/// input file.
+///
+/// * `options`: A reference to the `ConverterConfig` of the load, such as the format
+/// of the dump, according to which the JSON value is normalized before parsing it.
+///
+/// Returns:
+///
+/// a `Result` type with the `Ok` variant containing the `Parsed` entity, or `None`
+/// if the line is just one of the delimiters of the dump, and the `Err` variant
+/// containing a `LineError` with the classification of the failure and an error
+/// message if an error occurs during execution.
+pub fn parse_entity(
+    mut line: String,
+    line_number: u32,
+    options: &ConverterConfig,
+) -> Result<Option<Parsed>, LineError> {
+    // We have to remove the delimiters so the JSON parsing is performed in a safe environment. For
+    // us to do so, we remove possible blanks both at the end and at the beginning of each line.
+    // After such, we check if the line is empty or any of the possible delimiters ('[' or ']').
+    // Hence, what we are ensuring is that the JSON line is as safe as possible
+    line = line.trim().parse().unwrap(); //
+    if line.is_empty() || line == "[" || line == "]" {
+        return Ok(None); // we just skip the line. It is not needed :D
+    }
+
+    // Remove the trailing comma and newline character. This is extremely important for serde_json to
+    // process the lines properly. In general, a processing of the lines is required for serde_json
+    // to work. We are making sure that the last character is a closing bracket; that is, the line
+    // is a valid JSON
+    if line.ends_with(',') {
+        line.truncate(line.len() - 1);
+    }
+
+    // Notice that simd_json parses the line in place, modifying its contents. Hence, in case the
+    // parsing fails, we have to know beforehand whether the line was truncated or not :(
+    let json_failure = Failure::from_json(&line);
+
+    // For the very same reason, we keep a copy of the line, from which the claims that cannot be
+    // converted are salvaged in case the entity cannot be parsed as a whole
+    let mut original = line.clone();
+
+    // By using simd_json we parse the string to a Value. In this regard, the line has to be a valid
+    // JSON by itself. As we are sure that Wikidata dumps are an enumeration of JSON objects: one
+    // per line in the document, we can use this algorithm for retrieving each entity in the dump
+    let mut value = match unsafe { simd_json::from_str(&mut line) } {
+        Ok(value) => value,
+        Err(error) => {
+            return Err(LineError::new(
+                json_failure,
+                format!("Error parsing JSON at line {}: {}", line_number, error),
+            ))
+        }
+    };
+
+    // Older dumps encode some of the fields in a slightly different manner, so we normalize them
+    // before parsing. For the current dumps, this is a no-op :D
+    options.dump_format.normalize(&mut value);
+
+    // Neither the revision of the entity nor the datatype declared by properties are kept by the
+    // parser, so we retrieve them beforehand. The former allows telling apart the different versions
+    // of an entity found in several inputs, while the latter allows auditing the values stored :D
+    let revision = value
+        .get("lastrevid")
+        .and_then(|revision| revision.as_u64())
+        .unwrap_or_default();
+    let datatype = value
+        .get("datatype")
+        .and_then(|datatype| datatype.as_str())
+        .map(str::to_string);
+
+    // The same goes for the sitelinks, of which only their number is stored, as it is a common proxy
+    // for the notability, or the popularity, of the entity :D
+    let sitelinks = value
+        .get("sitelinks")
+        .and_then(Value::as_object)
+        .map_or(0, |sitelinks| sitelinks.len() as u32);
+
+    // Lemmas, forms and senses are specific to lexemes, so the parser does not keep them either.
+    // Those are only retrieved in case the user has asked for them :D
+    let lexeme = match options.lexemes {
+        true => Lexeme::from_json(&value, &options.id_encoding),
+        false => None,
+    };
+
+    // The same goes for the amounts of the quantities, which the parser reads as doubles, and the
+    // dates of the times, whose calendar it drops. Those are kept as found in the dump, so the
+    // amounts with many digits are not stored rounded, nor the dates out of their calendar :D
+    let quantities = exact_values(&value, "quantity", |value| {
+        Some(ExactAmounts::from_json(value))
+    });
+    let times = exact_values(&value, "time", |value| {
+        ExactDate::from_json(value, &options.id_encoding)
+    });
+
+    // The parser does not keep the datatype declared by the properties of the snaks either, which
+    // tells the table their values are stored in, even for those with no value or an unknown one :D
+    let datatypes = declared_datatypes(&value);
+
+    // The labels, descriptions and aliases take most of the entities, so those are emptied before
+    // parsing in case the user is not interested in them, which saves both time and memory :D
+    if options.schema.no_text {
+        if let Some(entity) = value.as_object_mut() {
+            for key in ["labels", "descriptions", "aliases"] {
+                if entity.contains_key(key) {
+                    entity.insert(key.to_string(), Value::Object(Default::default()));
+                }
+            }
+        }
+    }
+
+    // The extra columns declared by the user are computed out of the JSON value, which is consumed
+    // by the parser. Hence, a copy is kept, but only in case there is any of them :(
+    let json = if options.schema.is_empty() {
+        None
+    } else {
+        Some(value.clone())
+    };
+
+    // Once we have the JSON value parsed, we try to transform it into a Wikidata entity, that will
+    // be stored later. This is basically the same object as before, but arranged in a better manner
+    let error = match Entity::from_json(value) {
+        Ok(entity) => {
+            return Ok(Some(Parsed {
+                entity,
+                revision,
+                datatype,
+                sitelinks,
+                raw_claims: Vec::new(),
+                json,
+                lexeme,
+                quantities,
+                times,
+                datatypes,
+            }))
+        }
+        Err(error) => LineError::new(
+            Failure::from(&error),
+            format!("Error parsing Entity at line {}: {:?}", line_number, error),
+        ),
+    };
+
+    // In case the entity could not be parsed, it may be caused by some claims whose value cannot be
+    // converted, such as those of datatypes the parser is not aware of. Those are removed from the
+    // entity, which is parsed again, and stored as they are in the raw_claim table :D
+    let mut value = match unsafe { simd_json::from_str(&mut original) } {
+        Ok(value) => value,
+        Err(_) => return Err(error),
+    };
+    options.dump_format.normalize(&mut value);
+
+    let raw_claims = salvage(&mut value);
+    if raw_claims.is_empty() {
+        return Err(error);
+    }
+
+    match Entity::from_json(value) {
+        Ok(entity) => Ok(Some(Parsed {
+            entity,
+            revision,
+            datatype,
+            sitelinks,
+            raw_claims,
+            json,
+            lexeme,
+            quantities,
+            times,
+            datatypes,
+        })),
+        Err(_) => Err(error),
+    }
+}
+
+/// The function retrieves the values of the snaks of an entity that the parser does
+/// not keep as found in the dump, such as the exact amounts of the quantities. Every
+/// snak is looked at; that is, the main value of each claim, its qualifiers, and
+/// the snaks of its references.
+///
+/// Arguments:
+///
+/// * `value`: A reference to the JSON value of the entity.
+/// * `value_type`: The type of the data values retrieved, such as `quantity`.
+/// * `exact`: The function retrieving the value out of the JSON value of the data
+/// value, if it can be understood.
+///
+/// Returns:
+///
+/// A `HashMap` with the value of each of the snaks of the type, keyed by the
+/// identifier of its claim and its `SnakPosition` in it.
+fn exact_values<T>(
+    value: &Value,
+    value_type: &str,
+    exact: impl Fn(&Value) -> Option<T>,
+) -> HashMap<(String, SnakPosition), T> {
+    let statements = value
+        .get("claims")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flat_map(|claims| claims.values())
+        .filter_map(Value::as_array)
+        .flatten();
+
+    let mut values = HashMap::new();
+    for statement in statements {
+        let id = match statement.get("id").and_then(Value::as_str) {
+            Some(id) => id,
+            None => continue,
+        };
+
+        // The qualifiers and the snaks of the references are numbered as the parser reads them;
+        // that is, grouped by their property, in the order those are listed :D
+        let mut snaks: Vec<(SnakPosition, &Value)> = Vec::new();
+        if let Some(mainsnak) = statement.get("mainsnak") {
+            snaks.push((SnakPosition::Main, mainsnak));
+        }
+        snaks.extend(
+            ordered_snaks(statement, "qualifiers", "qualifiers-order")
+                .enumerate()
+                .map(|(index, snak)| (SnakPosition::Qualifier(index), snak)),
+        );
+        let references = statement.get("references").and_then(Value::as_array);
+        for (group, reference) in references.into_iter().flatten().enumerate() {
+            snaks.extend(
+                ordered_snaks(reference, "snaks", "snaks-order")
+                    .enumerate()
+                    .map(|(index, snak)| (SnakPosition::Reference(group, index), snak)),
+            );
+        }
+
+        for (position, snak) in snaks {
+            let datavalue = match snak.get("datavalue") {
+                Some(datavalue) => datavalue,
+                None => continue, // snaks with no value or an unknown one hold nothing :D
+            };
+            if datavalue.get("type").and_then(Value::as_str) != Some(value_type) {
+                continue;
+            }
+            if let Some(found) = datavalue.get("value").and_then(&exact) {
+                values.insert((id.to_string(), position), found);
+            }
+        }
+    }
+    values
+}
+
+/// The function returns the snaks of a map keyed by their property, such as the
+/// qualifiers of a claim, in the order its properties are listed in.
+///
+/// Arguments:
+///
+/// * `value`: A reference to the JSON value holding the map, such as a claim.
+/// * `key`: The key of the map, such as `qualifiers`.
+/// * `order`: The key of the list of its properties, such as `qualifiers-order`.
+///
+/// Returns:
+///
+/// An iterator over the JSON values of the snaks.
+fn ordered_snaks<'a>(
+    value: &'a Value,
+    key: &str,
+    order: &str,
+) -> impl Iterator<Item = &'a Value> {
+    let snaks = value.get(key);
+    value
+        .get(order)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .filter_map(move |property| snaks?.get(property)?.as_array())
+        .flatten()
+}
+
+/// The function restores the values of a snak that the parser does not keep as found
+/// in the dump; that is, the exact amounts of the quantities and the exact dates of
+/// the times. Those not found are left as parsed.
+///
+/// Arguments:
+///
+/// * `table`: A mutable reference to the `Table` the value of the snak is stored in.
+/// * `quantities`: The exact amounts of the quantities of the entity.
+/// * `times`: The exact dates of the times of the entity.
+/// * `statement_id`: The identifier of the claim the snak belongs to.
+/// * `snak`: The `SnakPosition` of the snak in the claim.
+fn restore_exact(
+    table: &mut Table,
+    quantities: &HashMap<(String, SnakPosition), ExactAmounts>,
+    times: &HashMap<(String, SnakPosition), ExactDate>,
+    statement_id: &str,
+    snak: SnakPosition,
+) {
+    match table {
+        Table::Quantity { exact, .. } => {
+            if let Some(amounts) = quantities.get(&(statement_id.to_string(), snak)) {
+                exact.clone_from(amounts);
+            }
+        }
+        Table::Time { date, .. } => {
+            if let Some(exact) = times.get(&(statement_id.to_string(), snak)) {
+                date.clone_from(exact);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The function retrieves the type of the values declared by each property of the
+/// snaks of an entity; that is, the ones of the main values of its claims, and the
+/// ones of their qualifiers and references. A property always declares the same
+/// datatype, so any of its snaks will do.
+///
+/// Arguments:
+///
+/// * `value`: A reference to the JSON value of the entity.
+///
+/// Returns:
+///
+/// A `HashMap` with the `DataType` of each property, leaving out the ones whose
+/// datatype is not known.
+fn declared_datatypes(value: &Value) -> HashMap<Pid, DataType> {
+    let statements = value
+        .get("claims")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flat_map(|claims| claims.values())
+        .filter_map(Value::as_array)
+        .flatten();
+
+    let mut snaks = Vec::new();
+    for statement in statements {
+        snaks.extend(statement.get("mainsnak"));
+        // Qualifiers and the snaks of the references are grouped by their property :D
+        let grouped = statement
+            .get("qualifiers")
+            .into_iter()
+            .chain(
+                statement
+                    .get("references")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|reference| reference.get("snaks")),
+            )
+            .filter_map(Value::as_object)
+            .flat_map(|snaks| snaks.values())
+            .filter_map(Value::as_array)
+            .flatten();
+        snaks.extend(grouped);
+    }
+
+    snaks
+        .into_iter()
+        .filter_map(|snak| {
+            let property = parse_property(snak.get("property")?.as_str()?).ok()?;
+            let datatype = DataType::from_declared(snak.get("datatype")?.as_str()?)?;
+            Some((property, datatype))
+        })
+        .collect()
+}
+
+/// The function decodes and parses a line read from the dump. It does not touch the
+/// database, so lines can be parsed on several threads at once, while the entities
+/// are stored by a single one.
+///
+/// Arguments:
+///
+/// * `json`: The input the line comes from.
+///
+/// * `line_number`: The line number of the line within the input.
+///
+/// * `line`: The bytes of the line, or the error found while reading it.
+///
+/// * `options`: A reference to the `ConverterConfig` of the load, such as the format
+/// of the dump, according to which the JSON value is normalized before parsing it,
+/// or whether invalid UTF-8 sequences are replaced.
+///
+/// Returns:
+///
+/// a `Result` type with the `Ok` variant containing the outcome of parsing the line,
+/// as returned by `parse_entity`, and the `Err` variant containing a `String` with
+/// an error message if the load has to be halted, as the input cannot be read
+/// anymore.
+pub fn parse_line(
+    json: &str,
+    line_number: usize,
+    line: std::io::Result<Vec<u8>>,
+    options: &ConverterConfig,
+) -> Result<Result<Option<Parsed>, LineError>, String> {
+    let line = match line.map(String::from_utf8) {
+        Ok(Ok(line)) => line,
+        Ok(Err(error)) if options.lossy_utf8 => {
+            eprintln!(
+                "Invalid UTF-8 in line {} from {}. Replaced by U+FFFD",
+                line_number, json
+            );
+            String::from_utf8_lossy(error.as_bytes()).into_owned()
+        }
+        Ok(Err(_)) => {
+            return Err(format!(
+                "Invalid UTF-8 in line {} from {}. Pass --lossy-utf8 to replace it",
+                line_number, json
+            ))
+        }
+        // Lines that are too long are skipped as any other line that cannot be stored, while the
+        // rest of the errors mean that the input cannot be read anymore :(
+        Err(error) => match line_too_long(&error) {
+            Some(too_long) => {
+                return Ok(Err(LineError::new(
+                    Failure::LineTooLong,
+                    format!("Error reading line {}: {}", line_number, too_long),
+                )))
+            }
+            None => return Err(format!("Error reading {}. {}", json, error)),
+        },
+    };
+
+    Ok(parse_entity(line, line_number as u32, options))
+}
+
+/// The function stores a Wikidata entity parsed out of a JSON dump file.
+///
+/// Arguments:
+///
+/// * `appender_helper`: A mutable reference to an AppenderHelper struct, which is
+/// used to append entities to a storage backend.
+///
+/// * `class_sampler`: An optional mutable reference to the `ClassSampler`, which
+/// decides which entities are kept when sampling by class. In case it is not
+/// provided, entities are kept no matter their class.
+///
+/// * `deduplicator`: An optional mutable reference to the `Deduplicator`, which
+/// decides what to do with the entities that have already been stored. In case it
+/// is not provided, every entity is stored. Those replacing a previous revision are
+/// kept by it, and only stored through `replace_entities`.
+///
+/// * `parsed`: The entity parsed out of a line of the dump, as returned by
+/// `parse_line`.
+///
+/// * `line_number`: The line number of the current line being processed in the
+/// input file.
+///
+/// * `options`: A reference to the `ConverterConfig` of the load, such as the tables
+/// and claims to be stored.
+///
+/// * `source`: The input file and the byte offset the line comes from, in case the
+/// provenance of the entities has to be recorded.
+///
+/// Returns:
+///
+/// a `Result` type with the `Ok` variant containing an empty tuple `()` if the
+/// function executes successfully, and the `Err` variant containing a `LineError`
+/// with the classification of the failure and an error message if an error occurs
+/// during execution.
+#[allow(clippy::too_many_arguments)]
+pub fn insert_entity(
+    appender_helper: &mut AppenderHelper,
+    class_sampler: Option<&mut ClassSampler>,
+    deduplicator: Option<&mut Deduplicator>,
+    parsed: Parsed,
+    line_number: u32,
+    options: &ConverterConfig,
+    source: Option<(&str, u64)>,
+) -> Result<(), LineError> {
+    // Entities whose identifier does not fit the range of their namespace are reported, as those
+    // would be stored as if they were other entities otherwise :(
+    let src_id = match entity_id(&parsed.entity, &options.id_encoding) {
+        Ok(src_id) => src_id,
+        Err(error) => {
+            return Err(LineError::new(
+                Failure::IdOutOfRange,
+                format!("Error storing entity at line {}: {}", line_number, error),
+            ))
+        }
+    };
+
+    // In case only a sample of the dump is stored, the entities not in the sample are just skipped
+    if let Some(sampler) = &options.sampler {
+        if !sampler.keep(src_id) {
+            return Ok(());
+        }
+    }
+
+    // Entities that are not an instance of any of the classes asked for by the user are skipped
+    // before anything is stored, as if those were not found in the dump at all :D
+    if !options.stores_instance_of(&parsed.entity) {
+        return Ok(());
+    }
+
+    // The same goes whenever a number of instances of each class is stored, in which case only
+    // those whose hash is among the smallest of the instances of any of their classes are kept :D
+    if let Some(class_sampler) = class_sampler {
+        if !class_sampler.keep(src_id, &instance_of(&parsed.entity, &options.id_encoding)) {
+            return Ok(());
+        }
+    }
+
+    // In case several inputs are processed, the entity may have been already stored from any of
+    // them. Hence, it is the deduplicator who decides whether it is stored again or not. Those
+    // replacing a previous revision are stored once it has been deleted, in batches :D
+    if let Some(deduplicator) = deduplicator {
+        match deduplicator.admit(src_id, parsed.revision) {
+            Admission::Store => (),
+            Admission::Skip => return Ok(()),
+            Admission::Replace => {
+                let replacement = Replacement {
+                    parsed,
+                    line_number,
+                    source: source.map(|(json, offset)| (json.to_string(), offset)),
+                };
+                deduplicator.defer(src_id, replacement);
+                return Ok(());
+            }
+        }
+    }
+
+    store(
+        appender_helper,
+        src_id,
+        parsed,
+        line_number,
+        options,
+        source,
+    )
+}
+
+/// The function deletes the previous revision of the entities waiting to be replaced
+/// and stores them right after, as `insert_entity` does with the rest.
+///
+/// Arguments:
+///
+/// * `appender_helper`: A mutable reference to an AppenderHelper struct, which is
+/// used to append entities to a storage backend.
+///
+/// * `connection`: A reference to the connection to the database, through which
+/// the previous revisions are deleted.
+///
+/// * `deduplicator`: A mutable reference to the `Deduplicator` holding the entities.
+///
+/// * `options`: A reference to the `ConverterConfig` of the load.
+///
+/// Returns:
+///
+/// a `Result` with the `LineError` of each of the entities that could not be
+/// stored, or a `String` with an error message in case the previous revisions could
+/// not be deleted, which halts the load.
+pub fn replace_entities(
+    appender_helper: &mut AppenderHelper,
+    connection: &Connection,
+    deduplicator: &mut Deduplicator,
+    options: &ConverterConfig,
+) -> Result<Vec<LineError>, String> {
+    let replacements = match deduplicator.replace(connection, appender_helper) {
+        Ok(replacements) => replacements,
+        Err(error) => return Err(format!("Error deleting replaced entities. {}", error)),
+    };
+
+    let mut errors = Vec::new();
+    for replacement in replacements {
+        let src_id = match entity_id(&replacement.parsed.entity, &options.id_encoding) {
+            Ok(src_id) => src_id,
+            Err(_) => continue, // the identifier was checked before deferring the entity :D
+        };
+        let source = replacement
+            .source
+            .as_ref()
+            .map(|(json, offset)| (json.as_str(), *offset));
+        if let Err(error) = store(
+            appender_helper,
+            src_id,
+            replacement.parsed,
+            replacement.line_number,
+            options,
+            source,
+        ) {
+            errors.push(error);
+        }
+    }
+    Ok(errors)
+}
+
+/// The function stores an entity already admitted, together with its provenance in
+/// case it has to be recorded.
+///
+/// Arguments:
+///
+/// * `appender_helper`: A mutable reference to the `AppenderHelper`.
+/// * `src_id`: The numeric identifier of the entity.
+/// * `parsed`: The `Parsed` entity.
+/// * `line_number`: The line the entity was found at.
+/// * `options`: A reference to the `ConverterConfig` of the load.
+/// * `source`: The input file and the byte offset the line comes from, if any.
+///
+/// Returns:
+///
+/// a `Result` with either an empty `Ok(())` or a `LineError` in case the entity
+/// could not be stored.
+fn store(
+    appender_helper: &mut AppenderHelper,
+    src_id: u64,
+    parsed: Parsed,
+    line_number: u32,
+    options: &ConverterConfig,
+    source: Option<(&str, u64)>,
+) -> Result<(), LineError> {
+    if let Err(error) = store_entity(appender_helper, parsed, options) {
+        return Err(LineError::new(
+            Failure::Storage,
+            format!("Error storing entity at line {}: {}", line_number, error),
+        ));
+    }
+
+    if let Some((json, offset)) = source {
+        if let Err(error) =
+            appender_helper.append_row(PROVENANCE_TABLE, params![src_id, json, line_number, offset])
+        {
+            return Err(LineError::new(
+                Failure::Storage,
+                format!(
+                    "Error storing provenance at line {}: {}",
+                    line_number, error
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The function returns the numeric identifier of an entity, as stored in the
+/// database.
+///
+/// Arguments:
+///
+/// * `entity`: A reference to the Wikidata entity.
+/// * `encoding`: The `IdEncoding` the identifiers are stored with.
+///
+/// Returns:
+///
+/// a `Result` with the `u64` identifier of the entity, or a `String` with an error
+/// message in case it does not fit the range of its namespace.
+pub fn entity_id(entity: &Entity, encoding: &IdEncoding) -> Result<u64, String> {
+    use wikidata::WikiId::*;
+
+    encoding.encode(match entity.id {
+        EntityId(id) => Id::Qid(id),
+        PropertyId(id) => Id::Pid(id),
+        LexemeId(id) => Id::Lid(id),
+    })
+}
+
+/// The function returns the type of an entity, as stored in the `entity_type` column
+/// of the `vertex` table; that is, `item`, `property` or `lexeme`.
+pub fn entity_type(entity: &Entity) -> &'static str {
+    use wikidata::WikiId::*;
+
+    match entity.id {
+        EntityId(_) => "item",
+        PropertyId(_) => "property",
+        LexemeId(_) => "lexeme",
+    }
+}
+
+/// The function returns the classes an entity is an instance of; that is, the items
+/// its non-deprecated instance of (P31) claims point to.
+///
+/// Arguments:
+///
+/// * `entity`: A reference to the Wikidata entity.
+/// * `encoding`: The `IdEncoding` the identifiers are stored with.
+///
+/// Returns:
+///
+/// A vector with the `u64` identifiers of the classes. Classes whose identifier is
+/// out of range are left out, as no entity could be stored with it anyway.
+pub fn instance_of(entity: &Entity, encoding: &IdEncoding) -> Vec<u64> {
+    entity
+        .claims
+        .iter()
+        .filter(|(property_id, claim_value)| {
+            *property_id == Pid(31) && claim_value.rank != Rank::Deprecated
+        })
+        .filter_map(|(_, claim_value)| match claim_value.data {
+            ClaimValueData::Item(id) => encoding.encode(Id::Qid(id)).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// This function stores entity information in a table, ignoring deprecated
+/// information.
+///
+/// Arguments:
+///
+/// * `appender_helper`: A mutable reference to an AppenderHelper struct, which is
+/// used to append data to a database table.
+///
+/// * `parsed`: The `Parsed` entity, which can be an item, property, or lexeme. It
+/// contains information such as the entity's ID, labels, descriptions, and claims
+/// (which are statements about the entity, such as its properties and values).
+///
+/// * `options`: A reference to the `ConverterConfig` of the load, which determines
+/// whether the qualifiers and the references of the claims are stored or not.
+///
+/// Returns:
+///
+/// a `Result` type with either an empty `Ok(())` value indicating success or a
+/// `String` value containing an error message in case of failure, such as any of
+/// the identifiers of the entity being out of the range of its namespace.
+pub fn store_entity(
+    appender_helper: &mut AppenderHelper,
+    parsed: Parsed,
+    options: &ConverterConfig,
+) -> Result<(), String> {
+    let entity = parsed.entity;
+    let (quantities, times) = (&parsed.quantities, &parsed.times);
+    let encoding = &options.id_encoding;
+    let src_id = entity_id(&entity, encoding)?;
+    let json = parsed.json.unwrap_or_default();
+
+    // The extra columns declared by the user for the vertices are computed out of the JSON value
+    // of the whole entity, and appended after the rest of the columns :D
+    let extra = options
+        .schema
+        .columns_of("vertex")
+        .map(|column| column.expression.evaluate(&json, false))
+        .collect::<Vec<_>>();
+
+    // We are only interested in the English label and description of the entity. This is because
+    // the rest of the information is not relevant for the processing that we are going to perform
+    // in the future. In this regard, we are only storing the English label and description of the
+    // entity in the vertices table of the database :D
+    let label = entity.labels.get(&LANG);
+    let description = entity.descriptions.get(&LANG);
+    // The revision is kept, so a newer dump can be loaded over the database later on, storing
+    // only the entities that have changed since :D
+    let revision = (parsed.revision > 0).then_some(parsed.revision);
+    // The type of the entity is stored as well, so items, properties and lexemes are told apart
+    // without decoding the ranges of their identifiers :D
+    let entity_type = entity_type(&entity);
+    // In case the user is not interested in the texts, the vertex table has no room for those :D
+    let mut row: Vec<&dyn ToSql> = match options.schema.no_text {
+        true => vec![&src_id],
+        false => vec![&src_id, &label, &description],
+    };
+    row.extend([&parsed.sitelinks as &dyn ToSql, &revision, &entity_type]);
+    row.extend(extra.iter().map(|value| value.as_ref()));
+    if appender_helper
+        .append_row("vertex", row.as_slice())
+        .is_err()
+    {
+        return Err(format!("Error inserting into VERTEX: {:?}", entity.id));
+    }
+
+    // The labels and descriptions in the languages asked for by the user are stored apart, a row
+    // per language, so the vertex table is kept as it is. Missing translations take no room :D
+    for language in &options.languages {
+        let label = entity.labels.get(language);
+        let description = entity.descriptions.get(language);
+        if (label.is_some() || description.is_some())
+            && appender_helper
+                .append_row(LABEL_TABLE, params![src_id, language.0, label, description])
+                .is_err()
+        {
+            return Err(format!("Error inserting into LABEL: {:?}", entity.id));
+        }
+
+        // Aliases are the other names an entity is known by, so those are looked up just as the
+        // labels are. An entity may have several of them per language, hence the table of their own
+        for alias in entity.aliases.get(language).into_iter().flatten() {
+            if appender_helper
+                .append_row(ALIAS_TABLE, params![src_id, language.0, alias])
+                .is_err()
+            {
+                return Err(format!("Error inserting into ALIAS: {:?}", entity.id));
+            }
+        }
+    }
+
+    // Lexemes are given their lemmas, forms and senses, so those are more than an opaque id :D
+    if let Some(lexeme) = &parsed.lexeme {
+        if let Err(error) = lexeme.append(appender_helper, src_id) {
+            return Err(format!("Error inserting into LEXEME: {:?}", error));
+        }
+    }
+
+    // Properties declare the datatype of their values, which is stored apart so the values can be
+    // audited against it once the load has finished :D
+    if let Some(datatype) = parsed.datatype {
+        if appender_helper
+            .append_row("property", params![src_id, datatype])
+            .is_err()
+        {
+            return Err(format!("Error inserting into PROPERTY: {:?}", entity.id));
+        }
+    }
+
+    // Claims whose value could not be converted are stored as they are found in the dump, so no
+    // information is silently lost. Those can be inspected, or converted, later on :D
+    for raw_claim in parsed.raw_claims {
+        if !options.stores_property(&raw_claim.property) {
+            continue;
+        }
+        if appender_helper
+            .append_row(
+                "raw_claim",
+                params![
+                    src_id,
+                    encoding.encode(Id::Pid(raw_claim.property))?,
+                    raw_claim.json
+                ],
+            )
+            .is_err()
+        {
+            return Err(format!("Error inserting into RAW_CLAIM: {:?}", entity.id));
+        }
+    }
+
+    // The extra columns of the claims are computed out of the JSON value of their statement, which
+    // is looked up by its identifier. A claim is truthy in case it is among the best ranked ones of
+    // its property; that is, it is preferred, or normal if no claim of the property is preferred :D
+    let mut statements = HashMap::new();
+    let mut preferred = HashSet::new();
+    if !options.schema.is_empty() {
+        if let Some(claims) = json.get("claims").and_then(Value::as_object) {
+            for statement in claims.values().filter_map(Value::as_array).flatten() {
+                if let Some(id) = statement.get("id").and_then(Value::as_str) {
+                    statements.insert(id, statement);
+                }
+            }
+        }
+    }
+    if !options.schema.is_empty() || options.truthy_only {
+        for (property_id, claim_value) in &entity.claims {
+            if claim_value.rank == Rank::Preferred {
+                preferred.insert(*property_id);
+            }
+        }
+    }
+
+    // Claims are found grouped by their property, so the position of each of them among the ones of
+    // its property is counted as those are stored. Notice that deprecated claims are counted too,
+    // so the positions are the very same as in the dump :D
+    let mut claim_indices: HashMap<Pid, u32> = HashMap::new();
+
+    for (property_id, claim_value) in entity.claims {
+        let counter = claim_indices.entry(property_id).or_insert(0);
+        let claim_index = *counter;
+        *counter += 1;
+
+        // Claims of the properties the user is not interested in are dropped altogether, but those
+        // are still counted, so the positions of the rest of them are kept as in the dump :D
+        if !options.stores_property(&property_id) {
+            continue;
+        }
+
+        // In case the claim value stores some outdated or wrong information, we ignore it. The
+        // deprecated annotation indicates that this piece of information should be ignored, unless
+        // the user asks for it, as those are told apart by the rank column anyway :D
+        if claim_value.rank != Rank::Deprecated || options.keep_deprecated {
+            let truthy = claim_value.rank == Rank::Preferred
+                || (claim_value.rank == Rank::Normal && !preferred.contains(&property_id));
+            if options.truthy_only && !truthy {
+                continue;
+            }
+            if !options.external_ids && matches!(claim_value.data, ClaimValueData::ExternalID(_)) {
+                continue;
+            }
+
+            let mut table = Table::from_claim(claim_value.data, encoding)?;
+            restore_exact(&mut table, quantities, times, &claim_value.id, SnakPosition::Main);
+            let statement = statements.get(claim_value.id.as_str()).copied();
+            let extra = options
+                .schema
+                .columns_of(table.as_ref())
+                .map(|column| {
+                    column
+                        .expression
+                        .evaluate(statement.unwrap_or(&Value::Null), truthy)
+                })
+                .collect::<Vec<_>>();
+
+            if let Err(error) = table.insert(
+                appender_helper,
+                &claim_value.id,
+                src_id, // identifier of the entity
+                encoding.encode(Id::Pid(property_id))?,
+                parsed.datatypes.get(&property_id),
+                claim_index,
+                rank_code(&claim_value.rank),
+                &extra,
+            ) {
+                return Err(format!("Error inserting into TABLE: {:?}", error));
+            }
+
+            // Qualifiers are stored in the qualifier_* tables, which have the same structure as the
+            // ones storing the main values, but referencing the claim they annotate :D
+            if options.qualifiers {
+                let qualifiers = claim_value.qualifiers.into_iter().enumerate();
+                for (index, (qualifier_id, qualifier_data)) in qualifiers {
+                    let mut table = Table::from_claim(qualifier_data, encoding)?;
+                    let snak = SnakPosition::Qualifier(index);
+                    restore_exact(&mut table, quantities, times, &claim_value.id, snak);
+                    if let Err(error) = table.insert_qualifier(
+                        appender_helper,
+                        &claim_value.id,
+                        src_id,
+                        encoding.encode(Id::Pid(qualifier_id))?,
+                        parsed.datatypes.get(&qualifier_id),
+                    ) {
+                        return Err(format!("Error inserting into QUALIFIER: {:?}", error));
+                    }
+                }
+            }
+
+            // References are stored in the reference_* tables the very same way, so where each claim
+            // comes from can be queried. Snaks of the same reference share its hash :D
+            if options.references {
+                for (group, reference) in claim_value.references.into_iter().enumerate() {
+                    for (index, (snak_id, snak_data)) in reference.claims.into_iter().enumerate() {
+                        let mut table = Table::from_claim(snak_data, encoding)?;
+                        let snak = SnakPosition::Reference(group, index);
+                        restore_exact(&mut table, quantities, times, &claim_value.id, snak);
+                        if let Err(error) = table.insert_reference(
+                            appender_helper,
+                            &claim_value.id,
+                            &reference.hash,
+                            src_id,
+                            encoding.encode(Id::Pid(snak_id))?,
+                            parsed.datatypes.get(&snak_id),
+                        ) {
+                            return Err(format!("Error inserting into REFERENCE: {:?}", error));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The function splits an input into the entities it holds, according to its
+/// format. XML dumps embed the JSON of each entity in the text of its revision, so
+/// those are extracted beforehand. Hence, the rest of the pipeline is the very same
+/// for every kind of dump. Entities are returned as bytes, and decoded afterwards,
+/// as damaged dumps may hold invalid UTF-8 sequences, which may be tolerated.
+///
+/// Arguments:
+///
+/// * `reader`: The buffered reader over the input.
+///
+/// * `input_format`: The format of the input.
+///
+/// * `max_line_length`: The maximum length of a line, in bytes.
+///
+/// Returns:
+///
+/// An iterator over the bytes of each of the entities.
+pub fn split_entities<'a, R: BufRead + 'a>(
+    reader: R,
+    input_format: &InputFormat,
+    max_line_length: usize,
+) -> Box<dyn Iterator<Item = std::io::Result<Vec<u8>>> + 'a> {
+    match input_format {
+        InputFormat::Json => Box::new(BoundedLines::new(reader, max_line_length)),
+        InputFormat::StreamJson => Box::new(JsonDocuments::new(reader, max_line_length)),
+        InputFormat::Xml => {
+            Box::new(XmlEntities::new(reader).map(|line| line.map(String::into_bytes)))
+        }
+        InputFormat::Ntriples => {
+            Box::new(NTriplesEntities::new(reader).map(|line| line.map(String::into_bytes)))
+        }
+    }
+}
+
+/// The function returns the number of placeholders already allocated in a database,
+/// so the ones allocated while updating it do not collide with those.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `encoding`: The `IdEncoding` the identifiers are stored with.
+///
+/// Returns:
+///
+/// a `Result` with the number of placeholders, or an `Error` if the edge tables
+/// could not be read.
+pub fn stored_placeholders(connection: &Connection, encoding: &IdEncoding) -> Result<u64, Error> {
+    let start = *encoding.range(IdKind::Placeholder).start();
+    connection.query_row(
+        "SELECT coalesce(max(dst_id) + 1 - ?, 0) FROM (\
+            SELECT dst_id FROM edge UNION ALL SELECT dst_id FROM qualifier_edge \
+            UNION ALL SELECT dst_id FROM reference_edge\
+         ) WHERE dst_id >= ?",
+        params![start, start],
+        |row| row.get::<_, u64>(0),
+    )
+}
+
+/// The hook run once every entity has been stored, right before the database is
+/// finalized. It is given the connection to the database and the configuration of
+/// the load, and returns an error message in case the load has to be halted.
+pub type AfterLoad = Box<dyn FnMut(&mut Connection, &ConverterConfig) -> Result<(), String>>;
+
+/// The `Converter` struct loads one or several dumps into a DuckDB database, so the
+/// conversion can be embedded in other programs, just as the command line tool
+/// does. It creates the tables, stores every entity of the inputs in batches, and
+/// finalizes the database. The stateful parts of the load, such as deduplicating
+/// the entities found more than once or rendering the progress, are optional, and
+/// are plugged in by setting the corresponding properties once it is created.
+///
+/// Properties:
+///
+/// * `config`: The `ConverterConfig` of the load, which is shared with the threads
+/// parsing the lines.
+/// * `checkpoint`: The `Checkpoint` the load is resumed from, if any. The first input
+/// has to be the one of the checkpoint, already skipped up to its offset.
+/// * `deduplicator`: The `Deduplicator` of the entities found more than once, if any.
+/// * `class_sampler`: The `ClassSampler` keeping a number of instances of each class,
+/// if any.
+/// * `dead_letters`: The `DeadLetters` the lines that could not be stored are
+/// written to, if any.
+/// * `progress`: The `Progress` rendered while loading, if any.
+/// * `load_log`: The `LoadLog` the throughput is recorded into, if any.
+/// * `watchdog`: The `MemoryWatchdog` ending the batch whenever the memory is running
+/// out, if any.
+/// * `budget`: The `SizeBudget` stopping the load once the database is too large,
+/// if any.
+/// * `snapshotter`: The `Snapshotter` copying the database every now and then, if any.
+/// * `after_load`: The `AfterLoad` hook, if any, such as for filling the gaps left
+/// by a filtered dump.
+pub struct Converter {
+    config: Arc<ConverterConfig>,
+    pub checkpoint: Option<Checkpoint>,
+    pub deduplicator: Option<Deduplicator>,
+    pub class_sampler: Option<ClassSampler>,
+    pub dead_letters: Option<DeadLetters>,
+    pub progress: Option<Progress>,
+    pub load_log: Option<LoadLog>,
+    pub watchdog: Option<MemoryWatchdog>,
+    pub budget: Option<SizeBudget>,
+    pub snapshotter: Option<Snapshotter>,
+    pub after_load: Option<AfterLoad>,
+}
+
+impl Converter {
+    /// The function creates a converter loading dumps as the given configuration
+    /// states, with none of the optional parts of the load plugged in.
+    ///
+    /// Arguments:
+    ///
+    /// * `config`: The `ConverterConfig` of the load.
+    ///
+    /// Returns:
+    ///
+    /// The `Converter`.
+    pub fn new(config: ConverterConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            checkpoint: None,
+            deduplicator: None,
+            class_sampler: None,
+            dead_letters: None,
+            progress: None,
+            load_log: None,
+            watchdog: None,
+            budget: None,
+            snapshotter: None,
+            after_load: None,
+        }
+    }
+
+    /// The function loads a single dump into a database, as `run_inputs` does.
+    ///
+    /// Arguments:
+    ///
+    /// * `reader`: The buffered reader over the dump, once it has been unpacked.
+    /// * `connection`: A mutable reference to the connection to the database the
+    /// dump is loaded into.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Report` of the lines that could not be stored, or a
+    /// `String` with an error message in case the load has been halted.
+    pub fn run<R: BufRead>(
+        &mut self,
+        reader: R,
+        connection: &mut Connection,
+    ) -> Result<Report, String> {
+        self.run_inputs(std::iter::once((Arc::from("the dump"), reader)), connection)
+    }
+
+    /// The function loads the given inputs into a database, one after the other. Lines
+    /// that cannot be stored are reported and skipped, while errors reading the inputs
+    /// or writing to the database halt the load. Once every entity has been stored,
+    /// the `AfterLoad` hook is run, and the database is finalized.
+    ///
+    /// Arguments:
+    ///
+    /// * `inputs`: The names of the inputs, as those are reported and recorded in the
+    /// checkpoints, together with the buffered readers over them.
+    /// * `connection`: A mutable reference to the connection to the database the
+    /// inputs are loaded into.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Report` of the lines that could not be stored, or a
+    /// `String` with an error message in case the load has been halted.
+    pub fn run_inputs<I, R>(
+        &mut self,
+        inputs: I,
+        connection: &mut Connection,
+    ) -> Result<Report, String>
+    where
+        I: Iterator<Item = (Arc<str>, R)>,
+        R: BufRead,
+    {
+        self.prepare(connection)?;
+        let report = self.load(inputs, connection)?;
+        self.finish(connection)?;
+        Ok(report)
+    }
+
+    /// The function creates the tables of the database, together with their indices
+    /// unless those are built once loaded, or not at all, and records the encoding
+    /// of the identifiers.
+    fn prepare(&self, connection: &mut Connection) -> Result<(), String> {
+        let config = &self.config;
+
+        // The identifiers are encoded as the configuration states, which is recorded in the database
+        // before anything is stored, unless it was built with another encoding :(
+        config.id_encoding.record(connection)?;
+
+        // We create the tables of the database so the elements can be inserted. For us to do so, we
+        // are creating one table per each primitive type that can be stored in Wikidata. For more
+        // details, refer to value.rs file in this same directory
+        if let Err(error) = create_tables(connection, &config.schema) {
+            return Err(format!("Error creating tables. {}", error));
+        }
+
+        // Maintaining the indices while appending slows the load down, so the user may rather have
+        // those built once at the end, or not have them at all, as DuckDB scans fast enough anyway
+        // :D
+        if !config.no_indices && !config.indices_after_load {
+            if let Err(error) = create_indices(connection) {
+                return Err(format!("Error creating indices. {}", error));
+            }
+        }
+
+        if config.unsafe_load {
+            if let Err(error) = set_durability(connection, false) {
+                return Err(format!("Error disabling checkpoints. {}", error));
+            }
+        }
+
+        if self.load_log.is_some() {
+            if let Err(error) = LoadLog::create_table(connection) {
+                return Err(format!("Error creating load log. {}", error));
+            }
+        }
+
+        if config.provenance {
+            if let Err(error) = provenance::create_table(connection) {
+                return Err(format!("Error creating provenance table. {}", error));
+            }
+        }
+
+        if !config.languages.is_empty() {
+            if let Err(error) = labels::create_tables(connection) {
+                return Err(format!("Error creating label and alias tables. {}", error));
+            }
+        }
+
+        if config.lexemes {
+            if let Err(error) = lexeme::create_tables(connection) {
+                return Err(format!("Error creating lexeme tables. {}", error));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The function stores every entity of the inputs, in batches, each of which is
+    /// committed in a transaction of its own.
+    fn load<I, R>(&mut self, inputs: I, connection: &mut Connection) -> Result<Report, String>
+    where
+        I: Iterator<Item = (Arc<str>, R)>,
+        R: BufRead,
+    {
+        let config = Arc::clone(&self.config);
+        let mut report = Report::default();
+
+        // The lines of every input are chained, so they can be consumed in batches no matter which
+        // of the inputs they come from. Each of them is tagged with the file and the line number,
+        // so the errors can be traced back to their origin. Besides, the bytes consumed from each
+        // input are counted, so the offset each line starts at is known once the previous one has
+        // been read :D
+        let input_format = config.input_format.clone();
+        let max_line_length = config.max_line_length;
+        let checkpoint = self.checkpoint.clone();
+        // Whenever a load is resumed, the first input is the one of the checkpoint, which is read
+        // from the line committed last on, so this line is skipped, as it was already stored. Its
+        // bytes are counted from the offset of the checkpoint, as the ones before it have been
+        // already skipped
+        let lines = inputs.enumerate().flat_map(move |(index, (json, reader))| {
+            let reader = Counted::new(reader);
+            let counter = reader.counter();
+            let resumed = checkpoint.as_ref().filter(|_| index == 0);
+            if let Some(checkpoint) = resumed {
+                counter.set(checkpoint.offset);
+            }
+            let first_line = resumed.map_or(0, |checkpoint| checkpoint.line);
+            let mut start = counter.get();
+            split_entities(reader, &input_format, max_line_length)
+                .enumerate() // we enumerate the iterator so we can know the line number
+                .map(move |(line_number, line)| {
+                    let offset = start;
+                    start = counter.get();
+                    (json.clone(), first_line + line_number, offset, line)
+                })
+                .skip(usize::from(resumed.is_some()))
+        });
+
+        // Parsing the JSON takes most of the time of the load, so the lines may be parsed on
+        // several threads, while the entities are still stored by this one, as the appenders of a
+        // transaction cannot be shared. Unless the user asks otherwise, those are stored as soon as
+        // parsed :D The raw line is only kept whenever the failures are written to the sidecar
+        // file, as it is consumed while parsing it otherwise
+        let keep_raw = self.dead_letters.is_some();
+        let parse = {
+            let config = Arc::clone(&config);
+            move |(json, line_number, offset, line): (
+                Arc<str>,
+                usize,
+                u64,
+                std::io::Result<Vec<u8>>,
+            )| {
+                let raw = match keep_raw {
+                    true => line.as_ref().ok().cloned(),
+                    false => None,
+                };
+                let parsed = parse_line(&json, line_number, line, &config);
+                (json, line_number, offset, raw, parsed)
+            }
+        };
+        let mut lines: Box<dyn Iterator<Item = _> + '_> = match config.threads.get() {
+            1 => Box::new(lines.map(parse).map(Ok)),
+            _ => Box::new(Pipeline::new(
+                lines,
+                config.threads,
+                config.preserve_order,
+                parse,
+            )),
+        };
+
+        // Placeholders have to be unique across loads, so those already allocated in the database
+        // are skipped, in case it was created by a previous one :D
+        let mut placeholders = match stored_placeholders(connection, &config.id_encoding) {
+            Ok(placeholders) => placeholders,
+            Err(error) => return Err(format!("Error reading placeholders. {}", error)),
+        };
+        let mut processed = 0;
+        let mut rows = HashMap::new();
+        let mut last_line = None;
+        let mut exhausted = false;
+        loop {
+            // Transactions can improve performance by reducing the number of disk
+            // writes and network round trips. When you wrap multiple inserts within a transaction,
+            // the database can optimize the write operations by batching them together and
+            // committing them as a single unit. This can reduce the overhead of repeated disk I/O
+            // operations and improve overall insert speed.
+            let mut transaction = match connection.transaction() {
+                Ok(transaction) => transaction,
+                Err(error) => return Err(format!("Error opening transaction. {}", error)),
+            };
+
+            // We set the drop behavior to commit so that the transaction is committed when it is
+            // dropped. Unless the load can be resumed, as the entities stored after the checkpoint
+            // would be stored once again by resuming it :(
+            let resumable = config.checkpoint_every.is_some() || self.checkpoint.is_some();
+            transaction.set_drop_behavior(match resumable {
+                true => DropBehavior::Rollback,
+                false => DropBehavior::Commit,
+            });
+
+            // Appenders also allow inserting entities in a better fashion. This allows a faster
+            // performance and an easier implementation of the algorithm. Notice that placeholders
+            // have to be unique across transactions, so the counter is carried over :D
+            let mut appender_helper = AppenderHelper::new(&transaction, config.id_encoding);
+            appender_helper.placeholders = placeholders;
+            appender_helper.rows = rows;
+            appender_helper.skipped = config.skipped.clone();
+            let mut tables = Vec::new();
+            if config.provenance {
+                tables.push(PROVENANCE_TABLE);
+            }
+            if !config.languages.is_empty() {
+                tables.extend([LABEL_TABLE, ALIAS_TABLE]);
+            }
+            if config.lexemes {
+                tables.extend(LEXEME_TABLES);
+            }
+            for table in tables {
+                match transaction.appender(table) {
+                    Ok(appender) => {
+                        appender_helper.appenders.insert(table, appender);
+                    }
+                    Err(error) => return Err(format!("Error appending to {}. {}", table, error)),
+                }
+            }
+
+            // Once the size budget has been reached, no more lines are read, so the load goes
+            // straight to its end, as if the dump had been fully processed :D
+            let remaining = if exhausted { 0 } else { usize::MAX };
+
+            let mut finished = true;
+            let mut aborted = false;
+            for mapped in lines.by_ref().take(remaining) {
+                let (json, line_number, offset, raw, parsed) = mapped?;
+                last_line = Some((json.clone(), line_number, offset));
+
+                // try to insert the entity in the database and handle errors appropriately. Notice
+                // that the lines that are just delimiters of the dump are parsed to nothing at all
+                if let Err(error) = parsed?.and_then(|parsed| match parsed {
+                    Some(parsed) => insert_entity(
+                        &mut appender_helper,
+                        self.class_sampler.as_mut(),
+                        self.deduplicator.as_mut(),
+                        parsed,
+                        line_number as u32,
+                        &config,
+                        config.provenance.then_some((&json, offset)),
+                    ),
+                    None => Ok(()),
+                }) {
+                    // do not halt execution in case an error happens, just warn the user :D
+                    eprintln!("Error inserting entity from {}. {}", json, error);
+                    if let Some(dead_letters) = self.dead_letters.as_mut() {
+                        if let Err(error) =
+                            dead_letters.write(&json, line_number, raw.as_deref(), &error)
+                        {
+                            return Err(format!("Error writing failed line. {}", error));
+                        }
+                    }
+                    report.record(error.failure);
+
+                    // A systematic failure, such as a change in the format of the dump, would make
+                    // most of the lines fail. Hence, we rather stop than finding it out hours later
+                    // :(
+                    if let Some(abort_after) = config.abort_after {
+                        if report.total() > abort_after {
+                            aborted = true;
+                            break;
+                        }
+                    }
+                }
+
+                // The entities replacing a previous revision are stored in batches, as deleting the
+                // rows of each of them on its own would take way longer :D
+                if let Some(deduplicator) = self.deduplicator.as_mut().filter(|d| d.is_full()) {
+                    for error in
+                        replace_entities(&mut appender_helper, &transaction, deduplicator, &config)?
+                    {
+                        eprintln!("Error inserting entity. {}", error);
+                        report.record(error.failure);
+                    }
+                }
+
+                processed += 1;
+
+                if let Some(progress) = self.progress.as_mut() {
+                    progress.tick(processed, &appender_helper.rows);
+                }
+
+                // The appenders buffer the rows until those are flushed, so the user may rather
+                // have them written more often, which takes less memory at the cost of some
+                // throughput :D
+                if let Some(batch_size) = config.batch_size {
+                    if processed % batch_size.get() == 0 {
+                        if let Err(error) = appender_helper.flush() {
+                            return Err(format!("Error flushing rows. {}", error));
+                        }
+                    }
+                }
+
+                if let Some(load_log) = self.load_log.as_mut().filter(|l| l.is_due()) {
+                    if let Err(error) =
+                        load_log.record(&transaction, processed as u64, &appender_helper.rows)
+                    {
+                        eprintln!("Error recording load log. {}", error);
+                    }
+                }
+
+                // In case the user has asked for periodic checkpoints, the batch is over once
+                // enough entities have been processed, so the WAL and the buffers do not grow
+                // unboundedly
+                if let Some(checkpoint_every) = config.checkpoint_every {
+                    if processed % checkpoint_every.get() == 0 {
+                        finished = false;
+                        break;
+                    }
+                }
+
+                // The same goes whenever the memory is running out, instead of waiting for the OOM
+                // killer to end the load :(
+                if let Some(watchdog) = self.watchdog.as_mut() {
+                    let batch_size = watchdog.batch_size();
+                    if watchdog.should_commit() {
+                        if watchdog.batch_size() != batch_size {
+                            eprintln!(
+                                "\nMemory limit approached. Committing every {} entities",
+                                watchdog.batch_size().unwrap_or_default()
+                            );
+                        }
+                        finished = false;
+                        break;
+                    }
+                }
+
+                // The size of the database can only be measured once the batch is committed and
+                // checkpointed, so the batch is ended every now and then :D
+                if self.budget.as_mut().is_some_and(SizeBudget::should_commit) {
+                    finished = false;
+                    break;
+                }
+
+                // Snapshots are taken once the batch is committed and checkpointed, as it is only
+                // then that the database file holds every row stored so far :D
+                if self.snapshotter.as_ref().is_some_and(Snapshotter::is_due) {
+                    finished = false;
+                    break;
+                }
+            }
+
+            // The entities still waiting to be replaced are stored within the batch, so the
+            // checkpoint recorded at its end does not leave any of them behind :D
+            if let Some(deduplicator) = self.deduplicator.as_mut() {
+                for error in
+                    replace_entities(&mut appender_helper, &transaction, deduplicator, &config)?
+                {
+                    eprintln!("Error inserting entity. {}", error);
+                    report.record(error.failure);
+                }
+            }
+
+            // The same goes for the instances pushed out of the sample by others with a smaller hash,
+            // which may have been stored already :(
+            if let Some(class_sampler) = self.class_sampler.as_mut() {
+                let evicted = class_sampler.take_evicted();
+                if !evicted.is_empty() {
+                    if let Err(error) = appender_helper
+                        .flush()
+                        .and_then(|_| dedup::delete(&transaction, &evicted))
+                    {
+                        return Err(format!("Error deleting entities out of the sample. {}", error));
+                    }
+                }
+            }
+
+            // Appenders buffer the rows in memory, so they have to be dropped (and hence flushed)
+            // before the transaction is committed. Otherwise, the last rows would not be visible to
+            // the queries that are run while finalizing the database :(
+            placeholders = appender_helper.placeholders;
+            rows = std::mem::take(&mut appender_helper.rows);
+            drop(appender_helper);
+
+            // The line the batch ends at is recorded within the very same transaction, so the load
+            // can be resumed right after it. Notice that it is only known whenever the entities are
+            // stored in the order of the dump, as those parsed on several threads may be stored out
+            // of it :(
+            if !finished || aborted {
+                if let Some((json, line, offset)) =
+                    last_line.as_ref().filter(|_| config.is_ordered())
+                {
+                    let checkpoint = Checkpoint {
+                        input: json.to_string(),
+                        line: *line,
+                        offset: *offset,
+                    };
+                    if let Err(error) = checkpoint.write(&transaction) {
+                        return Err(format!("Error recording checkpoint. {}", error));
+                    }
+                }
+            }
+
+            if !finished {
+                if let Err(error) = transaction.commit() {
+                    return Err(format!("Error committing transaction. {}", error));
+                }
+                if let Err(error) = connection.execute_batch("CHECKPOINT;") {
+                    return Err(format!("Error checkpointing database. {}", error));
+                }
+                if let Some(snapshotter) = self.snapshotter.as_mut().filter(|s| s.is_due()) {
+                    // A failed snapshot does not halt the load, as the next one may succeed :D
+                    match snapshotter.take(connection) {
+                        Ok(path) => println!(
+                            "\nSnapshot of {} entities taken at {}",
+                            processed,
+                            path.display()
+                        ),
+                        Err(error) => eprintln!("\nError taking snapshot. {}", error),
+                    }
+                }
+                // The line the load stopped at is recorded in the metadata, so it is known which
+                // part of the dump the database holds
+                if self.budget.as_mut().is_some_and(SizeBudget::is_exhausted) {
+                    if let Some((json, line_number, _)) = &last_line {
+                        let stopped_at = format!("{}:{}", json, line_number);
+                        if let Err(error) = metadata::set(connection, "stopped_at", &stopped_at) {
+                            return Err(format!(
+                                "Error recording where the load stopped. {}",
+                                error
+                            ));
+                        }
+                        println!(
+                            "\nSize budget reached. Stopping after {} entities, at line {} of {}",
+                            processed, line_number, json
+                        );
+                    }
+                    exhausted = true;
+                }
+                continue;
+            }
+
+            if let Some(progress) = self.progress.as_mut() {
+                progress.finish(processed, &rows);
+            }
+
+            // Once the whole dump has been processed, we summarize the failures found, so the user
+            // does not have to go through the whole error log to know what went wrong :D
+            if report.total() > 0 {
+                eprintln!("\n{}", report);
+            }
+            if let Some(dead_letters) = self.dead_letters.as_mut() {
+                if let Err(error) = dead_letters.flush() {
+                    return Err(format!("Error writing failed lines. {}", error));
+                }
+                if dead_letters.written() > 0 {
+                    eprintln!(
+                        "{} failed lines written to {}",
+                        dead_letters.written(),
+                        dead_letters.path().display()
+                    );
+                }
+            }
+
+            // In case the load has been aborted, what has been stored so far is committed and
+            // checkpointed, so the database can be inspected in order to find out what went wrong
+            if aborted {
+                if config.unsafe_load {
+                    if let Err(error) = set_durability(&transaction, true) {
+                        return Err(format!("Error enabling checkpoints. {}", error));
+                    }
+                }
+                if let Err(error) = transaction.commit() {
+                    return Err(format!("Error committing transaction. {}", error));
+                }
+                if let Err(error) = connection.execute_batch("CHECKPOINT;") {
+                    return Err(format!("Error checkpointing database. {}", error));
+                }
+                return Err(format!(
+                    "Load aborted after {} failed lines, {} processed",
+                    report.total(),
+                    processed
+                ));
+            }
+
+            // The last record of the log is the one of the whole load, no matter when the previous
+            // one was taken :D
+            if let Some(load_log) = self.load_log.as_mut() {
+                if let Err(error) = load_log.record(&transaction, processed as u64, &rows) {
+                    eprintln!("Error recording load log. {}", error);
+                }
+            }
+
+            // Once the whole dump has been stored, there is nothing left to resume :D
+            if let Err(error) = Checkpoint::clear(&transaction) {
+                return Err(format!("Error clearing checkpoint. {}", error));
+            }
+
+            // In case checkpoints were disabled, those are re-enabled before committing, so that
+            // the database file is consistent on disk once the load finishes
+            if config.unsafe_load {
+                if let Err(error) = set_durability(&transaction, true) {
+                    return Err(format!("Error enabling checkpoints. {}", error));
+                }
+            }
+
+            if let Err(error) = transaction.commit() {
+                return Err(format!("Error committing transaction. {}", error));
+            }
+
+            break;
+        }
+
+        if config.unsafe_load {
+            if let Err(error) = connection.execute_batch("CHECKPOINT;") {
+                return Err(format!("Error checkpointing database. {}", error));
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// The function runs the `AfterLoad` hook, builds the indices in case those were
+    /// left for the end, and finalizes the database.
+    fn finish(&mut self, connection: &mut Connection) -> Result<(), String> {
+        // The datatypes of the properties are only known once their triples have been read, which
+        // may be after those of the claims using them, so the latter are fixed at the end :(
+        if self.config.input_format == InputFormat::Ntriples {
+            match ntriples::fix_datatypes(connection, self.config.external_ids) {
+                Ok(0) => (),
+                Ok(fixed) => println!("\nFixed the datatypes of {} claims", fixed),
+                Err(error) => return Err(format!("Error fixing datatypes. {}", error)),
+            }
+        }
+
+        if let Some(after_load) = self.after_load.as_mut() {
+            after_load(connection, &self.config)?;
+        }
+
+        // Building the indices at once, over the whole tables, is way cheaper than updating them on
+        // every append
+        if self.config.indices_after_load {
+            if let Err(error) = create_indices(connection) {
+                return Err(format!("Error creating indices. {}", error));
+            }
+        }
+
+        // Once every entity has been stored, the database is finalized by installing the helper
+        // macros, such as instances_of(class_id) or subclasses_of(class_id), that ease querying it
+        if let Err(error) = finalize(connection, &self.config.derived, &self.config.partitions) {
+            return Err(format!("Error finalizing database. {}", error));
+        }
+
+        Ok(())
+    }
+}
+
+/// The function sets the durability guarantees of the database during the load.
+/// When those are relaxed, DuckDB does not checkpoint the write-ahead log into the
+/// database file until the load finishes, which avoids loads of disk writes.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// * `durable`: Whether the default checkpointing behavior has to be restored or
+/// automatic checkpoints have to be disabled.
+///
+/// Returns:
+///
+/// The function `set_durability` returns a `Result` enum with either an `Ok(())`
+/// value indicating that the function executed successfully, or an `Err` value
+/// containing an `Error` object if an error occurred during execution.
+fn set_durability(connection: &Connection, durable: bool) -> Result<(), Error> {
+    // DuckDB does not allow disabling fsyncs, but it checkpoints the WAL into the database file
+    // each time it grows over the threshold. By making it huge, checkpoints only happen when we
+    // explicitly ask for them. Note that 16MB is the default value of the threshold :D
+    connection.execute_batch(if durable {
+        "SET checkpoint_threshold = '16MB';"
+    } else {
+        "SET checkpoint_threshold = '1TB';"
+    })
+}
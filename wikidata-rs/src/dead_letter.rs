@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::validation::LineError;
+
+/// The `DeadLetters` struct writes the lines that could not be stored to a sidecar
+/// file, so those are not lost once the load has finished. Each of them is written
+/// as a JSON object of its own, one per line, with the input and the number of the
+/// line, the kind of failure, the error message and the raw line itself. Hence, the
+/// failures can be inspected, or loaded again on their own, by extracting the `raw`
+/// field of every object, such as with `jq -r .raw bad_lines.jsonl`.
+///
+/// Properties:
+///
+/// * `path`: The path of the sidecar file.
+/// * `writer`: The buffered writer over the sidecar file.
+/// * `written`: The number of lines written so far.
+pub struct DeadLetters {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    written: usize,
+}
+
+impl DeadLetters {
+    /// The function creates the sidecar file, truncating it in case it already
+    /// exists, so it only holds the failures of the current load.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: A reference to the path of the sidecar file.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `DeadLetters`, or an `Error` if the file could not be
+    /// created.
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(File::create(path)?),
+            written: 0,
+        })
+    }
+
+    /// The function writes a line that could not be stored to the sidecar file.
+    ///
+    /// Arguments:
+    ///
+    /// * `source`: The input the line comes from.
+    /// * `line_number`: The number of the line within the input.
+    /// * `raw`: The bytes of the line, or `None` in case those could not even be
+    /// read, as it happens with the lines that are too long.
+    /// * `error`: A reference to the `LineError` the line failed with.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// line is written, or an `Error` otherwise.
+    pub fn write(
+        &mut self,
+        source: &str,
+        line_number: usize,
+        raw: Option<&[u8]>,
+        error: &LineError,
+    ) -> Result<()> {
+        // Lines with invalid UTF-8 are only found here whenever those were replaced, so the raw
+        // line is written as it was replaced as well :D
+        let record = json!({
+            "source": source,
+            "line": line_number,
+            "failure": error.failure.to_string(),
+            "error": error.message,
+            "raw": raw.map(String::from_utf8_lossy),
+        });
+        writeln!(self.writer, "{}", record)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// The function returns the path of the sidecar file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The function returns the number of lines written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// The function flushes the lines buffered, so every one of them is on disk.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// lines are flushed, or an `Error` otherwise.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, HashMap};
+
+use duckdb::{Connection, Error};
+use strum_macros::EnumString;
+
+use crate::converter::Parsed;
+use crate::labels::{ALIAS_TABLE, LABEL_TABLE};
+use crate::lexeme::{FORM_FEATURE_TABLE, FORM_TABLE, LEMMA_TABLE, LEXEME_TABLE, SENSE_TABLE};
+use crate::provenance::PROVENANCE_TABLE;
+use crate::schema;
+use crate::stats::table_names;
+use crate::value::{AppenderHelper, Table};
+
+/// The `OnConflict` enum defines what to do with an entity that has already been
+/// stored, but whose revision differs from the one found. `Skip` keeps the entity
+/// stored first, while `Replace` removes it and stores the one found instead.
+/// Entities found twice with the very same revision are always skipped.
+#[derive(Clone, Debug, Default, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum OnConflict {
+    #[default]
+    Skip,
+    Replace,
+}
+
+/// The number of entities whose previous revision is deleted at once. Those are
+/// not stored until then, as their rows would be deleted along with the old ones.
+const REPLACEMENTS_PER_BATCH: usize = 10_000;
+
+/// The name of the temporary table holding the identifiers of the entities whose
+/// previous revision is being deleted.
+const CHANGED_TABLE: &str = "wd2duckdb_changed";
+
+/// The `Admission` enum defines what is done with an entity found during the load.
+/// `Store` stands for those found for the first time, `Skip` for those already
+/// stored that are kept as they are, and `Replace` for those whose previous
+/// revision has to be deleted before storing them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Admission {
+    Store,
+    Skip,
+    Replace,
+}
+
+/// The `Replacement` struct holds an entity whose previous revision has to be
+/// deleted before storing it, together with where it was found.
+///
+/// Properties:
+///
+/// * `parsed`: The `Parsed` entity.
+/// * `line_number`: The line the entity was found at.
+/// * `source`: The input file and the byte offset the line comes from, in case the
+/// provenance of the entities is recorded.
+pub struct Replacement {
+    pub parsed: Parsed,
+    pub line_number: u32,
+    pub source: Option<(String, u64)>,
+}
+
+/// The `Deduplicator` struct keeps track of the entities stored during the load,
+/// together with their revision, so those found again in other inputs are not
+/// stored twice.
+///
+/// Properties:
+///
+/// * `policy`: The `OnConflict` policy applied to entities already stored.
+/// * `revisions`: The `lastrevid` of each of the entities stored so far, indexed by
+/// their numeric identifier.
+/// * `replacements`: The entities to be replaced, indexed by their numeric
+/// identifier, whose previous revision has not been deleted yet.
+pub struct Deduplicator {
+    policy: OnConflict,
+    revisions: HashMap<u64, u64>,
+    replacements: BTreeMap<u64, Replacement>,
+}
+
+impl Deduplicator {
+    pub fn new(policy: OnConflict) -> Self {
+        Self {
+            policy,
+            revisions: HashMap::new(),
+            replacements: BTreeMap::new(),
+        }
+    }
+
+    /// The function creates a deduplicator aware of the entities already stored in
+    /// a database, as recorded by their `lastrevid`, so a newer dump can be loaded
+    /// over it, only storing again the entities that have changed.
+    ///
+    /// Arguments:
+    ///
+    /// * `policy`: The `OnConflict` policy applied to entities already stored.
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing the `Deduplicator`, or an
+    /// `Error` object if the revisions could not be read.
+    pub fn from_database(policy: OnConflict, connection: &Connection) -> Result<Self, Error> {
+        let mut statement =
+            connection.prepare("SELECT id, lastrevid FROM vertex WHERE lastrevid IS NOT NULL")?;
+        let revisions = statement
+            .query_map([], |row| Ok((row.get::<_, u64>(0)?, row.get::<_, u64>(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(Self {
+            policy,
+            revisions,
+            replacements: BTreeMap::new(),
+        })
+    }
+
+    /// The function decides what is done with an entity according to the entities
+    /// stored so far.
+    ///
+    /// Arguments:
+    ///
+    /// * `src_id`: The numeric identifier of the entity.
+    /// * `revision`: The `lastrevid` of the entity.
+    ///
+    /// Returns:
+    ///
+    /// The `Admission` of the entity. Those to be replaced have to be handed over
+    /// through `defer`, as their previous revision is deleted in batches.
+    pub fn admit(&mut self, src_id: u64, revision: u64) -> Admission {
+        match self.revisions.insert(src_id, revision) {
+            None => Admission::Store,
+            Some(stored) if stored == revision => Admission::Skip,
+            Some(stored) => match self.policy {
+                OnConflict::Skip => {
+                    self.revisions.insert(src_id, stored); // we keep track of the one stored
+                    Admission::Skip
+                }
+                OnConflict::Replace => Admission::Replace,
+            },
+        }
+    }
+
+    /// The function keeps an entity to be replaced until its previous revision is
+    /// deleted. In case the entity was already waiting, such as when it is found in
+    /// several inputs, only the one admitted last is kept.
+    ///
+    /// Arguments:
+    ///
+    /// * `src_id`: The numeric identifier of the entity.
+    /// * `replacement`: The `Replacement` holding the entity.
+    pub fn defer(&mut self, src_id: u64, replacement: Replacement) {
+        self.replacements.insert(src_id, replacement);
+    }
+
+    /// The function returns whether enough entities are waiting to be replaced, so
+    /// their previous revisions are better deleted right away.
+    pub fn is_full(&self) -> bool {
+        self.replacements.len() >= REPLACEMENTS_PER_BATCH
+    }
+
+    /// The function deletes the previous revision of every entity waiting to be
+    /// replaced, with a single statement per table, and returns those entities, so
+    /// they are stored right after.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection the rows are deleted through.
+    /// * `appender_helper`: A mutable reference to the `AppenderHelper`, which is
+    /// flushed before deleting any row, as some of those may still be buffered.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing the entities to be stored,
+    /// or an `Error` object if the previous revisions could not be deleted.
+    pub fn replace(
+        &mut self,
+        connection: &Connection,
+        appender_helper: &mut AppenderHelper,
+    ) -> Result<Vec<Replacement>, Error> {
+        if self.replacements.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        appender_helper.flush()?;
+        let ids = self.replacements.keys().copied().collect::<Vec<_>>();
+        delete(connection, &ids)?;
+
+        Ok(std::mem::take(&mut self.replacements)
+            .into_values()
+            .collect())
+    }
+}
+
+/// The function returns the statements deleting the rows of the changed entities
+/// from the tables that are only created whenever asked for, such as the `label` or
+/// the `lexeme` ones, leaving out those that do not exist in the database. Notice
+/// that the grammatical features of the forms are deleted before the forms, as those
+/// are only linked to the lexeme through them.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` with the `DELETE` statements, or an `Error` if the tables could not
+/// be listed.
+fn related_deletes(connection: &Connection) -> Result<Vec<String>, Error> {
+    let tables = table_names(connection)?;
+    let changed = format!("SELECT id FROM {}", CHANGED_TABLE);
+    let statements = [
+        (
+            LABEL_TABLE,
+            format!("DELETE FROM {} WHERE id IN ({})", LABEL_TABLE, changed),
+        ),
+        (
+            ALIAS_TABLE,
+            format!("DELETE FROM {} WHERE id IN ({})", ALIAS_TABLE, changed),
+        ),
+        (
+            PROVENANCE_TABLE,
+            format!("DELETE FROM {} WHERE id IN ({})", PROVENANCE_TABLE, changed),
+        ),
+        (
+            LEXEME_TABLE,
+            format!("DELETE FROM {} WHERE id IN ({})", LEXEME_TABLE, changed),
+        ),
+        (
+            LEMMA_TABLE,
+            format!("DELETE FROM {} WHERE id IN ({})", LEMMA_TABLE, changed),
+        ),
+        (
+            FORM_FEATURE_TABLE,
+            format!(
+                "DELETE FROM {} WHERE id IN (SELECT id FROM {} WHERE lexeme_id IN ({}))",
+                FORM_FEATURE_TABLE, FORM_TABLE, changed
+            ),
+        ),
+        (
+            FORM_TABLE,
+            format!(
+                "DELETE FROM {} WHERE lexeme_id IN ({})",
+                FORM_TABLE, changed
+            ),
+        ),
+        (
+            SENSE_TABLE,
+            format!(
+                "DELETE FROM {} WHERE lexeme_id IN ({})",
+                SENSE_TABLE, changed
+            ),
+        ),
+    ];
+    Ok(statements
+        .into_iter()
+        .filter(|(table_name, _)| tables.iter().any(|table| table == table_name))
+        .map(|(_, statement)| statement)
+        .collect())
+}
+
+/// The function deletes every row of the given entities; that is, their vertex,
+/// the values, qualifiers and references of their claims, and their rows in the
+/// rest of the tables of the database, such as their labels in other languages.
+/// The identifiers are stored in a temporary table first, so a single statement
+/// per table deletes the rows of all of them.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `ids`: The numeric identifiers of the entities.
+pub(crate) fn delete(connection: &Connection, ids: &[u64]) -> Result<(), Error> {
+    connection.execute_batch(&format!(
+        "CREATE OR REPLACE TEMP TABLE {} (id UBIGINT NOT NULL);",
+        CHANGED_TABLE
+    ))?;
+    // The identifiers are plain numbers, so those are written into the statement as they are :D
+    for chunk in ids.chunks(1_000) {
+        connection.execute_batch(&format!(
+            "INSERT INTO {} VALUES {};",
+            CHANGED_TABLE,
+            chunk
+                .iter()
+                .map(|id| format!("({})", id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))?;
+    }
+
+    let mut table_names: Vec<(&str, &str)> = Vec::new();
+    for table in Table::iterator() {
+        let column = match table {
+            Table::Vertex { .. } | Table::Property { .. } => "id",
+            _ => "src_id",
+        };
+        for scope in table.scopes() {
+            if !table_names.contains(&(table.table_name(scope), column)) {
+                table_names.push((table.table_name(scope), column));
+            }
+        }
+    }
+
+    for (table_name, column) in table_names {
+        connection.execute_batch(&format!(
+            "DELETE FROM {} WHERE {} IN (SELECT id FROM {});",
+            table_name, column, CHANGED_TABLE
+        ))?;
+    }
+
+    for statement in related_deletes(connection)? {
+        connection.execute_batch(&statement)?;
+    }
+
+    connection.execute_batch(&format!("DROP TABLE {};", CHANGED_TABLE))
+}
+
+/// The function removes the duplicated vertices once the load has finished, so the
+/// `vertex` table holds a single row per entity, no matter how many times it was
+/// found in the inputs. Of the rows of an entity, the one of the latest revision is
+/// kept, preferring those with a label and a description whenever there is a tie.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` with the number of rows removed, or an `Error` if those could not
+/// be removed.
+pub fn dedup_vertices(connection: &Connection) -> Result<usize, Error> {
+    // Databases built with --no-text have neither labels nor descriptions to break the ties :(
+    let texts = match schema::has_column(connection, "vertex", "label")? {
+        true => ", label IS NULL, description IS NULL",
+        false => "",
+    };
+
+    // Rows are removed in place, rather than creating the table again, so its indices are kept :D
+    connection.execute(
+        &format!(
+            "DELETE FROM vertex WHERE rowid IN (\
+                SELECT rowid FROM (\
+                    SELECT rowid, row_number() OVER (\
+                        PARTITION BY id ORDER BY lastrevid DESC NULLS LAST{}\
+                    ) AS position FROM vertex\
+                ) WHERE position > 1\
+            )",
+            texts
+        ),
+        [],
+    )
+}
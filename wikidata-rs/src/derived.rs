@@ -0,0 +1,181 @@
+use duckdb::{Connection, Error};
+use strum_macros::{EnumIter, EnumString};
+use wikidata::Pid;
+
+use crate::dtype::Target;
+use crate::id::{Id, IdEncoding};
+
+/// The `Derived` enum defines the optional tables that can be computed out of the
+/// already loaded ones once the load has finished. As those may take a while to be
+/// built for a full dump, the user has to explicitly ask for them; for example,
+/// `--derive quantity-si`.
+#[derive(Clone, Debug, PartialEq, EnumIter, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Derived {
+    QuantitySi,
+    QuantityStats,
+    DatatypeMismatch,
+    PropertyCooccurrence,
+}
+
+impl Derived {
+    /// The function returns the query whose results populate the derived table.
+    ///
+    /// Arguments:
+    ///
+    /// * `encoding`: The `IdEncoding` of the database the table is created in.
+    ///
+    /// Returns:
+    ///
+    /// A `String` containing the `SELECT` statement the table is created from.
+    fn query(&self, encoding: &IdEncoding) -> String {
+        match self {
+            // Units in Wikidata are annotated with their conversion to SI unit (P2370), which is
+            // stored as any other quantity: the amount is the factor and the unit is the SI one.
+            // Hence, normalizing is just a matter of joining the quantity table with itself. Note
+            // that quantities whose unit has no conversion (or that have no unit at all) are kept
+            // as they are, and that non-linear conversions (°F to K) are out of scope :(
+            // Units with several conversions are given the best ranked one, and the first of those
+            // in the dump, so the very same factor is picked on every run. Deprecated conversions
+            // are never picked, as those are known to be wrong
+            Derived::QuantitySi => format!(
+                "SELECT q.src_id, q.property_id, \
+                 q.amount * coalesce(c.amount, 1) AS amount_si, \
+                 coalesce(c.unit_id, q.unit_id) AS si_unit_id \
+                 FROM quantity q LEFT JOIN (\
+                    SELECT src_id, amount, unit_id FROM quantity WHERE property_id = {} AND rank > 0 \
+                    QUALIFY row_number() OVER (\
+                        PARTITION BY src_id ORDER BY rank DESC, claim_index, statement_id\
+                    ) = 1\
+                 ) c ON c.src_id = q.unit_id",
+                encoding.known(Id::Pid(Pid(2370)))
+            ),
+            // Quantities are summarized per property and unit, as comparing heights in meters with
+            // heights in centimeters makes no sense at all. Besides the percentiles, the number of
+            // outliers according to Tukey's fences (1.5 times the interquartile range beyond the
+            // quartiles) is computed, so impossible values, such as a human 5 meters tall, pop out
+            Derived::QuantityStats => "WITH stats AS (\
+                    SELECT property_id, unit_id, count(*) AS count, \
+                    min(amount) AS min, \
+                    quantile_cont(amount, 0.01) AS p01, \
+                    quantile_cont(amount, 0.25) AS p25, \
+                    quantile_cont(amount, 0.5) AS median, \
+                    quantile_cont(amount, 0.75) AS p75, \
+                    quantile_cont(amount, 0.99) AS p99, \
+                    max(amount) AS max, \
+                    avg(amount) AS mean, \
+                    stddev_samp(amount) AS stddev \
+                    FROM quantity GROUP BY property_id, unit_id\
+                 ), outliers AS (\
+                    SELECT q.property_id, q.unit_id, count(*) AS outliers \
+                    FROM quantity q JOIN stats s ON q.property_id = s.property_id \
+                    AND q.unit_id IS NOT DISTINCT FROM s.unit_id \
+                    WHERE q.amount < s.p25 - 1.5 * (s.p75 - s.p25) \
+                    OR q.amount > s.p75 + 1.5 * (s.p75 - s.p25) \
+                    GROUP BY q.property_id, q.unit_id\
+                 ) \
+                 SELECT s.*, coalesce(o.outliers, 0) AS outliers \
+                 FROM stats s LEFT JOIN outliers o ON s.property_id = o.property_id \
+                 AND s.unit_id IS NOT DISTINCT FROM o.unit_id"
+                .to_string(),
+            // The datatype declared by each property determines the table its values should land
+            // in. Hence, comparing both of them reveals anomalies of the dump, as well as bugs of
+            // the converter. Notice that claims with no value, or with an unknown one, are not
+            // taken into account, as those are stored in the edge table no matter the datatype
+            Derived::DatatypeMismatch => format!(
+                "WITH expected(datatype, table_name) AS (VALUES \
+                    ('wikibase-item', 'edge'), ('wikibase-property', 'edge'), \
+                    ('wikibase-lexeme', 'edge'), ('wikibase-form', 'edge'), \
+                    ('wikibase-sense', 'edge'), ('string', 'string'), \
+                    ('external-id', 'external_id'), ('url', 'url'), \
+                    ('commonsMedia', 'string'), ('monolingualtext', 'text'), \
+                    ('math', 'string'), ('musical-notation', 'string'), \
+                    ('geo-shape', 'string'), ('tabular-data', 'string'), \
+                    ('globe-coordinate', 'coordinates'), ('quantity', 'quantity'), \
+                    ('time', 'time')\
+                 ), landed AS (\
+                    SELECT property_id, 'edge' AS table_name, count(*) AS claims \
+                    FROM edge WHERE target < {} GROUP BY property_id \
+                    UNION ALL SELECT property_id, 'string', count(*) FROM string GROUP BY property_id \
+                    UNION ALL SELECT property_id, 'text', count(*) FROM text GROUP BY property_id \
+                    UNION ALL SELECT property_id, 'external_id', count(*) FROM external_id GROUP BY property_id \
+                    UNION ALL SELECT property_id, 'url', count(*) FROM url GROUP BY property_id \
+                    UNION ALL SELECT property_id, 'coordinates', count(*) FROM coordinates GROUP BY property_id \
+                    UNION ALL SELECT property_id, 'quantity', count(*) FROM quantity GROUP BY property_id \
+                    UNION ALL SELECT property_id, 'time', count(*) FROM time GROUP BY property_id\
+                 ) \
+                 SELECT l.property_id, p.datatype, e.table_name AS expected_table, \
+                 l.table_name AS actual_table, l.claims \
+                 FROM landed l LEFT JOIN property p ON p.id = l.property_id \
+                 LEFT JOIN expected e ON e.datatype = p.datatype \
+                 WHERE e.table_name IS DISTINCT FROM l.table_name",
+                u8::from(&Target::NoValue)
+            ),
+            // Each entity is reduced to the set of properties it has claims for, no matter how
+            // many, and the pairs of those are counted, which is what schema-inference and
+            // shape-mining tools start from. Pairs are only stored once, the lowest property
+            // first, while the diagonal (p1 = p2) holds the number of entities using a property,
+            // so the conditional frequencies can be computed out of the table itself :D
+            Derived::PropertyCooccurrence => "WITH properties AS (\
+                    SELECT DISTINCT src_id, property_id FROM (\
+                        SELECT src_id, property_id FROM edge \
+                        UNION ALL SELECT src_id, property_id FROM string \
+                        UNION ALL SELECT src_id, property_id FROM text \
+                        UNION ALL SELECT src_id, property_id FROM external_id \
+                        UNION ALL SELECT src_id, property_id FROM url \
+                        UNION ALL SELECT src_id, property_id FROM coordinates \
+                        UNION ALL SELECT src_id, property_id FROM quantity \
+                        UNION ALL SELECT src_id, property_id FROM time\
+                    )\
+                 ) \
+                 SELECT a.property_id AS p1, b.property_id AS p2, count(*) AS count \
+                 FROM properties a JOIN properties b ON a.src_id = b.src_id \
+                 AND a.property_id <= b.property_id \
+                 GROUP BY a.property_id, b.property_id"
+                .to_string(),
+        }
+    }
+
+    /// This function returns the SQL statement creating the derived table.
+    ///
+    /// Arguments:
+    ///
+    /// * `encoding`: The `IdEncoding` of the database the table is created in.
+    pub fn create_table_sql(&self, encoding: &IdEncoding) -> String {
+        format!(
+            "CREATE OR REPLACE TABLE {} AS {};",
+            self.as_ref(),
+            self.query(encoding)
+        )
+    }
+
+    /// This function creates the derived table in the database, replacing it in
+    /// case it already exists.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database, where the
+    /// tables the derived one is computed from are already populated.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// table creation is successful, or an `Error` object if there is an error during
+    /// the execution of the SQL statement.
+    pub fn create_table(&self, connection: &Connection) -> Result<(), Error> {
+        connection.execute_batch(&self.create_table_sql(&IdEncoding::of(connection)?))
+    }
+}
+
+/// This implementation of the `AsRef` trait returns the name of the derived table
+/// in the database.
+impl AsRef<str> for Derived {
+    fn as_ref(&self) -> &str {
+        match self {
+            Derived::QuantitySi => "quantity_si",
+            Derived::QuantityStats => "quantity_stats",
+            Derived::DatatypeMismatch => "datatype_mismatch",
+            Derived::PropertyCooccurrence => "property_cooccurrence",
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use polars::prelude::*;
 use std::fmt::Display;
 use strum_macros::EnumIter;
+use wikidata::Rank;
 
-/// This code defines an enumeration called `DataType` with five possible variants:
-/// `Quantity`, `Coordinate`, `String`, `DateTime`, and `Entity`. The
+/// This code defines an enumeration called `DataType` with eight possible variants:
+/// `Quantity`, `Coordinate`, `String`, `DateTime`, `Entity`, `Text`, standing for
+/// the texts tagged with their language, such as mottos, `ExternalId` and `Url`,
+/// the latter two being strings stored apart, as those are queried on their own. The
 /// `#[derive(Clone, Debug, PartialEq)]` attribute macros are used to automatically
 /// generate implementations of the `Clone`, `Debug`, and `PartialEq` traits for the
 /// `DataType` enum. This allows instances of the enum to be cloned, printed for
@@ -15,6 +18,40 @@ pub enum DataType {
     String,
     DateTime,
     Entity,
+    Text,
+    ExternalId,
+    Url,
+}
+
+impl DataType {
+    /// The function returns the type of the values of a property out of the datatype
+    /// it declares in the dump, such as `external-id` or `time`; that is, the table
+    /// its values are stored in. Notice that every datatype whose values are plain
+    /// strings, such as `commonsMedia` or `math`, is stored in the `string` table.
+    ///
+    /// Arguments:
+    ///
+    /// * `datatype`: The datatype declared by the property.
+    ///
+    /// Returns:
+    ///
+    /// An `Option` with the `DataType` of the values, or `None` for the datatypes
+    /// the converter is not aware of.
+    pub fn from_declared(datatype: &str) -> Option<DataType> {
+        match datatype {
+            "wikibase-item" | "wikibase-property" | "wikibase-lexeme" | "wikibase-form"
+            | "wikibase-sense" => Some(DataType::Entity),
+            "string" | "commonsMedia" | "math" | "musical-notation" | "geo-shape"
+            | "tabular-data" => Some(DataType::String),
+            "external-id" => Some(DataType::ExternalId),
+            "url" => Some(DataType::Url),
+            "monolingualtext" => Some(DataType::Text),
+            "globe-coordinate" => Some(DataType::Coordinate),
+            "quantity" => Some(DataType::Quantity),
+            "time" => Some(DataType::DateTime),
+            _ => None,
+        }
+    }
 }
 
 /// This code defines an implementation of the `AsRef` trait for the `DataType`
@@ -33,6 +70,9 @@ impl AsRef<str> for DataType {
             DataType::String => "string",
             DataType::DateTime => "time",
             DataType::Entity => "edge",
+            DataType::Text => "text",
+            DataType::ExternalId => "external_id",
+            DataType::Url => "url",
         }
     }
 }
@@ -63,6 +103,9 @@ impl From<&DataType> for u8 {
             DataType::String => 3,
             DataType::DateTime => 4,
             DataType::Entity => 5,
+            DataType::Text => 6,
+            DataType::ExternalId => 7,
+            DataType::Url => 8,
         }
     }
 }
@@ -81,3 +124,57 @@ impl Display for DataType {
         write!(f, "{}", value)
     }
 }
+
+/// The `Target` enum defines what the claims stored in the `edge` tables point to.
+/// Claims whose value is an entity, as well as those with no value (novalue) or an
+/// unknown one (somevalue), are all stored in the same table, so this hint allows
+/// graph consumers to filter them semantically without looking at the `dst_id`.
+/// Notice that forms and senses share the same range of identifiers, so those
+/// could not be told apart otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, EnumIter)]
+pub enum Target {
+    Item,
+    Property,
+    Lexeme,
+    Form,
+    Sense,
+    NoValue,
+    SomeValue,
+}
+
+/// This implementation converts a `Target` into the `u8` code stored in the
+/// `target` column of the `edge` tables. Codes are stable, so new targets are
+/// only ever appended.
+impl From<&Target> for u8 {
+    fn from(value: &Target) -> Self {
+        match value {
+            Target::Item => 1,
+            Target::Property => 2,
+            Target::Lexeme => 3,
+            Target::Form => 4,
+            Target::Sense => 5,
+            Target::NoValue => 6,
+            Target::SomeValue => 7,
+        }
+    }
+}
+
+/// The function converts the `Rank` of a claim into the `u8` code stored in the
+/// `rank` column of the tables of the main values. Codes follow the order of the
+/// ranks, so the best ranked claims of a property are the ones with the greatest
+/// code; that is, `0` for deprecated, `1` for normal and `2` for preferred.
+///
+/// Arguments:
+///
+/// * `rank`: A reference to the `Rank` of the claim.
+///
+/// Returns:
+///
+/// The code of the rank.
+pub fn rank_code(rank: &Rank) -> u8 {
+    match rank {
+        Rank::Deprecated => 0,
+        Rank::Normal => 1,
+        Rank::Preferred => 2,
+    }
+}
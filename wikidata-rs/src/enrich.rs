@@ -0,0 +1,170 @@
+use std::collections::BTreeSet;
+
+use duckdb::{params, Connection};
+use serde_json::Value;
+use strum_macros::EnumString;
+
+use crate::api::{api_id, WikidataApi, BATCH_SIZE};
+use crate::dtype::Target;
+use crate::id::IdEncoding;
+use crate::schema;
+use crate::LANG;
+
+/// The `Enrichment` enum defines the gaps of the database that can be filled, once
+/// the load has finished, out of the Wikidata API. `Labels` stands for the
+/// vertices of the items and properties that are referenced by the claims, but
+/// were not stored, as it happens whenever only a part of the dump is loaded.
+#[derive(Clone, Debug, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Enrichment {
+    Labels,
+}
+
+/// This function returns the query retrieving the entities referenced by the
+/// claims, but missing from the `vertex` table; that is, the properties of the
+/// claims, the entities those point to, and their units and globes.
+fn referenced_ids_sql() -> String {
+    let targets = [Target::Item, Target::Property]
+        .iter()
+        .map(|target| u8::from(target).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut selects = Vec::new();
+    for table in schema::tables() {
+        if table.column("property_id").is_some() {
+            selects.push(format!("SELECT property_id AS id FROM {}", table.name));
+        }
+        if table.column("target").is_some() {
+            selects.push(format!(
+                "SELECT dst_id FROM {} WHERE target IN ({})",
+                table.name, targets
+            ));
+        }
+        for column in ["unit_id", "globe_id"] {
+            if table.column(column).is_some() {
+                selects.push(format!("SELECT {} FROM {}", column, table.name));
+            }
+        }
+    }
+
+    format!(
+        "SELECT DISTINCT id FROM ({}) WHERE id IS NOT NULL AND id NOT IN (SELECT id FROM vertex) ORDER BY id",
+        selects.join(" UNION ALL ")
+    )
+}
+
+/// The `Enricher` struct fills the gaps of a database out of the Wikidata API.
+///
+/// Properties:
+///
+/// * `api`: The client of the Wikidata API.
+pub struct Enricher {
+    api: WikidataApi,
+}
+
+impl Enricher {
+    /// The function creates an enricher sending, at most, the given number of
+    /// requests per second.
+    ///
+    /// Arguments:
+    ///
+    /// * `requests_per_second`: The maximum rate of the requests.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Enricher`, or a `String` with an error message in case
+    /// the client of the API cannot be built.
+    pub fn new(requests_per_second: f64) -> Result<Self, String> {
+        Ok(Self {
+            api: WikidataApi::new(requests_per_second)?,
+        })
+    }
+
+    /// The function stores the vertices of the items and properties that are
+    /// referenced, but missing, with their English label and description, and the
+    /// number of their sitelinks, as retrieved from the API.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database, once the load
+    /// has finished.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the number of vertices stored and the number of those that
+    /// could not be found, or a `String` with an error message otherwise.
+    pub fn enrich_labels(&mut self, connection: &Connection) -> Result<(usize, usize), String> {
+        let encoding = match IdEncoding::of(connection) {
+            Ok(encoding) => encoding,
+            Err(error) => return Err(format!("Error reading metadata. {}", error)),
+        };
+        let ids = match connection
+            .prepare(&referenced_ids_sql())
+            .and_then(|mut statement| {
+                statement
+                    .query_map([], |row| row.get::<_, u64>(0))?
+                    .collect::<Result<BTreeSet<_>, _>>()
+            }) {
+            Ok(ids) => ids
+                .into_iter()
+                .filter_map(|id| api_id(&encoding, id).map(|api_id| (id, api_id)))
+                .collect::<Vec<_>>(),
+            Err(error) => return Err(format!("Error retrieving missing entities. {}", error)),
+        };
+
+        let mut insert = match connection.prepare(
+            "INSERT INTO vertex (id, label, description, sitelink_count, entity_type) \
+             VALUES (?, ?, ?, ?, ?)",
+        ) {
+            Ok(insert) => insert,
+            Err(error) => return Err(format!("Error preparing insert. {}", error)),
+        };
+
+        let (mut stored, mut missing) = (0, 0);
+        for batch in ids.chunks(BATCH_SIZE) {
+            let ids = batch
+                .iter()
+                .map(|(_, api_id)| api_id.as_str())
+                .collect::<Vec<_>>();
+            let entities = self
+                .api
+                .get_entities(&ids, "labels|descriptions|sitelinks")?;
+            for (id, api_id) in batch {
+                // Entities that have been deleted, or merged, are reported as missing :(
+                let entity = match entities.get(api_id) {
+                    Some(entity) if entity.get("missing").is_none() => entity,
+                    _ => {
+                        missing += 1;
+                        continue;
+                    }
+                };
+                let text = |field: &str| {
+                    entity
+                        .get(field)
+                        .and_then(|texts| texts.get(&LANG.0))
+                        .and_then(|text| text.get("value"))
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                };
+                let sitelinks = entity
+                    .get("sitelinks")
+                    .and_then(Value::as_object)
+                    .map_or(0, |sitelinks| sitelinks.len() as u32);
+                let entity_type = encoding.kind_of(*id).and_then(|kind| kind.entity_type());
+                if let Err(error) = insert.execute(params![
+                    id,
+                    text("labels"),
+                    text("descriptions"),
+                    sitelinks,
+                    entity_type
+                ]) {
+                    return Err(format!("Error inserting {}. {}", api_id, error));
+                }
+                stored += 1;
+            }
+        }
+
+        Ok((stored, missing))
+    }
+}
@@ -0,0 +1,869 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use duckdb::{params, Connection, Error, Row, Statement};
+use serde_json::{json, Map, Value};
+use strum_macros::EnumString;
+
+use crate::dtype::Target;
+use crate::id::{Id, IdEncoding};
+use crate::labels::{ALIAS_TABLE, LABEL_TABLE};
+use crate::partition;
+use crate::schema;
+use crate::value::Scope;
+use crate::LANG;
+
+/// The `ExportFormat` enum defines the formats the database can be exported to.
+/// `WikidataJson` stands for the format of the JSON dumps published by Wikidata,
+/// so the exported entities can be fed to any tool understanding those, while
+/// `Ntriples` stands for the truthy RDF dumps, which triple stores can load.
+#[derive(Clone, Debug, Default, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ExportFormat {
+    #[default]
+    WikidataJson,
+    Ntriples,
+}
+
+/// The `JsonExporter` struct reconstructs the JSON documents of the entities out of
+/// the tables of the database. Notice that this is done to the extent the schema
+/// allows: the labels, descriptions and aliases are only stored in English and in
+/// the languages asked for during the load, the datatype of the strings is not
+/// known, but for the external identifiers and the URLs (so the rest of them are
+/// exported as plain strings), and the qualifiers and references keep neither the
+/// order they had in the dump nor the hashes of their snaks.
+///
+/// Properties:
+///
+/// * `claims`: The statements retrieving the main snaks of the claims.
+/// * `qualifiers`: The statements retrieving the snaks of the qualifiers.
+/// * `references`: The statements retrieving the snaks of the references.
+/// * `statements`: The statement retrieving the identifier and the rank of each of
+/// the claims, no matter the table those are stored in.
+/// * `labels`: The statement retrieving the labels and descriptions stored in the
+/// `label` table.
+/// * `aliases`: The statement retrieving the aliases stored in the `alias` table.
+/// * `encoding`: The `IdEncoding` the identifiers of the database are stored with.
+pub struct JsonExporter<'conn> {
+    claims: SnakStatements<'conn>,
+    qualifiers: SnakStatements<'conn>,
+    references: SnakStatements<'conn>,
+    statements: Statement<'conn>,
+    labels: Statement<'conn>,
+    aliases: Statement<'conn>,
+    encoding: IdEncoding,
+}
+
+impl<'conn> JsonExporter<'conn> {
+    /// The function prepares the statements retrieving the claims of an entity, so
+    /// those are prepared just once, no matter how many entities are exported.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing the `JsonExporter`, or an
+    /// `Error` object if any of the statements could not be prepared.
+    pub fn new(connection: &'conn Connection) -> Result<Self, Error> {
+        // Databases built before some of the tables existed have none of them, as their values
+        // were either stored elsewhere or not stored at all, so there is nothing to retrieve :D
+        let mut stored = connection.prepare("SELECT table_name FROM information_schema.tables")?;
+        let stored = stored
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        // The claims of the partitioned properties are read out of their own tables as well :D
+        let tables = [
+            "edge",
+            "string",
+            "external_id",
+            "url",
+            "text",
+            "coordinates",
+            "quantity",
+            "time",
+        ]
+        .into_iter()
+        .filter(|table| stored.contains(*table))
+        .map(|table| partition::source(connection, table))
+        .collect::<Result<Vec<_>, _>>()?;
+
+        let labels = match stored.contains(LABEL_TABLE) {
+            false => {
+                "SELECT NULL::TEXT, NULL::TEXT, NULL::TEXT WHERE $1::UBIGINT IS NULL AND false"
+                    .to_string()
+            }
+            true => format!(
+                "SELECT lang, label, description FROM {} WHERE id = ? ORDER BY lang",
+                LABEL_TABLE
+            ),
+        };
+        let aliases = match stored.contains(ALIAS_TABLE) {
+            false => {
+                "SELECT NULL::TEXT, NULL::TEXT WHERE $1::UBIGINT IS NULL AND false".to_string()
+            }
+            true => format!("SELECT lang, alias FROM {} WHERE id = ?", ALIAS_TABLE),
+        };
+
+        Ok(Self {
+            claims: SnakStatements::new(connection, &stored, Scope::Statement)?,
+            qualifiers: SnakStatements::new(connection, &stored, Scope::Qualifier)?,
+            references: SnakStatements::new(connection, &stored, Scope::Reference)?,
+            statements: connection.prepare(
+                &tables
+                    .iter()
+                    .map(|table| format!(
+                        "SELECT property_id, claim_index, statement_id, rank FROM {} WHERE src_id = $1",
+                        table
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(" UNION ALL "),
+            )?,
+            labels: connection.prepare(&labels)?,
+            aliases: connection.prepare(&aliases)?,
+            encoding: IdEncoding::of(connection)?,
+        })
+    }
+
+    /// The function reconstructs the JSON document of an entity, following the
+    /// structure of the entities in the JSON dumps.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The numeric identifier of the entity; that is, its `id` in the
+    /// `vertex` table.
+    /// * `label`: The English label of the entity, if any.
+    /// * `description`: The English description of the entity, if any.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing the JSON value of the
+    /// entity, or `None` if the identifier does not belong to an item, a property or
+    /// a lexeme; or an `Error` object if any of the claims could not be retrieved.
+    pub fn entity(
+        &mut self,
+        id: u64,
+        label: Option<String>,
+        description: Option<String>,
+    ) -> Result<Option<Value>, Error> {
+        let encoding = &self.encoding;
+        let entity_id = match entity_id(encoding, id) {
+            Some(entity_id) => entity_id,
+            None => return Ok(None),
+        };
+
+        let snaks = self.claims.snaks(encoding, id, &entity_id)?;
+
+        // The qualifiers are told apart by the claim they annotate, and the references by their hash
+        // as well. Those are grouped by their property, but the order they had in the dump is not
+        // stored, so the properties are sorted by their identifier instead :(
+        let mut qualifiers: HashMap<String, Vec<Snak>> = HashMap::new();
+        for snak in self.qualifiers.snaks(encoding, id, &entity_id)? {
+            if let Some(statement_id) = snak.statement_id.clone() {
+                qualifiers.entry(statement_id).or_default().push(snak);
+            }
+        }
+        let mut references: HashMap<String, BTreeMap<String, Vec<Snak>>> = HashMap::new();
+        for snak in self.references.snaks(encoding, id, &entity_id)? {
+            if let (Some(statement_id), Some(reference_hash)) =
+                (snak.statement_id.clone(), snak.reference_hash.clone())
+            {
+                references
+                    .entry(statement_id)
+                    .or_default()
+                    .entry(reference_hash)
+                    .or_default()
+                    .push(snak);
+            }
+        }
+
+        // The identifier and the rank of each claim are retrieved at once, as those are stored in
+        // every table alike. A claim is told apart by its property and its position among the ones
+        // of the property :D
+        let mut statements = HashMap::new();
+        for row in self.statements.query_map(params![id], |row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, u32>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, u8>(3)?,
+            ))
+        })? {
+            let (property_id, claim_index, statement_id, rank) = row?;
+            statements.insert((property_id, claim_index), (statement_id, rank));
+        }
+
+        // Claims are grouped by their property, as the dumps do, and sorted as those were found in the
+        // dump, keeping the identifier they had in it. Claims whose identifier is not known are left
+        // without one, as a made-up identifier would not be a valid GUID of a statement anyway :(
+        let mut snaks = snaks
+            .into_iter()
+            .map(|snak| (snak.claim_index.unwrap_or_default(), snak))
+            .collect::<Vec<_>>();
+        snaks.sort_by_key(|(claim_index, snak)| (snak.property_id, *claim_index));
+        let mut claims = Map::new();
+        for (claim_index, snak) in snaks {
+            let property_id = snak.property_id;
+            let (statement_id, rank) = match statements.remove(&(property_id, claim_index)) {
+                Some((statement_id, rank)) => (Some(statement_id), rank),
+                None => {
+                    eprintln!(
+                        "Claim {} of P{} of {} has no identifier. Exporting it without one",
+                        claim_index,
+                        encoding.local_id(property_id).unwrap_or_default(),
+                        entity_id
+                    );
+                    (None, 1)
+                }
+            };
+            let mut statement = json!({
+                "mainsnak": snak.snak,
+                "type": "statement",
+                "rank": match rank {
+                    0 => "deprecated",
+                    2 => "preferred",
+                    _ => "normal",
+                },
+            });
+            if let Some(statement_id) = statement_id {
+                if let Some(snaks) = qualifiers.remove(&statement_id) {
+                    let (snaks, order) = grouped(encoding, snaks);
+                    statement["qualifiers"] = snaks;
+                    statement["qualifiers-order"] = order;
+                }
+                if let Some(hashes) = references.remove(&statement_id) {
+                    statement["references"] = hashes
+                        .into_iter()
+                        .map(|(hash, snaks)| {
+                            let (snaks, order) = grouped(encoding, snaks);
+                            json!({ "hash": hash, "snaks": snaks, "snaks-order": order })
+                        })
+                        .collect();
+                }
+                statement["id"] = json!(statement_id);
+            }
+            if let Value::Array(statements) = claims
+                .entry(format!(
+                    "P{}",
+                    encoding.local_id(property_id).unwrap_or_default()
+                ))
+                .or_insert_with(|| Value::Array(Vec::new()))
+            {
+                statements.push(statement);
+            }
+        }
+
+        // The English label and description are stored in the vertex table, while the rest of the
+        // languages are stored apart, together with the aliases, if asked for during the load :D
+        let mut labels = monolingual(label);
+        let mut descriptions = monolingual(description);
+        for row in self.labels.query_map(params![id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })? {
+            let (lang, label, description) = row?;
+            if let Some(label) = label {
+                labels[&lang] = json!({ "language": lang, "value": label });
+            }
+            if let Some(description) = description {
+                descriptions[&lang] = json!({ "language": lang, "value": description });
+            }
+        }
+        let mut aliases = Map::new();
+        for row in self.aliases.query_map(params![id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })? {
+            let (lang, alias) = row?;
+            if let Value::Array(aliases) = aliases
+                .entry(lang.clone())
+                .or_insert_with(|| Value::Array(Vec::new()))
+            {
+                aliases.push(json!({ "language": lang, "value": alias }));
+            }
+        }
+
+        let entity_type = match &entity_id[0..1] {
+            "P" => "property",
+            "L" => "lexeme",
+            _ => "item",
+        };
+
+        Ok(Some(json!({
+            "type": entity_type,
+            "id": entity_id,
+            "labels": labels,
+            "descriptions": descriptions,
+            "aliases": aliases,
+            "claims": claims,
+            "sitelinks": {},
+        })))
+    }
+}
+
+/// The `Snak` struct holds a snak exported out of the database, together with what
+/// it belongs to.
+///
+/// Properties:
+///
+/// * `property_id`: The numeric identifier of the property of the snak.
+/// * `claim_index`: The position of the claim among the ones of its property, for
+/// the main snaks.
+/// * `statement_id`: The identifier of the claim annotated, for the qualifiers and
+/// the references.
+/// * `reference_hash`: The hash of the reference, for the references.
+/// * `snak`: The JSON value of the snak.
+struct Snak {
+    property_id: u64,
+    claim_index: Option<u32>,
+    statement_id: Option<String>,
+    reference_hash: Option<String>,
+    snak: Value,
+}
+
+impl Snak {
+    /// The function describes where the snak is found, so the ones that cannot be
+    /// exported are reported.
+    fn describe(&self, encoding: &IdEncoding) -> String {
+        let property = format!(
+            "P{}",
+            encoding.local_id(self.property_id).unwrap_or_default()
+        );
+        match (&self.claim_index, &self.statement_id, &self.reference_hash) {
+            (_, Some(statement_id), Some(hash)) => {
+                format!("{} of reference {} of {}", property, hash, statement_id)
+            }
+            (_, Some(statement_id), None) => format!("qualifier {} of {}", property, statement_id),
+            (claim_index, None, _) => {
+                format!("claim {} of {}", claim_index.unwrap_or_default(), property)
+            }
+        }
+    }
+}
+
+/// The `SnakStatements` struct holds the statements retrieving the snaks of an
+/// entity in a certain scope; that is, the main snaks of its claims, or the snaks
+/// of their qualifiers or references.
+///
+/// Properties:
+///
+/// * `edge`: The statement retrieving the snaks stored in the `edge` table.
+/// * `string`: The statement retrieving the snaks stored in the `string`,
+/// `external_id` and `url` tables, together with their datatype.
+/// * `text`: The statement retrieving the snaks stored in the `text` table.
+/// * `coordinates`: The statement retrieving the snaks stored in the
+/// `coordinates` table.
+/// * `quantity`: The statement retrieving the snaks stored in the `quantity` table.
+/// * `time`: The statement retrieving the snaks stored in the `time` table.
+struct SnakStatements<'conn> {
+    edge: Statement<'conn>,
+    string: Statement<'conn>,
+    text: Statement<'conn>,
+    coordinates: Statement<'conn>,
+    quantity: Statement<'conn>,
+    time: Statement<'conn>,
+}
+
+impl<'conn> SnakStatements<'conn> {
+    /// The function prepares the statements retrieving the snaks of a scope. Every
+    /// statement retrieves the property of the snak, its `claim_index`, its
+    /// `statement_id` and its `reference_hash`, which are NULL in the scopes not
+    /// storing them, followed by the value of the snak.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    /// * `stored`: The names of the tables of the database.
+    /// * `scope`: The `Scope` of the snaks retrieved.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing the `SnakStatements`, or an
+    /// `Error` object if any of the statements could not be prepared.
+    fn new(
+        connection: &'conn Connection,
+        stored: &HashSet<String>,
+        scope: Scope,
+    ) -> Result<Self, Error> {
+        let (prefix, keys) = match scope {
+            Scope::Statement => ("", "property_id, claim_index, NULL::TEXT, NULL::TEXT"),
+            Scope::Qualifier => (
+                "qualifier_",
+                "property_id, NULL::UINTEGER, statement_id, NULL::TEXT",
+            ),
+            Scope::Reference => (
+                "reference_",
+                "property_id, NULL::UINTEGER, statement_id, reference_hash",
+            ),
+        };
+        // Tables not stored retrieve nothing at all, whatever their columns are, but those still take
+        // the identifier of the entity, so every statement is run alike :D
+        let select = |table: &str, columns: &str| -> Result<Option<String>, Error> {
+            let table_name = format!("{}{}", prefix, table);
+            if !stored.contains(&table_name) {
+                return Ok(None);
+            }
+            Ok(Some(format!(
+                "SELECT {}, {} FROM {} WHERE src_id = $1",
+                keys,
+                columns,
+                partition::source(connection, &table_name)?
+            )))
+        };
+        let prepare = |selects: Vec<Option<String>>, columns: usize| {
+            let selects = selects.into_iter().flatten().collect::<Vec<_>>();
+            match selects.is_empty() {
+                true => connection.prepare(&format!(
+                    "SELECT {} WHERE $1::UBIGINT IS NULL AND false",
+                    vec!["NULL"; columns + 4].join(", ")
+                )),
+                false => connection.prepare(&selects.join(" UNION ALL ")),
+            }
+        };
+
+        // In case the original strings of the quantities were kept during the load, those are
+        // exported instead of the doubles, so the amounts are round-tripped without any loss :D
+        let quantity = format!("{}quantity", prefix);
+        let quantity_strings = schema::has_column(connection, &quantity, "amount_text")?;
+        // Otherwise, the decimals are exported, but for those amounts not fitting in them, which
+        // are exported out of the doubles. Databases built before those existed have none :(
+        let quantity_decimals = schema::has_column(connection, &quantity, "amount_decimal")?;
+        let quantity_strings = match (quantity_strings, quantity_decimals) {
+            (false, false) => "NULL, NULL, NULL".to_string(),
+            (false, true) => [
+                "amount_decimal",
+                "lower_bound_decimal",
+                "upper_bound_decimal",
+            ]
+            .map(|column| {
+                format!(
+                    "CASE WHEN {0} >= 0 THEN '+' ELSE '' END || \
+                         rtrim(rtrim(CAST({0} AS TEXT), '0'), '.')",
+                    column
+                )
+            })
+            .join(", "),
+            (true, _) => "amount_text, lower_bound_text, upper_bound_text".to_string(),
+        };
+
+        // The dates are exported as found in the dump, whenever those were kept, as DATETIME neither
+        // holds the years beyond 9999 nor the calendar the dates are written in :D
+        let time = format!("{}time", prefix);
+        let time_dates = match schema::has_column(connection, &time, "year")? {
+            false => "NULL, NULL, NULL, NULL",
+            true => "year, month, day, calendar_model_id",
+        };
+
+        Ok(Self {
+            edge: prepare(vec![select("edge", "dst_id, target")?], 2)?,
+            string: prepare(
+                vec![
+                    select("string", "string, 'string'")?,
+                    select("external_id", "external_id, 'external-id'")?,
+                    select("url", "url, 'url'")?,
+                ],
+                2,
+            )?,
+            text: prepare(vec![select("text", "text, lang")?], 2)?,
+            coordinates: prepare(
+                vec![select(
+                    "coordinates",
+                    "latitude, longitude, precision, globe_id",
+                )?],
+                4,
+            )?,
+            quantity: prepare(
+                vec![select(
+                    "quantity",
+                    &format!(
+                        "amount, lower_bound, upper_bound, unit_id, {}",
+                        quantity_strings
+                    ),
+                )?],
+                7,
+            )?,
+            time: prepare(
+                vec![select(
+                    "time",
+                    &format!("CAST(time AS TEXT), precision, {}", time_dates),
+                )?],
+                6,
+            )?,
+        })
+    }
+
+    /// The function retrieves the snaks of an entity in the scope of the statements.
+    ///
+    /// Arguments:
+    ///
+    /// * `encoding`: The `IdEncoding` the identifiers of the database are stored with.
+    /// * `id`: The numeric identifier of the entity.
+    /// * `entity_id`: The identifier of the entity, such as `Q42`, which the snaks
+    /// that cannot be exported are reported with.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing the snaks, or an `Error`
+    /// object if any of them could not be retrieved.
+    fn snaks(
+        &mut self,
+        encoding: &IdEncoding,
+        id: u64,
+        entity_id: &str,
+    ) -> Result<Vec<Snak>, Error> {
+        let mut snaks: Vec<Snak> = Vec::new();
+        let key = |row: &Row| -> Result<_, Error> {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, Option<u32>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        };
+        let snak = |(property_id, claim_index, statement_id, reference_hash), snak| Snak {
+            property_id,
+            claim_index,
+            statement_id,
+            reference_hash,
+            snak,
+        };
+
+        // Entities, unknown values and missing values are all stored in the edge table. Those are
+        // told apart by the target column, which tells what each of the claims points to :D
+        for row in self.edge.query_map(params![id], |row| {
+            Ok((key(row)?, row.get::<_, u64>(4)?, row.get::<_, u8>(5)?))
+        })? {
+            let (key, dst_id, target) = row?;
+            let property_id = key.0;
+            let value = if target == u8::from(&Target::NoValue) {
+                bare_snak(encoding, property_id, "novalue")
+            } else if target == u8::from(&Target::SomeValue) {
+                bare_snak(encoding, property_id, "somevalue")
+            } else if let Some((datatype, value)) = entity_id_value(encoding, dst_id) {
+                value_snak(encoding, property_id, datatype, value)
+            } else {
+                continue; // the destination cannot be told, so the claim is skipped :(
+            };
+            snaks.push(snak(key, value));
+        }
+
+        for row in self.string.query_map(params![id], |row| {
+            Ok((
+                key(row)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })? {
+            let (key, string, datatype) = row?;
+            let value = json!({ "value": string, "type": "string" });
+            let value = value_snak(encoding, key.0, &datatype, value);
+            snaks.push(snak(key, value));
+        }
+
+        for row in self.text.query_map(params![id], |row| {
+            Ok((
+                key(row)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })? {
+            let (key, text, lang) = row?;
+            let value = json!({
+                "value": { "text": text, "language": lang },
+                "type": "monolingualtext",
+            });
+            let value = value_snak(encoding, key.0, "monolingualtext", value);
+            snaks.push(snak(key, value));
+        }
+
+        for row in self.coordinates.query_map(params![id], |row| {
+            Ok((
+                key(row)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, f64>(5)?,
+                row.get::<_, f64>(6)?,
+                row.get::<_, u64>(7)?,
+            ))
+        })? {
+            let (key, latitude, longitude, precision, globe_id) = row?;
+            let value = json!({
+                "value": {
+                    "latitude": latitude,
+                    "longitude": longitude,
+                    "altitude": null,
+                    "precision": precision,
+                    "globe": entity_uri(encoding, globe_id),
+                },
+                "type": "globecoordinate",
+            });
+            let value = value_snak(encoding, key.0, "globe-coordinate", value);
+            snaks.push(snak(key, value));
+        }
+
+        for row in self.quantity.query_map(params![id], |row| {
+            Ok((
+                key(row)?,
+                row.get::<_, f64>(4)?,
+                row.get::<_, Option<f64>>(5)?,
+                row.get::<_, Option<f64>>(6)?,
+                row.get::<_, Option<u64>>(7)?,
+                row.get::<_, Option<String>>(8)?,
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, Option<String>>(10)?,
+            ))
+        })? {
+            let (
+                key,
+                amount,
+                lower_bound,
+                upper_bound,
+                unit_id,
+                amount_text,
+                lower_bound_text,
+                upper_bound_text,
+            ) = row?;
+            let mut quantity = Map::new();
+            quantity.insert(
+                "amount".to_string(),
+                json!(amount_text.unwrap_or_else(|| format!("{:+}", amount))),
+            );
+            quantity.insert(
+                "unit".to_string(),
+                json!(unit_id
+                    .map(|id| entity_uri(encoding, id))
+                    .unwrap_or_else(|| "1".to_string())),
+            );
+            if let Some(upper_bound) = upper_bound {
+                quantity.insert(
+                    "upperBound".to_string(),
+                    json!(upper_bound_text.unwrap_or_else(|| format!("{:+}", upper_bound))),
+                );
+            }
+            if let Some(lower_bound) = lower_bound {
+                quantity.insert(
+                    "lowerBound".to_string(),
+                    json!(lower_bound_text.unwrap_or_else(|| format!("{:+}", lower_bound))),
+                );
+            }
+            let value = json!({ "value": quantity, "type": "quantity" });
+            let value = value_snak(encoding, key.0, "quantity", value);
+            snaks.push(snak(key, value));
+        }
+
+        for row in self.time.query_map(params![id], |row| {
+            Ok((
+                key(row)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, u8>(5)?,
+                row.get::<_, Option<i64>>(6)?,
+                row.get::<_, Option<u8>>(7)?,
+                row.get::<_, Option<u8>>(8)?,
+                row.get::<_, Option<u64>>(9)?,
+            ))
+        })? {
+            let (key, time, precision, year, month, day, calendar_model_id) = row?;
+            let time = match (year, month, day) {
+                (Some(year), Some(month), Some(day)) => format!(
+                    "{}{:04}-{:02}-{:02}T00:00:00Z",
+                    if year < 0 { '-' } else { '+' },
+                    year.unsigned_abs(),
+                    month,
+                    day
+                ),
+                // Years beyond 9999 have no time, or an infinite one in older databases, so those
+                // recording no dates cannot export them. The snak is reported, rather than dropped
+                // silently :(
+                _ => match time.as_deref().and_then(wikidata_time) {
+                    Some(time) => time,
+                    None => {
+                        let skipped = snak(key, Value::Null);
+                        eprintln!(
+                            "Skipping {} of {}. Its time {} cannot be exported",
+                            skipped.describe(encoding),
+                            entity_id,
+                            time.as_deref().unwrap_or("NULL")
+                        );
+                        continue;
+                    }
+                },
+            };
+            let value = json!({
+                "value": {
+                    "time": time,
+                    "timezone": 0,
+                    "before": 0,
+                    "after": 0,
+                    "precision": precision,
+                    "calendarmodel": calendar_model_id
+                        .map(|id| entity_uri(encoding, id))
+                        .unwrap_or_else(|| "http://www.wikidata.org/entity/Q1985727".to_string()),
+                },
+                "type": "time",
+            });
+            let value = value_snak(encoding, key.0, "time", value);
+            snaks.push(snak(key, value));
+        }
+
+        Ok(snaks)
+    }
+}
+
+/// The function groups the snaks of a qualifier or a reference by their property,
+/// as the dumps do.
+///
+/// Arguments:
+///
+/// * `encoding`: The `IdEncoding` the identifiers are stored with.
+/// * `snaks`: The snaks to be grouped.
+///
+/// Returns:
+///
+/// A tuple with the map from each property to its snaks, and the array with the
+/// order of the properties.
+fn grouped(encoding: &IdEncoding, mut snaks: Vec<Snak>) -> (Value, Value) {
+    snaks.sort_by_key(|snak| snak.property_id);
+    let mut map = Map::new();
+    let mut order = Vec::new();
+    for snak in snaks {
+        let property = format!(
+            "P{}",
+            encoding.local_id(snak.property_id).unwrap_or_default()
+        );
+        if !map.contains_key(&property) {
+            order.push(json!(property));
+        }
+        if let Value::Array(snaks) = map
+            .entry(property)
+            .or_insert_with(|| Value::Array(Vec::new()))
+        {
+            snaks.push(snak.snak);
+        }
+    }
+    (Value::Object(map), Value::Array(order))
+}
+
+/// The function returns the identifier of an entity out of its numeric identifier,
+/// as long as it belongs to an item, a property or a lexeme. Forms and senses are
+/// not considered, as those are exported within their lexeme.
+///
+/// Arguments:
+///
+/// * `encoding`: The `IdEncoding` the identifier is stored with.
+/// * `id`: The numeric identifier of the entity.
+///
+/// Returns:
+///
+/// An `Option` with the identifier of the entity, such as `Q42`.
+fn entity_id(encoding: &IdEncoding, id: u64) -> Option<String> {
+    match encoding.decode(id).ok()? {
+        id @ (Id::Qid(_) | Id::Pid(_) | Id::Lid(_)) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// The function returns the concept URI of an entity, which is how the globes and
+/// the units are referenced in the dumps.
+fn entity_uri(encoding: &IdEncoding, id: u64) -> String {
+    format!(
+        "http://www.wikidata.org/entity/{}",
+        entity_id(encoding, id).unwrap_or_default()
+    )
+}
+
+/// The function returns the datatype and the data value of a snak pointing to an
+/// entity, following the encoding of the dumps.
+///
+/// Arguments:
+///
+/// * `encoding`: The `IdEncoding` the identifier is stored with.
+/// * `id`: The numeric identifier of the entity pointed to.
+///
+/// Returns:
+///
+/// An `Option` with the datatype and the data value, or `None` if the identifier
+/// does not belong to an entity, such as the one of a placeholder.
+fn entity_id_value(encoding: &IdEncoding, id: u64) -> Option<(&'static str, Value)> {
+    let id = encoding.decode(id).ok()?;
+    let (datatype, entity_type, numeric_id) = match &id {
+        Id::Qid(qid) => ("wikibase-item", "item", Some(qid.0)),
+        Id::Pid(pid) => ("wikibase-property", "property", Some(pid.0)),
+        Id::Lid(lid) => ("wikibase-lexeme", "lexeme", Some(lid.0)),
+        // Forms and senses have no numeric identifier of their own in the dumps :D
+        Id::Fid(_) => ("wikibase-form", "form", None),
+        Id::Sid(_) => ("wikibase-sense", "sense", None),
+        Id::DataType(_) | Id::Placeholder(_) => return None,
+    };
+    let mut value = json!({ "entity-type": entity_type, "id": id.to_string() });
+    if let Some(numeric_id) = numeric_id {
+        value["numeric-id"] = json!(numeric_id);
+    }
+    Some((
+        datatype,
+        json!({ "value": value, "type": "wikibase-entityid" }),
+    ))
+}
+
+/// The function builds a snak holding a value.
+fn value_snak(encoding: &IdEncoding, property_id: u64, datatype: &str, datavalue: Value) -> Value {
+    json!({
+        "snaktype": "value",
+        "property": format!("P{}", encoding.local_id(property_id).unwrap_or_default()),
+        "datatype": datatype,
+        "datavalue": datavalue,
+    })
+}
+
+/// The function builds a snak holding no value at all; that is, either an unknown
+/// value (`somevalue`) or a missing one (`novalue`).
+fn bare_snak(encoding: &IdEncoding, property_id: u64, snaktype: &str) -> Value {
+    json!({
+        "snaktype": snaktype,
+        "property": format!("P{}", encoding.local_id(property_id).unwrap_or_default()),
+    })
+}
+
+/// The function builds the map of a monolingual field, such as the labels, out of
+/// the English text stored in the database.
+fn monolingual(text: Option<String>) -> Value {
+    let mut map = Map::new();
+    if let Some(text) = text {
+        map.insert(
+            LANG.0.to_owned(),
+            json!({ "language": LANG.0.to_owned(), "value": text }),
+        );
+    }
+    Value::Object(map)
+}
+
+/// The function transforms a time, as printed by DuckDB, into the encoding of the
+/// dumps; for example, `2001-01-01 00:00:00` is transformed into
+/// `+2001-01-01T00:00:00Z`.
+///
+/// Arguments:
+///
+/// * `time`: A string slice with the time, as printed by DuckDB.
+///
+/// Returns:
+///
+/// An `Option` with the encoded time, or `None` if the time is infinite.
+fn wikidata_time(time: &str) -> Option<String> {
+    if time.ends_with("infinity") {
+        return None;
+    }
+
+    let (time, sign) = match time.strip_suffix(" (BC)") {
+        Some(time) => (time, '-'),
+        None => (time, '+'),
+    };
+    let (date, clock) = time.split_once(' ').unwrap_or((time, "00:00:00"));
+
+    Some(format!(
+        "{}{}T{}Z",
+        sign,
+        date,
+        clock.get(0..8).unwrap_or(clock)
+    ))
+}
@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use serde_json::Value;
+use wikidata::Lid;
+
+use crate::api::{WikidataApi, BATCH_SIZE};
+use crate::id::{parse_item, parse_property, Id};
+
+/// The properties of the entities requested, which are every one of those found
+/// in the dumps, so the entities fetched are stored just as if those were loaded
+/// from a dump.
+const PROPS: &str = "info|sitelinks|aliases|labels|descriptions|claims|datatype";
+
+/// The function parses the identifier of an entity given by the user, such as
+/// `Q42`, `P31` or `L7`, into the one known by the API.
+///
+/// Arguments:
+///
+/// * `id`: A string slice with the identifier of the entity.
+///
+/// Returns:
+///
+/// a `Result` with the identifier of the entity, or a `String` with an error
+/// message in case it is neither an item, a property nor a lexeme.
+fn entity_id(id: &str) -> Result<String, String> {
+    let id = id.trim();
+    let parsed = match id.get(0..1) {
+        Some("Q") => Id::Qid(parse_item(id)?),
+        Some("P") => Id::Pid(parse_property(id)?),
+        Some("L") => match id[1..].parse::<u64>() {
+            Ok(number) => Id::Lid(Lid(number)),
+            Err(_) => return Err(format!("Not valid lexeme: {}", id)),
+        },
+        _ => return Err(format!("Not valid entity: {}", id)),
+    };
+    Ok(parsed.to_string())
+}
+
+/// The `Fetcher` struct retrieves single entities out of the Wikidata API, so
+/// small databases can be built, such as for testing, without downloading a
+/// whole dump.
+///
+/// Properties:
+///
+/// * `api`: The client of the Wikidata API.
+pub struct Fetcher {
+    api: WikidataApi,
+}
+
+impl Fetcher {
+    /// The function creates a fetcher sending, at most, the given number of requests
+    /// per second.
+    ///
+    /// Arguments:
+    ///
+    /// * `requests_per_second`: The maximum rate of the requests.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Fetcher`, or a `String` with an error message in case
+    /// the client of the API cannot be built.
+    pub fn new(requests_per_second: f64) -> Result<Self, String> {
+        Ok(Self {
+            api: WikidataApi::new(requests_per_second)?,
+        })
+    }
+
+    /// The function requests the given entities from the API, in batches, and writes
+    /// them in the format of the JSON dumps; that is, as an array with an entity per
+    /// line. Hence, those can be loaded as any other dump.
+    ///
+    /// Arguments:
+    ///
+    /// * `ids`: The identifiers of the entities, such as `Q42`.
+    /// * `writer`: A mutable reference to the writer the dump is written to.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the number of entities written and the identifiers of those
+    /// that could not be found, or a `String` with an error message otherwise.
+    pub fn write_dump<W: Write>(
+        &mut self,
+        ids: &[String],
+        writer: &mut W,
+    ) -> Result<(usize, Vec<String>), String> {
+        // Every identifier is checked before sending the first request, so a typo is not found
+        // once half of the entities have been already fetched :D
+        let mut requested = Vec::new();
+        for id in ids {
+            let id = entity_id(id)?;
+            if !requested.contains(&id) {
+                requested.push(id);
+            }
+        }
+
+        let mut written = HashSet::new();
+        let mut missing = Vec::new();
+        let write = |writer: &mut W, entity: &Value, first: bool| match first {
+            true => write!(writer, "[\n{}", entity),
+            false => write!(writer, ",\n{}", entity),
+        };
+        for batch in requested.chunks(BATCH_SIZE) {
+            let ids = batch.iter().map(String::as_str).collect::<Vec<_>>();
+            let entities = self.api.get_entities(&ids, PROPS)?;
+            for id in batch {
+                // Redirected entities are returned under the identifier they are redirected to,
+                // which may have been requested as well, so it is only written once :D
+                let entity = match entities.get(id).or_else(|| {
+                    entities
+                        .as_object()?
+                        .values()
+                        .find(|entity| entity.get("redirects").is_some_and(|r| r["from"] == *id))
+                }) {
+                    Some(entity) if entity.get("missing").is_none() => entity,
+                    _ => {
+                        missing.push(id.to_owned());
+                        continue;
+                    }
+                };
+                let entity_id = entity.get("id").and_then(Value::as_str).unwrap_or(id);
+                if written.insert(entity_id.to_owned()) {
+                    if let Err(error) = write(writer, entity, written.len() == 1) {
+                        return Err(format!("Error writing {}. {}", entity_id, error));
+                    }
+                }
+            }
+        }
+
+        let closing = match written.is_empty() {
+            true => writeln!(writer, "[\n]"),
+            false => writeln!(writer, "\n]"),
+        };
+        if let Err(error) = closing.and_then(|_| writer.flush()) {
+            return Err(format!("Error writing dump. {}", error));
+        }
+
+        Ok((written.len(), missing))
+    }
+}
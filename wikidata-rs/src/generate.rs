@@ -0,0 +1,444 @@
+use std::io::{Result, Write};
+
+use serde_json::{json, Map, Value};
+
+use crate::sample::splitmix64;
+
+/// The properties of the sample dumps; that is, their identifier, datatype and
+/// label. There is one per datatype, at least, and the identifiers are the ones
+/// those have in Wikidata, so the helper macros, such as `instances_of`, work on
+/// the sample as well.
+const PROPERTIES: [(u64, &str, &str); 19] = [
+    (31, "wikibase-item", "instance of"),
+    (279, "wikibase-item", "subclass of"),
+    (1687, "wikibase-property", "Wikidata property"),
+    (1545, "string", "series ordinal"),
+    (1476, "monolingualtext", "title"),
+    (214, "external-id", "VIAF ID"),
+    (18, "commonsMedia", "image"),
+    (856, "url", "official website"),
+    (2534, "math", "defining formula"),
+    (3896, "geo-shape", "geoshape"),
+    (6883, "musical-notation", "LilyPond notation"),
+    (4179, "tabular-data", "tabular population"),
+    (1082, "quantity", "population"),
+    (2044, "quantity", "elevation above sea level"),
+    (569, "time", "date of birth"),
+    (625, "globe-coordinate", "coordinate location"),
+    (5238, "wikibase-lexeme", "combines lexemes"),
+    (5830, "wikibase-form", "subject form"),
+    (5137, "wikibase-sense", "item for this sense"),
+];
+
+/// The number of items that are the classes the rest of the items are instances
+/// of. Those are the first items of the sample.
+const CLASSES: u64 = 5;
+
+/// Every how many entities a lexeme is generated, instead of an item.
+const LEXEME_EVERY: u64 = 10;
+
+/// The `Generator` struct generates fake, yet schema-realistic, entities following
+/// the format of the JSON dumps of Wikidata, so pipelines can be tested without
+/// downloading a real dump. Every datatype is covered, as well as the edge cases
+/// found in the real dumps, such as dates before the common era, unknown values
+/// (somevalue snaks), missing values (novalue snaks), deprecated and preferred
+/// claims, coordinates on other globes, quantities with no unit, or entities with
+/// no English label. Entities only depend on the seed, so samples are reproducible.
+///
+/// Properties:
+///
+/// * `state`: The state of the pseudorandom number generator.
+/// * `items`: The number of items generated, which claims may point to.
+/// * `lexemes`: The number of lexemes generated, which claims may point to.
+pub struct Generator {
+    state: u64,
+    items: u64,
+    lexemes: u64,
+}
+
+impl Generator {
+    /// The function creates a generator of a sample with the given number of
+    /// entities, besides the properties.
+    ///
+    /// Arguments:
+    ///
+    /// * `entities`: The number of items and lexemes of the sample.
+    /// * `seed`: The seed the entities are generated with.
+    ///
+    /// Returns:
+    ///
+    /// The `Generator`.
+    pub fn new(entities: u64, seed: u64) -> Self {
+        let lexemes = entities / LEXEME_EVERY;
+        Self {
+            state: seed,
+            items: entities - lexemes,
+            lexemes,
+        }
+    }
+
+    /// The function returns the next pseudorandom number.
+    fn next(&mut self) -> u64 {
+        self.state = splitmix64(self.state);
+        self.state
+    }
+
+    /// The function returns a pseudorandom number below the given bound.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound.max(1)
+    }
+
+    /// The function returns whether an event happening once every `n` times
+    /// happens or not.
+    fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+
+    /// The function writes the whole sample as a JSON dump; that is, an array with
+    /// an entity per line, the properties coming first.
+    ///
+    /// Arguments:
+    ///
+    /// * `writer`: The writer the dump is written to.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with an empty `Ok(())`, or an `Error` if the dump could not be
+    /// written.
+    pub fn write_dump<W: Write>(&mut self, writer: &mut W) -> Result<()> {
+        let mut entities: Vec<Value> = PROPERTIES
+            .iter()
+            .map(|(id, datatype, label)| property(*id, datatype, label))
+            .collect();
+        let (mut item, mut lexeme) = (0, 0);
+        while item < self.items || lexeme < self.lexemes {
+            if ((item + lexeme + 1) % LEXEME_EVERY == 0 && lexeme < self.lexemes)
+                || item == self.items
+            {
+                lexeme += 1;
+                entities.push(self.lexeme(lexeme));
+            } else {
+                item += 1;
+                entities.push(self.item(item));
+            }
+        }
+
+        writeln!(writer, "[")?;
+        for (index, entity) in entities.iter().enumerate() {
+            let separator = if index + 1 < entities.len() { "," } else { "" };
+            writeln!(writer, "{}{}", entity, separator)?;
+        }
+        writeln!(writer, "]")?;
+        writer.flush()
+    }
+
+    /// The function generates an item, with a claim of each datatype, at most.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The number of the item, such as `42` for `Q42`.
+    fn item(&mut self, id: u64) -> Value {
+        let entity_id = format!("Q{}", id);
+        let mut claims = Map::new();
+
+        // The first items are the classes, which are subclasses of each other, while the rest of
+        // them are instances of those :D
+        let (property, class) = match id <= CLASSES {
+            true => (279, (id % CLASSES) + 1),
+            false => (31, self.below(CLASSES) + 1),
+        };
+        claims.insert(
+            format!("P{}", property),
+            json!([statement(
+                &entity_id,
+                0,
+                value_snak(property, "wikibase-item", item_value(class)),
+                "normal",
+                Map::new()
+            )]),
+        );
+
+        for (index, (property, datatype, _)) in PROPERTIES.iter().enumerate().skip(2) {
+            if !self.one_in(2) {
+                continue;
+            }
+            let count = 1 + self.below(2);
+            let statements = (0..count)
+                .map(|n| {
+                    let snak = self.snak(*property, datatype);
+                    let rank = match self.below(10) {
+                        0 => "deprecated",
+                        1 => "preferred",
+                        _ => "normal",
+                    };
+                    let mut qualifiers = Map::new();
+                    if self.one_in(4) {
+                        qualifiers.insert("P569".to_string(), json!([self.snak(569, "time")]));
+                    }
+                    statement(&entity_id, index * 2 + n as usize, snak, rank, qualifiers)
+                })
+                .collect::<Vec<_>>();
+            claims.insert(format!("P{}", property), Value::Array(statements));
+        }
+
+        // Some of the items have no English label, or no sitelink at all, as it happens for most
+        // of the items in Wikidata :(
+        let labels = match self.one_in(20) {
+            true => json!({ "de": text("de", &format!("Ding {}", id)) }),
+            false => json!({
+                "en": text("en", &format!("Thing \"{}\" — ñ", id)),
+                "de": text("de", &format!("Ding {}", id)),
+            }),
+        };
+        let sitelinks = match self.one_in(3) {
+            true => json!({}),
+            false => json!({
+                "enwiki": { "site": "enwiki", "title": format!("Thing {}", id), "badges": [] }
+            }),
+        };
+
+        json!({
+            "type": "item",
+            "id": entity_id,
+            "labels": labels,
+            "descriptions": { "en": text("en", &format!("sample item number {}", id)) },
+            "aliases": { "en": [text("en", &format!("T{}", id))] },
+            "claims": claims,
+            "sitelinks": sitelinks,
+            "lastrevid": 1_000_000 + id,
+        })
+    }
+
+    /// The function generates a lexeme, with a couple of forms and a sense.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The number of the lexeme, such as `7` for `L7`.
+    fn lexeme(&mut self, id: u64) -> Value {
+        let entity_id = format!("L{}", id);
+        let lemma = format!("word{}", id);
+        let forms = (1..=2)
+            .map(|n| {
+                json!({
+                    "id": format!("{}-F{}", entity_id, n),
+                    "representations": { "en": text("en", &format!("{}{}", lemma, "s".repeat(n - 1))) },
+                    "grammaticalFeatures": ["Q110786"],
+                    "claims": {},
+                })
+            })
+            .collect::<Vec<_>>();
+        let sense = json!({
+            "id": format!("{}-S1", entity_id),
+            "glosses": { "en": text("en", &format!("meaning of {}", lemma)) },
+            "claims": {},
+        });
+        let mut claims = Map::new();
+        claims.insert(
+            "P5137".to_string(),
+            json!([statement(
+                &entity_id,
+                0,
+                self.snak(5137, "wikibase-sense"),
+                "normal",
+                Map::new()
+            )]),
+        );
+
+        json!({
+            "type": "lexeme",
+            "id": entity_id,
+            "lemmas": { "en": text("en", &lemma) },
+            "lexicalCategory": "Q1084",
+            "language": "Q1860",
+            "claims": claims,
+            "forms": forms,
+            "senses": [sense],
+            "lastrevid": 2_000_000 + id,
+        })
+    }
+
+    /// The function generates a snak of a property, whose value is of the given
+    /// datatype. Now and then, the snak holds an unknown value (somevalue) or no
+    /// value at all (novalue).
+    ///
+    /// Arguments:
+    ///
+    /// * `property`: The number of the property, such as `31` for `P31`.
+    /// * `datatype`: The datatype of the property, such as `wikibase-item`.
+    fn snak(&mut self, property: u64, datatype: &str) -> Value {
+        match self.below(20) {
+            0 => return bare_snak(property, datatype, "somevalue"),
+            1 => return bare_snak(property, datatype, "novalue"),
+            _ => (),
+        }
+
+        let n = self.below(1_000);
+        let value = match datatype {
+            "wikibase-item" => item_value(1 + self.below(self.items)),
+            "wikibase-property" => {
+                let (id, _, _) = PROPERTIES[self.below(PROPERTIES.len() as u64) as usize];
+                json!({
+                    "value": { "entity-type": "property", "numeric-id": id, "id": format!("P{}", id) },
+                    "type": "wikibase-entityid",
+                })
+            }
+            "wikibase-lexeme" => {
+                let id = 1 + self.below(self.lexemes);
+                json!({
+                    "value": { "entity-type": "lexeme", "numeric-id": id, "id": format!("L{}", id) },
+                    "type": "wikibase-entityid",
+                })
+            }
+            "wikibase-form" => json!({
+                "value": { "entity-type": "form", "id": format!("L{}-F1", 1 + self.below(self.lexemes)) },
+                "type": "wikibase-entityid",
+            }),
+            "wikibase-sense" => json!({
+                "value": { "entity-type": "sense", "id": format!("L{}-S1", 1 + self.below(self.lexemes)) },
+                "type": "wikibase-entityid",
+            }),
+            "monolingualtext" => json!({
+                "value": { "text": format!("Título {}", n), "language": "es" },
+                "type": "monolingualtext",
+            }),
+            "quantity" => {
+                // Quantities may have no unit, which is encoded as "1", and no bounds :D
+                let mut value = json!({ "amount": format!("+{}.5", n), "unit": "1" });
+                if self.one_in(2) {
+                    value["unit"] = json!("http://www.wikidata.org/entity/Q11573");
+                    value["lowerBound"] = json!(format!("+{}", n));
+                    value["upperBound"] = json!(format!("+{}", n + 1));
+                }
+                if self.one_in(10) {
+                    value["amount"] = json!(format!("-{}", n));
+                }
+                json!({ "value": value, "type": "quantity" })
+            }
+            "time" => {
+                // Dates before the common era, and years with a lower precision, are found in the
+                // dumps as well, which have their own quirks, such as zeroed months and days :(
+                let (time, precision) = match self.below(4) {
+                    0 => (format!("-{:04}-00-00T00:00:00Z", 1 + n), 9),
+                    1 => (format!("+{:04}-00-00T00:00:00Z", 1000 + n), 9),
+                    _ => (
+                        format!(
+                            "+{:04}-{:02}-{:02}T00:00:00Z",
+                            1000 + n,
+                            1 + n % 12,
+                            1 + n % 28
+                        ),
+                        11,
+                    ),
+                };
+                json!({
+                    "value": {
+                        "time": time,
+                        "timezone": 0,
+                        "before": 0,
+                        "after": 0,
+                        "precision": precision,
+                        "calendarmodel": "http://www.wikidata.org/entity/Q1985727",
+                    },
+                    "type": "time",
+                })
+            }
+            "globe-coordinate" => {
+                let globe = match self.one_in(10) {
+                    true => "http://www.wikidata.org/entity/Q111",
+                    false => "http://www.wikidata.org/entity/Q2",
+                };
+                json!({
+                    "value": {
+                        "latitude": (self.below(18_000) as f64) / 100.0 - 90.0,
+                        "longitude": (self.below(36_000) as f64) / 100.0 - 180.0,
+                        "altitude": null,
+                        "precision": 0.01,
+                        "globe": globe,
+                    },
+                    "type": "globecoordinate",
+                })
+            }
+            "commonsMedia" => string_value(format!("Sample image {}.jpg", n)),
+            "url" => string_value(format!("https://example.org/{}", n)),
+            "math" => string_value(format!("x^{{{}}}", n)),
+            "geo-shape" => string_value(format!("Data:Sample shape {}.map", n)),
+            "musical-notation" => string_value(format!("\\relative c' {{ c{} }}", 1 + n % 8)),
+            "tabular-data" => string_value(format!("Data:Sample table {}.tab", n)),
+            "external-id" => string_value(format!("{:08}", n)),
+            _ => string_value(format!("{}", n)),
+        };
+
+        value_snak(property, datatype, value)
+    }
+}
+
+/// The function generates a property of the given datatype.
+fn property(id: u64, datatype: &str, label: &str) -> Value {
+    json!({
+        "type": "property",
+        "datatype": datatype,
+        "id": format!("P{}", id),
+        "labels": { "en": text("en", label) },
+        "descriptions": { "en": text("en", &format!("sample property of datatype {}", datatype)) },
+        "aliases": {},
+        "claims": {},
+        "lastrevid": id,
+    })
+}
+
+/// The function builds a text in a language, as labels and descriptions are.
+fn text(language: &str, value: &str) -> Value {
+    json!({ "language": language, "value": value })
+}
+
+/// The function builds the data value pointing to an item.
+fn item_value(id: u64) -> Value {
+    json!({
+        "value": { "entity-type": "item", "numeric-id": id, "id": format!("Q{}", id) },
+        "type": "wikibase-entityid",
+    })
+}
+
+/// The function builds the data value of a string.
+fn string_value(value: String) -> Value {
+    json!({ "value": value, "type": "string" })
+}
+
+/// The function builds a snak holding a value.
+fn value_snak(property: u64, datatype: &str, datavalue: Value) -> Value {
+    json!({
+        "snaktype": "value",
+        "property": format!("P{}", property),
+        "datavalue": datavalue,
+        "datatype": datatype,
+    })
+}
+
+/// The function builds a snak holding no value, or an unknown one.
+fn bare_snak(property: u64, datatype: &str, snaktype: &str) -> Value {
+    json!({
+        "snaktype": snaktype,
+        "property": format!("P{}", property),
+        "datatype": datatype,
+    })
+}
+
+/// The function builds a statement out of its main snak and its qualifiers.
+fn statement(
+    entity_id: &str,
+    index: usize,
+    mainsnak: Value,
+    rank: &str,
+    qualifiers: Map<String, Value>,
+) -> Value {
+    let order = qualifiers.keys().cloned().collect::<Vec<_>>();
+    json!({
+        "mainsnak": mainsnak,
+        "type": "statement",
+        "qualifiers": qualifiers,
+        "qualifiers-order": order,
+        "id": format!("{}$00000000-0000-0000-0000-{:012}", entity_id, index),
+        "rank": rank,
+        "references": [],
+    })
+}
@@ -0,0 +1,85 @@
+use duckdb::{Connection, Error};
+use wikidata::Qid;
+
+use crate::id::{Id, IdEncoding};
+use crate::schema;
+
+/// The tables storing coordinates, together with the name of the view joining
+/// each of those with the `globe` dimension table.
+const VIEWS: [(&str, &str); 3] = [
+    ("coordinates", "coordinates_globe"),
+    ("qualifier_coordinates", "qualifier_coordinates_globe"),
+    ("reference_coordinates", "reference_coordinates_globe"),
+];
+
+/// The function returns the SQL statements creating the `globe` dimension table,
+/// with a row for each of the globes the coordinates are placed on, and the views
+/// joining the coordinates with it. Most of the coordinates in Wikidata are placed
+/// on the Earth (Q2), but there are plenty of them on the Moon, Mars, Mercury...
+/// which make no sense at all together with the terrestrial ones.
+///
+/// Arguments:
+///
+/// * `labels`: Whether the `vertex` table stores the labels, which it does not in
+/// case the database was built with `--no-text`.
+///
+/// * `encoding`: The `IdEncoding` of the database the table is created in.
+///
+/// Returns:
+///
+/// A vector of `String`s containing the `CREATE` statements, in the order those
+/// have to be executed.
+pub fn create_globe_sql(labels: bool, encoding: &IdEncoding) -> Vec<String> {
+    // Globes are entities as any other, so their labels are only known once the whole dump has
+    // been loaded, as those may be found after the coordinates placed on them :D
+    let mut statements = vec![format!(
+        "CREATE OR REPLACE TABLE globe AS \
+         SELECT g.globe_id, 'Q' || g.globe_id AS qid_text, {} AS label \
+         FROM ({}) g LEFT JOIN vertex v ON v.id = g.globe_id \
+         ORDER BY g.globe_id;",
+        match labels {
+            true => "v.label",
+            false => "NULL::TEXT",
+        },
+        VIEWS
+            .iter()
+            .map(|(table, _)| format!("SELECT DISTINCT globe_id FROM {}", table))
+            .collect::<Vec<_>>()
+            .join(" UNION ")
+    )];
+
+    statements.extend(VIEWS.iter().map(|(table, view)| {
+        format!(
+            "CREATE OR REPLACE VIEW {} AS \
+             SELECT c.*, g.qid_text AS globe, g.label AS globe_label, \
+             c.globe_id = {} AS is_earth \
+             FROM {} c LEFT JOIN globe g ON g.globe_id = c.globe_id;",
+            view,
+            encoding.known(Id::Qid(Qid(2))),
+            table
+        )
+    }));
+
+    statements
+}
+
+/// This function creates the `globe` dimension table, and the views joining the
+/// coordinates with it, replacing those in case they already exist.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database, where the
+/// coordinates and the vertices are already populated.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// creation is successful, or an `Error` object if there is an error during the
+/// execution of the SQL statements.
+pub fn create_globe(connection: &Connection) -> Result<(), Error> {
+    let labels = schema::has_column(connection, "vertex", "label")?;
+    for statement in create_globe_sql(labels, &IdEncoding::of(connection)?) {
+        connection.execute_batch(&statement)?;
+    }
+    Ok(())
+}
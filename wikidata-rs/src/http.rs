@@ -0,0 +1,304 @@
+use std::io::{Error, ErrorKind, Read, Result};
+use std::thread::sleep;
+use std::time::Duration;
+
+use bytes::Bytes;
+use reqwest::header::{
+    HeaderValue, CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Client, Response, StatusCode};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::api::USER_AGENT;
+
+/// The schemes of the URLs pointing to a web server, such as the one of
+/// `dumps.wikimedia.org`.
+const SCHEMES: [&str; 2] = ["http://", "https://"];
+
+/// The number of times a request is retried in a row before giving up, whenever
+/// the connection is lost or the server is not available.
+const MAX_RETRIES: u32 = 8;
+
+/// The maximum seconds waited before retrying a request, as the waits are doubled
+/// on each retry.
+const MAX_BACKOFF: u64 = 60;
+
+/// The function returns whether a path given by the user points to a web server,
+/// such as `https://dumps.wikimedia.org/...`, instead of to the local file system.
+pub fn is_http(path: &str) -> bool {
+    SCHEMES.iter().any(|scheme| path.starts_with(scheme))
+}
+
+/// The function returns the size of the file a URL points to, as told by the
+/// server, so the progress of the load can be estimated.
+///
+/// Arguments:
+///
+/// * `url`: The URL of the file.
+///
+/// Returns:
+///
+/// An `Option` with the size of the file, in bytes, or `None` in case it is not
+/// known, as the server does not tell or the request failed.
+pub fn content_length(url: &str) -> Option<u64> {
+    let client = client().ok()?;
+    let response = runtime().ok()?.block_on(client.head(url).send()).ok()?;
+    // The header is read as it is, as the responses to HEAD requests have no body, whose size is
+    // what the client reports :(
+    match response.status().is_success() {
+        true => response
+            .headers()
+            .get(CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok(),
+        false => None,
+    }
+}
+
+/// The function builds the HTTP client the files are downloaded with, which
+/// identifies itself as the Wikimedia policy requires.
+fn client() -> Result<Client> {
+    Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(Error::other)
+}
+
+/// The function creates the runtime the requests are run in. A single thread is
+/// enough, as the chunks of the file are requested one at a time.
+fn runtime() -> Result<Runtime> {
+    Builder::new_current_thread().enable_all().build()
+}
+
+/// The `HttpReader` struct streams a file served over HTTP, such as the dumps
+/// published at `dumps.wikimedia.org`, so those can be converted as those are
+/// downloaded, without staging them on the local disk first. Whenever the
+/// connection is lost, the download is resumed from the last byte received,
+/// through a `Range` request, so a network error does not start it all over. The
+/// request is conditioned on the file being the one the download started with,
+/// through `If-Range`, so a file replaced in the meantime is never spliced.
+///
+/// Properties:
+///
+/// * `client`: The HTTP client the requests are sent with.
+/// * `runtime`: The runtime the requests are run in.
+/// * `url`: The URL of the file.
+/// * `response`: The response the file is being read from.
+/// * `chunk`: What is left of the last chunk received.
+/// * `offset`: The number of bytes of the file received so far.
+/// * `length`: The size of the file, in bytes, in case the server tells.
+/// * `validator`: The `ETag` of the file, or its `Last-Modified` date in case it
+/// has no strong one, as told by the first response.
+pub struct HttpReader {
+    client: Client,
+    runtime: Runtime,
+    url: String,
+    response: Response,
+    chunk: Bytes,
+    offset: u64,
+    length: Option<u64>,
+    validator: Option<HeaderValue>,
+}
+
+impl HttpReader {
+    /// The function starts the download of a file served over HTTP.
+    ///
+    /// Arguments:
+    ///
+    /// * `url`: The URL of the file, such as
+    /// `https://dumps.wikimedia.org/wikidatawiki/entities/latest-all.json.bz2`.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `HttpReader`, or an `Error` if the file could not be
+    /// found or the server could not be reached after retrying.
+    pub fn open(url: &str) -> Result<Self> {
+        let client = client()?;
+        let runtime = runtime()?;
+        let response = request(&client, &runtime, url, 0, None)?;
+        let length = response.content_length();
+        // Weak ETags cannot be used to resume a download, as those do not tell whether the bytes
+        // are the very same, so the date of the last modification is used instead :(
+        let headers = response.headers();
+        let validator = headers
+            .get(ETAG)
+            .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+            .or_else(|| headers.get(LAST_MODIFIED))
+            .cloned();
+
+        Ok(Self {
+            client,
+            runtime,
+            url: url.to_string(),
+            response,
+            chunk: Bytes::new(),
+            offset: 0,
+            length,
+            validator,
+        })
+    }
+
+    /// The function retrieves the next chunk of the file, resuming the download in
+    /// case the connection has been lost, or has been closed before the whole file
+    /// was received.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the next chunk, which is empty once the file is over, or an
+    /// `Error` if the download could not be resumed.
+    fn next_chunk(&mut self) -> Result<Bytes> {
+        let mut retries = 0;
+        loop {
+            let error = match self.runtime.block_on(self.response.chunk()) {
+                Ok(Some(chunk)) => return Ok(chunk),
+                Ok(None) => match self.length {
+                    Some(length) if self.offset < length => Error::new(
+                        ErrorKind::UnexpectedEof,
+                        format!("The connection was closed at byte {}", self.offset),
+                    ),
+                    _ => return Ok(Bytes::new()),
+                },
+                Err(error) => Error::other(error),
+            };
+
+            retries += 1;
+            if retries > MAX_RETRIES {
+                return Err(error);
+            }
+            eprintln!(
+                "Error downloading {}, resuming from byte {}. {}",
+                self.url, self.offset, error
+            );
+            sleep(backoff(retries));
+            self.response = request(
+                &self.client,
+                &self.runtime,
+                &self.url,
+                self.offset,
+                self.validator.as_ref(),
+            )?;
+        }
+    }
+}
+
+impl Read for HttpReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        // Chunks are requested one at a time, and only once the previous one has been consumed.
+        // Hence, the whole file is never held in memory, nor on disk, no matter its size :D
+        while self.chunk.is_empty() {
+            self.chunk = self.next_chunk()?;
+            if self.chunk.is_empty() {
+                return Ok(0);
+            }
+            self.offset += self.chunk.len() as u64;
+        }
+
+        let read = buf.len().min(self.chunk.len());
+        buf[..read].copy_from_slice(&self.chunk.split_to(read));
+        Ok(read)
+    }
+}
+
+/// The function requests a file from the given byte onwards, retrying whenever
+/// the server cannot be reached, or it is not available for the time being.
+///
+/// Arguments:
+///
+/// * `client`: A reference to the HTTP client the request is sent with.
+/// * `runtime`: A reference to the runtime the request is run in.
+/// * `url`: The URL of the file.
+/// * `offset`: The first byte of the file requested.
+/// * `validator`: The `ETag` or `Last-Modified` date of the file the download
+/// started with, if any, which the range requested is conditioned on.
+///
+/// Returns:
+///
+/// a `Result` with the response, or an `Error` if the file could not be retrieved,
+/// the server cannot resume the download from the byte requested, or the file
+/// changed since the download started.
+fn request(
+    client: &Client,
+    runtime: &Runtime,
+    url: &str,
+    offset: u64,
+    validator: Option<&HeaderValue>,
+) -> Result<Response> {
+    let mut retries = 0;
+    loop {
+        let mut request = client.get(url);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+            if let Some(validator) = validator {
+                request = request.header(IF_RANGE, validator.clone());
+            }
+        }
+
+        let error = match runtime.block_on(request.send()) {
+            Ok(response) => match response.status() {
+                StatusCode::OK if offset == 0 => return Ok(response),
+                StatusCode::PARTIAL_CONTENT if offset > 0 => {
+                    // The range sent must start at the byte requested, or the bytes would be spliced
+                    // at the wrong place :(
+                    let start = response
+                        .headers()
+                        .get(CONTENT_RANGE)
+                        .and_then(|range| range.to_str().ok())
+                        .and_then(|range| range.strip_prefix("bytes "))
+                        .and_then(|range| range.split('-').next())
+                        .and_then(|start| start.parse::<u64>().ok());
+                    match start {
+                        Some(start) if start == offset => return Ok(response),
+                        _ => {
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                format!("{} did not send the range requested", url),
+                            ))
+                        }
+                    }
+                }
+                // Whenever the file changed, the server sends the new one as a whole, while those
+                // ignoring the range send the whole file again. Neither can be resumed :(
+                StatusCode::OK if validator.is_some() => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "{} changed since the download started, or cannot resume it",
+                            url
+                        ),
+                    ))
+                }
+                StatusCode::OK => {
+                    return Err(Error::new(
+                        ErrorKind::Unsupported,
+                        format!("{} does not support resuming downloads", url),
+                    ))
+                }
+                status if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS => {
+                    Error::other(format!("{} answered {}", url, status))
+                }
+                status => {
+                    return Err(Error::new(
+                        ErrorKind::NotFound,
+                        format!("{} answered {}", url, status),
+                    ))
+                }
+            },
+            Err(error) => Error::other(error),
+        };
+
+        retries += 1;
+        if retries > MAX_RETRIES {
+            return Err(error);
+        }
+        eprintln!("Error requesting {}, retrying. {}", url, error);
+        sleep(backoff(retries));
+    }
+}
+
+/// The function returns the time waited before the given retry, which is doubled
+/// on each of them, up to `MAX_BACKOFF` seconds.
+fn backoff(retry: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(retry - 1).min(MAX_BACKOFF))
+}
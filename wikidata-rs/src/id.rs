@@ -1,13 +1,423 @@
-use std::num::Wrapping;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use duckdb::types::Type;
+use duckdb::{Connection, Error};
+use strum::IntoEnumIterator;
 
 use crate::dtype::DataType;
+use crate::metadata;
 use wikidata::{Fid, Lid, Pid, Qid, Sid};
 
+/// The key the encoding of the identifiers is stored under in the metadata.
+const ENCODING_KEY: &str = "id_encoding";
+
+/// The room given to the forms, and to the senses, of each lexeme within their
+/// namespace; that is, `L7-F2` is encoded as the offset of the forms plus
+/// `7 * 100000 + 2`. It is a power of ten, so the identifiers stay readable, and
+/// greater than the number of any form or sense, which is a `u16`.
+pub const SUB_ENTITIES: u64 = 100_000;
+
+/// The `IdEncoding` struct defines how the identifiers of the different namespaces
+/// are packed into a single range of numbers; that is, the offset each namespace
+/// starts at. Encodings are versioned, and the one a database was built with is
+/// recorded in its metadata, so a database is never read, nor appended to, with an
+/// encoding other than its own. Identifiers are converted through the encoding of
+/// the database at hand, so a process may work with databases of several of them.
+///
+/// Properties:
+///
+/// * `version`: The version of the encoding, or `0` for custom offsets.
+/// * `property`: The offset of the properties. Items start at `0`.
+/// * `lexeme`: The offset of the lexemes.
+/// * `form`: The offset of the forms, each lexeme being given `SUB_ENTITIES` of them.
+/// * `sense`: The offset of the senses, each lexeme being given `SUB_ENTITIES` of them.
+/// * `datatype`: The offset of the datatypes.
+/// * `placeholder`: The offset of the placeholders of the unknown values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdEncoding {
+    pub version: u32,
+    pub property: u64,
+    pub lexeme: u64,
+    pub form: u64,
+    pub sense: u64,
+    pub datatype: u64,
+    pub placeholder: u64,
+}
+
+impl IdEncoding {
+    /// The encoding of the 32-bit identifiers, which keeps the offsets of the first
+    /// versions of the tool, and leaves room for a handful of lexemes with forms or
+    /// senses. Notice that those versions wrapped the forms and senses around into
+    /// the ranges of the items and properties, which this encoding does not, so the
+    /// databases built back then, which record no encoding, cannot be read with it.
+    pub const V1: IdEncoding = IdEncoding {
+        version: 1,
+        property: 1_000_000_000,
+        lexeme: 2_000_000_000,
+        form: 3_000_000_000,
+        sense: 3_500_000_000,
+        datatype: 4_000_000_000,
+        placeholder: 4_100_000_000,
+    };
+
+    /// The encoding of the 64-bit identifiers, which leaves room for a quadrillion
+    /// entities in each namespace, and for every form and sense of ten billion
+    /// lexemes, so none of those has to wrap around into the range of another.
+    pub const V2: IdEncoding = IdEncoding {
+        version: 2,
+        property: 1_000_000_000_000_000,
+        lexeme: 2_000_000_000_000_000,
+        form: 3_000_000_000_000_000,
+        sense: 4_000_000_000_000_000,
+        datatype: 5_000_000_000_000_000,
+        placeholder: 6_000_000_000_000_000,
+    };
+
+    /// The function retrieves the encoding recorded in the metadata of a database.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `IdEncoding`, or `None` in case the database records none,
+    /// in which case it is either empty or built before the encodings were recorded,
+    /// with the default one. An `Error` is returned in case the metadata cannot be
+    /// read, or the encoding recorded is not valid.
+    pub fn read(connection: &Connection) -> Result<Option<IdEncoding>, Error> {
+        match metadata::get(connection, ENCODING_KEY)? {
+            Some(encoding) => match encoding.parse() {
+                Ok(encoding) => Ok(Some(encoding)),
+                Err(error) => Err(Error::FromSqlConversionFailure(0, Type::Text, error.into())),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// The function returns the encoding the identifiers of a database are stored
+    /// with; that is, the one recorded in its metadata, or the default one in case
+    /// it records none.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `IdEncoding`, or an `Error` in case the metadata cannot be
+    /// read, or the encoding recorded is not valid.
+    pub fn of(connection: &Connection) -> Result<IdEncoding, Error> {
+        Ok(Self::read(connection)?.unwrap_or_default())
+    }
+
+    /// The function records the encoding in the metadata of a database.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// encoding is recorded, or an `Error` object otherwise.
+    pub fn write(&self, connection: &Connection) -> Result<(), Error> {
+        metadata::set(connection, ENCODING_KEY, &self.to_string())
+    }
+
+    /// The function records the encoding in the metadata of a database, unless it was
+    /// built with another one, in which case appending to it would silently mix both
+    /// of them. Notice that those databases recording no encoding, but holding
+    /// vertices, were built before it was recorded, with the default one.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` type with either an empty `Ok(())` value indicating success or a
+    /// `String` value containing an error message in case the database encodes the
+    /// identifiers otherwise, or its metadata cannot be read or written.
+    pub fn record(&self, connection: &Connection) -> Result<(), String> {
+        let stored = match Self::read(connection) {
+            Ok(Some(stored)) => Some(stored),
+            Ok(None) => match connection.query_row(
+                "SELECT count(*) FROM information_schema.tables WHERE table_name = 'vertex'",
+                [],
+                |row| row.get::<_, i64>(0),
+            ) {
+                Ok(0) => None,
+                Ok(_) => Some(IdEncoding::default()),
+                Err(error) => return Err(format!("Error reading metadata. {}", error)),
+            },
+            Err(error) => return Err(format!("Error reading metadata. {}", error)),
+        };
+        if let Some(stored) = stored.filter(|stored| stored != self) {
+            return Err(format!(
+                "The database encodes identifiers as {}, not as {}",
+                stored, self
+            ));
+        }
+        match self.write(connection) {
+            Ok(()) => Ok(()),
+            Err(error) => Err(format!("Error writing metadata. {}", error)),
+        }
+    }
+
+    /// The function returns the range of the numeric identifiers of a namespace.
+    ///
+    /// Arguments:
+    ///
+    /// * `kind`: The `IdKind` of the namespace.
+    pub fn range(&self, kind: IdKind) -> RangeInclusive<u64> {
+        match kind {
+            IdKind::Item => 0..=self.property - 1,
+            IdKind::Property => self.property..=self.lexeme - 1,
+            IdKind::Lexeme => self.lexeme..=self.form - 1,
+            IdKind::Form => self.form..=self.sense - 1,
+            IdKind::Sense => self.sense..=self.datatype - 1,
+            IdKind::DataType => self.datatype..=self.placeholder - 1,
+            IdKind::Placeholder => self.placeholder..=u64::MAX,
+        }
+    }
+
+    /// The function encodes an identifier into the number it is stored as. The
+    /// offset of its namespace is added to its number; for example, if the `Id` is a
+    /// `Fid` (form ID), the number of its lexeme times `SUB_ENTITIES`, plus the form
+    /// ID's numeric suffix, is added to the offset of the forms.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The `Id` to be encoded.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the numeric identifier, or a `String` with an error message in
+    /// case it does not fit the range of its namespace, such as an item beyond the
+    /// offset of the properties, rather than overflowing into the identifier of
+    /// another entity.
+    pub fn encode(&self, id: Id) -> Result<u64, String> {
+        let encoded = match &id {
+            Id::Fid(fid) => sub_entity(fid.0, fid.1)
+                .and_then(|number| offset(number, self.form, self.sense)),
+            Id::Lid(lid) => offset(lid.0, self.lexeme, self.form),
+            Id::Pid(pid) => offset(pid.0, self.property, self.lexeme),
+            Id::Qid(qid) => offset(qid.0, 0, self.property),
+            Id::Sid(sid) => sub_entity(sid.0, sid.1)
+                .and_then(|number| offset(number, self.sense, self.datatype)),
+            Id::DataType(dt) => offset(u8::from(dt) as u64, self.datatype, self.placeholder),
+            Id::Placeholder(n) => n.checked_add(self.placeholder),
+        };
+        encoded.ok_or_else(|| format!("Identifier out of range: {}", id))
+    }
+
+    /// The function returns the numeric identifier of an identifier known beforehand,
+    /// such as the ones of the instance of (P31) or the subclass of (P279) properties,
+    /// which fit the range of their namespace in any sensible encoding. Hence, the
+    /// identifiers found in the dumps, or given by the user, are converted through
+    /// `encode` instead, so those are reported rather than panicking. It is kept
+    /// private to the crate, as it panics for an identifier out of range.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The `Id` to be encoded.
+    ///
+    /// Returns:
+    ///
+    /// The numeric identifier, as stored in the database.
+    pub(crate) fn known(&self, id: Id) -> u64 {
+        self.encode(id).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// The function decodes a numeric identifier, as stored in the database, back
+    /// into an `Id`. The namespace of the identifier is found out of the range it
+    /// falls in, and its offset is subtracted, so `1000000000000031` is decoded into
+    /// `P31` under `V2`, and `3000000000700002` into `L7-F2`.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The numeric identifier, as stored in the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Id`, or a `String` with an error message for the
+    /// identifiers falling in the ranges left unused.
+    pub fn decode(&self, id: u64) -> Result<Id, String> {
+        let lexeme = |number: u64| match u16::try_from(number % SUB_ENTITIES) {
+            Ok(sub) => Some((Lid(number / SUB_ENTITIES), sub)),
+            Err(_) => None,
+        };
+        let decoded = match (self.kind_of(id), self.local_id(id)) {
+            (Some(IdKind::Item), Some(number)) => Some(Id::Qid(Qid(number))),
+            (Some(IdKind::Property), Some(number)) => Some(Id::Pid(Pid(number))),
+            (Some(IdKind::Lexeme), Some(number)) => Some(Id::Lid(Lid(number))),
+            (Some(IdKind::Form), Some(number)) => {
+                lexeme(number).map(|(lid, sub)| Id::Fid(Fid(lid, sub)))
+            }
+            (Some(IdKind::Sense), Some(number)) => {
+                lexeme(number).map(|(lid, sub)| Id::Sid(Sid(lid, sub)))
+            }
+            (Some(IdKind::DataType), Some(number)) => DataType::iter()
+                .find(|dt| u8::from(dt) as u64 == number)
+                .map(Id::DataType),
+            (Some(IdKind::Placeholder), Some(number)) => Some(Id::Placeholder(number)),
+            _ => None,
+        };
+        decoded.ok_or_else(|| format!("Not valid identifier: {}", id))
+    }
+
+    /// The function classifies a numeric identifier, as stored in the database, into
+    /// its namespace, without building the string of the identifier, such as `Q42`.
+    /// Hence, it is cheap enough to be called for every row of a table.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The numeric identifier, as stored in the database.
+    ///
+    /// Returns:
+    ///
+    /// An `Option` with the `IdKind` of the identifier. As the namespaces follow one
+    /// another up to `u64::MAX`, it is only `None` for encodings whose offsets do not
+    /// increase, which are rejected when parsed.
+    pub fn kind_of(&self, id: u64) -> Option<IdKind> {
+        [
+            IdKind::Item,
+            IdKind::Property,
+            IdKind::Lexeme,
+            IdKind::Form,
+            IdKind::Sense,
+            IdKind::DataType,
+            IdKind::Placeholder,
+        ]
+        .into_iter()
+        .find(|kind| self.range(*kind).contains(&id))
+    }
+
+    /// The function returns whether a numeric identifier, as stored in the database,
+    /// is the one of an item.
+    pub fn is_item(&self, id: u64) -> bool {
+        self.range(IdKind::Item).contains(&id)
+    }
+
+    /// The function returns whether a numeric identifier, as stored in the database,
+    /// is the one of a property.
+    pub fn is_property(&self, id: u64) -> bool {
+        self.range(IdKind::Property).contains(&id)
+    }
+
+    /// The function returns the number of an identifier within its namespace; that
+    /// is, `42` for the numeric identifier of both `Q42` and `P42`, and `700002` for
+    /// the one of `L7-F2`.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The numeric identifier, as stored in the database.
+    ///
+    /// Returns:
+    ///
+    /// An `Option` with the number, or `None` in case the identifier does not belong
+    /// to any namespace.
+    pub fn local_id(&self, id: u64) -> Option<u64> {
+        self.kind_of(id).map(|kind| id - self.range(kind).start())
+    }
+}
+
+impl Default for IdEncoding {
+    fn default() -> Self {
+        IdEncoding::V2
+    }
+}
+
+/// This code formats the encoding as the list of its offsets, such as
+/// `version=1,property=1000000000,...`, which is how it is recorded in the metadata.
+impl Display for IdEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version={},property={},lexeme={},form={},sense={},datatype={},placeholder={}",
+            self.version,
+            self.property,
+            self.lexeme,
+            self.form,
+            self.sense,
+            self.datatype,
+            self.placeholder
+        )
+    }
+}
+
+/// This code parses an encoding out of either its version, such as `v2`, or the
+/// list of its offsets, such as `property=1000000000,lexeme=2000000000`. The offsets
+/// not listed are taken from `V2`, and the version is `0` unless it is listed, as
+/// those are custom offsets. An error message is returned in case the offsets are
+/// not valid or do not increase from one namespace to the next.
+impl FromStr for IdEncoding {
+    type Err = String;
+
+    fn from_str(encoding: &str) -> Result<Self, Self::Err> {
+        match encoding.trim() {
+            "v1" => return Ok(IdEncoding::V1),
+            "v2" => return Ok(IdEncoding::V2),
+            _ => (),
+        }
+
+        let mut parsed = IdEncoding {
+            version: 0,
+            ..IdEncoding::V2
+        };
+        for pair in encoding.split(',') {
+            let (key, value) = match pair.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("Not valid offset, expected NAME=N: {}", pair)),
+            };
+            let value = match value.parse::<u64>() {
+                Ok(value) => value,
+                Err(_) => return Err(format!("Not valid offset: {}", pair)),
+            };
+            match key {
+                "version" => match u32::try_from(value) {
+                    Ok(version) => parsed.version = version,
+                    Err(_) => return Err(format!("Not valid version: {}", pair)),
+                },
+                "property" => parsed.property = value,
+                "lexeme" => parsed.lexeme = value,
+                "form" => parsed.form = value,
+                "sense" => parsed.sense = value,
+                "datatype" => parsed.datatype = value,
+                "placeholder" => parsed.placeholder = value,
+                _ => return Err(format!("Not valid namespace: {}", key)),
+            }
+        }
+
+        // Ranges are told apart by the offsets, so those have to increase from one namespace to the
+        // next. Otherwise, a property may be taken for a lexeme, or the other way around :(
+        let offsets = [
+            0,
+            parsed.property,
+            parsed.lexeme,
+            parsed.form,
+            parsed.sense,
+            parsed.datatype,
+            parsed.placeholder,
+        ];
+        if offsets.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(format!("Offsets do not increase: {}", encoding));
+        }
+
+        Ok(parsed)
+    }
+}
+
 /// The `Id` enum is defining different types of identifiers that can be used in the
 /// Wikidata database. Each variant of the enum corresponds to a different type of
 /// identifier: `Fid` for a form ID, `Lid` for a lexeme ID, `Pid` for a property ID,
 /// `Qid` for a item ID, and `Sid` for a sense ID. This enum is used to represent
-/// and manipulate these different types of IDs in the code.
+/// and manipulate these different types of IDs in the code. `Placeholder` stands
+/// for the identifiers allocated to unknown values (somevalue snaks), which behave
+/// as blank nodes do in RDF.
 pub enum Id {
     Fid(Fid),
     Lid(Lid),
@@ -15,74 +425,283 @@ pub enum Id {
     Qid(Qid),
     Sid(Sid),
     DataType(DataType),
+    Placeholder(u64),
 }
 
-/// This code defines a conversion function from a string slice (`&str`) to an `Id`
-/// enum. The function takes a string slice as input and matches the first character
-/// of the string to determine the type of ID. If the first character is "L", "P",
-/// "Q", "F", or "S", the function creates a corresponding `Lid`, `Pid`, `Qid`,
-/// `Fid`, or `Sid` value and returns it wrapped in the `Id` enum. If the first
-/// character is anything else, the function panics with an error message. The
-/// function is implemented using the `From` trait, which allows for automatic
-/// conversion between types.
-impl<'a> From<&'a str> for Id {
-    fn from(value: &'a str) -> Self {
-        match value.get(0..1) {
-            Some("L") => Self::Lid(Lid(value[1..].parse::<u64>().unwrap())),
-            Some("P") => Self::Pid(Pid(value[1..].parse::<u64>().unwrap())),
-            Some("Q") => Self::Qid(Qid(value[1..].parse::<u64>().unwrap())),
-            Some("F") => {
-                let mut parts = value[1..].split('-');
-                Self::Fid(Fid(
-                    Lid(parts.next().unwrap().parse::<u64>().unwrap()),
-                    parts.next().unwrap()[1..].parse::<u16>().unwrap(),
-                ))
-            }
-            Some("S") => {
-                let mut parts = value[1..].split('-');
-                Self::Sid(Sid(
-                    Lid(parts.next().unwrap().parse::<u64>().unwrap()),
-                    parts.next().unwrap()[1..].parse::<u16>().unwrap(),
-                ))
-            }
-            Some("@") => match &value[1..] {
-                "Quantity" => Self::DataType(DataType::Quantity),
-                "Coordinate" => Self::DataType(DataType::Coordinate),
-                "String" => Self::DataType(DataType::String),
-                "DateTime" => Self::DataType(DataType::DateTime),
-                "Entity" => Self::DataType(DataType::Entity),
-                &_ => panic!("Unknown data type: {}", value),
+/// This code parses the identifier of an entity as given by the user, such as `Q42`,
+/// `P31`, `L7`, `L7-F2` or `L7-S1`. An error message is returned for anything
+/// else, so a typo is reported instead of halting the tool.
+impl FromStr for Id {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Not valid entity: {}", id);
+        let number = |digits: &str| match digits.chars().all(|c| c.is_ascii_digit()) {
+            true => digits.parse::<u64>().map_err(|_| error()),
+            false => Err(error()),
+        };
+
+        let id = id.trim();
+        match id.get(0..1) {
+            Some("Q") => Ok(Self::Qid(Qid(number(&id[1..])?))),
+            Some("P") => Ok(Self::Pid(Pid(number(&id[1..])?))),
+            Some("L") => match id[1..].split_once('-') {
+                None => Ok(Self::Lid(Lid(number(&id[1..])?))),
+                Some((lexeme, sub)) => {
+                    let lexeme = Lid(number(lexeme)?);
+                    let sub_number = |digits: &str| match number(digits) {
+                        Ok(n) => u16::try_from(n).map_err(|_| error()),
+                        Err(error) => Err(error),
+                    };
+                    match sub.get(0..1) {
+                        Some("F") => Ok(Self::Fid(Fid(lexeme, sub_number(&sub[1..])?))),
+                        Some("S") => Ok(Self::Sid(Sid(lexeme, sub_number(&sub[1..])?))),
+                        _ => Err(error()),
+                    }
+                }
             },
-            _ => panic!("Not valid value: {}", value),
+            _ => Err(error()),
         }
     }
 }
 
-/// This code defines a conversion function from an `Id` enum to a `u32` integer.
-/// The function takes an `Id` value as input and matches on its variant to
-/// determine the type of ID. Depending on the type of ID, the function performs a
-/// different calculation to convert it to a `u32` integer. For example, if the `Id`
-/// is a `Fid` (form ID), the function converts its corresponding `Lid` (lexeme ID)
-/// to a `u32` integer and adds the form ID's numeric suffix multiplied by 100
-/// billion. The resulting `u32` integer is returned. This conversion function
-/// allows for easy comparison and manipulation of different types of IDs in the
-/// code.
-impl From<Id> for u32 {
-    fn from(id: Id) -> Self {
-        match id {
-            Id::Fid(fid) => {
-                (Wrapping(u32::from(Id::Lid(fid.0))) + Wrapping(fid.1 as u32 + 3_000_000_000)).0
-            }
-            Id::Lid(lid) => lid.0 as u32 + 2_000_000_000,
-            Id::Pid(pid) => pid.0 as u32 + 1_000_000_000,
-            Id::Qid(qid) => qid.0 as u32,
-            Id::Sid(sid) => {
-                (Wrapping(u32::from(Id::Lid(sid.0)))
-                    + Wrapping(sid.1 as u32 + 3_000_000_000)
-                    + Wrapping(500_000_000))
-                .0
+/// The function adds the offset of a namespace to a number, as long as the result
+/// falls before the offset of the next namespace.
+///
+/// Arguments:
+///
+/// * `number`: The number of the identifier within its namespace.
+/// * `start`: The offset of the namespace.
+/// * `end`: The offset of the next namespace.
+///
+/// Returns:
+///
+/// An `Option` with the numeric identifier, or `None` in case it does not fit.
+fn offset(number: u64, start: u64, end: u64) -> Option<u64> {
+    Some(number)
+        .filter(|number| *number < end - start)
+        .map(|number| number + start)
+}
+
+/// The function returns the number of a form, or of a sense, within its namespace;
+/// that is, the number of its lexeme times `SUB_ENTITIES`, plus its own.
+///
+/// Arguments:
+///
+/// * `lexeme`: The `Lid` of the lexeme the form or sense belongs to.
+/// * `number`: The number of the form or sense within its lexeme.
+///
+/// Returns:
+///
+/// An `Option` with the number, or `None` in case it does not fit a `u64`.
+fn sub_entity(lexeme: Lid, number: u16) -> Option<u64> {
+    lexeme
+        .0
+        .checked_mul(SUB_ENTITIES)
+        .and_then(|lexeme| lexeme.checked_add(number as u64))
+}
+
+/// This code formats the identifier as it is found in the dumps, such as `Q42` or
+/// `L7-F2`, so it can be reported whenever it cannot be converted, or shown in
+/// place of the numeric identifier stored in the database. Datatypes and
+/// placeholders, which are not Wikidata entities, are written as `@Quantity` and
+/// `_:7`, respectively.
+impl Display for Id {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Id::Fid(fid) => write!(f, "L{}-F{}", fid.0 .0, fid.1),
+            Id::Lid(lid) => write!(f, "L{}", lid.0),
+            Id::Pid(pid) => write!(f, "P{}", pid.0),
+            Id::Qid(qid) => write!(f, "Q{}", qid.0),
+            Id::Sid(sid) => write!(f, "L{}-S{}", sid.0 .0, sid.1),
+            Id::DataType(dt) => write!(f, "@{:?}", dt),
+            Id::Placeholder(n) => write!(f, "_:{}", n),
+        }
+    }
+}
+
+/// The `IdKind` enum defines the namespaces the numeric identifiers stored in the
+/// database belong to, each of which is given a range of its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdKind {
+    Item,
+    Property,
+    Lexeme,
+    Form,
+    Sense,
+    DataType,
+    Placeholder,
+}
+
+impl IdKind {
+    /// The function returns the type of the entities of the namespace, as stored in
+    /// the `entity_type` column of the `vertex` table, or `None` for the namespaces
+    /// that are not stored there, such as the one of the datatypes. Forms and senses
+    /// are stored in the tables of their lexemes instead.
+    pub fn entity_type(&self) -> Option<&'static str> {
+        match self {
+            IdKind::Item => Some("item"),
+            IdKind::Property => Some("property"),
+            IdKind::Lexeme => Some("lexeme"),
+            IdKind::Form | IdKind::Sense | IdKind::DataType | IdKind::Placeholder => None,
+        }
+    }
+}
+
+impl Id {
+    /// The function returns the namespace of the identifier.
+    pub fn kind(&self) -> IdKind {
+        match self {
+            Id::Qid(_) => IdKind::Item,
+            Id::Pid(_) => IdKind::Property,
+            Id::Lid(_) => IdKind::Lexeme,
+            Id::Fid(_) => IdKind::Form,
+            Id::Sid(_) => IdKind::Sense,
+            Id::DataType(_) => IdKind::DataType,
+            Id::Placeholder(_) => IdKind::Placeholder,
+        }
+    }
+
+    /// The function returns whether the identifier is the one of an item.
+    pub fn is_item(&self) -> bool {
+        self.kind() == IdKind::Item
+    }
+
+    /// The function returns whether the identifier is the one of a property.
+    pub fn is_property(&self) -> bool {
+        self.kind() == IdKind::Property
+    }
+}
+
+/// The function parses the identifier of a property, such as `P31`, as given by
+/// the user.
+///
+/// Arguments:
+///
+/// * `property`: A string slice with the identifier of the property.
+///
+/// Returns:
+///
+/// a `Result` with the `Pid` of the property, or a `String` with an error message
+/// in case it is not a valid property.
+pub fn parse_property(property: &str) -> Result<Pid, String> {
+    match property.trim().strip_prefix('P').map(str::parse::<u64>) {
+        Some(Ok(id)) => Ok(Pid(id)),
+        _ => Err(format!("Not valid property: {}", property)),
+    }
+}
+
+/// The function parses the identifier of an item, such as `Q5`, as given by the
+/// user.
+///
+/// Arguments:
+///
+/// * `item`: A string slice with the identifier of the item.
+///
+/// Returns:
+///
+/// a `Result` with the `Qid` of the item, or a `String` with an error message in
+/// case it is not a valid item.
+pub fn parse_item(item: &str) -> Result<Qid, String> {
+    match item.trim().strip_prefix('Q').map(str::parse::<u64>) {
+        Some(Ok(id)) => Ok(Qid(id)),
+        _ => Err(format!("Not valid item: {}", item)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_encodings() {
+        assert_eq!("v1".parse::<IdEncoding>(), Ok(IdEncoding::V1));
+        assert_eq!(" v2 ".parse::<IdEncoding>(), Ok(IdEncoding::V2));
+    }
+
+    #[test]
+    fn parses_custom_offsets() {
+        let encoding = "version=7, property=500000000000000, placeholder=7000000000000000"
+            .parse::<IdEncoding>()
+            .unwrap();
+        assert_eq!(
+            encoding,
+            IdEncoding {
+                version: 7,
+                property: 500_000_000_000_000,
+                placeholder: 7_000_000_000_000_000,
+                ..IdEncoding::V2
             }
-            Id::DataType(dt) => u8::from(&dt) as u32 + 4_000_000_000,
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_encodings() {
+        for encoding in [
+            "v3",
+            "property",
+            "property=-1",
+            "item=5",
+            "version=4294967296",
+            "lexeme=500",
+            "property=3000000000000000",
+        ] {
+            assert!(encoding.parse::<IdEncoding>().is_err(), "{}", encoding);
         }
     }
+
+    #[test]
+    fn encodes_into_the_namespaces() {
+        let encoding = IdEncoding::V2;
+        assert_eq!(encoding.encode(Id::Qid(Qid(42))), Ok(42));
+        assert_eq!(encoding.encode(Id::Pid(Pid(31))), Ok(1_000_000_000_000_031));
+        assert_eq!(
+            encoding.encode(Id::Fid(Fid(Lid(7), 2))),
+            Ok(3_000_000_000_700_002)
+        );
+        assert!(encoding.encode(Id::Qid(Qid(encoding.property))).is_err());
+    }
+
+    #[test]
+    fn parses_entity_identifiers() {
+        assert_eq!("Q42".parse::<Id>().unwrap().to_string(), "Q42");
+        assert_eq!(" L7-F2 ".parse::<Id>().unwrap().to_string(), "L7-F2");
+        for id in ["", "Q", "Q4x", "X1", "L7-F", "L7-F70000", "L7-X1"] {
+            assert!(id.parse::<Id>().is_err(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn decodes_what_is_encoded() {
+        let ids = [
+            Id::Qid(Qid(42)),
+            Id::Pid(Pid(31)),
+            Id::Lid(Lid(7)),
+            Id::Fid(Fid(Lid(7), 2)),
+            Id::Sid(Sid(Lid(7), 1)),
+            Id::Placeholder(5),
+        ];
+        for id in ids {
+            let expected = id.to_string();
+            let encoded = IdEncoding::V2.encode(id).unwrap();
+            assert_eq!(IdEncoding::V2.decode(encoded).unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn rejects_unused_identifiers() {
+        // Forms and senses are numbered below 65536 within their lexeme, so the rest of the
+        // sub-entity numbers are left unused :(
+        assert!(IdEncoding::V2.decode(3_000_000_000_765_536).is_err());
+        assert!(IdEncoding::V2.decode(4_000_000_000_799_999).is_err());
+    }
+
+    #[test]
+    fn converts_through_several_encodings_at_once() {
+        let v1 = IdEncoding::V1.encode(Id::Pid(Pid(31))).unwrap();
+        let v2 = IdEncoding::V2.encode(Id::Pid(Pid(31))).unwrap();
+        assert_eq!((v1, v2), (1_000_000_031, 1_000_000_000_000_031));
+        assert_eq!(IdEncoding::V1.decode(v1).unwrap().to_string(), "P31");
+        assert_eq!(IdEncoding::V1.kind_of(v2), Some(IdKind::Placeholder));
+    }
 }
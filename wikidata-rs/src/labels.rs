@@ -0,0 +1,69 @@
+use duckdb::{Connection, Error};
+
+/// The name of the table storing the labels and descriptions of the entities in
+/// the languages asked for by the user.
+pub const LABEL_TABLE: &str = "label";
+
+/// The name of the table storing the aliases of the entities in the languages
+/// asked for by the user.
+pub const ALIAS_TABLE: &str = "alias";
+
+/// The function checks the code of a language, such as `de` or `zh-hans`, so it can
+/// be safely used as a key of the JSON of the entities, or within the name of a
+/// column.
+///
+/// Arguments:
+///
+/// * `language`: The code of the language.
+///
+/// Returns:
+///
+/// a `Result` with the code of the language, or a `String` with an error message
+/// in case it is not valid.
+pub fn parse_language(language: &str) -> Result<String, String> {
+    if language.is_empty()
+        || !language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(format!("Not valid language: {}", language));
+    }
+    Ok(language.to_string())
+}
+
+/// This function returns the SQL statements creating the `label` table, with a row
+/// per entity and language, holding its label and its description in such language,
+/// and the `alias` table, with a row per alias of each entity in such language.
+/// Entities with no terms at all in a language are given no row for it.
+pub fn create_tables_sql() -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\
+            id UBIGINT NOT NULL, \
+            lang TEXT NOT NULL, \
+            label TEXT, \
+            description TEXT\
+        ); \
+        CREATE TABLE IF NOT EXISTS {} (\
+            id UBIGINT NOT NULL, \
+            lang TEXT NOT NULL, \
+            alias TEXT NOT NULL\
+        );",
+        LABEL_TABLE, ALIAS_TABLE
+    )
+}
+
+/// This function creates the `label` and `alias` tables in the database, in case
+/// those do not exist yet.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// table creation is successful, or an `Error` object if there is an error during
+/// the execution of the SQL statement.
+pub fn create_tables(connection: &Connection) -> Result<(), Error> {
+    connection.execute_batch(&create_tables_sql())
+}
@@ -0,0 +1,273 @@
+use duckdb::{params, Connection, Error};
+use serde_json::Value;
+use wikidata::{Fid, Lid, Sid};
+
+use crate::id::{parse_item, Id, IdEncoding};
+use crate::value::AppenderHelper;
+
+/// The name of the table storing the language and the lexical category of each
+/// lexeme.
+pub const LEXEME_TABLE: &str = "lexeme";
+
+/// The name of the table storing the lemmas of the lexemes; that is, their
+/// canonical spelling, as found in a dictionary.
+pub const LEMMA_TABLE: &str = "lemma";
+
+/// The name of the table storing the representations of the forms of the lexemes,
+/// such as the plural of a noun or the past tense of a verb.
+pub const FORM_TABLE: &str = "form";
+
+/// The name of the table storing the grammatical features of the forms, such as
+/// plural (Q146786) or past tense (Q1994301).
+pub const FORM_FEATURE_TABLE: &str = "form_feature";
+
+/// The name of the table storing the glosses of the senses of the lexemes; that
+/// is, the short definitions of their meanings.
+pub const SENSE_TABLE: &str = "sense";
+
+/// The tables storing the lexical details of the lexemes.
+pub const LEXEME_TABLES: [&str; 5] = [
+    LEXEME_TABLE,
+    LEMMA_TABLE,
+    FORM_TABLE,
+    FORM_FEATURE_TABLE,
+    SENSE_TABLE,
+];
+
+/// The `Form` struct contains the lexical details of a form of a lexeme.
+///
+/// Properties:
+///
+/// * `id`: The numeric identifier of the form, which is the same one the claims
+/// pointing to it store as their `dst_id`.
+/// * `representations`: The spellings of the form, as pairs of language and text.
+/// * `grammatical_features`: The numeric identifiers of the items describing the
+/// grammatical features of the form.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Form {
+    pub id: u64,
+    pub representations: Vec<(String, String)>,
+    pub grammatical_features: Vec<u64>,
+}
+
+/// The `Sense` struct contains the lexical details of a sense of a lexeme.
+///
+/// Properties:
+///
+/// * `id`: The numeric identifier of the sense, which is the same one the claims
+/// pointing to it store as their `dst_id`.
+/// * `glosses`: The glosses of the sense, as pairs of language and text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sense {
+    pub id: u64,
+    pub glosses: Vec<(String, String)>,
+}
+
+/// The `Lexeme` struct contains the lexical details of a lexeme, which are not
+/// kept by the parser of the entities, as those are specific to lexemes. Hence,
+/// those are retrieved from the JSON value of the entity beforehand.
+///
+/// Properties:
+///
+/// * `language`: The numeric identifier of the item of the language of the lexeme.
+/// * `lexical_category`: The numeric identifier of the item of the lexical
+/// category of the lexeme, such as noun (Q1084) or verb (Q24905).
+/// * `lemmas`: The lemmas of the lexeme, as pairs of language and text.
+/// * `forms`: The forms of the lexeme.
+/// * `senses`: The senses of the lexeme.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lexeme {
+    pub language: Option<u64>,
+    pub lexical_category: Option<u64>,
+    pub lemmas: Vec<(String, String)>,
+    pub forms: Vec<Form>,
+    pub senses: Vec<Sense>,
+}
+
+impl Lexeme {
+    /// The function retrieves the lexical details out of the JSON value of an entity.
+    /// Forms and senses whose identifier cannot be understood, or does not fit the
+    /// range of its namespace, are skipped.
+    ///
+    /// Arguments:
+    ///
+    /// * `value`: A reference to the JSON value of the entity.
+    /// * `encoding`: The `IdEncoding` the identifiers are stored with.
+    ///
+    /// Returns:
+    ///
+    /// An `Option` with the `Lexeme`, or `None` in case the entity is not a lexeme.
+    pub fn from_json(value: &Value, encoding: &IdEncoding) -> Option<Self> {
+        if value.get("type").and_then(Value::as_str) != Some("lexeme") {
+            return None;
+        }
+
+        let forms = value
+            .get("forms")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|form| {
+                let (lexeme, number) = sub_id(form, 'F')?;
+                Some(Form {
+                    id: encoding.encode(Id::Fid(Fid(lexeme, number))).ok()?,
+                    representations: terms(form.get("representations")),
+                    grammatical_features: form
+                        .get("grammaticalFeatures")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|value| item_id(value, encoding))
+                        .collect(),
+                })
+            })
+            .collect();
+
+        let senses = value
+            .get("senses")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|sense| {
+                let (lexeme, number) = sub_id(sense, 'S')?;
+                Some(Sense {
+                    id: encoding.encode(Id::Sid(Sid(lexeme, number))).ok()?,
+                    glosses: terms(sense.get("glosses")),
+                })
+            })
+            .collect();
+
+        Some(Self {
+            language: value.get("language").and_then(|value| item_id(value, encoding)),
+            lexical_category: value.get("lexicalCategory").and_then(|value| item_id(value, encoding)),
+            lemmas: terms(value.get("lemmas")),
+            forms,
+            senses,
+        })
+    }
+
+    /// The function appends the lexical details of the lexeme to their tables.
+    ///
+    /// Arguments:
+    ///
+    /// * `appender_helper`: A mutable reference to the `AppenderHelper`, which should
+    /// hold the appenders of the `LEXEME_TABLES`.
+    /// * `id`: The numeric identifier of the lexeme.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with either an empty `Ok(())` or an `Error` if any of the rows
+    /// could not be appended.
+    pub fn append(&self, appender_helper: &mut AppenderHelper, id: u64) -> Result<(), Error> {
+        appender_helper.append_row(
+            LEXEME_TABLE,
+            params![id, self.language, self.lexical_category],
+        )?;
+        for (language, lemma) in &self.lemmas {
+            appender_helper.append_row(LEMMA_TABLE, params![id, language, lemma])?;
+        }
+        for form in &self.forms {
+            for (language, representation) in &form.representations {
+                appender_helper
+                    .append_row(FORM_TABLE, params![form.id, id, language, representation])?;
+            }
+            for feature in &form.grammatical_features {
+                appender_helper.append_row(FORM_FEATURE_TABLE, params![form.id, feature])?;
+            }
+        }
+        for sense in &self.senses {
+            for (language, gloss) in &sense.glosses {
+                appender_helper.append_row(SENSE_TABLE, params![sense.id, id, language, gloss])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The function parses the identifier of a form or a sense, such as `L7-F2` or
+/// `L7-S1`, into the lexeme it belongs to and its number within it.
+fn sub_id(value: &Value, kind: char) -> Option<(Lid, u16)> {
+    let id = value.get("id").and_then(Value::as_str)?;
+    let (lexeme, number) = id.strip_prefix('L')?.split_once('-')?;
+    Some((
+        Lid(lexeme.parse().ok()?),
+        number.strip_prefix(kind)?.parse().ok()?,
+    ))
+}
+
+/// The function parses the identifier of an item, such as `Q1084`, into its
+/// numeric identifier.
+fn item_id(value: &Value, encoding: &IdEncoding) -> Option<u64> {
+    let qid = parse_item(value.as_str()?).ok()?;
+    encoding.encode(Id::Qid(qid)).ok()
+}
+
+/// The function retrieves the terms of a map keyed by language, such as the lemmas
+/// or the glosses, as pairs of language and text. Every language is kept, as the
+/// lemmas and representations are the very word in its several spellings, rather
+/// than translations of it.
+fn terms(value: Option<&Value>) -> Vec<(String, String)> {
+    value
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(language, term)| {
+            let text = term.get("value").and_then(Value::as_str)?;
+            Some((language.to_owned(), text.to_owned()))
+        })
+        .collect()
+}
+
+/// This function returns the SQL statements creating the tables storing the lexical
+/// details of the lexemes: the `lexeme` table, with their language and lexical
+/// category; the `lemma` table, with a row per lemma; the `form` table, with a row
+/// per representation of each form; the `form_feature` table, with a row per
+/// grammatical feature of each form; and the `sense` table, with a row per gloss of
+/// each sense.
+pub fn create_tables_sql() -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\
+            id UBIGINT NOT NULL, \
+            language_id UBIGINT, \
+            lexical_category_id UBIGINT\
+        ); \
+        CREATE TABLE IF NOT EXISTS {} (\
+            id UBIGINT NOT NULL, \
+            lang TEXT NOT NULL, \
+            lemma TEXT NOT NULL\
+        ); \
+        CREATE TABLE IF NOT EXISTS {} (\
+            id UBIGINT NOT NULL, \
+            lexeme_id UBIGINT NOT NULL, \
+            lang TEXT NOT NULL, \
+            representation TEXT NOT NULL\
+        ); \
+        CREATE TABLE IF NOT EXISTS {} (\
+            id UBIGINT NOT NULL, \
+            feature_id UBIGINT NOT NULL\
+        ); \
+        CREATE TABLE IF NOT EXISTS {} (\
+            id UBIGINT NOT NULL, \
+            lexeme_id UBIGINT NOT NULL, \
+            lang TEXT NOT NULL, \
+            gloss TEXT NOT NULL\
+        );",
+        LEXEME_TABLE, LEMMA_TABLE, FORM_TABLE, FORM_FEATURE_TABLE, SENSE_TABLE
+    )
+}
+
+/// This function creates the tables storing the lexical details of the lexemes in
+/// the database, in case those do not exist yet.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// table creation is successful, or an `Error` object if there is an error during
+/// the execution of the SQL statements.
+pub fn create_tables(connection: &Connection) -> Result<(), Error> {
+    connection.execute_batch(&create_tables_sql())
+}
@@ -1,22 +1,206 @@
 use lazy_static::lazy_static;
 use wikidata::Lang;
 
+/// `pub mod api;` is creating a public module named `api`. This module contains
+/// code related to requesting entities from the Wikidata API, at a bounded rate.
+pub mod api;
+/// `pub mod archive;` is creating a public module named `archive`. This module
+/// contains code related to unpacking the archives the dumps are shipped in, such
+/// as multistream bz2 files or tarred chunks.
+pub mod archive;
+/// `pub mod backfill;` is creating a public module named `backfill`. This module
+/// contains code related to storing, once the load has finished, the vertices of
+/// the units and globes that are referenced, but were not stored.
+pub mod backfill;
+/// `pub mod budget;` is creating a public module named `budget`. This module
+/// contains code related to keeping the size of the database under a budget.
+pub mod budget;
+/// `pub mod checkpoint;` is creating a public module named `checkpoint`. This
+/// module contains code related to recording where the load is, so an interrupted
+/// one can be resumed.
+pub mod checkpoint;
+/// `pub mod checksum;` is creating a public module named `checksum`. This module
+/// contains code related to computing the checksums of the inputs as those are
+/// read, so it is known which dump a database was built from.
+pub mod checksum;
+/// `pub mod columns;` is creating a public module named `columns`. This module
+/// contains code related to the extra columns users can add to the tables, which
+/// are computed during the load out of simple expressions.
+pub mod columns;
+/// `pub mod compat;` is creating a public module named `compat`. This module
+/// contains code related to normalizing the entities of older dumps, so those can
+/// be processed in the same manner as the current ones.
+pub mod compat;
+/// `pub mod converter;` is creating a public module named `converter`. This module
+/// contains code related to converting the entities of a dump into rows of the
+/// database, so the conversion can be embedded in other programs through the
+/// `Converter`.
+pub mod converter;
+/// `pub mod dead_letter;` is creating a public module named `dead_letter`. This
+/// module contains code related to writing the lines that could not be stored to
+/// a sidecar file, so those can be inspected or loaded again afterwards.
+pub mod dead_letter;
+/// `pub mod dedup;` is creating a public module named `dedup`. This module contains
+/// code related to detecting the entities that are found more than once among the
+/// inputs, so those are not stored twice.
+pub mod dedup;
+/// `pub mod derived;` is creating a public module named `derived`. This module
+/// contains code related to the optional tables that are computed out of the loaded
+/// ones once the load has finished, such as the quantities normalized to SI units.
+pub mod derived;
 /// `pub mod dtype;` is creating a public module named `dtype`. This module can be
 /// accessed from other parts of the codebase and contains code related to data
 /// types.
 pub mod dtype;
+/// `pub mod enrich;` is creating a public module named `enrich`. This module
+/// contains code related to filling the gaps of the database, once the load has
+/// finished, out of the Wikidata API, such as the labels of missing entities.
+pub mod enrich;
+/// `pub mod export;` is creating a public module named `export`. This module
+/// contains code related to exporting the database back to other formats, such as
+/// the JSON dumps of Wikidata.
+pub mod export;
+/// `pub mod fetch;` is creating a public module named `fetch`. This module contains
+/// code related to retrieving single entities out of the Wikidata API, so small
+/// databases can be built without downloading a whole dump.
+pub mod fetch;
+/// `pub mod generate;` is creating a public module named `generate`. This module
+/// contains code related to generating fake, yet realistic, dumps, so pipelines can
+/// be tested without downloading the real ones.
+pub mod generate;
+/// `pub mod globe;` is creating a public module named `globe`. This module
+/// contains code related to the dimension table of the globes the coordinates are
+/// placed on, so those on the Moon or Mars can be told apart from terrestrial ones.
+pub mod globe;
+/// `pub mod http;` is creating a public module named `http`. This module contains
+/// code related to streaming the dumps served over HTTP, such as the ones of
+/// `dumps.wikimedia.org`, resuming the downloads whenever the connection is lost.
+pub mod http;
 /// `pub mod id;` is creating a public module named `id`. This module
 /// contains code related to generating and managing Wikibase unique identifiers
 /// or IDs within the codebase.
 pub mod id;
+/// `pub mod labels;` is creating a public module named `labels`. This module
+/// contains code related to storing the labels and descriptions of the entities in
+/// several languages.
+pub mod labels;
+/// `pub mod lexeme;` is creating a public module named `lexeme`. This module
+/// contains code related to the lexical details of the lexemes, such as their
+/// lemmas, forms and senses, which are stored in tables of their own.
+pub mod lexeme;
+/// `pub mod lines;` is creating a public module named `lines`. This module contains
+/// code related to splitting the dumps into their lines, without reading those that
+/// are too long into memory.
+pub mod lines;
+/// `pub mod load_log;` is creating a public module named `load_log`. This module
+/// contains code related to recording the throughput of the load into the database
+/// itself, so the performance of different runs can be compared.
+pub mod load_log;
+/// `pub mod macros;` is creating a public module named `macros`. This module
+/// contains code related to the SQL macros installed in the database once the load
+/// has finished, such as the ones reasoning over the class hierarchy.
+pub mod macros;
+/// `pub mod memory;` is creating a public module named `memory`. This module
+/// contains code related to keeping the memory used by the load under a limit, so
+/// long loads are not ended by the OOM killer.
+pub mod memory;
+/// `pub mod metadata;` is creating a public module named `metadata`. This module
+/// contains code related to recording how the database was built, such as the
+/// encoding of the identifiers, so those reading it know how to interpret it.
+pub mod metadata;
+/// `pub mod motherduck;` is creating a public module named `motherduck`. This
+/// module contains code related to uploading the databases built to MotherDuck.
+pub mod motherduck;
+/// `pub mod ntriples;` is creating a public module named `ntriples`. This module
+/// contains code related to building the entities out of the triples of the
+/// N-Triples dumps, such as the truthy ones.
+pub mod ntriples;
+/// `pub mod order;` is creating a public module named `order`. This module contains
+/// code related to restoring the input order of the entities when those are
+/// processed in parallel.
+pub mod order;
+/// `pub mod output;` is creating a public module named `output`. This module
+/// contains code related to writing the tables of the database to other formats,
+/// such as Parquet, once the load has finished.
+pub mod output;
+/// `pub mod partition;` is creating a public module named `partition`. This module
+/// contains code related to storing the claims of the hottest properties in tables
+/// of their own, so those are fast to scan.
+pub mod partition;
+/// `pub mod path;` is creating a public module named `path`. This module contains
+/// code related to compiling SPARQL-like property paths, such as `P31/P279*`, into
+/// recursive SQL queries over the edge table.
+pub mod path;
+/// `pub mod pipeline;` is creating a public module named `pipeline`. This module
+/// contains code related to parsing the entities of the dump on several threads.
+pub mod pipeline;
+/// `pub mod pragma;` is creating a public module named `pragma`. This module
+/// contains code related to the settings of DuckDB applied once the database is
+/// opened, such as its memory limit or its temporary directory.
+pub mod pragma;
+/// `pub mod preset;` is creating a public module named `preset`. This module
+/// contains code related to the bundles of options reducing the size of the
+/// database, such as the minimal one.
+pub mod preset;
+/// `pub mod progress;` is creating a public module named `progress`. This module
+/// contains code related to reporting the progress of the load, such as its
+/// throughput and the time left.
+pub mod progress;
+/// `pub mod provenance;` is creating a public module named `provenance`. This
+/// module contains code related to recording the input, and the position within
+/// it, each entity stored comes from.
+pub mod provenance;
+/// `pub mod raw;` is creating a public module named `raw`. This module contains
+/// code related to keeping the claims whose value cannot be converted, so those
+/// are stored as they are found in the dump instead of being dropped.
+pub mod raw;
+/// `pub mod remote;` is creating a public module named `remote`. This module
+/// contains code related to streaming the dumps staged in object stores, such as
+/// S3, without downloading them first.
+pub mod remote;
+/// `pub mod sample;` is creating a public module named `sample`. This module
+/// contains code related to keeping just a random sample of the entities.
+pub mod sample;
+/// `pub mod schema;` is creating a public module named `schema`. This module
+/// contains code related to describing the tables and columns of the database,
+/// with stable names, so external tools can generate queries against it.
+pub mod schema;
+/// `pub mod snapshot;` is creating a public module named `snapshot`. This module
+/// contains code related to taking consistent copies of the database while it is
+/// being loaded, so those can be explored before the load finishes.
+pub mod snapshot;
+/// `pub mod stats;` is creating a public module named `stats`. This module
+/// contains code related to the statistics computed once the load has finished,
+/// such as the number of instances of each class.
+pub mod stats;
+/// `pub mod throttle;` is creating a public module named `throttle`. This module
+/// contains code related to bounding the rate at which the input dump is read.
+pub mod throttle;
+/// `pub mod udf;` is creating a public module named `udf`. This module contains
+/// code related to the scalar functions written in Rust that are registered on the
+/// connections querying the database, such as the one decoding the identifiers.
+pub mod udf;
 /// `pub mod value;` is creating a public module named `value`. This module contains
 /// code related to representing and manipulating Wikibase values, such as strings,
 /// numbers, and dates.
 pub mod value;
+/// `pub mod validation;` is creating a public module named `validation`. This module
+/// contains code related to classifying the lines of the dump that could not be
+/// stored, and to summarizing those failures at the end of the load.
+pub mod validation;
+/// `pub mod verify;` is creating a public module named `verify`. This module
+/// contains code related to spot-checking a database against the current revision
+/// of its entities in Wikidata.
+pub mod verify;
+/// `pub mod watch;` is creating a public module named `watch`. This module contains
+/// code related to watching a directory for the chunks of a dump as those arrive.
+pub mod watch;
+/// `pub mod xml;` is creating a public module named `xml`. This module contains
+/// code related to extracting the entities embedded in the XML dumps.
+pub mod xml;
 
 // Allows the declaration of Global variables using functions inside of them. In this case,
 // lazy_static! environment allows calling the to_owned function
 lazy_static! {
     pub static ref LANG: Lang = Lang("en".to_owned());
-    pub static ref INSERTS_PER_TRANSACTION: usize = 1_000;
 }
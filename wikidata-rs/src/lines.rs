@@ -0,0 +1,426 @@
+use std::cell::Cell;
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::io::{BufRead, Error, ErrorKind, Read, Result};
+use std::rc::Rc;
+
+/// The `LineTooLong` struct is the error returned for the lines that are longer
+/// than the limit, which are skipped instead of being read into memory.
+///
+/// Properties:
+///
+/// * `length`: The length of the line, in bytes.
+/// * `max_length`: The maximum length of a line, in bytes.
+#[derive(Clone, Debug)]
+pub struct LineTooLong {
+    pub length: usize,
+    pub max_length: usize,
+}
+
+impl Display for LineTooLong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Line of {} bytes is longer than the limit of {} bytes",
+            self.length, self.max_length
+        )
+    }
+}
+
+impl StdError for LineTooLong {}
+
+/// The function returns the `LineTooLong` error an I/O error stands for, if any,
+/// so those lines can be skipped while the rest of the I/O errors halt the load.
+pub fn line_too_long(error: &Error) -> Option<&LineTooLong> {
+    error
+        .get_ref()
+        .and_then(|error| error.downcast_ref::<LineTooLong>())
+}
+
+/// The `BoundedLines` struct splits a dump into its lines, as raw bytes, without
+/// ever holding more than a certain number of bytes of any of them in memory. Some
+/// entities, such as the heavily referenced items, take several megabytes, so the
+/// buffer grows as needed; however, a damaged dump without line breaks would be
+/// read into memory as a whole. Hence, lines longer than the limit are consumed
+/// until their end, but discarded, and reported as a `LineTooLong` error.
+///
+/// Properties:
+///
+/// * `reader`: The reader over the dump.
+/// * `max_length`: The maximum length of a line, in bytes.
+pub struct BoundedLines<R> {
+    reader: R,
+    max_length: usize,
+}
+
+impl<R: BufRead> BoundedLines<R> {
+    /// The function splits a reader into lines no longer than the given limit.
+    ///
+    /// Arguments:
+    ///
+    /// * `reader`: The reader over the dump.
+    /// * `max_length`: The maximum length of a line, in bytes.
+    ///
+    /// Returns:
+    ///
+    /// The `BoundedLines` iterator.
+    pub fn new(reader: R, max_length: usize) -> Self {
+        Self { reader, max_length }
+    }
+}
+
+impl<R: BufRead> Iterator for BoundedLines<R> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = Vec::new();
+        let mut length = 0;
+        let mut found = false;
+
+        loop {
+            let buffer = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Some(Err(error)),
+            };
+            if buffer.is_empty() {
+                break; // the end of the dump has been reached
+            }
+            found = true;
+
+            let (chunk, consumed, complete) = match buffer.iter().position(|&byte| byte == b'\n') {
+                Some(position) => (&buffer[..position], position + 1, true),
+                None => (buffer, buffer.len(), false),
+            };
+
+            // Once the line is known to be too long, the rest of it is just skipped, so the memory
+            // used is bounded by the limit no matter how long the line is :D
+            length += chunk.len();
+            if length <= self.max_length {
+                line.extend_from_slice(chunk);
+            } else if !line.is_empty() {
+                line = Vec::new();
+            }
+
+            self.reader.consume(consumed);
+            if complete {
+                break;
+            }
+        }
+
+        if !found {
+            return None;
+        }
+        if length > self.max_length {
+            return Some(Err(too_long(length, self.max_length)));
+        }
+
+        if line.last() == Some(&b'\r') {
+            line.pop(); // as BufRead::lines does, CRLF line endings are removed
+        }
+        Some(Ok(line))
+    }
+}
+
+/// The `JsonDocuments` struct splits a dump into the JSON objects it contains, no
+/// matter how those are laid out; that is, pretty-printed over several lines, or
+/// concatenated with no line break at all. Whatever is found between the objects,
+/// such as whitespace, commas or the brackets of an enclosing array, is skipped.
+/// The documents are bounded in the same manner as the lines of `BoundedLines`.
+///
+/// Properties:
+///
+/// * `reader`: The reader over the dump.
+/// * `max_length`: The maximum length of a document, in bytes.
+pub struct JsonDocuments<R> {
+    reader: R,
+    max_length: usize,
+}
+
+impl<R: BufRead> JsonDocuments<R> {
+    /// The function splits a reader into JSON documents no longer than the given
+    /// limit.
+    ///
+    /// Arguments:
+    ///
+    /// * `reader`: The reader over the dump.
+    /// * `max_length`: The maximum length of a document, in bytes.
+    ///
+    /// Returns:
+    ///
+    /// The `JsonDocuments` iterator.
+    pub fn new(reader: R, max_length: usize) -> Self {
+        Self { reader, max_length }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonDocuments<R> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut document = Vec::new();
+        let mut length = 0;
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        loop {
+            let buffer = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Some(Err(error)),
+            };
+            if buffer.is_empty() {
+                // The dump was truncated in the middle of a document, which is returned as it is,
+                // so it is reported as truncated JSON later on :(
+                return match depth {
+                    0 => None,
+                    _ if length > self.max_length => Some(Err(too_long(length, self.max_length))),
+                    _ => Some(Ok(document)),
+                };
+            }
+
+            // Outside of the objects, everything but the opening bracket of the next one is skipped
+            let mut start = 0;
+            if depth == 0 {
+                match buffer.iter().position(|&byte| byte == b'{') {
+                    Some(position) => start = position,
+                    None => {
+                        let consumed = buffer.len();
+                        self.reader.consume(consumed);
+                        continue;
+                    }
+                }
+            }
+
+            // Brackets found within strings do not count, and neither do escaped quotes, so those
+            // have to be tracked byte by byte. Multi-byte UTF-8 sequences never contain any ASCII
+            // byte, so the document can be scanned without decoding it :D
+            let mut end = None;
+            for (position, &byte) in buffer.iter().enumerate().skip(start) {
+                if in_string {
+                    match byte {
+                        _ if escaped => escaped = false,
+                        b'\\' => escaped = true,
+                        b'"' => in_string = false,
+                        _ => (),
+                    }
+                    continue;
+                }
+                match byte {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(position + 1);
+                            break;
+                        }
+                    }
+                    _ => (),
+                }
+            }
+
+            let chunk = &buffer[start..end.unwrap_or(buffer.len())];
+            length += chunk.len();
+            if length <= self.max_length {
+                document.extend_from_slice(chunk);
+            } else if !document.is_empty() {
+                document = Vec::new();
+            }
+
+            let consumed = end.unwrap_or(buffer.len());
+            self.reader.consume(consumed);
+            if end.is_some() {
+                break;
+            }
+        }
+
+        if length > self.max_length {
+            return Some(Err(too_long(length, self.max_length)));
+        }
+        Some(Ok(document))
+    }
+}
+
+/// The function returns the I/O error wrapping a `LineTooLong` error.
+fn too_long(length: usize, max_length: usize) -> Error {
+    Error::new(ErrorKind::InvalidData, LineTooLong { length, max_length })
+}
+
+/// The `Counted` struct wraps a reader, counting the bytes consumed from it, so
+/// the offset each line of the dump starts at is known. The counter is shared, as
+/// the reader is moved into the iterator splitting it into lines.
+///
+/// Properties:
+///
+/// * `inner`: The reader being counted.
+/// * `consumed`: The number of bytes consumed so far.
+pub struct Counted<R> {
+    inner: R,
+    consumed: Rc<Cell<u64>>,
+}
+
+impl<R: BufRead> Counted<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            consumed: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// The function returns the counter of the bytes consumed, which keeps being
+    /// updated as the reader is consumed.
+    pub fn counter(&self) -> Rc<Cell<u64>> {
+        self.consumed.clone()
+    }
+}
+
+impl<R: BufRead> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.consumed.set(self.consumed.get() + read as u64);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for Counted<R> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.consumed.set(self.consumed.get() + amt as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    /// The function collects what an iterator splitting a dump returns, with the
+    /// contents of each line or document, or the length of the ones too long.
+    fn collect<I: Iterator<Item = Result<Vec<u8>>>>(
+        iterator: I,
+    ) -> Vec<std::result::Result<String, usize>> {
+        iterator
+            .map(|item| match item {
+                Ok(bytes) => Ok(String::from_utf8(bytes).unwrap()),
+                Err(error) => Err(line_too_long(&error).unwrap().length),
+            })
+            .collect()
+    }
+
+    /// The function splits a dump into its JSON documents, reading it a few bytes at
+    /// a time, so the documents are split across several reads.
+    fn documents(dump: &str, capacity: usize) -> Vec<std::result::Result<String, usize>> {
+        let reader = BufReader::with_capacity(capacity, dump.as_bytes());
+        collect(JsonDocuments::new(reader, 64))
+    }
+
+    /// The function splits a dump into its lines, just as `documents` does.
+    fn lines(dump: &str, capacity: usize) -> Vec<std::result::Result<String, usize>> {
+        let reader = BufReader::with_capacity(capacity, dump.as_bytes());
+        collect(BoundedLines::new(reader, 16))
+    }
+
+    #[test]
+    fn splits_pretty_printed_and_concatenated_documents() {
+        let dump = "[\n{\"a\": 1},\n{\n  \"b\": {\"c\": [1, 2]}\n}{\"d\": 2}\n]\n";
+        let expected = vec![
+            Ok("{\"a\": 1}".to_string()),
+            Ok("{\n  \"b\": {\"c\": [1, 2]}\n}".to_string()),
+            Ok("{\"d\": 2}".to_string()),
+        ];
+        assert_eq!(documents(dump, 1024), expected);
+        // The documents are split the same no matter where the reads end :D
+        for capacity in 1..8 {
+            assert_eq!(documents(dump, capacity), expected, "{}", capacity);
+        }
+    }
+
+    #[test]
+    fn skips_the_brackets_within_strings() {
+        let dump = "{\"a\": \"}{][\"}, {\"b\": \"{\"}";
+        for capacity in [1, 3, 1024] {
+            assert_eq!(
+                documents(dump, capacity),
+                vec![
+                    Ok("{\"a\": \"}{][\"}".to_string()),
+                    Ok("{\"b\": \"{\"}".to_string())
+                ],
+                "{}",
+                capacity
+            );
+        }
+    }
+
+    #[test]
+    fn skips_the_escaped_quotes() {
+        // The first string holds an escaped quote followed by a bracket, while the second one ends
+        // with an escaped backslash, so its quote does end it :D
+        let dump = r#"{"a": "\"}"}{"b": "\\"}{"c": 3}"#;
+        for capacity in [1, 2, 1024] {
+            assert_eq!(
+                documents(dump, capacity),
+                vec![
+                    Ok(r#"{"a": "\"}"}"#.to_string()),
+                    Ok(r#"{"b": "\\"}"#.to_string()),
+                    Ok(r#"{"c": 3}"#.to_string()),
+                ],
+                "{}",
+                capacity
+            );
+        }
+    }
+
+    #[test]
+    fn skips_the_documents_too_long() {
+        let long = format!("{{\"a\": \"{}\"}}", "x".repeat(100));
+        let dump = format!("{}\n{{\"b\": 1}}\n{{\"c\": ", long);
+        for capacity in [1, 7, 1024] {
+            assert_eq!(
+                documents(&dump, capacity),
+                vec![
+                    Err(long.len()),
+                    Ok("{\"b\": 1}".to_string()),
+                    // A truncated document is returned as it is, so it fails to be parsed :(
+                    Ok("{\"c\": ".to_string()),
+                ],
+                "{}",
+                capacity
+            );
+        }
+    }
+
+    #[test]
+    fn splits_lines_across_reads() {
+        let dump = "{\"a\": 1}\r\n\n{\"b\": \"0123456789\"}\n{\"c\": 3}";
+        for capacity in [1, 3, 1024] {
+            assert_eq!(
+                lines(dump, capacity),
+                vec![
+                    Ok("{\"a\": 1}".to_string()),
+                    Ok(String::new()),
+                    Err(19),
+                    Ok("{\"c\": 3}".to_string()),
+                ],
+                "{}",
+                capacity
+            );
+        }
+    }
+
+    #[test]
+    fn counts_the_bytes_consumed_up_to_each_line() {
+        let reader = Counted::new(BufReader::with_capacity(4, "ab\ncde\n\nf".as_bytes()));
+        let counter = reader.counter();
+        let offsets = BoundedLines::new(reader, 16)
+            .map(|_| counter.get())
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![3, 7, 8, 9]);
+    }
+}
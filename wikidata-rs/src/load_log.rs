@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use duckdb::{params, Connection, Error};
+
+use crate::memory::rss;
+
+/// The `LoadLog` struct records the throughput of the load into the `_load_log`
+/// table of the database itself, every now and then, so the performance of
+/// different hardware, or options, can be compared after the fact.
+///
+/// Properties:
+///
+/// * `interval`: The time between two records of the throughput.
+/// * `options`: The command line the load was run with.
+/// * `start_time`: The instant the load started.
+/// * `last_time`: The instant of the last record.
+/// * `last_entities`: The number of entities processed at the last record.
+pub struct LoadLog {
+    interval: Duration,
+    options: String,
+    start_time: Instant,
+    last_time: Instant,
+    last_entities: u64,
+}
+
+impl LoadLog {
+    /// The function creates the log of a load that has just started.
+    ///
+    /// Arguments:
+    ///
+    /// * `interval`: The time between two records of the throughput.
+    /// * `options`: The command line the load was run with, so records of different
+    /// loads can be told apart.
+    ///
+    /// Returns:
+    ///
+    /// The `LoadLog`.
+    pub fn new(interval: Duration, options: String) -> Self {
+        let now = Instant::now();
+        Self {
+            interval,
+            options,
+            start_time: now,
+            last_time: now,
+            last_entities: 0,
+        }
+    }
+
+    /// This function returns the SQL statement creating the `_load_log` table. The
+    /// rows appended to each of the tables are stored as a map from the name of the
+    /// table to the number of rows, as the tables depend on the options of the load.
+    pub fn create_table_sql() -> String {
+        "CREATE TABLE IF NOT EXISTS _load_log (\
+            logged_at TIMESTAMP NOT NULL, \
+            elapsed DOUBLE NOT NULL, \
+            entities UBIGINT NOT NULL, \
+            rows MAP(TEXT, UBIGINT) NOT NULL, \
+            rss UBIGINT, \
+            rate DOUBLE NOT NULL, \
+            options TEXT NOT NULL\
+        );"
+        .to_string()
+    }
+
+    /// This function creates the `_load_log` table in the database, in case it does
+    /// not exist yet.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// table creation is successful, or an `Error` object if there is an error during
+    /// the execution of the SQL statement.
+    pub fn create_table(connection: &Connection) -> Result<(), Error> {
+        connection.execute_batch(&Self::create_table_sql())
+    }
+
+    /// The function returns whether the throughput has to be recorded; that is,
+    /// whether enough time has passed since the last record.
+    pub fn is_due(&self) -> bool {
+        self.last_time.elapsed() >= self.interval
+    }
+
+    /// The function records the throughput of the load into the `_load_log` table.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    /// * `entities`: The number of entities processed so far.
+    /// * `rows`: The number of rows appended so far to each of the tables.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// record is inserted, or an `Error` object otherwise.
+    pub fn record(
+        &mut self,
+        connection: &Connection,
+        entities: u64,
+        rows: &HashMap<&'static str, u64>,
+    ) -> Result<(), Error> {
+        // The rate is the one since the last record, not since the start of the load. Hence, the
+        // slowdowns, such as those caused by the lexemes at the end of the dump, do show up :D
+        let seconds = self.last_time.elapsed().as_secs_f64();
+        let rate = match seconds > 0.0 {
+            true => (entities - self.last_entities) as f64 / seconds,
+            false => 0.0,
+        };
+
+        // Table names are ours, and counts are numbers, so the map literal is built in place, as
+        // maps cannot be bound as parameters :(
+        let mut rows = rows.iter().collect::<Vec<_>>();
+        rows.sort();
+        let rows = rows
+            .iter()
+            .map(|(table_name, count)| format!("'{}': {}", table_name, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        connection.execute(
+            &format!(
+                "INSERT INTO _load_log VALUES (current_timestamp, ?, ?, MAP {{{}}}, ?, ?, ?)",
+                rows
+            ),
+            params![
+                self.start_time.elapsed().as_secs_f64(),
+                entities,
+                rss(),
+                rate,
+                self.options
+            ],
+        )?;
+
+        self.last_time = Instant::now();
+        self.last_entities = entities;
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+
+use duckdb::{Connection, Error};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use wikidata::{Pid, Qid};
+
+use crate::dtype::DataType;
+use crate::id::{Id, IdEncoding, SUB_ENTITIES};
+use crate::partition::{self, Partition};
+use crate::schema;
+
+/// The `Macro` enum defines the helper macros that are installed in the database
+/// once the load has finished. Those allow the most common queries over the graph
+/// to be written as one-liners, such as `SELECT * FROM instances_of(5)`.
+#[derive(Clone, Debug, PartialEq, EnumIter)]
+pub enum Macro {
+    SubclassesOf,
+    InstancesOf,
+    WithinBbox,
+    Near,
+    TimeStart,
+    TimeEnd,
+    TimeOverlaps,
+    CommonsUrl,
+    WdIdToStr,
+    Label,
+}
+
+impl Macro {
+    /// The function returns the SQL definition of the macro; that is, its
+    /// parameters and its body.
+    ///
+    /// Arguments:
+    ///
+    /// * `sources`: What the macro reads instead of each of the generic tables whose
+    /// claims have been moved to partitions, as given by `partition::source`.
+    ///
+    /// * `encoding`: The `IdEncoding` of the database, which the identifiers of the
+    /// macro are written with.
+    ///
+    /// Returns:
+    ///
+    /// A `String` containing everything that follows the name of the macro in a
+    /// `CREATE MACRO` statement.
+    fn definition(&self, sources: &HashMap<&str, String>, encoding: &IdEncoding) -> String {
+        let source = |table: &str| match sources.get(table) {
+            Some(source) => source.to_owned(),
+            None => table.to_string(),
+        };
+        let subclass_of = encoding.known(Id::Pid(Pid(279)));
+        let instance_of = encoding.known(Id::Pid(Pid(31)));
+        let earth = encoding.known(Id::Qid(Qid(2)));
+
+        // The subclass hierarchy of Wikidata is a graph, not a tree, and it does contain cycles. By
+        // using UNION instead of UNION ALL in the recursive CTEs, the already visited classes are
+        // discarded and the recursion stops once no new class is found :D
+        let subclasses = format!(
+            "WITH RECURSIVE subclasses(id) AS (\
+                SELECT class_id AS id \
+                UNION \
+                SELECT e.src_id FROM {} e JOIN subclasses s ON e.dst_id = s.id \
+                WHERE e.property_id = {}\
+            )",
+            source("edge"),
+            subclass_of
+        );
+
+        match self {
+            Macro::SubclassesOf => format!(
+                "(class_id) AS TABLE {} SELECT id FROM subclasses",
+                subclasses
+            ),
+            Macro::InstancesOf => format!(
+                "(class_id) AS TABLE {} SELECT DISTINCT e.src_id AS id FROM {} e \
+                 JOIN subclasses s ON e.dst_id = s.id WHERE e.property_id = {}",
+                subclasses,
+                source("edge"),
+                instance_of
+            ),
+            Macro::WithinBbox => format!(
+                "(min_lat, min_lon, max_lat, max_lon) AS TABLE \
+                SELECT src_id, property_id, latitude, longitude, globe_id FROM {} \
+                WHERE latitude BETWEEN min_lat AND max_lat AND longitude BETWEEN min_lon AND max_lon",
+                source("coordinates")
+            ),
+            // Distances are computed using the haversine formula over the mean radius of the Earth.
+            // Note that coordinates placed on other globes (the Moon, Mars...) are left out, as the
+            // distance in kilometers would make no sense for them :(
+            Macro::Near => format!(
+                "(lat, lon, km) AS TABLE SELECT * FROM (\
+                    SELECT src_id, property_id, latitude, longitude, \
+                    2 * 6371.0088 * asin(sqrt(\
+                        pow(sin(radians(latitude - lat) / 2), 2) + \
+                        cos(radians(lat)) * cos(radians(latitude)) * \
+                        pow(sin(radians(longitude - lon) / 2), 2)\
+                    )) AS distance \
+                    FROM {} WHERE globe_id = {}\
+                ) WHERE distance <= km",
+                source("coordinates"),
+                earth
+            ),
+            // Times in Wikidata are annotated with a precision: 14 for seconds, 11 for days, 9 for
+            // years, 8 for decades, 7 for centuries... down to 0 for billions of years. Hence, the
+            // stored DATETIME is just a representative of the interval it stands for. Spans over
+            // 10,000 years cannot be represented as DuckDB timestamps, so those are unbounded :(
+            // The parameters are cast, as the macro is bound with those untyped when it is created,
+            // and date_trunc is not able to choose among its overloads otherwise.
+            Macro::TimeStart => "(ts, prec) AS CASE \
+                WHEN prec >= 14 THEN ts::TIMESTAMP \
+                WHEN prec = 13 THEN date_trunc('minute', ts::TIMESTAMP) \
+                WHEN prec = 12 THEN date_trunc('hour', ts::TIMESTAMP) \
+                WHEN prec = 11 THEN date_trunc('day', ts::TIMESTAMP) \
+                WHEN prec = 10 THEN date_trunc('month', ts::TIMESTAMP) \
+                WHEN prec = 9 THEN date_trunc('year', ts::TIMESTAMP) \
+                WHEN prec >= 5 THEN make_timestamp(\
+                    CAST(floor(year(ts::TIMESTAMP) / pow(10, 9 - prec)) * pow(10, 9 - prec) AS BIGINT), \
+                    1, 1, 0, 0, 0) \
+                ELSE '-infinity'::TIMESTAMP END"
+                .to_string(),
+            Macro::TimeEnd => "(ts, prec) AS CASE \
+                WHEN prec >= 14 THEN ts::TIMESTAMP + INTERVAL 1 SECOND \
+                WHEN prec = 13 THEN time_start(ts, prec) + INTERVAL 1 MINUTE \
+                WHEN prec = 12 THEN time_start(ts, prec) + INTERVAL 1 HOUR \
+                WHEN prec = 11 THEN time_start(ts, prec) + INTERVAL 1 DAY \
+                WHEN prec = 10 THEN time_start(ts, prec) + INTERVAL 1 MONTH \
+                WHEN prec >= 5 THEN time_start(ts, prec) + to_years(CAST(pow(10, 9 - prec) AS INTEGER)) \
+                ELSE 'infinity'::TIMESTAMP END"
+                .to_string(),
+            Macro::TimeOverlaps => format!(
+                "(src, p, from_time, to_time) AS EXISTS (\
+                SELECT 1 FROM {} t WHERE t.src_id = src AND t.property_id = p \
+                AND time_start(t.time, t.precision) < to_time \
+                AND time_end(t.time, t.precision) > from_time)",
+                source("time")
+            ),
+            // Files uploaded to Commons are stored under a path derived from the MD5 hash of their
+            // name, once the spaces are replaced by underscores: the first hexadecimal digit of the
+            // hash, and then the first two of them. Hence, the URL can be computed right away :D
+            Macro::CommonsUrl => "(file) AS 'https://upload.wikimedia.org/wikipedia/commons/' || \
+                substr(md5(replace(file, ' ', '_')), 1, 1) || '/' || \
+                substr(md5(replace(file, ' ', '_')), 1, 2) || '/' || \
+                replace(file, ' ', '_')"
+                .to_string(),
+            // Identifiers are decoded the very same way `IdEncoding::decode` does, out of the ranges
+            // of the encoding of the database. Forms and senses are split into the number of their
+            // lexeme and their own one, unless the latter is beyond a `u16` :D
+            Macro::WdIdToStr => {
+                let datatypes = DataType::iter()
+                    .map(|dt| {
+                        let name = Id::DataType(dt.clone()).to_string();
+                        format!("WHEN id = {} THEN '{}' ", encoding.known(Id::DataType(dt)), name)
+                    })
+                    .collect::<String>();
+                let sub_entity = |start: u64, end: u64, kind: char| {
+                    format!(
+                        "WHEN id < {end} THEN CASE WHEN (id - {start}) % {n} < 65536 \
+                         THEN 'L' || ((id - {start}) // {n}) || '-{kind}' || ((id - {start}) % {n}) \
+                         ELSE NULL END ",
+                        start = start,
+                        end = end,
+                        n = SUB_ENTITIES,
+                        kind = kind
+                    )
+                };
+                format!(
+                    "(id) AS CASE \
+                     WHEN id < {} THEN 'Q' || id \
+                     WHEN id < {} THEN 'P' || (id - {}) \
+                     WHEN id < {} THEN 'L' || (id - {}) \
+                     {}{}{}\
+                     WHEN id >= {} THEN '_:' || (id - {}) \
+                     ELSE NULL END",
+                    encoding.property,
+                    encoding.lexeme,
+                    encoding.property,
+                    encoding.form,
+                    encoding.lexeme,
+                    sub_entity(encoding.form, encoding.sense, 'F'),
+                    sub_entity(encoding.sense, encoding.datatype, 'S'),
+                    datatypes,
+                    encoding.placeholder,
+                    encoding.placeholder
+                )
+            }
+            // The parameter is not named `id`, as it would be taken for the column of the vertex
+            // table within the subquery, matching every single vertex :(
+            Macro::Label => "(entity_id) AS (\
+                SELECT label FROM vertex WHERE vertex.id = entity_id LIMIT 1)"
+                .to_string(),
+        }
+    }
+
+    /// This function returns the SQL statement creating the macro over the generic
+    /// tables, as those are before any claim is moved to a partition.
+    ///
+    /// Arguments:
+    ///
+    /// * `encoding`: The `IdEncoding` of the database the macro is created in.
+    pub fn create_macro_sql(&self, encoding: &IdEncoding) -> String {
+        format!(
+            "CREATE OR REPLACE MACRO {}{};",
+            self.as_ref(),
+            self.definition(&HashMap::new(), encoding)
+        )
+    }
+
+    /// This function creates the macro in the database, replacing any previous
+    /// definition of it.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database where the
+    /// macro is to be created, whose identifiers are written with the encoding it
+    /// records.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// macro creation is successful, or an `Error` object if there is an error during
+    /// the execution of the SQL statement. Notice that the `label` macro is not
+    /// created over the databases built with `--no-text`, whose vertices have no
+    /// labels to read, as any call to it would fail otherwise.
+    pub fn create_macro(&self, connection: &Connection) -> Result<(), Error> {
+        if *self == Macro::Label && !schema::has_column(connection, "vertex", "label")? {
+            return Ok(());
+        }
+        connection.execute_batch(&format!(
+            "CREATE OR REPLACE MACRO {}{};",
+            self.as_ref(),
+            self.definition(&sources(connection)?, &IdEncoding::of(connection)?)
+        ))
+    }
+}
+
+/// The function returns what the macros read instead of each of the generic tables
+/// whose claims have been moved to partitions, such as `edge_p31`, so the claims of
+/// the partitioned properties are not left out of them.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` with the subqueries, indexed by the name of the generic table they
+/// stand for, or an `Error` object if the tables could not be listed.
+fn sources(connection: &Connection) -> Result<HashMap<&'static str, String>, Error> {
+    let mut sources = HashMap::new();
+    for table_name in Partition::table_names() {
+        let source = partition::source(connection, table_name)?;
+        if source != table_name {
+            sources.insert(table_name, source);
+        }
+    }
+    Ok(sources)
+}
+
+/// This implementation of the `AsRef` trait returns the name under which the macro
+/// is stored in the database.
+impl AsRef<str> for Macro {
+    fn as_ref(&self) -> &str {
+        match self {
+            Macro::SubclassesOf => "subclasses_of",
+            Macro::InstancesOf => "instances_of",
+            Macro::WithinBbox => "within_bbox",
+            Macro::Near => "near",
+            Macro::TimeStart => "time_start",
+            Macro::TimeEnd => "time_end",
+            Macro::TimeOverlaps => "time_overlaps",
+            Macro::CommonsUrl => "commons_url",
+            Macro::WdIdToStr => "wd_id_to_str",
+            Macro::Label => "label",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The function creates a database in memory holding the vertex of `Q42`, with
+    /// its label, or without it as the databases built with `--no-text` do.
+    fn database(texts: bool) -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        let (columns, values) = match texts {
+            true => (
+                "id UBIGINT, label TEXT, description TEXT",
+                "42, 'Douglas Adams', NULL",
+            ),
+            false => ("id UBIGINT", "42"),
+        };
+        connection
+            .execute_batch(&format!(
+                "CREATE TABLE vertex ({}); INSERT INTO vertex VALUES ({});",
+                columns, values
+            ))
+            .unwrap();
+        connection
+    }
+
+    #[test]
+    fn resolves_labels_from_the_vertex_table() {
+        let connection = database(true);
+        Macro::Label.create_macro(&connection).unwrap();
+        let label = |id: u64| -> Option<String> {
+            connection
+                .query_row("SELECT label(?)", [id], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(label(42).as_deref(), Some("Douglas Adams"));
+        assert_eq!(label(7), None);
+    }
+
+    #[test]
+    fn skips_the_label_macro_without_labels() {
+        let connection = database(false);
+        Macro::Label.create_macro(&connection).unwrap();
+        let macros: i64 = connection
+            .query_row(
+                "SELECT count(*) FROM duckdb_functions() WHERE function_name = 'label'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(macros, 0);
+    }
+}
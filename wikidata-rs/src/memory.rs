@@ -0,0 +1,113 @@
+use std::fs::read_to_string;
+
+/// The number of entities processed between two readings of the memory used, as
+/// reading it means reading a file, which is not worth doing for every entity.
+const CHECK_EVERY: usize = 1000;
+
+/// The fraction of the limit from which the memory is considered to be running
+/// out, so there is still some room left while the batch is being committed.
+const HIGH_WATERMARK: f64 = 0.9;
+
+/// The smallest batch the watchdog shrinks the batches to, so the load does not
+/// end up committing after every single entity.
+const MIN_BATCH_SIZE: usize = 10_000;
+
+/// The function returns the resident set size of the process, in bytes, which is
+/// only known on Linux, where it is read out of `/proc/self/status`.
+pub fn rss() -> Option<u64> {
+    let status = read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kilobytes * 1024)
+}
+
+/// The `MemoryWatchdog` struct keeps the memory used by the load under a limit.
+/// The appenders and the transaction buffer the rows in memory until those are
+/// committed, so the memory grows with the size of the batch. Hence, whenever the
+/// resident set size approaches the limit, the batch is ended, so it is committed
+/// and checkpointed, and the following batches are made smaller. The memory of
+/// DuckDB itself is bounded by giving it half of the limit.
+///
+/// Properties:
+///
+/// * `limit`: The maximum memory the process should use, in bytes.
+/// * `batch_size`: The number of entities each batch is bounded to, once the
+/// memory has approached the limit.
+/// * `in_batch`: The number of entities processed in the current batch.
+/// * `unchecked`: The number of entities processed since the last reading.
+pub struct MemoryWatchdog {
+    limit: u64,
+    batch_size: Option<usize>,
+    in_batch: usize,
+    unchecked: usize,
+}
+
+impl MemoryWatchdog {
+    /// The function creates a watchdog keeping the memory under the given limit.
+    ///
+    /// Arguments:
+    ///
+    /// * `limit`: The maximum memory the process should use, in bytes.
+    ///
+    /// Returns:
+    ///
+    /// The `MemoryWatchdog`.
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            batch_size: None,
+            in_batch: 0,
+            unchecked: 0,
+        }
+    }
+
+    /// The function returns the memory limit DuckDB is given, in bytes, which is
+    /// half of the limit, so the rest is left for the buffers of the appenders.
+    pub fn duckdb_limit(&self) -> u64 {
+        self.limit / 2
+    }
+
+    /// The function returns the number of entities each batch is bounded to, if the
+    /// batches have been shrunk.
+    pub fn batch_size(&self) -> Option<usize> {
+        self.batch_size
+    }
+
+    /// The function is called once per entity processed, and returns whether the
+    /// batch has to be ended; that is, committed and checkpointed, so the memory
+    /// held by its buffers is released.
+    ///
+    /// Returns:
+    ///
+    /// A `bool` which is `true` whenever the memory is approaching the limit, in
+    /// which case the following batches are shrunk to half of the current one, or
+    /// the batch has reached the size it has been shrunk to.
+    pub fn should_commit(&mut self) -> bool {
+        self.in_batch += 1;
+        self.unchecked += 1;
+
+        if self
+            .batch_size
+            .is_some_and(|batch_size| self.in_batch >= batch_size)
+        {
+            self.in_batch = 0;
+            return true;
+        }
+
+        if self.unchecked < CHECK_EVERY {
+            return false;
+        }
+        self.unchecked = 0;
+
+        // Memory is only known on Linux. Elsewhere, the watchdog relies on the limit given to
+        // DuckDB, which is the best we can do :(
+        match rss() {
+            Some(rss) if rss as f64 >= self.limit as f64 * HIGH_WATERMARK => {
+                self.batch_size = Some((self.in_batch / 2).max(MIN_BATCH_SIZE));
+                self.in_batch = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use duckdb::{params, Connection, Error};
+
+/// The name of the table storing the metadata of the database.
+pub const METADATA_TABLE: &str = "wd2duckdb_meta";
+
+/// The name the table storing the metadata was given by older versions of the
+/// tool, which is renamed as soon as anything is written to it.
+const LEGACY_METADATA_TABLE: &str = "_metadata";
+
+/// The version of the schema of the databases built by this version of the tool,
+/// as described by the `schema` module. It is only ever increased whenever a table
+/// or a column is renamed, removed or retyped, which only happens in a new major
+/// version of the crate. Adding tables or columns does not change it, so tools
+/// generating queries should not rely on the columns being the only ones, nor on
+/// their position. Databases recording another version are refused by the tools
+/// updating or querying them, as their tables may not be the expected ones.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The key the version of the schema is stored under.
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// The function returns the name of the table storing the metadata in the given
+/// database, which is the legacy one for those built by older versions of the
+/// tool, or `None` in case there is no metadata at all.
+fn existing_table(connection: &Connection) -> Result<Option<&'static str>, Error> {
+    for table in [METADATA_TABLE, LEGACY_METADATA_TABLE] {
+        let tables: i64 = connection.query_row(
+            "SELECT count(*) FROM information_schema.tables WHERE table_name = ?",
+            params![table],
+            |row| row.get(0),
+        )?;
+        if tables > 0 {
+            return Ok(Some(table));
+        }
+    }
+    Ok(None)
+}
+
+/// This function returns the SQL statement creating the `wd2duckdb_meta` table, which
+/// stores how the database was built as pairs of keys and values, so the tools
+/// reading it later on know how to interpret its contents.
+pub fn create_table_sql() -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+        METADATA_TABLE
+    )
+}
+
+/// This function creates the `wd2duckdb_meta` table in the database, in case it
+/// does not exist yet. The legacy `_metadata` table is renamed instead, so the
+/// metadata recorded by older versions of the tool is kept.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// table creation is successful, or an `Error` object if there is an error during
+/// the execution of the SQL statement.
+pub fn create_table(connection: &Connection) -> Result<(), Error> {
+    match existing_table(connection)? {
+        Some(LEGACY_METADATA_TABLE) => connection.execute_batch(&format!(
+            "ALTER TABLE {} RENAME TO {};",
+            LEGACY_METADATA_TABLE, METADATA_TABLE
+        )),
+        _ => connection.execute_batch(&create_table_sql()),
+    }
+}
+
+/// The function stores a value in the metadata of the database, replacing the
+/// previous one, if any. The table is created beforehand in case it does not
+/// exist yet.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `key`: The key the value is stored under, such as `id_encoding`.
+/// * `value`: The value to be stored.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// value is stored, or an `Error` object otherwise.
+pub fn set(connection: &Connection, key: &str, value: &str) -> Result<(), Error> {
+    create_table(connection)?;
+    connection.execute(
+        &format!("INSERT OR REPLACE INTO {} VALUES (?, ?)", METADATA_TABLE),
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+/// The function retrieves a value from the metadata of the database.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `key`: The key the value is stored under.
+///
+/// Returns:
+///
+/// a `Result` with the value, or `None` in case there is no such key, or the
+/// database has no metadata at all, as it happens for the ones built by older
+/// versions of the tool.
+pub fn get(connection: &Connection, key: &str) -> Result<Option<String>, Error> {
+    let table = match existing_table(connection)? {
+        Some(table) => table,
+        None => return Ok(None),
+    };
+
+    let mut statement =
+        connection.prepare(&format!("SELECT value FROM {} WHERE key = ?", table))?;
+    let value = statement
+        .query_map(params![key], |row| row.get::<_, String>(0))?
+        .next()
+        .transpose()?;
+    Ok(value)
+}
+
+/// The function retrieves every entry of the metadata of the database, which tells
+/// how it was built.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` with the pairs of key and value, in alphabetical order of their keys,
+/// which is empty in case the database has no metadata at all.
+pub fn entries(connection: &Connection) -> Result<Vec<(String, String)>, Error> {
+    let table = match existing_table(connection)? {
+        Some(table) => table,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut statement =
+        connection.prepare(&format!("SELECT key, value FROM {} ORDER BY key", table))?;
+    let entries = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
+/// The function removes a value from the metadata of the database, if any.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `key`: The key the value is stored under.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// value is removed, or there was no such value, or an `Error` object otherwise.
+pub fn remove(connection: &Connection, key: &str) -> Result<(), Error> {
+    create_table(connection)?;
+    connection.execute(
+        &format!("DELETE FROM {} WHERE key = ?", METADATA_TABLE),
+        params![key],
+    )?;
+    Ok(())
+}
+
+/// The function records the version of the schema of the database, which is the
+/// one of this version of the tool.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// version is stored, or an `Error` object otherwise.
+pub fn set_schema_version(connection: &Connection) -> Result<(), Error> {
+    set(connection, SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_string())
+}
+
+/// The function checks that the schema of the database is the one this version of
+/// the tool works with, so a database built by another one is not updated, nor
+/// queried, as if it was not. Notice that the databases built before the schema
+/// was versioned record no version at all, so those are taken as they are.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` with an empty `Ok(())` in case the schema is compatible, or a
+/// `String` with an error message otherwise.
+pub fn check_schema_version(connection: &Connection) -> Result<(), String> {
+    let version = match get(connection, SCHEMA_VERSION_KEY) {
+        Ok(version) => version,
+        Err(error) => return Err(format!("Error reading metadata. {}", error)),
+    };
+    match version {
+        Some(version) if version != SCHEMA_VERSION.to_string() => Err(format!(
+            "The database has schema version {}, while this version of wd2duckdb works with {}",
+            version, SCHEMA_VERSION
+        )),
+        _ => Ok(()),
+    }
+}
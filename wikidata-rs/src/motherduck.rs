@@ -0,0 +1,99 @@
+use std::env::{temp_dir, var_os};
+use std::path::{Path, PathBuf};
+
+use duckdb::{Connection, Error};
+
+/// The function returns the name of the MotherDuck database a database given by
+/// the user stands for, if any; that is, `my_db` out of `md:my_db`.
+pub fn database_name(database: &str) -> Option<&str> {
+    database
+        .strip_prefix("md:")
+        .filter(|name| !name.is_empty() && !name.contains(['/', '\\', '"', '\'']))
+}
+
+/// The function returns the path to the local database a MotherDuck database is
+/// built into before uploading it. Loading a dump straight into MotherDuck would
+/// mean millions of round trips over the network, so the database is built locally
+/// first, in the temporary directory of the system, and uploaded at once.
+///
+/// Arguments:
+///
+/// * `name`: The name of the MotherDuck database.
+///
+/// Returns:
+///
+/// A `PathBuf` to the local database.
+pub fn staging_path(name: &str) -> PathBuf {
+    temp_dir().join(format!("wd2duckdb-{}.duckdb", name))
+}
+
+/// The function connects to MotherDuck, whose databases are attached to an
+/// in-memory one. The token has to be set, as the extension would otherwise wait
+/// for the user to log in through the browser.
+fn connect() -> Result<Connection, String> {
+    if var_os("motherduck_token").is_none() && var_os("MOTHERDUCK_TOKEN").is_none() {
+        return Err("The motherduck_token environment variable is not set".to_string());
+    }
+
+    let connection = match Connection::open_in_memory() {
+        Ok(connection) => connection,
+        Err(error) => return Err(format!("Error opening connection. {}", error)),
+    };
+    match connection.execute_batch("INSTALL motherduck; LOAD motherduck; ATTACH 'md:';") {
+        Ok(_) => Ok(connection),
+        Err(error) => Err(format!("Error connecting to MotherDuck. {}", error)),
+    }
+}
+
+/// The function checks that a database can be uploaded to MotherDuck before it
+/// is built, which may take hours; that is, that MotherDuck can be reached with
+/// the token given, and that no database of the same name exists there already.
+///
+/// Arguments:
+///
+/// * `name`: The name of the MotherDuck database to be created.
+///
+/// Returns:
+///
+/// a `Result` with either an empty `Ok(())` or a `String` with the reason the
+/// database could not be uploaded.
+pub fn check(name: &str) -> Result<(), String> {
+    let connection = connect()?;
+    let exists: bool = match connection.query_row(
+        "SELECT count(*) > 0 FROM duckdb_databases() WHERE database_name = ?",
+        [name],
+        |row| row.get(0),
+    ) {
+        Ok(exists) => exists,
+        Err(error) => return Err(format!("Error listing the MotherDuck databases. {}", error)),
+    };
+
+    match exists {
+        true => Err(format!("The MotherDuck database {} already exists", name)),
+        false => Ok(()),
+    }
+}
+
+/// The function uploads a database that has been built locally to MotherDuck, as a
+/// database of its own. The token is read from the `motherduck_token` environment
+/// variable, as usual.
+///
+/// Arguments:
+///
+/// * `local`: The path to the local database, which has to be closed already.
+/// * `name`: The name of the MotherDuck database to be created.
+///
+/// Returns:
+///
+/// a `Result` with either an empty `Ok(())` or a `String` with the error, for
+/// example, because the database already exists in MotherDuck.
+pub fn upload(local: &Path, name: &str) -> Result<(), String> {
+    let connection = connect()?;
+    connection
+        .execute_batch(&format!(
+            "CREATE DATABASE \"{}\" FROM '{}';",
+            name,
+            local.to_string_lossy().replace('\'', "''")
+        ))
+        .map_err(|error: Error| error.to_string())
+}
@@ -0,0 +1,1219 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use duckdb::{Connection, Error};
+use serde_json::{json, Map, Value};
+
+use crate::dtype::{DataType, Target};
+
+/// The prefix of the IRIs of the entities, such as `wd:Q42`.
+const ENTITY: &str = "http://www.wikidata.org/entity/";
+/// The prefix of the IRIs of the truthy claims, such as `wdt:P31`.
+const DIRECT: &str = "http://www.wikidata.org/prop/direct/";
+/// The prefix of the classes of the entities with no value for a property, such as
+/// `wdno:P40`.
+const NOVALUE: &str = "http://www.wikidata.org/prop/novalue/";
+/// The prefix of the IRIs of the documents describing the entities, which hold
+/// their revision.
+const ENTITY_DATA: &str = "https://www.wikidata.org/wiki/Special:EntityData/";
+/// The prefix of the skolemized blank nodes, which stand for unknown values.
+const GENID: &str = "http://www.wikidata.org/.well-known/genid/";
+/// The prefix of the terms of the Wikibase ontology, such as `wikibase:lemma`.
+const WIKIBASE: &str = "http://wikiba.se/ontology#";
+const WIKIBASE_PROPERTY_TYPE: &str = "http://wikiba.se/ontology#propertyType";
+const WIKIBASE_LEMMA: &str = "http://wikiba.se/ontology#lemma";
+const WIKIBASE_LEXICAL_CATEGORY: &str = "http://wikiba.se/ontology#lexicalCategory";
+/// The prefix of the datatypes of XML Schema, such as `xsd:dateTime`.
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+/// The prefix of the files of Wikimedia Commons, such as the images.
+const COMMONS_FILE: &str = "http://commons.wikimedia.org/wiki/Special:FilePath/";
+/// The prefix of the data pages of Wikimedia Commons, such as the geographic shapes.
+const COMMONS_DATA: &str = "http://commons.wikimedia.org/data/main/";
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const SKOS_ALT_LABEL: &str = "http://www.w3.org/2004/02/skos/core#altLabel";
+const SCHEMA_DESCRIPTION: &str = "http://schema.org/description";
+const SCHEMA_VERSION: &str = "http://schema.org/version";
+const SCHEMA_ABOUT: &str = "http://schema.org/about";
+const DCT_LANGUAGE: &str = "http://purl.org/dc/terms/language";
+const GEO_WKT: &str = "http://www.opengis.net/ont/geosparql#wktLiteral";
+const MATHML: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// The `Term` enum defines the terms a triple is made of: IRIs, blank nodes and
+/// literals, which may be tagged with a language or typed with a datatype.
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    Iri(String),
+    Blank(String),
+    Literal {
+        value: String,
+        language: Option<String>,
+        datatype: Option<String>,
+    },
+}
+
+/// The `NTriplesEntities` struct groups the triples of an N-Triples dump, such as
+/// the truthy ones published by Wikidata, by the entity they describe, and builds
+/// the JSON of each of them, so those can be fed to the very same pipeline as the
+/// lines of a JSON dump. The triples of an entity are found one after the other in
+/// the dumps; that is, those of its document and its sitelinks included. Values
+/// are mapped to the datatype their literal stands for, as the truthy dumps do not
+/// tell the datatype of the claims; yet, the strings of the properties whose
+/// datatype has already been found are given it, such as `external-id`. Those of
+/// the properties found later on are fixed once the load is over, through
+/// `fix_datatypes`.
+///
+/// Properties:
+///
+/// * `reader`: The reader over the N-Triples dump.
+/// * `line_number`: The number of the line being read, so the malformed ones can
+/// be reported.
+/// * `datatypes`: The datatype of each property found so far, by its identifier.
+/// * `entity`: The entity whose triples are being read, if any.
+pub struct NTriplesEntities<R> {
+    reader: R,
+    line_number: usize,
+    datatypes: HashMap<String, String>,
+    entity: Option<EntityBuilder>,
+}
+
+impl<R: BufRead> NTriplesEntities<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line_number: 0,
+            datatypes: HashMap::new(),
+            entity: None,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NTriplesEntities<R> {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            self.line_number += 1;
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return self.entity.take().map(|entity| Ok(entity.build())),
+                Ok(_) => (),
+                Err(error) => return Some(Err(error)),
+            }
+
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let (subject, predicate, object) = match parse_triple(trimmed) {
+                Some(triple) => triple,
+                None => {
+                    eprintln!("Skipping malformed triple at line {}", self.line_number);
+                    continue;
+                }
+            };
+
+            // The triples that are not about an entity, such as those describing a sitelink but
+            // the one linking it to its entity, belong to the entity being read :D
+            let id = match about(&subject, &predicate, &object) {
+                Some(id) => id,
+                None => {
+                    if let Some(entity) = self.entity.as_mut() {
+                        entity.add(&subject, &predicate, object, &mut self.datatypes);
+                    }
+                    continue;
+                }
+            };
+
+            // Once a triple of another entity is found, the one being read is over :D
+            let done = match &self.entity {
+                Some(entity) if entity.id != id => self.entity.replace(EntityBuilder::new(id)),
+                Some(_) => None,
+                None => {
+                    self.entity = Some(EntityBuilder::new(id));
+                    None
+                }
+            };
+            if let Some(entity) = self.entity.as_mut() {
+                entity.add(&subject, &predicate, object, &mut self.datatypes);
+            }
+            if let Some(done) = done {
+                return Some(Ok(done.build()));
+            }
+        }
+    }
+}
+
+/// The function gives the claims loaded out of an N-Triples dump the datatype their
+/// property declares, which is only known once the `propertyType` triple of the
+/// property has been read. Hence, the claims read before it, or before resuming the
+/// load, are stored as plain strings, or as pointing to items in case those have
+/// no value, or an unknown one. Once the load is over, the `property` table tells
+/// the datatype of every property, so the strings of the external identifiers are
+/// moved to their table, and the rest of the claims are given the right code.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `external_ids`: Whether the external identifiers are stored, or dropped.
+///
+/// Returns:
+///
+/// a `Result` with the number of claims fixed, or an `Error` if any of them could
+/// not be.
+pub fn fix_datatypes(connection: &Connection, external_ids: bool) -> Result<usize, Error> {
+    let string = u8::from(&DataType::String);
+    let external_id = u8::from(&DataType::ExternalId);
+    let misplaced = format!(
+        "datatype = {} AND property_id IN \
+         (SELECT id FROM property WHERE datatype = 'external-id')",
+        string
+    );
+
+    let mut fixed = 0;
+    if external_ids {
+        fixed += connection.execute(
+            &format!(
+                "INSERT INTO external_id BY NAME \
+                 SELECT * EXCLUDE (string, datatype), string AS external_id, {} AS datatype \
+                 FROM string WHERE {}",
+                external_id, misplaced
+            ),
+            [],
+        )?;
+    }
+    let removed = connection.execute(&format!("DELETE FROM string WHERE {}", misplaced), [])?;
+    if !external_ids {
+        fixed += removed;
+    }
+
+    // Claims with no value, or an unknown one, are given the code of the table the values of
+    // their property are stored in, as any other claim :D
+    let mut statement = connection.prepare("SELECT DISTINCT datatype FROM property")?;
+    let declared = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut codes: HashMap<u8, Vec<String>> = HashMap::new();
+    for datatype in declared {
+        if let Some(data_type) = DataType::from_declared(&datatype) {
+            codes
+                .entry(u8::from(&data_type))
+                .or_default()
+                .push(format!("'{}'", datatype.replace('\'', "''")));
+        }
+    }
+    for (code, datatypes) in codes {
+        fixed += connection.execute(
+            &format!(
+                "UPDATE edge SET datatype = {} \
+                 WHERE target IN ({}, {}) AND datatype IS DISTINCT FROM {} AND property_id IN \
+                 (SELECT id FROM property WHERE datatype IN ({}))",
+                code,
+                u8::from(&Target::NoValue),
+                u8::from(&Target::SomeValue),
+                code,
+                datatypes.join(", ")
+            ),
+            [],
+        )?;
+    }
+
+    Ok(fixed)
+}
+
+/// The `EntityBuilder` struct gathers the triples of an entity, so its JSON is built
+/// once every one of them has been read.
+///
+/// Properties:
+///
+/// * `id`: The identifier of the entity, such as `Q42`.
+/// * `revision`: The revision of the entity, if its document has been found.
+/// * `datatype`: The datatype of the entity, in case it is a property.
+/// * `labels`: The labels of the entity, by language.
+/// * `descriptions`: The descriptions of the entity, by language.
+/// * `aliases`: The aliases of the entity, by language.
+/// * `claims`: The statements of the entity, by property.
+/// * `sitelinks`: The sitelinks of the entity, by site.
+/// * `lexical`: The lemmas, language and lexical category, in case it is a lexeme.
+/// * `statements`: The number of statements built so far, so each of them is given
+/// an identifier of its own.
+struct EntityBuilder {
+    id: String,
+    revision: Option<u64>,
+    datatype: Option<String>,
+    labels: Map<String, Value>,
+    descriptions: Map<String, Value>,
+    aliases: Map<String, Value>,
+    claims: Map<String, Value>,
+    sitelinks: Map<String, Value>,
+    lexical: Map<String, Value>,
+    statements: usize,
+}
+
+impl EntityBuilder {
+    fn new(id: String) -> Self {
+        Self {
+            id,
+            revision: None,
+            datatype: None,
+            labels: Map::new(),
+            descriptions: Map::new(),
+            aliases: Map::new(),
+            claims: Map::new(),
+            sitelinks: Map::new(),
+            lexical: Map::new(),
+            statements: 0,
+        }
+    }
+
+    /// The function adds a triple of the entity. Those the JSON of the entity has no
+    /// room for, such as the ones of the full dumps describing the statement nodes,
+    /// are skipped.
+    ///
+    /// Arguments:
+    ///
+    /// * `subject`: A reference to the subject of the triple.
+    /// * `predicate`: The IRI of the predicate of the triple.
+    /// * `object`: The object of the triple.
+    /// * `datatypes`: A mutable reference to the datatypes of the properties found so
+    /// far, which is updated in case the entity is a property.
+    fn add(
+        &mut self,
+        subject: &Term,
+        predicate: &str,
+        object: Term,
+        datatypes: &mut HashMap<String, String>,
+    ) {
+        // Sitelinks are described by triples of their own, whose subject is the article
+        if predicate == SCHEMA_ABOUT {
+            if let Term::Iri(article) = subject {
+                if let Some((site, title)) = sitelink(article) {
+                    self.sitelinks.insert(
+                        site.clone(),
+                        json!({ "site": site, "title": title, "badges": [] }),
+                    );
+                }
+            }
+            return;
+        }
+
+        // The revision is held by the document describing the entity, rather than by itself,
+        // while the triples about its forms, senses or statements are skipped
+        match subject {
+            Term::Iri(subject) if subject.starts_with(ENTITY_DATA) => {
+                if predicate == SCHEMA_VERSION {
+                    if let Term::Literal { value, .. } = &object {
+                        self.revision = value.parse().ok();
+                    }
+                }
+                return;
+            }
+            Term::Iri(subject) if subject.strip_prefix(ENTITY) == Some(self.id.as_str()) => (),
+            _ => return,
+        }
+
+        if let Some(property) = predicate.strip_prefix(DIRECT) {
+            let property = property.to_string();
+            let datatype = datatypes.get(&property).map(String::as_str);
+            if let Some(snak) = snak(&property, datatype, object) {
+                self.push_statement(property, snak);
+            }
+            return;
+        }
+
+        match (predicate, object) {
+            (RDF_TYPE, Term::Iri(class)) => {
+                if let Some(property) = class.strip_prefix(NOVALUE) {
+                    let datatype = datatypes.get(property).map(String::as_str);
+                    let snak = bare_snak(property, datatype.unwrap_or("wikibase-item"), "novalue");
+                    self.push_statement(property.to_string(), snak);
+                }
+            }
+            (
+                RDFS_LABEL,
+                Term::Literal {
+                    value,
+                    language: Some(language),
+                    ..
+                },
+            ) => {
+                self.labels
+                    .insert(language.clone(), text(&language, &value));
+            }
+            (
+                SCHEMA_DESCRIPTION,
+                Term::Literal {
+                    value,
+                    language: Some(language),
+                    ..
+                },
+            ) => {
+                self.descriptions
+                    .insert(language.clone(), text(&language, &value));
+            }
+            (
+                SKOS_ALT_LABEL,
+                Term::Literal {
+                    value,
+                    language: Some(language),
+                    ..
+                },
+            ) => {
+                if let Value::Array(aliases) = self
+                    .aliases
+                    .entry(language.clone())
+                    .or_insert_with(|| Value::Array(Vec::new()))
+                {
+                    aliases.push(text(&language, &value));
+                }
+            }
+            (WIKIBASE_PROPERTY_TYPE, Term::Iri(iri)) => {
+                if let Some(datatype) = iri.strip_prefix(WIKIBASE).map(property_datatype) {
+                    datatypes.insert(self.id.clone(), datatype.clone());
+                    self.datatype = Some(datatype);
+                }
+            }
+            (
+                WIKIBASE_LEMMA,
+                Term::Literal {
+                    value,
+                    language: Some(language),
+                    ..
+                },
+            ) => {
+                let lemmas = self
+                    .lexical
+                    .entry("lemmas")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                lemmas[&language] = text(&language, &value);
+            }
+            (WIKIBASE_LEXICAL_CATEGORY, Term::Iri(iri)) => {
+                if let Some(id) = iri.strip_prefix(ENTITY) {
+                    self.lexical
+                        .insert("lexicalCategory".to_string(), json!(id));
+                }
+            }
+            (DCT_LANGUAGE, Term::Iri(iri)) => {
+                if let Some(id) = iri.strip_prefix(ENTITY) {
+                    self.lexical.insert("language".to_string(), json!(id));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The function adds a statement out of its main snak. The truthy dumps hold
+    /// neither the identifier nor the rank of the statements, so those are given a
+    /// generated identifier and the normal rank.
+    fn push_statement(&mut self, property: String, mainsnak: Value) {
+        let statement = json!({
+            "mainsnak": mainsnak,
+            "type": "statement",
+            "id": format!("{}$ntriples-{}", self.id, self.statements),
+            "rank": "normal",
+            "references": [],
+        });
+        self.statements += 1;
+        if let Value::Array(statements) = self
+            .claims
+            .entry(property)
+            .or_insert_with(|| Value::Array(Vec::new()))
+        {
+            statements.push(statement);
+        }
+    }
+
+    /// The function builds the JSON of the entity, following the structure of the
+    /// entities in the JSON dumps.
+    fn build(self) -> String {
+        let entity_type = match &self.id[0..1] {
+            "P" => "property",
+            "L" => "lexeme",
+            _ => "item",
+        };
+        let mut entity = json!({
+            "type": entity_type,
+            "id": self.id,
+            "labels": self.labels,
+            "descriptions": self.descriptions,
+            "aliases": self.aliases,
+            "claims": self.claims,
+            "sitelinks": self.sitelinks,
+        });
+        if let Some(revision) = self.revision {
+            entity["lastrevid"] = json!(revision);
+        }
+        if let Some(datatype) = self.datatype {
+            entity["datatype"] = json!(datatype);
+        }
+        if entity_type == "lexeme" {
+            entity["lemmas"] = json!({});
+            entity["forms"] = json!([]);
+            entity["senses"] = json!([]);
+            for (key, value) in self.lexical {
+                entity[key] = value;
+            }
+        }
+        entity.to_string()
+    }
+}
+
+/// The function returns the identifier of the entity a triple is about: that of its
+/// subject, be it the entity or its document, or that of its object, in case the
+/// triple links a sitelink to its entity. Triples about forms, senses, statements or
+/// values return `None`, as those are part of the entity being read.
+fn about(subject: &Term, predicate: &str, object: &Term) -> Option<String> {
+    let iri = match (subject, predicate, object) {
+        (_, SCHEMA_ABOUT, Term::Iri(object)) => object.as_str(),
+        (Term::Iri(subject), _, _) => subject.as_str(),
+        _ => return None,
+    };
+    let id = iri
+        .strip_prefix(ENTITY)
+        .or_else(|| iri.strip_prefix(ENTITY_DATA))?;
+    let mut chars = id.chars();
+    match (chars.next(), chars.as_str()) {
+        (Some('Q' | 'P' | 'L'), number)
+            if !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit()) =>
+        {
+            Some(id.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// The function builds the main snak of a truthy claim out of its object, mapping
+/// the object to the datatype it stands for. Objects that cannot be mapped, such as
+/// malformed coordinates, return `None`.
+///
+/// Arguments:
+///
+/// * `property`: The identifier of the property of the claim, such as `P31`.
+/// * `datatype`: The datatype of the property, in case it is already known.
+/// * `object`: The object of the triple.
+///
+/// Returns:
+///
+/// An `Option` with the JSON of the snak.
+fn snak(property: &str, datatype: Option<&str>, object: Term) -> Option<Value> {
+    let (datatype, datavalue) = match object {
+        Term::Blank(_) => {
+            return Some(bare_snak(
+                property,
+                datatype.unwrap_or("wikibase-item"),
+                "somevalue",
+            ))
+        }
+        Term::Iri(iri) if iri.starts_with(GENID) => {
+            return Some(bare_snak(
+                property,
+                datatype.unwrap_or("wikibase-item"),
+                "somevalue",
+            ))
+        }
+        Term::Iri(iri) => match iri.strip_prefix(ENTITY) {
+            Some(id) => entity_value(id)?,
+            None => match (
+                iri.strip_prefix(COMMONS_FILE),
+                iri.strip_prefix(COMMONS_DATA),
+            ) {
+                (Some(file), _) => ("commonsMedia", string_value(percent_decode(file))),
+                (_, Some(page)) if page.ends_with(".map") => {
+                    ("geo-shape", string_value(percent_decode(page)))
+                }
+                (_, Some(page)) => ("tabular-data", string_value(percent_decode(page))),
+                _ => ("url", string_value(iri)),
+            },
+        },
+        Term::Literal {
+            value,
+            language: Some(language),
+            ..
+        } => (
+            "monolingualtext",
+            json!({
+                "value": { "text": value, "language": language },
+                "type": "monolingualtext",
+            }),
+        ),
+        Term::Literal {
+            value,
+            datatype: Some(literal_type),
+            ..
+        } => match literal_type.strip_prefix(XSD) {
+            Some("decimal" | "integer" | "double") => ("quantity", quantity_value(&value)),
+            Some("dateTime" | "date") => ("time", time_value(&value)),
+            _ if literal_type == GEO_WKT => ("globe-coordinate", coordinates_value(&value)?),
+            _ if literal_type == MATHML => ("math", string_value(value)),
+            _ => (string_datatype(datatype), string_value(value)),
+        },
+        Term::Literal { value, .. } => (string_datatype(datatype), string_value(value)),
+    };
+
+    Some(json!({
+        "snaktype": "value",
+        "property": property,
+        "datavalue": datavalue,
+        "datatype": datatype,
+    }))
+}
+
+/// The function builds a snak holding no value, or an unknown one.
+fn bare_snak(property: &str, datatype: &str, snaktype: &str) -> Value {
+    json!({
+        "snaktype": snaktype,
+        "property": property,
+        "datatype": datatype,
+    })
+}
+
+/// The function returns the datatype of a plain string, which is the one of its
+/// property whenever it is known to hold strings, such as `external-id`.
+fn string_datatype(datatype: Option<&str>) -> &'static str {
+    match datatype {
+        Some("external-id") => "external-id",
+        Some("musical-notation") => "musical-notation",
+        Some("math") => "math",
+        _ => "string",
+    }
+}
+
+/// The function builds the data value pointing to an entity, together with its
+/// datatype, out of its identifier, such as `Q42` or `L7-F2`.
+fn entity_value(id: &str) -> Option<(&'static str, Value)> {
+    let (datatype, entity_type) = match id.split_once('-') {
+        Some((_, sub)) if sub.starts_with('F') => ("wikibase-form", "form"),
+        Some((_, sub)) if sub.starts_with('S') => ("wikibase-sense", "sense"),
+        Some(_) => return None,
+        None => match id.get(0..1)? {
+            "Q" => ("wikibase-item", "item"),
+            "P" => ("wikibase-property", "property"),
+            "L" => ("wikibase-lexeme", "lexeme"),
+            _ => return None,
+        },
+    };
+    let mut value = json!({ "entity-type": entity_type, "id": id });
+    if let Ok(number) = id[1..].parse::<u64>() {
+        value["numeric-id"] = json!(number);
+    }
+    Some((
+        datatype,
+        json!({ "value": value, "type": "wikibase-entityid" }),
+    ))
+}
+
+/// The function builds the data value of a string.
+fn string_value(value: String) -> Value {
+    json!({ "value": value, "type": "string" })
+}
+
+/// The function builds the data value of a quantity out of its amount. The truthy
+/// dumps hold neither the unit nor the bounds of the quantities, so those are
+/// missing.
+fn quantity_value(amount: &str) -> Value {
+    let amount = match amount.starts_with(['+', '-']) {
+        true => amount.to_string(),
+        false => format!("+{}", amount),
+    };
+    json!({ "value": { "amount": amount, "unit": "1" }, "type": "quantity" })
+}
+
+/// The function builds the data value of a time out of its timestamp. The truthy
+/// dumps hold no precision, so the day is taken as such, and the dates are assumed
+/// to be in the Gregorian calendar, as those are normalized to it.
+fn time_value(time: &str) -> Value {
+    let time = match time.starts_with(['+', '-']) {
+        true => time.to_string(),
+        false => format!("+{}", time),
+    };
+    json!({
+        "value": {
+            "time": time,
+            "timezone": 0,
+            "before": 0,
+            "after": 0,
+            "precision": 11,
+            "calendarmodel": "http://www.wikidata.org/entity/Q1985727",
+        },
+        "type": "time",
+    })
+}
+
+/// The function builds the data value of a pair of coordinates out of its WKT
+/// literal, such as `Point(4.66 50.64)`, which may be preceded by the IRI of the
+/// globe, in case it is not the Earth. The precision is not found in the truthy
+/// dumps, so the one of the digits written is taken as such.
+fn coordinates_value(wkt: &str) -> Option<Value> {
+    let (globe, point) = match wkt.strip_prefix('<') {
+        Some(rest) => {
+            let (globe, point) = rest.split_once('>')?;
+            (globe.to_string(), point.trim())
+        }
+        None => (format!("{}Q2", ENTITY), wkt.trim()),
+    };
+    let (longitude, latitude) = point
+        .strip_prefix("Point(")?
+        .strip_suffix(')')?
+        .split_once(' ')?;
+    let decimals = [longitude, latitude]
+        .iter()
+        .map(|coordinate| {
+            coordinate
+                .split_once('.')
+                .map_or(0, |(_, digits)| digits.len())
+        })
+        .max()
+        .unwrap_or_default();
+    Some(json!({
+        "value": {
+            "latitude": latitude.parse::<f64>().ok()?,
+            "longitude": longitude.parse::<f64>().ok()?,
+            "altitude": null,
+            "precision": 10f64.powi(-(decimals as i32)),
+            "globe": globe,
+        },
+        "type": "globecoordinate",
+    }))
+}
+
+/// The function builds a text in a language, as labels and descriptions are.
+fn text(language: &str, value: &str) -> Value {
+    json!({ "language": language, "value": value })
+}
+
+/// The function returns the datatype of the properties out of the name of its term
+/// in the Wikibase ontology, such as `wikibase-item` for `WikibaseItem`.
+fn property_datatype(name: &str) -> String {
+    if name == "CommonsMedia" {
+        return "commonsMedia".to_string();
+    }
+    let mut datatype = String::new();
+    for (index, char) in name.char_indices() {
+        if char.is_uppercase() && index > 0 {
+            datatype.push('-');
+        }
+        datatype.extend(char.to_lowercase());
+    }
+    datatype
+}
+
+/// The function returns the site and the title of a sitelink out of the IRI of its
+/// article, such as `enwiki` and `Douglas Adams` for
+/// `https://en.wikipedia.org/wiki/Douglas_Adams`.
+fn sitelink(article: &str) -> Option<(String, String)> {
+    let (host, title) = article.strip_prefix("https://")?.split_once("/wiki/")?;
+    let (language, project) = host.strip_suffix(".org")?.split_once('.')?;
+    let project = match project {
+        "wikipedia" | "wikimedia" => "wiki",
+        project => project,
+    };
+    Some((
+        format!("{}{}", language.replace('-', "_"), project),
+        percent_decode(title).replace('_', " "),
+    ))
+}
+
+/// The function decodes the percent-encoded characters of an IRI, such as `%20`.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = match (bytes[index], text.get(index + 1..index + 3)) {
+            (b'%', Some(hex)) => u8::from_str_radix(hex, 16).ok(),
+            _ => None,
+        };
+        match byte {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The function parses a line of an N-Triples document into its subject, predicate
+/// and object.
+///
+/// Arguments:
+///
+/// * `line`: The line, without the line break.
+///
+/// Returns:
+///
+/// An `Option` with the triple, or `None` in case the line is not a valid triple.
+fn parse_triple(line: &str) -> Option<(Term, String, Term)> {
+    let (subject, rest) = parse_term(line)?;
+    let (predicate, rest) = match parse_term(rest)? {
+        (Term::Iri(predicate), rest) => (predicate, rest),
+        _ => return None,
+    };
+    let (object, rest) = parse_term(rest)?;
+    match rest.trim() {
+        "." => Some((subject, predicate, object)),
+        _ => None,
+    }
+}
+
+/// The function parses the term the input starts with, returning it together with
+/// the rest of the input.
+fn parse_term(input: &str) -> Option<(Term, &str)> {
+    let input = input.trim_start();
+    if let Some(rest) = input.strip_prefix('<') {
+        let (iri, rest) = rest.split_once('>')?;
+        return Some((Term::Iri(unescape(iri)?), rest));
+    }
+    if input.starts_with("_:") {
+        let end = input.find(char::is_whitespace)?;
+        return Some((Term::Blank(input[2..end].to_string()), &input[end..]));
+    }
+
+    // The literal is over at the first quote that is not escaped
+    let rest = input.strip_prefix('"')?;
+    let mut escaped = false;
+    let mut end = None;
+    for (index, char) in rest.char_indices() {
+        match char {
+            '"' if !escaped => {
+                end = Some(index);
+                break;
+            }
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    let end = end?;
+    let value = unescape(&rest[..end])?;
+    let rest = &rest[end + 1..];
+
+    if let Some(tagged) = rest.strip_prefix('@') {
+        let end = tagged.find(char::is_whitespace).unwrap_or(tagged.len());
+        let term = Term::Literal {
+            value,
+            language: Some(tagged[..end].to_string()),
+            datatype: None,
+        };
+        return Some((term, &tagged[end..]));
+    }
+    if let Some(typed) = rest.strip_prefix("^^<") {
+        let (datatype, rest) = typed.split_once('>')?;
+        let term = Term::Literal {
+            value,
+            language: None,
+            datatype: Some(datatype.to_string()),
+        };
+        return Some((term, rest));
+    }
+    let term = Term::Literal {
+        value,
+        language: None,
+        datatype: None,
+    };
+    Some((term, rest))
+}
+
+/// The function replaces the escape sequences of N-Triples by the characters they
+/// stand for; that is, the ones such as `\n` or `\"`, and the Unicode ones, such as
+/// `\u00E9` or `\U0001F600`.
+fn unescape(text: &str) -> Option<String> {
+    if !text.contains('\\') {
+        return Some(text.to_string());
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+        let escaped = match chars.next()? {
+            't' => '\t',
+            'b' => '\u{8}',
+            'n' => '\n',
+            'r' => '\r',
+            'f' => '\u{c}',
+            'u' => code_point(&mut chars, 4)?,
+            'U' => code_point(&mut chars, 8)?,
+            other => other, // quotes and backslashes stand for themselves
+        };
+        unescaped.push(escaped);
+    }
+    Some(unescaped)
+}
+
+/// The function reads the hexadecimal digits of a Unicode escape sequence.
+fn code_point(chars: &mut std::str::Chars, digits: usize) -> Option<char> {
+    let hex = chars.take(digits).collect::<String>();
+    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)
+}
+
+/// The function writes the truthy triples of an entity, given its JSON, following
+/// the RDF dumps of Wikidata; that is, its labels, descriptions and aliases, and the
+/// best ranked values of each of its properties through the `wdt:` predicates.
+/// Unknown values are written as skolemized blank nodes, while missing ones are
+/// written as the `wdno:` class of the entity. Hence, the triples written are read
+/// back by `NTriplesEntities` into the very same entity.
+///
+/// Arguments:
+///
+/// * `entity`: A reference to the JSON value of the entity, as found in the dumps.
+///
+/// Returns:
+///
+/// A `String` with the triples, one per line, which is empty in case the entity
+/// has no identifier.
+pub fn entity_triples(entity: &Value) -> String {
+    let id = match entity.get("id").and_then(Value::as_str) {
+        Some(id) => id,
+        None => return String::new(),
+    };
+    let subject = format!("<{}{}>", ENTITY, id);
+    let mut triples = String::new();
+    let mut push = |predicate: &str, object: &str| {
+        triples.push_str(&format!("{} <{}> {} .\n", subject, predicate, object));
+    };
+
+    for (field, predicate) in [
+        ("labels", RDFS_LABEL),
+        ("descriptions", SCHEMA_DESCRIPTION),
+        ("aliases", SKOS_ALT_LABEL),
+    ] {
+        let terms = entity.get(field).and_then(Value::as_object).into_iter();
+        for term in terms.flatten().flat_map(|(_, term)| match term {
+            Value::Array(terms) => terms.iter().collect(),
+            term => vec![term],
+        }) {
+            if let (Some(language), Some(value)) = (
+                term.get("language").and_then(Value::as_str),
+                term.get("value").and_then(Value::as_str),
+            ) {
+                push(predicate, &format!("{}@{}", literal(value), language));
+            }
+        }
+    }
+
+    let claims = entity.get("claims").and_then(Value::as_object).into_iter();
+    for (property, statements) in claims.flatten() {
+        let statements = statements.as_array().map_or(&[][..], Vec::as_slice);
+        // Only the best ranked values are truthy; that is, the preferred ones, or the normal ones
+        // in case no value of the property is preferred :D
+        let best = match statements.iter().any(|s| rank(s) == Some("preferred")) {
+            true => "preferred",
+            false => "normal",
+        };
+        for statement in statements
+            .iter()
+            .filter(|statement| rank(statement).unwrap_or("normal") == best)
+        {
+            let snak = &statement["mainsnak"];
+            match snak.get("snaktype").and_then(Value::as_str) {
+                Some("novalue") => push(RDF_TYPE, &format!("<{}{}>", NOVALUE, property)),
+                Some("somevalue") => {
+                    let statement_id = statement
+                        .get("id")
+                        .and_then(Value::as_str)
+                        .unwrap_or(property);
+                    let genid = statement_id.replace(|char: char| !char.is_alphanumeric(), "-");
+                    push(
+                        &format!("{}{}", DIRECT, property),
+                        &format!("<{}{}>", GENID, genid),
+                    );
+                }
+                _ => {
+                    if let Some(object) = object(snak) {
+                        push(&format!("{}{}", DIRECT, property), &object);
+                    }
+                }
+            }
+        }
+    }
+
+    triples
+}
+
+/// The function returns the rank of a statement, such as `preferred`.
+fn rank(statement: &Value) -> Option<&str> {
+    statement.get("rank").and_then(Value::as_str)
+}
+
+/// The function writes the object of a truthy triple out of the main snak of a
+/// claim holding a value, following the encoding of the RDF dumps.
+///
+/// Arguments:
+///
+/// * `snak`: A reference to the JSON value of the snak.
+///
+/// Returns:
+///
+/// An `Option` with the object, or `None` in case the value cannot be written.
+fn object(snak: &Value) -> Option<String> {
+    let datavalue = snak.get("datavalue")?;
+    let value = datavalue.get("value")?;
+    let datatype = snak.get("datatype").and_then(Value::as_str);
+    let object = match datavalue.get("type")?.as_str()? {
+        "wikibase-entityid" => format!("<{}{}>", ENTITY, value.get("id")?.as_str()?),
+        "string" => match (datatype, value.as_str()?) {
+            (Some("commonsMedia"), file) => format!("<{}{}>", COMMONS_FILE, percent_encode(file)),
+            (Some("geo-shape" | "tabular-data"), page) => {
+                format!("<{}{}>", COMMONS_DATA, percent_encode(page))
+            }
+            // URLs may already be percent-encoded, so their percent signs are kept as they are
+            (Some("url"), url) => format!("<{}>", percent_encode(url).replace("%25", "%")),
+            (Some("math"), formula) => format!("{}^^<{}>", literal(formula), MATHML),
+            (_, string) => literal(string),
+        },
+        "monolingualtext" => format!(
+            "{}@{}",
+            literal(value.get("text")?.as_str()?),
+            value.get("language")?.as_str()?
+        ),
+        "quantity" => {
+            let amount = value.get("amount")?.as_str()?;
+            format!(
+                "{}^^<{}decimal>",
+                literal(amount.strip_prefix('+').unwrap_or(amount)),
+                XSD
+            )
+        }
+        "time" => {
+            let time = value.get("time")?.as_str()?;
+            format!(
+                "{}^^<{}dateTime>",
+                literal(time.strip_prefix('+').unwrap_or(time)),
+                XSD
+            )
+        }
+        "globecoordinate" => {
+            // Coordinates on other globes than the Earth are preceded by the IRI of their globe :D
+            let point = format!(
+                "Point({} {})",
+                value.get("longitude")?.as_f64()?,
+                value.get("latitude")?.as_f64()?
+            );
+            let point = match value.get("globe").and_then(Value::as_str) {
+                Some(globe) if globe != format!("{}Q2", ENTITY) => format!("<{}> {}", globe, point),
+                _ => point,
+            };
+            format!("{}^^<{}>", literal(&point), GEO_WKT)
+        }
+        _ => return None,
+    };
+    Some(object)
+}
+
+/// The function writes a literal, escaping the characters that cannot be found
+/// within the quotes.
+fn literal(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            char => literal.push(char),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// The function encodes the characters that are not allowed within an IRI, such as
+/// the spaces of the names of the files, so `percent_decode` restores them.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            ' ' | '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' | '%' => {
+                encoded.push_str(&format!("%{:02X}", char as u32))
+            }
+            char if char.is_control() => {
+                let mut bytes = [0; 4];
+                for byte in char.encode_utf8(&mut bytes).bytes() {
+                    encoded.push_str(&format!("%{:02X}", byte));
+                }
+            }
+            char => encoded.push(char),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(value: &str, language: Option<&str>, datatype: Option<&str>) -> Term {
+        Term::Literal {
+            value: value.to_string(),
+            language: language.map(str::to_string),
+            datatype: datatype.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parses_iri_objects() {
+        let triple = parse_triple(
+            "<http://www.wikidata.org/entity/Q42> \
+             <http://www.wikidata.org/prop/direct/P31> \
+             <http://www.wikidata.org/entity/Q5> .",
+        );
+        assert_eq!(
+            triple,
+            Some((
+                Term::Iri(format!("{}Q42", ENTITY)),
+                format!("{}P31", DIRECT),
+                Term::Iri(format!("{}Q5", ENTITY)),
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_literals() {
+        let (_, _, object) = parse_triple(&format!(
+            "<{}Q42> <{}> \"Douglas Adams\"@en .",
+            ENTITY, RDFS_LABEL
+        ))
+        .unwrap();
+        assert_eq!(object, literal("Douglas Adams", Some("en"), None));
+
+        let (_, _, object) = parse_triple(&format!(
+            "<{}Q42> <{}P1082> \"+42\"^^<{}decimal> .",
+            ENTITY, DIRECT, XSD
+        ))
+        .unwrap();
+        assert_eq!(
+            object,
+            literal("+42", None, Some(&format!("{}decimal", XSD)))
+        );
+
+        let (_, _, object) =
+            parse_triple(&format!("<{}Q42> <{}P1545> \"7\" .", ENTITY, DIRECT)).unwrap();
+        assert_eq!(object, literal("7", None, None));
+    }
+
+    #[test]
+    fn unescapes_literals() {
+        let (_, _, object) = parse_triple(&format!(
+            "<{}Q42> <{}> \"say \\\"hi\\\"\\n\\u00E9\"@en .",
+            ENTITY, RDFS_LABEL
+        ))
+        .unwrap();
+        assert_eq!(object, literal("say \"hi\"\né", Some("en"), None));
+    }
+
+    #[test]
+    fn parses_blank_nodes() {
+        let (subject, _, object) = parse_triple(&format!("_:b0 <{}P31> _:b1 .", DIRECT)).unwrap();
+        assert_eq!(subject, Term::Blank("b0".to_string()));
+        assert_eq!(object, Term::Blank("b1".to_string()));
+    }
+
+    #[test]
+    fn rejects_malformed_triples() {
+        for line in [
+            "",
+            "# a comment",
+            "<http://a> <http://b> <http://c>",
+            "<http://a> \"b\" <http://c> .",
+            "<http://a> <http://b> \"unterminated .",
+            "<http://a> <http://b> <http://c> . extra",
+        ] {
+            assert_eq!(parse_triple(line), None, "{}", line);
+        }
+    }
+
+    /// The function groups the given N-Triples into the JSON of the entities those
+    /// describe.
+    fn entities(triples: &[String]) -> Vec<Value> {
+        NTriplesEntities::new(triples.join("\n").as_bytes())
+            .map(|entity| serde_json::from_str(&entity.unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn groups_the_triples_by_entity() {
+        let entities = entities(&[
+            format!("<{}Q42> <{}> \"Douglas Adams\"@en .", ENTITY, RDFS_LABEL),
+            format!("<{}Q42> <{}P31> <{}Q5> .", ENTITY, DIRECT, ENTITY),
+            format!("<{}Q42> <{}P31> <{}Q36180> .", ENTITY, DIRECT, ENTITY),
+            format!("<{}Q1> <{}> \"universe\"@en .", ENTITY, RDFS_LABEL),
+            format!("<{}L7> <{}> \"cat\"@en .", ENTITY, WIKIBASE_LEMMA),
+        ]);
+
+        assert_eq!(entities.len(), 3);
+        assert_eq!(entities[0]["id"], "Q42");
+        assert_eq!(entities[0]["labels"]["en"]["value"], "Douglas Adams");
+        assert_eq!(entities[0]["claims"]["P31"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            entities[0]["claims"]["P31"][1]["mainsnak"]["datavalue"]["value"]["id"],
+            "Q36180"
+        );
+        assert_eq!(entities[1]["id"], "Q1");
+        assert_eq!(entities[2]["type"], "lexeme");
+        assert_eq!(entities[2]["lemmas"]["en"]["value"], "cat");
+    }
+
+    #[test]
+    fn reads_the_sitelinks_and_the_revisions() {
+        let entities = entities(&[
+            format!(
+                "<https://en.wikipedia.org/wiki/Douglas_Adams> <{}> <{}Q42> .",
+                SCHEMA_ABOUT, ENTITY
+            ),
+            "<https://en.wikipedia.org/wiki/Douglas_Adams> <http://schema.org/name> \
+             \"Douglas Adams\"@en ."
+                .to_string(),
+            format!(
+                "<{}Q42> <{}> \"2171221585\"^^<{}integer> .",
+                ENTITY_DATA, SCHEMA_VERSION, XSD
+            ),
+            format!("<{}Q42> <{}P31> <{}Q5> .", ENTITY, DIRECT, ENTITY),
+            format!(
+                "<https://de.wikiquote.org/wiki/Caf%C3%A9> <{}> <{}Q1> .",
+                SCHEMA_ABOUT, ENTITY
+            ),
+        ]);
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0]["lastrevid"], 2171221585u64);
+        assert_eq!(
+            entities[0]["sitelinks"]["enwiki"]["title"],
+            "Douglas Adams"
+        );
+        assert_eq!(entities[0]["claims"]["P31"].as_array().unwrap().len(), 1);
+        assert_eq!(entities[1]["id"], "Q1");
+        assert!(entities[1].get("lastrevid").is_none());
+        assert_eq!(entities[1]["sitelinks"]["dewikiquote"]["title"], "Café");
+    }
+
+    #[test]
+    fn gives_the_strings_the_datatype_of_their_property_once_known() {
+        let entities = entities(&[
+            format!("<{}Q42> <{}P214> \"113230702\" .", ENTITY, DIRECT),
+            format!(
+                "<{}P214> <{}> <{}ExternalId> .",
+                ENTITY, WIKIBASE_PROPERTY_TYPE, WIKIBASE
+            ),
+            format!("<{}Q1> <{}P214> \"1\" .", ENTITY, DIRECT),
+        ]);
+
+        // The first string is read before the datatype of its property, which is fixed once
+        // loaded :(
+        assert_eq!(entities[0]["claims"]["P214"][0]["mainsnak"]["datatype"], "string");
+        assert_eq!(entities[1]["datatype"], "external-id");
+        assert_eq!(
+            entities[2]["claims"]["P214"][0]["mainsnak"]["datatype"],
+            "external-id"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+
+/// The `ReorderBuffer` struct restores the input order of the items processed by
+/// several workers in parallel. Each item is tagged with the sequence number of the
+/// line it comes from, and it is held in the buffer until every item before it has
+/// been released. Hence, the output is stable between runs, which allows diffing
+/// the tables of two databases built out of the same dump.
+///
+/// Properties:
+///
+/// * `next`: The sequence number of the next item to be released.
+/// * `pending`: The items that arrived before some of the ones preceding them,
+/// sorted by their sequence number.
+#[derive(Debug)]
+pub struct ReorderBuffer<T> {
+    next: u64,
+    pending: BTreeMap<u64, T>,
+}
+
+impl<T> ReorderBuffer<T> {
+    /// The function creates an empty buffer, waiting for the item whose sequence
+    /// number is 0.
+    pub fn new() -> Self {
+        Self {
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// The function pushes an item into the buffer and releases every item that is
+    /// ready to be written; that is, all of those whose predecessors have already
+    /// been released.
+    ///
+    /// Arguments:
+    ///
+    /// * `sequence`: The sequence number of the item.
+    /// * `item`: The item itself.
+    ///
+    /// Returns:
+    ///
+    /// A vector with the items ready to be written, in input order. It is empty in
+    /// case some of the items before the one pushed are yet to arrive.
+    pub fn push(&mut self, sequence: u64, item: T) -> Vec<T> {
+        self.pending.insert(sequence, item);
+
+        let mut ready = Vec::new();
+        while let Some(item) = self.pending.remove(&self.next) {
+            ready.push(item);
+            self.next += 1;
+        }
+        ready
+    }
+
+    /// The function returns the number of items held in the buffer, waiting for
+    /// some of their predecessors to arrive.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// The function returns whether there is no item held in the buffer.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+impl<T> Default for ReorderBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
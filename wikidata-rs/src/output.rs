@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use duckdb::Connection;
+use strum_macros::EnumString;
+
+use crate::stats::table_names;
+
+/// The `OutputFormat` enum defines the formats the result of the load is given in.
+/// `Duckdb` stands for the database file alone, while `Parquet` stands for a
+/// Parquet file per table as well, which is what Spark, Polars or Athena read.
+#[derive(Clone, Debug, Default, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum OutputFormat {
+    #[default]
+    Duckdb,
+    Parquet,
+}
+
+/// The function returns the path to the directory the Parquet files of a database
+/// are written to; that is, the one of the database followed by `.parquet`.
+///
+/// Arguments:
+///
+/// * `database`: A reference to the path of the database.
+///
+/// Returns:
+///
+/// A `PathBuf` to the directory.
+pub fn parquet_path(database: &Path) -> PathBuf {
+    let mut path = database.as_os_str().to_owned();
+    path.push(".parquet");
+    PathBuf::from(path)
+}
+
+/// The function writes each of the tables of the database to a Parquet file of
+/// its own, named after the table, such as `vertex.parquet`. Views and macros are
+/// left out, as those cannot be stored in Parquet, but can be rebuilt over the
+/// files, as they only depend on the tables.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database, once the load
+/// has finished.
+/// * `directory`: A reference to the path of the directory the files are written
+/// to, which is created in case it does not exist yet.
+///
+/// Returns:
+///
+/// a `Result` with the number of tables written, or a `String` with an error
+/// message in case any of them could not be.
+pub fn export_parquet(connection: &Connection, directory: &Path) -> Result<usize, String> {
+    if let Err(error) = fs::create_dir_all(directory) {
+        return Err(format!("Error creating {}. {}", directory.display(), error));
+    }
+
+    let tables = match table_names(connection) {
+        Ok(tables) => tables,
+        Err(error) => return Err(format!("Error listing tables. {}", error)),
+    };
+
+    // Each table is copied on its own, so the files are named after the tables, no matter the
+    // naming scheme of EXPORT DATABASE, which is free to change between versions of DuckDB :D
+    for table in &tables {
+        let path = directory.join(format!("{}.parquet", table));
+        if let Err(error) = connection.execute_batch(&format!(
+            "COPY \"{}\" TO '{}' (FORMAT PARQUET);",
+            table,
+            path.to_string_lossy().replace('\'', "''")
+        )) {
+            return Err(format!("Error writing {}. {}", path.display(), error));
+        }
+    }
+
+    Ok(tables.len())
+}
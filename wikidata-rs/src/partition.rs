@@ -0,0 +1,244 @@
+use std::str::FromStr;
+
+use duckdb::{params, Connection, Error};
+use wikidata::Pid;
+
+use crate::id::{parse_property, Id, IdEncoding};
+use crate::value::Table;
+
+/// The `Partition` struct represents a property whose claims are stored in tables
+/// of their own, such as `edge_p31` or `coordinates_p625`, instead of the generic
+/// ones. Extremely hot properties are thus fast to scan, while the long tail of the
+/// properties is kept in the generic tables.
+///
+/// Properties:
+///
+/// * `property`: The `Pid` of the property whose claims are partitioned.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Partition {
+    pub property: Pid,
+}
+
+impl Partition {
+    /// The function returns the name of the table storing the claims of the property
+    /// that are found in the given generic table.
+    ///
+    /// Arguments:
+    ///
+    /// * `table_name`: The name of the generic table; for example, `edge`.
+    ///
+    /// Returns:
+    ///
+    /// A `String` with the name of the table; for example, `edge_p31`.
+    pub fn table_name(&self, table_name: &str) -> String {
+        format!("{}_p{}", table_name, self.property.0)
+    }
+
+    /// This function returns the names of the generic tables the claims of the
+    /// property may be stored in; that is, every table but the ones of vertices,
+    /// properties and raw claims.
+    pub fn table_names() -> Vec<&'static str> {
+        let mut table_names: Vec<&str> = Vec::new();
+        for table in Table::iterator() {
+            if let Table::Vertex { .. } | Table::Property { .. } | Table::RawClaim { .. } = table {
+                continue; // vertices are not claims, and raw claims have no value to partition
+            }
+            if !table_names.contains(&table.as_ref()) {
+                table_names.push(table.as_ref());
+            }
+        }
+        table_names
+    }
+
+    /// This function returns the SQL statements moving the claims of the property out
+    /// of a generic table into the one of the partition, together with its indices.
+    ///
+    /// Arguments:
+    ///
+    /// * `table_name`: The name of the generic table; for example, `edge`.
+    /// * `property_id`: The numeric identifier of the property.
+    ///
+    /// Returns:
+    ///
+    /// A `String` with the SQL statements.
+    pub fn create_table_sql(&self, table_name: &str, property_id: u64) -> String {
+        let partition_name = self.table_name(table_name);
+        format!(
+            "CREATE TABLE {} AS SELECT * FROM {} WHERE property_id = {};\
+             DELETE FROM {} WHERE property_id = {};\
+             CREATE INDEX IF NOT EXISTS {}_src_id_index ON {} (src_id);\
+             CREATE INDEX IF NOT EXISTS {}_dst_id_index ON {} (dst_id);",
+            partition_name,
+            table_name,
+            property_id,
+            table_name,
+            property_id,
+            partition_name,
+            partition_name,
+            partition_name,
+            partition_name,
+        )
+    }
+
+    /// This function creates the tables of the partition out of the generic ones,
+    /// once the load has finished. Only the tables where the property has any claim
+    /// are created, as a property always has the same datatype. Notice that the rows
+    /// are moved, and not copied, so those are not stored twice. Hence, the queries
+    /// needing every claim read the generic tables together with their partitions,
+    /// as given by `source`.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// tables are created successfully, or an `Error` object if the property does not
+    /// fit the range of its namespace, or there is an error during the execution of
+    /// the SQL statements.
+    pub fn create_tables(&self, connection: &Connection) -> Result<(), Error> {
+        let property_id = IdEncoding::of(connection)?
+            .encode(Id::Pid(self.property))
+            .map_err(|error| Error::ToSqlConversionFailure(error.into()))?;
+
+        for table_name in Self::table_names() {
+            let claims: i64 = connection.query_row(
+                &format!("SELECT count(*) FROM {} WHERE property_id = ?", table_name),
+                params![property_id],
+                |row| row.get(0),
+            )?;
+
+            if claims > 0 {
+                in_transaction(connection, &self.create_table_sql(table_name, property_id))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The function runs the given statements within a transaction, so the claims are
+/// never lost, nor duplicated, in case any of them fails halfway.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `sql`: The SQL statements to be run.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if every
+/// statement is run and committed, or an `Error` object otherwise.
+fn in_transaction(connection: &Connection, sql: &str) -> Result<(), Error> {
+    connection.execute_batch("BEGIN TRANSACTION;")?;
+    match connection.execute_batch(sql) {
+        Ok(()) => connection.execute_batch("COMMIT;"),
+        Err(error) => {
+            let _ = connection.execute_batch("ROLLBACK;"); // the original error is the one of use
+            Err(error)
+        }
+    }
+}
+
+/// The function returns the names of the tables the claims of a generic table have
+/// been moved to by the partitions, such as `edge_p31` for the `edge` table.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `table_name`: The name of the generic table; for example, `edge`.
+///
+/// Returns:
+///
+/// a `Result` with the names of the tables of the partitions, which is empty if
+/// there is none, or an `Error` object if the tables could not be listed.
+pub fn partitions_of(connection: &Connection, table_name: &str) -> Result<Vec<String>, Error> {
+    let prefix = format!("{}_p", table_name);
+    let mut statement = connection.prepare(
+        "SELECT table_name FROM information_schema.tables \
+         WHERE table_name LIKE ? ORDER BY table_name",
+    )?;
+    let tables = statement
+        .query_map(params![format!("{}%", prefix)], |row| {
+            row.get::<_, String>(0)
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    // Underscores are wildcards for LIKE, so the names are checked once again :(
+    Ok(tables
+        .into_iter()
+        .filter(|table| {
+            table.strip_prefix(&prefix).is_some_and(|property| {
+                !property.is_empty() && property.chars().all(|c| c.is_ascii_digit())
+            })
+        })
+        .collect())
+}
+
+/// The function returns what the queries needing every claim of a generic table
+/// have to read; that is, the generic table itself, in case none of its claims
+/// has been moved to a partition, or a subquery joining it with its partitions.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `table_name`: The name of the generic table; for example, `edge`.
+///
+/// Returns:
+///
+/// a `Result` with the name of the table, or the subquery, to be placed in the
+/// `FROM` clause, or an `Error` object if the tables could not be listed.
+pub fn source(connection: &Connection, table_name: &str) -> Result<String, Error> {
+    let partitions = partitions_of(connection, table_name)?;
+    if partitions.is_empty() {
+        return Ok(table_name.to_string());
+    }
+    Ok(format!(
+        "(SELECT * FROM {} UNION ALL {})",
+        table_name,
+        partitions
+            .iter()
+            .map(|partition| format!("SELECT * FROM {}", partition))
+            .collect::<Vec<_>>()
+            .join(" UNION ALL ")
+    ))
+}
+
+/// This function moves the claims of every partition back to the generic tables,
+/// dropping the tables of the partitions. It is run before loading into a database
+/// that has been partitioned, so the entities replaced, the derived tables and the
+/// statistics see every claim, and the partitions asked for are created again once
+/// the load has finished.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// claims are moved back, or an `Error` object otherwise.
+pub fn merge(connection: &Connection) -> Result<(), Error> {
+    for table_name in Partition::table_names() {
+        for partition in partitions_of(connection, table_name)? {
+            in_transaction(
+                connection,
+                &format!(
+                    "INSERT INTO {} SELECT * FROM {}; DROP TABLE {};",
+                    table_name, partition, partition
+                ),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// This code parses the identifier of the property to be partitioned, such as
+/// `P31`. An error message is returned in case it is not a valid property.
+impl FromStr for Partition {
+    type Err = String;
+
+    fn from_str(property: &str) -> Result<Self, Self::Err> {
+        parse_property(property).map(|property| Self { property })
+    }
+}
@@ -0,0 +1,379 @@
+use wikidata::Pid;
+
+use crate::id::{Id, IdEncoding};
+
+/// The `Modifier` enum represents the repetition operators that can follow a
+/// property in a SPARQL-like property path. `One` stands for a plain property,
+/// `ZeroOrOne` for `?`, `ZeroOrMore` for `*` and `OneOrMore` for `+`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Modifier {
+    One,
+    ZeroOrOne,
+    ZeroOrMore,
+    OneOrMore,
+}
+
+/// The `Step` struct represents each of the elements of a property path; that is,
+/// the property to be traversed, whether it has to be traversed backwards (`^P31`)
+/// and the repetition operator applied to it.
+///
+/// Properties:
+///
+/// * `property_id`: The numeric identifier of the property as it is stored in the
+/// `property_id` column of the edge table.
+/// * `inverse`: Whether the edge has to be followed from the `dst_id` to the `src_id`.
+/// * `modifier`: The repetition operator applied to the step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub property_id: u64,
+    pub inverse: bool,
+    pub modifier: Modifier,
+}
+
+/// The `PropertyPath` struct is a sequence of steps separated by `/`, such as
+/// `P31/P279*`. It can be compiled into a recursive SQL query over the edge table
+/// so SPARQL-minded users can traverse the graph without writing the CTEs by hand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyPath {
+    pub steps: Vec<Step>,
+}
+
+impl Step {
+    /// The function returns the SQL expressions that select the columns at both
+    /// ends of an edge, taking into account whether the step is inverse or not.
+    ///
+    /// Returns:
+    ///
+    /// A tuple containing the column the step starts from and the column where it
+    /// arrives to.
+    fn columns(&self) -> (&str, &str) {
+        if self.inverse {
+            ("dst_id", "src_id")
+        } else {
+            ("src_id", "dst_id")
+        }
+    }
+
+    /// The function creates the SQL query that advances every pair stored in the
+    /// `previous` relation one edge further through the property of the step.
+    ///
+    /// Arguments:
+    ///
+    /// * `previous`: The name of the relation (CTE) that contains the pairs of
+    /// vertices to be extended.
+    /// * `edge`: The relation the edges are read from.
+    ///
+    /// Returns:
+    ///
+    /// A `String` containing a `SELECT` statement returning `src_id` and `dst_id`.
+    fn advance(&self, previous: &str, edge: &str) -> String {
+        let (from, to) = self.columns();
+        format!(
+            "SELECT p.src_id, e.{} AS dst_id FROM {} p JOIN {} e ON e.{} = p.dst_id AND e.property_id = {}",
+            to, previous, edge, from, self.property_id
+        )
+    }
+
+    /// The function compiles the step into the body of a common table expression
+    /// named `current` that extends the pairs of the `previous` one.
+    ///
+    /// Arguments:
+    ///
+    /// * `previous`: The name of the CTE holding the pairs reached so far.
+    /// * `current`: The name of the CTE that is being defined.
+    /// * `edge`: The relation the edges are read from.
+    ///
+    /// Returns:
+    ///
+    /// A `String` containing the SQL body of the common table expression.
+    fn compile(&self, previous: &str, current: &str, edge: &str) -> String {
+        // Recursive steps rely on UNION rather than UNION ALL, so that cycles in the graph (which
+        // are more common than one would expect in the subclass hierarchy) do not make the
+        // recursion run forever: once no new pair is found, the fixpoint has been reached :D
+        match self.modifier {
+            Modifier::One => self.advance(previous, edge),
+            Modifier::ZeroOrOne => format!(
+                "SELECT src_id, dst_id FROM {} UNION {}",
+                previous,
+                self.advance(previous, edge)
+            ),
+            Modifier::ZeroOrMore => format!(
+                "SELECT src_id, dst_id FROM {} UNION {}",
+                previous,
+                self.advance(current, edge)
+            ),
+            Modifier::OneOrMore => format!(
+                "{} UNION {}",
+                self.advance(previous, edge),
+                self.advance(current, edge)
+            ),
+        }
+    }
+}
+
+impl PropertyPath {
+    /// The function compiles the property path into a recursive SQL query over the
+    /// edge table. The query returns the `src_id` and `dst_id` of every pair of
+    /// vertices that are connected through the path.
+    ///
+    /// Arguments:
+    ///
+    /// * `from`: An optional identifier of the vertex the path starts from. In case
+    /// it is not provided, every vertex stored in the database is considered.
+    /// * `edge`: The relation the edges are read from; that is, the `edge` table, or
+    /// the union of it and its partitions, as `partition::source` returns.
+    ///
+    /// Returns:
+    ///
+    /// A `String` with the SQL query.
+    pub fn to_sql(&self, from: Option<u64>, edge: &str) -> String {
+        // The first relation is the identity over the vertices the path starts from. This allows
+        // the zero-length paths of `*` and `?` to be handled in the same way as the rest :D
+        let mut ctes = vec![match from {
+            Some(id) => format!(
+                "path_0(src_id, dst_id) AS (SELECT {} AS src_id, {} AS dst_id)",
+                id, id
+            ),
+            None => "path_0(src_id, dst_id) AS (SELECT id AS src_id, id AS dst_id FROM vertex)"
+                .to_string(),
+        }];
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let previous = format!("path_{}", index);
+            let current = format!("path_{}", index + 1);
+            ctes.push(format!(
+                "{}(src_id, dst_id) AS ({})",
+                current,
+                step.compile(&previous, &current, edge)
+            ));
+        }
+
+        format!(
+            "WITH RECURSIVE {} SELECT DISTINCT src_id, dst_id FROM path_{}",
+            ctes.join(", "),
+            self.steps.len()
+        )
+    }
+
+    /// The function parses a property path such as `P31/P279*` or `^P50/P27`. Each
+    /// step is an optional `^` (inverse), a property identifier and an optional
+    /// repetition operator (`?`, `*` or `+`).
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: A string slice with the property path.
+    /// * `encoding`: The `IdEncoding` of the database the path is run against, which
+    /// the properties are encoded with.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `PropertyPath`, or a `String` with an error message in
+    /// case the path is not well formed, or any of its properties is out of range.
+    pub fn parse(path: &str, encoding: &IdEncoding) -> Result<Self, String> {
+        let mut steps = Vec::new();
+
+        for step in path.split('/') {
+            let mut step = step.trim();
+
+            let inverse = step.starts_with('^');
+            if inverse {
+                step = &step[1..];
+            }
+
+            let modifier = match step.chars().last() {
+                Some('?') => Modifier::ZeroOrOne,
+                Some('*') => Modifier::ZeroOrMore,
+                Some('+') => Modifier::OneOrMore,
+                _ => Modifier::One,
+            };
+            if modifier != Modifier::One {
+                step = &step[..step.len() - 1];
+            }
+
+            let property_id = match step.strip_prefix('P').map(str::parse::<u64>) {
+                Some(Ok(id)) => encoding.encode(Id::Pid(Pid(id)))?,
+                _ => return Err(format!("Not valid property in path: {}", step)),
+            };
+
+            steps.push(Step {
+                property_id,
+                inverse,
+                modifier,
+            });
+        }
+
+        Ok(Self { steps })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use duckdb::Connection;
+
+    use super::*;
+    use crate::partition::{self, Partition};
+
+    fn property_id(id: u64) -> u64 {
+        IdEncoding::V2.encode(Id::Pid(Pid(id))).unwrap()
+    }
+
+    fn parse(path: &str) -> Result<PropertyPath, String> {
+        PropertyPath::parse(path, &IdEncoding::V2)
+    }
+
+    /// The function creates a database in memory with a small graph, whose subclass
+    /// hierarchy has a cycle: `1 -> 2 -> 3 -> 1`, besides `4 -> 1`. Then, `10` is an
+    /// instance of `1`, and `11` of `4`.
+    fn graph() -> Connection {
+        let connection = Connection::open_in_memory().unwrap();
+        let (p31, p279) = (property_id(31), property_id(279));
+        connection
+            .execute_batch(&format!(
+                "CREATE TABLE vertex (id UBIGINT); \
+                 INSERT INTO vertex VALUES (1), (2), (3), (4), (10), (11); \
+                 CREATE TABLE edge (src_id UBIGINT, property_id UBIGINT, dst_id UBIGINT); \
+                 INSERT INTO edge VALUES (1, {p279}, 2), (2, {p279}, 3), (3, {p279}, 1), \
+                 (4, {p279}, 1), (10, {p31}, 1), (11, {p31}, 4);",
+                p31 = p31,
+                p279 = p279
+            ))
+            .unwrap();
+        connection
+    }
+
+    /// The function runs a property path against the database, returning the pairs
+    /// of vertices it connects.
+    fn run(
+        connection: &Connection,
+        path: &str,
+        from: Option<u64>,
+        edge: &str,
+    ) -> BTreeSet<(u64, u64)> {
+        let sql = parse(path).unwrap().to_sql(from, edge);
+        let mut statement = connection.prepare(&sql).unwrap();
+        let pairs = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<BTreeSet<_>, _>>()
+            .unwrap();
+        pairs
+    }
+
+    /// The function returns the pairs connecting each of the given sources to each
+    /// of the given targets.
+    fn pairs(sources: &[u64], targets: &[u64]) -> BTreeSet<(u64, u64)> {
+        sources
+            .iter()
+            .flat_map(|src| targets.iter().map(move |dst| (*src, *dst)))
+            .collect()
+    }
+
+    #[test]
+    fn follows_zero_or_more_steps() {
+        let connection = graph();
+        let mut expected = pairs(&[1, 2, 3, 4], &[1, 2, 3]);
+        expected.extend([(4, 4), (10, 10), (11, 11)]);
+        assert_eq!(run(&connection, "P279*", None, "edge"), expected);
+    }
+
+    #[test]
+    fn follows_one_or_more_steps() {
+        let connection = graph();
+        assert_eq!(
+            run(&connection, "P279+", None, "edge"),
+            pairs(&[1, 2, 3, 4], &[1, 2, 3])
+        );
+        assert_eq!(
+            run(&connection, "P279+", Some(4), "edge"),
+            pairs(&[4], &[1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn follows_inverse_steps() {
+        let connection = graph();
+        assert_eq!(
+            run(&connection, "^P31", None, "edge"),
+            BTreeSet::from([(1, 10), (4, 11)])
+        );
+    }
+
+    #[test]
+    fn follows_sequences_of_steps() {
+        let connection = graph();
+        let mut expected = pairs(&[10, 11], &[1, 2, 3]);
+        expected.insert((11, 4));
+        assert_eq!(run(&connection, "P31/P279*", None, "edge"), expected);
+        assert_eq!(
+            run(&connection, "P31/P279*", Some(10), "edge"),
+            pairs(&[10], &[1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn follows_the_claims_moved_to_partitions() {
+        let connection = graph();
+        let partition = Partition { property: Pid(279) };
+        connection
+            .execute_batch(&partition.create_table_sql("edge", property_id(279)))
+            .unwrap();
+        let edge = partition::source(&connection, "edge").unwrap();
+        assert_ne!(edge, "edge");
+
+        let mut expected = pairs(&[10, 11], &[1, 2, 3]);
+        expected.insert((11, 4));
+        assert_eq!(run(&connection, "P31/P279*", None, &edge), expected);
+        assert!(run(&connection, "P31/P279*", None, "edge")
+            .iter()
+            .all(|(_, dst)| [1, 4].contains(dst)));
+    }
+
+    #[test]
+    fn parses_sequence_of_steps() {
+        let path = parse("P31/P279*").unwrap();
+        assert_eq!(
+            path.steps,
+            vec![
+                Step {
+                    property_id: property_id(31),
+                    inverse: false,
+                    modifier: Modifier::One,
+                },
+                Step {
+                    property_id: property_id(279),
+                    inverse: false,
+                    modifier: Modifier::ZeroOrMore,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_inverse_steps_and_modifiers() {
+        let path = parse(" ^P50+ / P27? ").unwrap();
+        assert_eq!(
+            path.steps,
+            vec![
+                Step {
+                    property_id: property_id(50),
+                    inverse: true,
+                    modifier: Modifier::OneOrMore,
+                },
+                Step {
+                    property_id: property_id(27),
+                    inverse: false,
+                    modifier: Modifier::ZeroOrOne,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_paths() {
+        for path in ["", "Q5", "P", "P31/", "P3x", "^", "P31**", "wdt:P31"] {
+            assert!(parse(path).is_err(), "{}", path);
+        }
+    }
+}
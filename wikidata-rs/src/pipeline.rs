@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+use std::any::Any;
+use std::num::NonZeroUsize;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{channel, sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::order::ReorderBuffer;
+
+/// The number of items each worker may be given ahead of the consumer, so the
+/// workers are kept busy while the consumer is writing, but the memory held by the
+/// items in flight is still bounded.
+const IN_FLIGHT_PER_THREAD: usize = 256;
+
+/// The `Pipeline` struct maps the items of an iterator on several worker threads,
+/// while the iterator itself is consumed on the calling thread. Hence, the lines of
+/// a dump are read by the caller, parsed by the workers, and the parsed entities are
+/// handed back to the caller, which is the only one writing to the database. The
+/// items are either returned as soon as those are ready, or in the order of the
+/// input whenever it has to be preserved. An item whose mapping panics is returned
+/// as an error, so the caller is never left waiting for it.
+///
+/// Properties:
+///
+/// * `input`: The iterator whose items are mapped.
+/// * `jobs`: The channel the items are sent to the workers through, which is closed
+/// once the input is over.
+/// * `results`: The channel the mapped items are received from, tagged with their
+/// position within the input.
+/// * `workers`: The handles of the worker threads.
+/// * `reorder`: The `ReorderBuffer` restoring the order of the input, if it has to
+/// be preserved.
+/// * `ready`: The items mapped that are yet to be returned.
+/// * `in_flight`: The number of items sent to the workers, but not received yet.
+/// * `sent`: The number of items sent to the workers so far.
+/// * `capacity`: The maximum number of items in flight.
+pub struct Pipeline<I, T, U> {
+    input: I,
+    jobs: Option<SyncSender<(u64, T)>>,
+    results: Receiver<(u64, Result<U, String>)>,
+    workers: Vec<JoinHandle<()>>,
+    reorder: Option<ReorderBuffer<Result<U, String>>>,
+    ready: VecDeque<Result<U, String>>,
+    in_flight: usize,
+    sent: u64,
+    capacity: usize,
+}
+
+impl<I, T, U> Pipeline<I, T, U>
+where
+    I: Iterator<Item = T>,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    /// The function spawns the workers mapping the items of the given iterator.
+    ///
+    /// Arguments:
+    ///
+    /// * `input`: The iterator whose items are mapped.
+    /// * `threads`: The number of worker threads.
+    /// * `preserve_order`: Whether the items are returned in the order of the input,
+    /// or as soon as those are ready.
+    /// * `map`: The function mapping each item, which is shared by the workers.
+    ///
+    /// Returns:
+    ///
+    /// The `Pipeline`, which is an iterator over the mapped items.
+    pub fn new<F>(input: I, threads: NonZeroUsize, preserve_order: bool, map: F) -> Self
+    where
+        F: Fn(T) -> U + Send + Sync + 'static,
+    {
+        let capacity = threads.get() * IN_FLIGHT_PER_THREAD;
+        let (jobs, receiver) = sync_channel::<(u64, T)>(capacity);
+        let (sender, results) = channel();
+
+        // Workers take turns to receive the next item, so the one that is idle gets it :D
+        let receiver = Arc::new(Mutex::new(receiver));
+        let map = Arc::new(map);
+        let workers = (0..threads.get())
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let sender = sender.clone();
+                let map = Arc::clone(&map);
+                thread::spawn(move || loop {
+                    let job = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return, // another worker has panicked :(
+                    };
+                    match job {
+                        Ok((sequence, item)) => {
+                            // A panic is handed back as the result of the item, so the caller does
+                            // not wait forever for it, nor for the items following it :D
+                            let mapped = catch_unwind(AssertUnwindSafe(|| map(item)))
+                                .map_err(|panic| panic_message(panic.as_ref()));
+                            if sender.send((sequence, mapped)).is_err() {
+                                return; // the pipeline has been dropped
+                            }
+                        }
+                        Err(_) => return, // the input is over
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            input,
+            jobs: Some(jobs),
+            results,
+            workers,
+            reorder: preserve_order.then(ReorderBuffer::new),
+            ready: VecDeque::new(),
+            in_flight: 0,
+            sent: 0,
+            capacity,
+        }
+    }
+}
+
+impl<I, T, U> Iterator for Pipeline<I, T, U>
+where
+    I: Iterator<Item = T>,
+{
+    type Item = Result<U, String>;
+
+    fn next(&mut self) -> Option<Result<U, String>> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+
+            // The workers are handed as many items as those fit, so they are kept busy while the
+            // caller is busy with the items returned. Once the input is over, the channel is
+            // closed, so the workers finish as soon as they are done with the items left
+            while self.in_flight < self.capacity {
+                let item = match self.jobs.as_ref().and_then(|_| self.input.next()) {
+                    Some(item) => item,
+                    None => {
+                        self.jobs = None;
+                        break;
+                    }
+                };
+                let sent = self
+                    .jobs
+                    .as_ref()
+                    .is_some_and(|jobs| jobs.send((self.sent, item)).is_ok());
+                assert!(sent, "Every worker of the pipeline has panicked");
+                self.sent += 1;
+                self.in_flight += 1;
+            }
+
+            if self.in_flight == 0 {
+                return None;
+            }
+
+            let (sequence, item) = self
+                .results
+                .recv()
+                .expect("Every worker of the pipeline has panicked");
+            self.in_flight -= 1;
+            match self.reorder.as_mut() {
+                Some(reorder) => self.ready.extend(reorder.push(sequence, item)),
+                None => self.ready.push_back(item),
+            }
+        }
+    }
+}
+
+/// The function returns the message a worker has panicked with, which is usually
+/// either a `&str` or a `String`.
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    let message = match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+        (Some(message), _) => message,
+        (_, Some(message)) => message.as_str(),
+        _ => "unknown cause",
+    };
+    format!("A worker of the pipeline has panicked: {}", message)
+}
+
+impl<I, T, U> Drop for Pipeline<I, T, U> {
+    fn drop(&mut self) {
+        // Closing the channel lets the workers finish, so those can be joined :D
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_the_panics_of_the_workers_as_errors() {
+        let threads = NonZeroUsize::new(4).unwrap();
+        let mapped = Pipeline::new(0..100, threads, true, |item: u32| match item {
+            42 => panic!("no answers allowed"),
+            _ => item * 2,
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(mapped.len(), 100);
+        for (item, mapped) in mapped.into_iter().enumerate() {
+            match item {
+                42 => assert!(mapped.unwrap_err().contains("no answers allowed")),
+                _ => assert_eq!(mapped, Ok(item as u32 * 2)),
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+use duckdb::{Connection, Error};
+
+/// The `Pragma` struct declares a setting of DuckDB applied to the connection
+/// right after it is opened, such as `memory_limit=8GB` or `threads=4`, so those
+/// left at their defaults are not the ones exhausting the resources of the machine
+/// during large loads.
+///
+/// Properties:
+///
+/// * `key`: The name of the setting, such as `memory_limit`.
+/// * `value`: The value of the setting, as given by the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pragma {
+    pub key: String,
+    pub value: String,
+}
+
+impl Pragma {
+    /// The function creates a setting out of its name and value.
+    pub fn new(key: &str, value: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    /// This function returns the SQL statement applying the setting. The value is
+    /// always quoted, as DuckDB casts it to the type of the setting anyway, so paths
+    /// and sizes need no special treatment.
+    pub fn sql(&self) -> String {
+        format!(
+            "PRAGMA {} = '{}';",
+            self.key,
+            self.value.replace('\'', "''")
+        )
+    }
+}
+
+/// This code parses a setting out of its name and value, separated by an equals
+/// sign, such as `enable_object_cache=true`. Only letters, digits and underscores
+/// are allowed in the name, as it is written into the statement as it is.
+impl FromStr for Pragma {
+    type Err = String;
+
+    fn from_str(pragma: &str) -> Result<Self, Self::Err> {
+        let (key, value) = match pragma.trim().split_once('=') {
+            Some(pair) => pair,
+            None => return Err(format!("Not valid pragma, expected KEY=VALUE: {}", pragma)),
+        };
+
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("Not valid pragma name: {}", key));
+        }
+
+        Ok(Self::new(key, value.trim()))
+    }
+}
+
+/// This function applies the given settings to a connection, in order, so the
+/// latter ones override the former whenever the same setting is repeated.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `pragmas`: A slice of the settings to be applied.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if every
+/// setting is applied, or an `Error` object if DuckDB rejects any of them, such as
+/// those that are unknown or whose value is not valid.
+pub fn apply(connection: &Connection, pragmas: &[Pragma]) -> Result<(), Error> {
+    for pragma in pragmas {
+        connection.execute_batch(&pragma.sql())?;
+    }
+    Ok(())
+}
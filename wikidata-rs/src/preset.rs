@@ -0,0 +1,74 @@
+use std::fmt::Display;
+
+use strum_macros::EnumString;
+
+/// The `Preset` enum defines bundles of the options reducing the size of the
+/// database, so casual users get a small, yet useful, database without learning
+/// every one of them. Notice that labels and descriptions are always stored in
+/// English only, no matter the preset.
+///
+/// Variants:
+///
+/// * `Minimal`: Only the truthy claims are stored; that is, the best ranked ones
+/// of each property, and the external identifiers, the qualifiers and the
+/// references are dropped, as those take most of the database while being of
+/// little use for querying the graph.
+/// * `GraphOnly`: Only the truthy claims between entities are stored; that is, the
+/// `vertex`, `property` and `edge` tables are the only ones populated.
+/// * `Full`: Everything is stored, including the qualifiers and the references of
+/// the claims.
+#[derive(Clone, Debug, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Preset {
+    Minimal,
+    GraphOnly,
+    Full,
+}
+
+/// This code displays a preset as it is given in the command line, such as
+/// `graph-only`, so the preset a database was built with can be recorded.
+impl Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Preset::Minimal => write!(f, "minimal"),
+            Preset::GraphOnly => write!(f, "graph-only"),
+            Preset::Full => write!(f, "full"),
+        }
+    }
+}
+
+impl Preset {
+    /// The function returns the tables populated under the preset, or an empty
+    /// vector in case every table is.
+    pub fn tables(&self) -> Vec<String> {
+        match self {
+            Preset::GraphOnly => vec!["vertex", "property", "edge"],
+            Preset::Minimal | Preset::Full => vec![],
+        }
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+    }
+
+    /// The function returns whether only the truthy claims are stored under the
+    /// preset.
+    pub fn truthy_only(&self) -> bool {
+        matches!(self, Preset::Minimal | Preset::GraphOnly)
+    }
+
+    /// The function returns whether the external identifiers are dropped under the
+    /// preset.
+    pub fn drops_external_ids(&self) -> bool {
+        matches!(self, Preset::Minimal | Preset::GraphOnly)
+    }
+
+    /// The function returns whether the qualifiers are stored under the preset.
+    pub fn qualifiers(&self) -> bool {
+        !matches!(self, Preset::Minimal | Preset::GraphOnly)
+    }
+
+    /// The function returns whether the references are stored under the preset.
+    pub fn references(&self) -> bool {
+        !matches!(self, Preset::Minimal | Preset::GraphOnly)
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::io::{stdout, Read, Result, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The minimum time between two renders of the progress, so the terminal is not
+/// flooded, no matter how fast the entities are processed.
+const REFRESH: Duration = Duration::from_millis(250);
+
+/// The `Metered` struct wraps a reader so the bytes read from it are counted in a
+/// counter shared with the `Progress`. Readers are wrapped before unpacking them,
+/// so the bytes counted are the ones actually read from the storage, which are the
+/// ones the size of the inputs is known for.
+///
+/// Properties:
+///
+/// * `inner`: The reader being metered.
+/// * `read`: The number of bytes read so far, shared by every input.
+pub struct Metered<R> {
+    inner: R,
+    read: Arc<AtomicU64>,
+}
+
+impl<R: Read> Metered<R> {
+    pub fn new(inner: R, read: Arc<AtomicU64>) -> Self {
+        Self { inner, read }
+    }
+}
+
+impl<R: Read> Read for Metered<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// The `Progress` struct reports how the load is going: the number of entities
+/// processed, the entities and megabytes read per second, the estimated time left
+/// whenever the size of the inputs is known, and the rows appended to each table.
+/// It is rendered in two lines, which are overwritten on each render.
+///
+/// Properties:
+///
+/// * `start`: The instant the load started.
+/// * `rendered`: The instant of the last render, if any.
+/// * `read`: The number of bytes read from the inputs so far.
+/// * `total`: The size of the inputs, in bytes, in case it is known.
+/// * `quiet`: Whether nothing is rendered at all, as in batch jobs.
+pub struct Progress {
+    start: Instant,
+    rendered: Option<Instant>,
+    read: Arc<AtomicU64>,
+    total: Option<u64>,
+    quiet: bool,
+}
+
+impl Progress {
+    /// The function creates the progress of a load starting right now.
+    ///
+    /// Arguments:
+    ///
+    /// * `read`: The counter of the bytes read, which the readers of the inputs are
+    /// given through `Metered`.
+    /// * `total`: The size of the inputs, in bytes, or `None` in case it is not
+    /// known, as it happens with the standard input or the remote objects.
+    /// * `quiet`: Whether nothing is rendered at all.
+    ///
+    /// Returns:
+    ///
+    /// The `Progress` of the load.
+    pub fn new(read: Arc<AtomicU64>, total: Option<u64>, quiet: bool) -> Self {
+        Self {
+            start: Instant::now(),
+            rendered: None,
+            read,
+            total,
+            quiet,
+        }
+    }
+
+    /// The function renders the progress, unless it has just been rendered.
+    ///
+    /// Arguments:
+    ///
+    /// * `entities`: The number of entities processed so far.
+    /// * `rows`: The number of rows appended so far to each of the tables.
+    pub fn tick(&mut self, entities: usize, rows: &HashMap<&str, u64>) {
+        if self
+            .rendered
+            .is_none_or(|rendered| rendered.elapsed() >= REFRESH)
+        {
+            self.render(entities, rows);
+        }
+    }
+
+    /// The function renders the progress for the last time, leaving it on screen,
+    /// so whatever is printed afterwards goes below it.
+    ///
+    /// Arguments:
+    ///
+    /// * `entities`: The number of entities processed.
+    /// * `rows`: The number of rows appended to each of the tables.
+    pub fn finish(&mut self, entities: usize, rows: &HashMap<&str, u64>) {
+        if self.quiet {
+            return;
+        }
+        self.render(entities, rows);
+        println!("\n");
+    }
+
+    fn render(&mut self, entities: usize, rows: &HashMap<&str, u64>) {
+        if self.quiet {
+            return;
+        }
+        self.rendered = Some(Instant::now());
+
+        let elapsed = self.start.elapsed();
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        let read = self.read.load(Ordering::Relaxed);
+
+        let mut status = format!(
+            "{} entities processed in {} | {:.0} entities/s | {:.1} MB/s",
+            entities,
+            format_duration(elapsed),
+            entities as f64 / seconds,
+            read as f64 / 1_000_000.0 / seconds
+        );
+        // The time left is estimated out of the bytes read, as the number of entities of the
+        // inputs is not known until those are over :D
+        if let Some(total) = self.total.filter(|total| *total > 0) {
+            let done = (read as f64 / total as f64).min(1.0);
+            status.push_str(&format!(" | {:.1}%", done * 100.0));
+            if done > 0.0 {
+                let left = Duration::from_secs_f64(seconds * (1.0 - done) / done);
+                status.push_str(&format!(" | ETA {}", format_duration(left)));
+            }
+        }
+
+        let mut tables = rows
+            .iter()
+            .filter(|(_, rows)| **rows > 0)
+            .collect::<Vec<_>>();
+        tables.sort_by(|(a, a_rows), (b, b_rows)| b_rows.cmp(a_rows).then(a.cmp(b)));
+        let tables = tables
+            .into_iter()
+            .map(|(table, rows)| format!("{} {}", table, rows))
+            .collect::<Vec<_>>()
+            .join(" | ");
+
+        // Both lines are cleared before being written, and the cursor is moved back to the first
+        // one, so the next render overwrites them :D
+        print!("\x1B[2K\r{}\n\x1B[2K  {}\x1B[1A\r", status, tables);
+        let _ = stdout().flush();
+    }
+}
+
+/// The function formats a duration as hours, minutes and seconds, such as
+/// `1h 02m 03s`, leaving the hours out when there are none.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds / 3600 {
+        0 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        hours => format!("{}h {:02}m {:02}s", hours, seconds / 60 % 60, seconds % 60),
+    }
+}
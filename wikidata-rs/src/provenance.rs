@@ -0,0 +1,36 @@
+use duckdb::{Connection, Error};
+
+/// The name of the table recording where each entity stored comes from.
+pub const PROVENANCE_TABLE: &str = "provenance";
+
+/// This function returns the SQL statement creating the `provenance` table, with
+/// the input file, the number of the line and the byte offset each entity stored
+/// comes from. The offset is the one within the decompressed input, where the line
+/// starts, so shard-specific corruption can be debugged.
+pub fn create_table_sql() -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (\
+            id UBIGINT NOT NULL, \
+            source TEXT NOT NULL, \
+            line UBIGINT NOT NULL, \
+            byte_offset UBIGINT NOT NULL\
+        );",
+        PROVENANCE_TABLE
+    )
+}
+
+/// This function creates the `provenance` table in the database, in case it does
+/// not exist yet.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// table creation is successful, or an `Error` object if there is an error during
+/// the execution of the SQL statement.
+pub fn create_table(connection: &Connection) -> Result<(), Error> {
+    connection.execute_batch(&create_table_sql())
+}
@@ -0,0 +1,99 @@
+use serde_json::{json, Map, Value};
+use wikidata::{Entity, Pid};
+
+/// The `RawClaim` struct contains a claim whose value could not be converted, such
+/// as those of datatypes the parser is not aware of yet, or oddly encoded ones. The
+/// claim is kept as it is found in the dump, so no information is silently lost.
+///
+/// Properties:
+///
+/// * `property`: The `Pid` of the property of the claim.
+/// * `json`: The JSON of the statement, as found in the dump.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawClaim {
+    pub property: Pid,
+    pub json: String,
+}
+
+/// The function removes the claims that cannot be converted from the JSON value of
+/// an entity that could not be parsed, so the rest of the entity can be parsed and
+/// stored as usual. Each statement is checked on its own by parsing an entity that
+/// only contains such statement; hence, this is only meant to be called once the
+/// whole entity has failed to be parsed.
+///
+/// Arguments:
+///
+/// * `entity`: A mutable reference to the JSON value of the entity.
+///
+/// Returns:
+///
+/// A vector with the `RawClaim`s removed from the entity. It is empty in case the
+/// entity cannot be parsed because of anything other than its claims.
+pub fn salvage(entity: &mut Value) -> Vec<RawClaim> {
+    let mut raw_claims = Vec::new();
+
+    let (entity_type, id) = match (entity.get("type"), entity.get("id")) {
+        (Some(entity_type), Some(id)) => (entity_type.clone(), id.clone()),
+        _ => return raw_claims,
+    };
+
+    // In case an entity holding no claims cannot be parsed either, the failure is not caused by the
+    // claims, so there is nothing to be salvaged :(
+    let baseline = json!({
+        "type": entity_type,
+        "id": id,
+        "labels": {},
+        "descriptions": {},
+        "aliases": {},
+        "sitelinks": {},
+        "claims": {},
+    });
+    if Entity::from_json(baseline).is_err() {
+        return raw_claims;
+    }
+
+    let claims = match entity.get_mut("claims").and_then(Value::as_object_mut) {
+        Some(claims) => claims,
+        None => return raw_claims,
+    };
+
+    for (property, statements) in claims.iter_mut() {
+        let property_id = match property.strip_prefix('P').map(str::parse::<u64>) {
+            Some(Ok(id)) => Pid(id),
+            _ => continue, // the claim cannot be related to any property :(
+        };
+
+        let statements = match statements.as_array_mut() {
+            Some(statements) => statements,
+            None => continue,
+        };
+
+        // The statements that are not parsed on their own are the ones to be removed, as those are
+        // the ones causing the whole entity to fail. The rest of them are left untouched :D
+        statements.retain(|statement| {
+            let mut probe_claims = Map::new();
+            probe_claims.insert(property.clone(), json!([statement]));
+            let probe = json!({
+                "type": entity_type,
+                "id": id,
+                "labels": {},
+                "descriptions": {},
+                "aliases": {},
+                "sitelinks": {},
+                "claims": probe_claims,
+            });
+
+            if Entity::from_json(probe).is_ok() {
+                true
+            } else {
+                raw_claims.push(RawClaim {
+                    property: property_id,
+                    json: statement.to_string(),
+                });
+                false
+            }
+        });
+    }
+
+    raw_claims
+}
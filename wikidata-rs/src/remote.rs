@@ -0,0 +1,221 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use bytes::Bytes;
+use futures::stream::{BoxStream, StreamExt};
+#[cfg(feature = "aws")]
+use object_store::aws::AmazonS3Builder;
+#[cfg(feature = "azure")]
+use object_store::azure::MicrosoftAzureBuilder;
+#[cfg(feature = "gcp")]
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::runtime::{Builder, Runtime};
+
+/// The schemes of the URLs pointing to an object store. Notice that S3, Google
+/// Cloud Storage and Azure Blob Storage require the `aws`, `gcp` and `azure`
+/// features, respectively.
+const SCHEMES: [&str; 3] = ["s3://", "gs://", "az://"];
+
+/// The function returns whether a path given by the user points to an object
+/// store, such as `s3://bucket/key`, instead of to the local file system.
+pub fn is_remote(path: &str) -> bool {
+    SCHEMES.iter().any(|scheme| path.starts_with(scheme))
+}
+
+/// The function connects to the object store a URL points to. The credentials are
+/// resolved in the standard manner of each of the providers; that is, out of their
+/// environment variables, such as `AWS_*`, `GOOGLE_*` or `AZURE_*`, or out of the
+/// metadata of the instance the tool is run in.
+///
+/// Arguments:
+///
+/// * `url`: The URL of the object, such as `s3://bucket/latest-all.json.bz2`.
+///
+/// Returns:
+///
+/// a `Result` with the object store and the path to the object within it, or an
+/// `Error` if the URL is not valid or its provider has not been enabled.
+// Builds with no provider enabled reject every URL, so the rest of the function is never reached
+#[cfg_attr(
+    not(any(feature = "aws", feature = "gcp", feature = "azure")),
+    allow(unreachable_code, unused_variables)
+)]
+fn connect(url: &str) -> Result<(Box<dyn ObjectStore>, Path)> {
+    let (scheme, key) = match url
+        .split_once("://")
+        .map(|(scheme, rest)| (scheme, rest.split_once('/')))
+    {
+        Some((scheme, Some((_, key)))) if !key.is_empty() => (scheme, key),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Not valid object store URL",
+            ))
+        }
+    };
+
+    let store: Box<dyn ObjectStore> = match scheme {
+        #[cfg(feature = "aws")]
+        "s3" => Box::new(
+            AmazonS3Builder::from_env()
+                .with_url(url)
+                .build()
+                .map_err(remote_error)?,
+        ),
+        #[cfg(feature = "gcp")]
+        "gs" => Box::new(
+            GoogleCloudStorageBuilder::from_env()
+                .with_url(url)
+                .build()
+                .map_err(remote_error)?,
+        ),
+        #[cfg(feature = "azure")]
+        "az" => Box::new(
+            MicrosoftAzureBuilder::from_env()
+                .with_url(url)
+                .build()
+                .map_err(remote_error)?,
+        ),
+        scheme => {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("{}:// URLs are not supported by this build", scheme),
+            ))
+        }
+    };
+
+    Ok((store, Path::from(key)))
+}
+
+/// The function creates the runtime the requests to an object store are run in.
+/// A single thread is enough, as the requests are issued one at a time.
+fn runtime() -> Result<Runtime> {
+    Builder::new_current_thread().enable_all().build()
+}
+
+/// The `RemoteReader` struct streams an object stored in an object store, so dumps
+/// staged in S3 can be processed without downloading them to the local disk first.
+/// Object stores are only accessed asynchronously, so the reader owns a runtime
+/// that blocks on each of the chunks of the object as those are requested.
+///
+/// Properties:
+///
+/// * `runtime`: The runtime the requests to the object store are run in.
+/// * `stream`: The stream over the chunks of the object.
+/// * `chunk`: What is left of the last chunk received.
+pub struct RemoteReader {
+    runtime: Runtime,
+    stream: BoxStream<'static, object_store::Result<Bytes>>,
+    chunk: Bytes,
+}
+
+impl RemoteReader {
+    /// The function opens an object stored in an object store.
+    ///
+    /// Arguments:
+    ///
+    /// * `url`: The URL of the object, such as `s3://bucket/latest-all.json.bz2`.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `RemoteReader`, or an `Error` if the object could not be
+    /// found or the credentials are not valid.
+    pub fn open(url: &str) -> Result<Self> {
+        let (store, path) = connect(url)?;
+        let runtime = runtime()?;
+        let stream = runtime
+            .block_on(store.get(&path))
+            .map_err(remote_error)?
+            .into_stream();
+
+        Ok(Self {
+            runtime,
+            stream,
+            chunk: Bytes::new(),
+        })
+    }
+}
+
+impl Read for RemoteReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        // Chunks are requested one at a time, and only once the previous one has been consumed.
+        // Hence, the whole object is never held in memory, no matter its size :D
+        while self.chunk.is_empty() {
+            match self.runtime.block_on(self.stream.next()) {
+                Some(chunk) => self.chunk = chunk.map_err(remote_error)?,
+                None => return Ok(0),
+            }
+        }
+
+        let read = buf.len().min(self.chunk.len());
+        buf[..read].copy_from_slice(&self.chunk.split_to(read));
+        Ok(read)
+    }
+}
+
+/// The `RemoteWriter` struct uploads an object to an object store as it is being
+/// written, in several parts, so exports do not have to be staged on the local disk
+/// first. Object stores do not allow appending to an object, so the upload is only
+/// completed, and the object created, once the writer is flushed. Hence, it cannot
+/// be written to afterwards.
+///
+/// Properties:
+///
+/// * `runtime`: The runtime the requests to the object store are run in.
+/// * `upload`: The multipart upload, which is `None` once it has been completed.
+pub struct RemoteWriter {
+    runtime: Runtime,
+    upload: Option<Box<dyn AsyncWrite + Unpin + Send>>,
+}
+
+impl RemoteWriter {
+    /// The function starts the upload of an object to an object store.
+    ///
+    /// Arguments:
+    ///
+    /// * `url`: The URL of the object, such as `s3://bucket/export.json`.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `RemoteWriter`, or an `Error` if the upload could not be
+    /// started.
+    pub fn create(url: &str) -> Result<Self> {
+        let (store, path) = connect(url)?;
+        let runtime = runtime()?;
+        let (_, upload) = runtime
+            .block_on(store.put_multipart(&path))
+            .map_err(remote_error)?;
+
+        Ok(Self {
+            runtime,
+            upload: Some(upload),
+        })
+    }
+}
+
+impl Write for RemoteWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match self.upload.as_mut() {
+            Some(upload) => self.runtime.block_on(upload.write(buf)),
+            None => Err(Error::new(
+                ErrorKind::BrokenPipe,
+                "The upload is already completed",
+            )),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self.upload.take() {
+            Some(mut upload) => self.runtime.block_on(upload.shutdown()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The function converts the errors of the object store into I/O errors, so those
+/// are reported in the same manner as the ones of the local file system.
+fn remote_error(error: object_store::Error) -> Error {
+    Error::other(error)
+}
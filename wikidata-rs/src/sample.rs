@@ -0,0 +1,214 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::str::FromStr;
+
+use wikidata::Qid;
+
+use crate::id::{parse_item, Id, IdEncoding};
+
+/// The `Sampler` struct decides which entities are kept when only a sample of the
+/// dump is to be stored. Each entity is kept with the given probability, and the
+/// decision is taken out of a hash of its identifier and the seed. Hence, samples
+/// are statistically representative of the whole dump, unlike the first entities
+/// of the dump, which are biased toward the early identifiers, and reproducible:
+/// the same seed always keeps the same entities, no matter the order of the dump.
+///
+/// Properties:
+///
+/// * `threshold`: The hashes below this value are the ones of the entities kept.
+/// * `seed`: The seed the hashes are computed with.
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    threshold: u64,
+    seed: u64,
+}
+
+impl Sampler {
+    /// The function creates a sampler keeping each entity with the given probability.
+    ///
+    /// Arguments:
+    ///
+    /// * `probability`: The probability of keeping an entity, between 0 and 1.
+    /// * `seed`: The seed the decisions are taken with.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Sampler`, or a `String` with an error message in case the
+    /// probability is not between 0 and 1.
+    pub fn new(probability: f64, seed: u64) -> Result<Self, String> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(format!("Not valid probability: {}", probability));
+        }
+
+        Ok(Self {
+            threshold: (probability * u64::MAX as f64) as u64,
+            seed,
+        })
+    }
+
+    /// The function returns whether an entity is kept in the sample or not.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The numeric identifier of the entity.
+    pub fn keep(&self, id: u64) -> bool {
+        self.threshold == u64::MAX || splitmix64(splitmix64(self.seed) ^ id) < self.threshold
+    }
+}
+
+/// The function computes the SplitMix64 hash of a value, which spreads consecutive
+/// identifiers uniformly over the whole range of `u64`.
+pub(crate) fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// The `ClassQuota` struct declares how many instances of a class are to be kept
+/// when sampling by class, such as `Q5=10000`.
+///
+/// Properties:
+///
+/// * `class`: The identifier of the class.
+/// * `limit`: The maximum number of instances of the class that are kept.
+#[derive(Clone, Debug)]
+pub struct ClassQuota {
+    pub class: Qid,
+    pub limit: u64,
+}
+
+/// This code parses a quota out of the class and the number of its instances to
+/// keep, separated by an equals sign, such as `Q5=10000`. An error message is
+/// returned in case either of them is not valid.
+impl FromStr for ClassQuota {
+    type Err = String;
+
+    fn from_str(quota: &str) -> Result<Self, Self::Err> {
+        let (class, limit) = match quota.trim().split_once('=') {
+            Some(pair) => pair,
+            None => return Err(format!("Not valid quota, expected CLASS=N: {}", quota)),
+        };
+
+        let class = match parse_item(class) {
+            Ok(class) => class,
+            Err(_) => return Err(format!("Not valid class: {}", class)),
+        };
+
+        match limit.trim().parse::<u64>() {
+            Ok(limit) => Ok(Self { class, limit }),
+            Err(_) => Err(format!("Not valid number of instances: {}", limit)),
+        }
+    }
+}
+
+/// The `ClassSampler` struct decides which entities are kept when sampling by
+/// class; that is, when up to a number of instances (P31) of each class are to be
+/// kept, so the sample is balanced among the classes instead of following their
+/// distribution in the dump. The instances kept of each class are the ones with
+/// the smallest hashes of their identifier and the seed, as with the `Sampler`.
+/// Hence, those are spread over the whole dump, and the same seed always keeps the
+/// same entities, no matter the order of the dump. As the dump is read just once,
+/// the instances pushed out of a class by others with a smaller hash may have been
+/// stored already, so those are handed back to be deleted.
+///
+/// Properties:
+///
+/// * `limits`: The maximum number of instances kept of each of the classes,
+/// indexed by the numeric identifier of the class.
+/// * `kept`: The hashes and identifiers of the instances kept of each class so
+/// far, whose largest hash is the first one to be pushed out.
+/// * `classes`: The number of classes each of the entities kept is kept for.
+/// * `evicted`: The entities no class keeps anymore, which are to be deleted.
+/// * `seed`: The seed the hashes are computed with.
+#[derive(Clone, Debug)]
+pub struct ClassSampler {
+    limits: HashMap<u64, u64>,
+    kept: HashMap<u64, BinaryHeap<(u64, u64)>>,
+    classes: HashMap<u64, u32>,
+    evicted: Vec<u64>,
+    seed: u64,
+}
+
+impl ClassSampler {
+    /// The function creates a sampler keeping up to the given number of instances of
+    /// each of the classes.
+    ///
+    /// Arguments:
+    ///
+    /// * `quotas`: A slice of the `ClassQuota`s, one per class.
+    /// * `seed`: The seed the instances kept are chosen with.
+    /// * `encoding`: The `IdEncoding` the classes are stored with.
+    ///
+    /// Returns:
+    ///
+    /// The `ClassSampler`.
+    pub fn new(quotas: &[ClassQuota], seed: u64, encoding: &IdEncoding) -> Self {
+        // Classes out of the range of the items cannot be matched by any entity stored, so those are
+        // just left out :D
+        Self {
+            limits: quotas
+                .iter()
+                .filter_map(|quota| Some((encoding.encode(Id::Qid(quota.class)).ok()?, quota.limit)))
+                .collect(),
+            kept: HashMap::new(),
+            classes: HashMap::new(),
+            evicted: Vec::new(),
+            seed: splitmix64(seed),
+        }
+    }
+
+    /// The function returns whether an entity is kept in the sample or not, which is
+    /// the case whenever its hash is among the smallest ones of the instances of any
+    /// of its classes found so far. In that case, the entity takes the place of the
+    /// one with the largest hash of every full class it is kept for.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The numeric identifier of the entity.
+    /// * `classes`: The numeric identifiers of the classes the entity is an instance
+    /// of.
+    pub fn keep(&mut self, id: u64, classes: &[u64]) -> bool {
+        // An entity found again, such as in several inputs, is left to the deduplicator :D
+        if self.classes.contains_key(&id) {
+            return true;
+        }
+
+        let hash = splitmix64(self.seed ^ id);
+        let mut kept = 0;
+        for class in classes {
+            let limit = match self.limits.get(class) {
+                Some(limit) if *limit > 0 => *limit,
+                _ => continue,
+            };
+            let instances = self.kept.entry(*class).or_default();
+            if instances.len() as u64 >= limit {
+                match instances.peek() {
+                    Some((largest, _)) if hash < *largest => (),
+                    _ => continue,
+                }
+                if let Some((_, pushed_out)) = instances.pop() {
+                    if let Some(count) = self.classes.get_mut(&pushed_out) {
+                        *count -= 1;
+                        if *count == 0 {
+                            self.classes.remove(&pushed_out);
+                            self.evicted.push(pushed_out);
+                        }
+                    }
+                }
+            }
+            instances.push((hash, id));
+            kept += 1;
+        }
+
+        if kept > 0 {
+            self.classes.insert(id, kept);
+        }
+        kept > 0
+    }
+
+    /// The function returns the entities that have been pushed out of the sample since
+    /// the last time it was called, so their rows are deleted.
+    pub fn take_evicted(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.evicted)
+    }
+}
@@ -0,0 +1,125 @@
+use duckdb::{params, Connection, Error};
+
+use crate::value::Table;
+
+pub use crate::metadata::SCHEMA_VERSION;
+
+/// The `ColumnSchema` struct describes a column of a table of the database.
+///
+/// Properties:
+///
+/// * `name`: The name of the column, such as `src_id`.
+/// * `sql_type`: The DuckDB type of the column, such as `UBIGINT`.
+/// * `nullable`: Whether the column may hold `NULL` values or not.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnSchema {
+    pub name: &'static str,
+    pub sql_type: &'static str,
+    pub nullable: bool,
+}
+
+/// The `TableSchema` struct describes a table of the database, as created by the
+/// load; that is, without the extra columns declared by the user.
+///
+/// Properties:
+///
+/// * `name`: The name of the table, such as `edge` or `qualifier_time`.
+/// * `columns`: The columns of the table, in the order those are created.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableSchema {
+    pub name: &'static str,
+    pub columns: Vec<ColumnSchema>,
+}
+
+impl TableSchema {
+    /// The function returns the column of the table with the given name, if any.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the column, such as `dst_id`.
+    pub fn column(&self, name: &str) -> Option<&ColumnSchema> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+}
+
+/// The function returns the schema of every table the load creates, so external
+/// tools generating queries against the databases do not depend on the internals
+/// of the crate. The names returned are stable, as described in `SCHEMA_VERSION`.
+///
+/// Returns:
+///
+/// A vector with the `TableSchema` of each of the tables, in the order those are
+/// created. Notice that several kinds of values share the `edge` tables, which are
+/// only returned once, and that the `label` and `description` of the `vertex` table
+/// are left out of the databases built with `--no-text`.
+pub fn tables() -> Vec<TableSchema> {
+    let mut tables: Vec<TableSchema> = Vec::new();
+
+    for table in Table::iterator() {
+        for scope in table.scopes() {
+            let (name, columns) = table.table_definition(scope);
+            if tables.iter().any(|table| table.name == name) {
+                continue; // several values share the table :D
+            }
+            tables.push(TableSchema {
+                name,
+                columns: columns
+                    .into_iter()
+                    .map(
+                        |(name, column_type)| match column_type.strip_suffix(" NOT NULL") {
+                            Some(sql_type) => ColumnSchema {
+                                name,
+                                sql_type,
+                                nullable: false,
+                            },
+                            None => ColumnSchema {
+                                name,
+                                sql_type: column_type,
+                                nullable: true,
+                            },
+                        },
+                    )
+                    .collect(),
+            });
+        }
+    }
+
+    tables
+}
+
+/// The function returns the schema of a table the load creates.
+///
+/// Arguments:
+///
+/// * `name`: The name of the table, such as `vertex` or `qualifier_edge`.
+///
+/// Returns:
+///
+/// An `Option` with the `TableSchema`, or `None` if there is no such table.
+pub fn table(name: &str) -> Option<TableSchema> {
+    tables().into_iter().find(|table| table.name == name)
+}
+
+/// The function returns whether a table of a database has the given column, as
+/// those built by older versions of the tool, or with some of the options, lack
+/// some of the columns returned by `tables`.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+/// * `table`: The name of the table, such as `vertex`.
+/// * `column`: The name of the column, such as `label`.
+///
+/// Returns:
+///
+/// a `Result` with whether the column exists, or an `Error` object if the schema
+/// of the database cannot be read.
+pub fn has_column(connection: &Connection, table: &str, column: &str) -> Result<bool, Error> {
+    let columns: i64 = connection.query_row(
+        "SELECT count(*) FROM information_schema.columns \
+         WHERE table_name = ? AND column_name = ?",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    Ok(columns > 0)
+}
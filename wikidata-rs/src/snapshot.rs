@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use duckdb::Connection;
+use strum_macros::EnumString;
+
+/// The `SnapshotFormat` enum defines the formats the snapshots of the database are
+/// taken in. `Duckdb` stands for a copy of the database file itself, while
+/// `Parquet` stands for a directory with a Parquet file per table, which can be
+/// read by any other tool, such as Spark or Polars.
+#[derive(Clone, Debug, Default, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum SnapshotFormat {
+    #[default]
+    Duckdb,
+    Parquet,
+}
+
+/// The `Snapshotter` struct takes consistent copies of a database while it is being
+/// loaded, every now and then. DuckDB files being written cannot be opened by other
+/// processes, so analysts can start exploring the snapshot long before the load
+/// finishes. Snapshots are only taken right after a checkpoint, when the database
+/// file holds every row committed so far, and replace the previous one atomically.
+///
+/// Properties:
+///
+/// * `interval`: The time between two snapshots.
+/// * `format`: The `SnapshotFormat` of the snapshots.
+/// * `database`: The path to the database being loaded.
+/// * `last_time`: The instant of the last snapshot, or of the start of the load.
+pub struct Snapshotter {
+    interval: Duration,
+    format: SnapshotFormat,
+    database: PathBuf,
+    last_time: Instant,
+}
+
+impl Snapshotter {
+    /// The function creates the snapshotter of a load that has just started.
+    ///
+    /// Arguments:
+    ///
+    /// * `interval`: The time between two snapshots.
+    /// * `format`: The `SnapshotFormat` of the snapshots.
+    /// * `database`: The path to the database being loaded.
+    ///
+    /// Returns:
+    ///
+    /// The `Snapshotter`.
+    pub fn new(interval: Duration, format: SnapshotFormat, database: &Path) -> Self {
+        Self {
+            interval,
+            format,
+            database: database.to_path_buf(),
+            last_time: Instant::now(),
+        }
+    }
+
+    /// The function returns the path the snapshots are written to; that is, the one
+    /// of the database followed by `.snapshot`, which is a directory for Parquet.
+    pub fn path(&self) -> PathBuf {
+        let mut path = self.database.clone().into_os_string();
+        path.push(".snapshot");
+        PathBuf::from(path)
+    }
+
+    /// The function returns whether a snapshot has to be taken; that is, whether
+    /// enough time has passed since the last one.
+    pub fn is_due(&self) -> bool {
+        self.last_time.elapsed() >= self.interval
+    }
+
+    /// The function takes a snapshot of the database. It is written next to the
+    /// previous one, which is replaced once the new one is complete, so readers never
+    /// find a partial snapshot.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database, which has to
+    /// be checkpointed right before, with no transaction open.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the path of the snapshot, or a `String` with an error message
+    /// in case it could not be taken.
+    pub fn take(&mut self, connection: &Connection) -> Result<PathBuf, String> {
+        // The next snapshot is due an interval later, even if this one fails, so the load does not
+        // keep on trying after every entity :(
+        self.last_time = Instant::now();
+
+        let path = self.path();
+        let mut partial = path.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+
+        match self.format {
+            // Once checkpointed, the database file holds every row committed, and nothing else is
+            // written to it until the next batch starts, so it can be copied as it is :D
+            SnapshotFormat::Duckdb => {
+                if let Err(error) = fs::copy(&self.database, &partial) {
+                    return Err(format!("Error copying database. {}", error));
+                }
+            }
+            SnapshotFormat::Parquet => {
+                if partial.exists() {
+                    if let Err(error) = fs::remove_dir_all(&partial) {
+                        return Err(format!("Error removing partial snapshot. {}", error));
+                    }
+                }
+                if let Err(error) = connection.execute_batch(&format!(
+                    "EXPORT DATABASE '{}' (FORMAT PARQUET);",
+                    partial.display()
+                )) {
+                    return Err(format!("Error exporting database. {}", error));
+                }
+                if path.exists() {
+                    if let Err(error) = fs::remove_dir_all(&path) {
+                        return Err(format!("Error removing previous snapshot. {}", error));
+                    }
+                }
+            }
+        }
+
+        if let Err(error) = fs::rename(&partial, &path) {
+            return Err(format!("Error replacing snapshot. {}", error));
+        }
+
+        Ok(path)
+    }
+}
+
+/// The function parses a duration out of a number followed by its unit: `s` for
+/// seconds, `m` for minutes, `h` for hours and `d` for days, such as `1h` or `90m`.
+/// Numbers with no unit are taken as seconds.
+///
+/// Arguments:
+///
+/// * `duration`: A string slice with the duration.
+///
+/// Returns:
+///
+/// a `Result` with the `Duration`, or a `String` with an error message in case it
+/// is not valid, or it is zero.
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let duration = duration.trim();
+    let (number, seconds) = match duration.char_indices().last() {
+        Some((index, 's')) => (&duration[..index], 1),
+        Some((index, 'm')) => (&duration[..index], 60),
+        Some((index, 'h')) => (&duration[..index], 60 * 60),
+        Some((index, 'd')) => (&duration[..index], 24 * 60 * 60),
+        _ => (duration, 1),
+    };
+
+    match number.trim().parse::<u64>() {
+        Ok(number) if number > 0 => Ok(Duration::from_secs(number * seconds)),
+        _ => Err(format!("Not valid duration: {}", duration)),
+    }
+}
@@ -0,0 +1,326 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use duckdb::{params, Connection, Error};
+use serde_json::{json, Map, Value};
+use strum_macros::EnumString;
+use wikidata::Pid;
+
+use crate::dtype::Target;
+use crate::id::{Id, IdEncoding, IdKind};
+use crate::metadata;
+use crate::partition::{self, Partition};
+
+/// The function returns the SQL statement creating the `class_stats` table, with
+/// the number of instances of each class; that is, of entities that are an
+/// instance of (P31) it. Hence, it gives an instant overview of what the database
+/// contains, which may have been filtered or sampled, and it supports stratified
+/// sampling by class. Notice that instances of the subclasses are not counted.
+///
+/// Arguments:
+///
+/// * `encoding`: The `IdEncoding` of the database the table is created in.
+///
+/// Returns:
+///
+/// A `String` containing the `CREATE` statement.
+pub fn create_class_stats_sql(encoding: &IdEncoding) -> String {
+    // Claims with no value, or with an unknown one, point to no entity, so those are left out, as
+    // the class they point to is not a class at all :D
+    format!(
+        "CREATE OR REPLACE TABLE class_stats AS \
+         SELECT dst_id AS class_id, count(DISTINCT src_id) AS instance_count \
+         FROM edge WHERE property_id = {} AND target < {} \
+         GROUP BY dst_id ORDER BY instance_count DESC;",
+        encoding.known(Id::Pid(Pid(31))),
+        u8::from(&Target::NoValue)
+    )
+}
+
+/// This function creates the `class_stats` table, replacing it in case it
+/// already exists.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database, where the edge
+/// table is already populated.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// table creation is successful, or an `Error` object if there is an error during
+/// the execution of the SQL statement.
+pub fn create_class_stats(connection: &Connection) -> Result<(), Error> {
+    connection.execute_batch(&create_class_stats_sql(&IdEncoding::of(connection)?))
+}
+
+/// The function retrieves the names of the tables of the database, leaving the
+/// views out, in alphabetical order.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` with the names of the tables, or an `Error` if those could not be
+/// retrieved.
+pub fn table_names(connection: &Connection) -> Result<Vec<String>, Error> {
+    let mut statement = connection.prepare(
+        "SELECT table_name FROM information_schema.tables \
+         WHERE table_schema = 'main' AND table_type = 'BASE TABLE' ORDER BY table_name",
+    )?;
+    let names = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(names)
+}
+
+/// The function counts the rows of each of the tables of the database, so what it
+/// holds can be told at a glance, no matter the options it was built with.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection to the database.
+///
+/// Returns:
+///
+/// a `Result` with the name of each table together with its number of rows, or an
+/// `Error` if those could not be counted.
+pub fn table_counts(connection: &Connection) -> Result<Vec<(String, u64)>, Error> {
+    let mut counts = Vec::new();
+    for table in table_names(connection)? {
+        let count = connection.query_row(
+            &format!("SELECT count(*)::UBIGINT FROM \"{}\"", table),
+            [],
+            |row| row.get::<_, u64>(0),
+        )?;
+        counts.push((table, count));
+    }
+    Ok(counts)
+}
+
+/// The `StatsFormat` enum defines how the statistics of a database are printed.
+/// `Table` stands for a plain text layout meant to be read, while `Json` stands for
+/// a single JSON object, meant to be consumed by other tools.
+#[derive(Clone, Debug, Default, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum StatsFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+/// The `DatabaseStats` struct gathers what an already created database holds, so
+/// it can be told at a glance, no matter the options it was built with.
+///
+/// Properties:
+///
+/// * `tables`: The name of each table together with its number of rows.
+/// * `items`: The number of items stored in the vertex table.
+/// * `properties`: The number of properties stored in the vertex table.
+/// * `lexemes`: The number of lexemes stored in the vertex table.
+/// * `claims`: The numeric identifier of each property together with its number
+/// of claims, the most used first.
+/// * `metadata`: The entries of the metadata; that is, how the database was built.
+/// * `size`: The size of the database file, in bytes, if known.
+/// * `encoding`: The `IdEncoding` of the database, which the identifiers of the
+/// properties are decoded with.
+#[derive(Clone, Debug, Default)]
+pub struct DatabaseStats {
+    pub tables: Vec<(String, u64)>,
+    pub items: u64,
+    pub properties: u64,
+    pub lexemes: u64,
+    pub claims: Vec<(u64, u64)>,
+    pub metadata: Vec<(String, String)>,
+    pub size: Option<u64>,
+    pub encoding: IdEncoding,
+}
+
+impl DatabaseStats {
+    /// The function gathers the statistics of a database. Notice that the entities
+    /// are classified out of the ranges of the encoding recorded by the database.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    /// * `database`: A reference to the path to the database file.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `DatabaseStats`, or an `Error` if those could not be
+    /// gathered.
+    pub fn collect(connection: &Connection, database: &Path) -> Result<Self, Error> {
+        let tables = table_counts(connection)?;
+        let exists = |table: &str| tables.iter().any(|(name, _)| name == table);
+
+        let mut stats = DatabaseStats {
+            metadata: metadata::entries(connection)?,
+            encoding: IdEncoding::of(connection)?,
+            size: std::fs::metadata(database)
+                .ok()
+                .map(|metadata| metadata.len()),
+            ..DatabaseStats::default()
+        };
+
+        if exists("vertex") {
+            let count = |kind: IdKind| {
+                let range = stats.encoding.range(kind);
+                connection.query_row(
+                    "SELECT count(*)::UBIGINT FROM vertex WHERE id BETWEEN ? AND ?",
+                    params![range.start(), range.end()],
+                    |row| row.get::<_, u64>(0),
+                )
+            };
+            stats.items = count(IdKind::Item)?;
+            stats.properties = count(IdKind::Property)?;
+            stats.lexemes = count(IdKind::Lexeme)?;
+        }
+
+        // Claims are spread over the tables of the values, so the ones of each property are counted
+        // in every one of them, and in their partitions. Qualifiers and references are left out, as
+        // those are no claims :D
+        let mut claim_tables = Vec::new();
+        for table in Partition::table_names() {
+            if exists(table) {
+                claim_tables.push(format!(
+                    "SELECT property_id FROM {}",
+                    partition::source(connection, table)?
+                ));
+            }
+        }
+        if !claim_tables.is_empty() {
+            let mut statement = connection.prepare(&format!(
+                "SELECT property_id, count(*)::UBIGINT AS claims FROM ({}) \
+                 GROUP BY property_id ORDER BY claims DESC, property_id",
+                claim_tables.join(" UNION ALL ")
+            ))?;
+            stats.claims = statement
+                .query_map([], |row| Ok((row.get::<_, u64>(0)?, row.get::<_, u64>(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+        }
+
+        stats.tables = tables;
+        Ok(stats)
+    }
+
+    /// The function returns the statistics as a JSON object.
+    ///
+    /// Arguments:
+    ///
+    /// * `top`: The number of the most used properties listed.
+    ///
+    /// Returns:
+    ///
+    /// A JSON `Value` with the statistics.
+    pub fn to_json(&self, top: usize) -> Value {
+        let tables = self
+            .tables
+            .iter()
+            .map(|(table, rows)| (table.to_owned(), json!(rows)))
+            .collect::<Map<_, _>>();
+        let metadata = self
+            .metadata
+            .iter()
+            .map(|(key, value)| (key.to_owned(), json!(value)))
+            .collect::<Map<_, _>>();
+        let top_properties = self
+            .claims
+            .iter()
+            .take(top)
+            .map(|(property_id, claims)| {
+                json!({ "property": property_name(&self.encoding, *property_id), "claims": claims })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "size_bytes": self.size,
+            "tables": tables,
+            "entities": {
+                "items": self.items,
+                "properties": self.properties,
+                "lexemes": self.lexemes,
+            },
+            "distinct_properties": self.claims.len(),
+            "top_properties": top_properties,
+            "metadata": metadata,
+        })
+    }
+
+    /// The function returns the statistics laid out as plain text tables.
+    ///
+    /// Arguments:
+    ///
+    /// * `top`: The number of the most used properties listed.
+    ///
+    /// Returns:
+    ///
+    /// A `StatsTable`, which is printed through its `Display` implementation.
+    pub fn to_table(&self, top: usize) -> StatsTable<'_> {
+        StatsTable { stats: self, top }
+    }
+}
+
+/// The `StatsTable` struct lays the statistics of a database out as plain text.
+///
+/// Properties:
+///
+/// * `stats`: A reference to the `DatabaseStats` being printed.
+/// * `top`: The number of the most used properties listed.
+pub struct StatsTable<'a> {
+    stats: &'a DatabaseStats,
+    top: usize,
+}
+
+/// This implementation of the `Display` trait prints the number of rows of each
+/// table, the entities of each type, the most used properties, the size of the
+/// database and its metadata, one section after the other.
+impl Display for StatsTable<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stats = self.stats;
+        let width = stats
+            .tables
+            .iter()
+            .map(|(table, _)| table.len())
+            .max()
+            .unwrap_or_default();
+        for (table, count) in &stats.tables {
+            writeln!(f, "{:width$}  {:>12}", table, count, width = width)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "items       {:>12}", stats.items)?;
+        writeln!(f, "properties  {:>12}", stats.properties)?;
+        writeln!(f, "lexemes     {:>12}", stats.lexemes)?;
+
+        writeln!(f)?;
+        writeln!(f, "{} distinct properties", stats.claims.len())?;
+        for (property_id, claims) in stats.claims.iter().take(self.top) {
+            writeln!(f, "  {:<10}  {:>12}", property_name(&stats.encoding, *property_id), claims)?;
+        }
+
+        if let Some(size) = stats.size {
+            writeln!(f)?;
+            writeln!(f, "size: {:.1} MB", size as f64 / 1_000_000.0)?;
+        }
+
+        // The metadata tells how the database was built, such as the encoding of its identifiers :D
+        if !stats.metadata.is_empty() {
+            writeln!(f)?;
+            for (key, value) in &stats.metadata {
+                writeln!(f, "{}: {}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The function returns the identifier of a property, such as `P31`, out of its
+/// numeric identifier, or the number itself in case it cannot be decoded.
+fn property_name(encoding: &IdEncoding, property_id: u64) -> String {
+    match encoding.decode(property_id) {
+        Ok(id) => id.to_string(),
+        Err(_) => property_id.to_string(),
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::{Read, Result};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// The `Throttled` struct wraps a reader so the bytes are read at a bounded rate.
+/// Conversions running on a shared NAS or over network storage can thus be kept
+/// from starving the rest of the tenants of the same storage. The rate is enforced
+/// over the whole load: whenever the reader is ahead of the allowed budget, it
+/// sleeps until the budget catches up.
+///
+/// Properties:
+///
+/// * `inner`: The reader being throttled.
+/// * `bytes_per_second`: The maximum number of bytes read per second.
+/// * `start`: The instant the first byte was requested.
+/// * `read`: The number of bytes read so far.
+pub struct Throttled<R> {
+    inner: R,
+    bytes_per_second: f64,
+    start: Option<Instant>,
+    read: u64,
+}
+
+impl<R: Read> Throttled<R> {
+    /// The function wraps a reader so it does not read more than the given number of
+    /// megabytes per second.
+    ///
+    /// Arguments:
+    ///
+    /// * `inner`: The reader to be throttled.
+    /// * `max_mbps`: The maximum rate, in megabytes (10^6 bytes) per second.
+    ///
+    /// Returns:
+    ///
+    /// The `Throttled` reader.
+    pub fn new(inner: R, max_mbps: f64) -> Self {
+        Self {
+            inner,
+            bytes_per_second: max_mbps * 1_000_000.0,
+            start: None,
+            read: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for Throttled<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+
+        // We wait until the bytes read so far fit in the budget of the elapsed time.
+        // Notice that reads are not split, so a single read may exceed the rate for a
+        // while; it is compensated by the sleep before the next one :D
+        let expected = Duration::from_secs_f64(self.read as f64 / self.bytes_per_second);
+        let elapsed = start.elapsed();
+        if expected > elapsed {
+            sleep(expected - elapsed);
+        }
+
+        let read = self.inner.read(buf)?;
+        self.read += read as u64;
+        Ok(read)
+    }
+}
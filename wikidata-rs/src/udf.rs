@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::sync::Mutex;
+
+use duckdb::core::{DataChunkHandle, Inserter, LogicalTypeId};
+use duckdb::ffi::{duckdb_string_t, duckdb_string_t_data, duckdb_string_t_length};
+use duckdb::vscalar::{ScalarFunctionSignature, VScalar};
+use duckdb::vtab::arrow::WritableVector;
+use duckdb::{Connection, Error};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::id::{Id, IdEncoding};
+use crate::schema;
+
+/// The connection the labels are looked up through, as DuckDB creates the state of
+/// the functions on its own, and thus cannot be handed any. A clone of the one the
+/// functions are registered on is used, as the query calling them is still running
+/// on that one.
+static LABELS: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// The encoding the identifiers are decoded and encoded with, which is the one of
+/// the database the functions were registered on last, for the very same reason.
+static ENCODING: Mutex<Option<IdEncoding>> = Mutex::new(None);
+
+/// The query looking the labels of a batch of entities up, whose `{}` is replaced
+/// by their identifiers.
+const LABELS_SQL: &str = "SELECT id, label FROM vertex WHERE id IN ({})";
+
+/// The `Udf` enum defines the scalar functions written in Rust that are registered
+/// on the connections of the `query` and `serve` subcommands. Those do the same as
+/// the `wd_id_to_str` and `label` macros, but decode the identifiers with the very
+/// encoding of the database and look the labels up by batches, which is both exact
+/// and faster over huge results. Those are not stored in the database, so other clients
+/// are left with the macros.
+#[derive(Clone, Debug, PartialEq, EnumIter)]
+pub enum Udf {
+    DecodeId,
+    EncodeId,
+    Label,
+}
+
+impl Udf {
+    /// This function registers the scalar function on the given connection, so the
+    /// queries run through it can call it.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection the function is registered on.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// function is registered, or an `Error` object otherwise. Notice that `wd_label`
+    /// is not registered over the databases built with `--no-text`, as the `label`
+    /// macro is not created either.
+    pub fn register(&self, connection: &Connection) -> Result<(), Error> {
+        if matches!(self, Udf::DecodeId | Udf::EncodeId) {
+            let mut encoding = ENCODING.lock().unwrap_or_else(|error| error.into_inner());
+            *encoding = Some(IdEncoding::of(connection)?);
+        }
+        match self {
+            Udf::DecodeId => connection.register_scalar_function::<DecodeId>(self.as_ref()),
+            Udf::EncodeId => connection.register_scalar_function::<EncodeId>(self.as_ref()),
+            // Databases built with --no-text have no labels on the vertex table to be read :(
+            Udf::Label if !schema::has_column(connection, "vertex", "label")? => Ok(()),
+            Udf::Label => {
+                // A poisoned lock only means another query panicked while looking labels up,
+                // which leaves the connection as it was :D
+                let mut labels = LABELS.lock().unwrap_or_else(|error| error.into_inner());
+                *labels = Some(connection.try_clone()?);
+                connection.register_scalar_function::<Label>(self.as_ref())
+            }
+        }
+    }
+}
+
+/// This implementation of the `AsRef` trait returns the name under which the scalar
+/// function is registered. Those differ from the ones of the macros, so both can be
+/// called side by side, such as when comparing their results.
+impl AsRef<str> for Udf {
+    fn as_ref(&self) -> &str {
+        match self {
+            Udf::DecodeId => "wd_decode_id",
+            Udf::EncodeId => "wd_encode_id",
+            Udf::Label => "wd_label",
+        }
+    }
+}
+
+/// The function registers every scalar function on the given connection.
+///
+/// Arguments:
+///
+/// * `connection`: A reference to the connection the functions are registered on.
+///
+/// Returns:
+///
+/// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+/// functions are registered, or an `Error` object otherwise.
+pub fn register(connection: &Connection) -> Result<(), Error> {
+    Udf::iter().try_for_each(|udf| udf.register(connection))
+}
+
+/// The function returns the encoding the identifiers are converted with, which is
+/// the default one until the functions are registered.
+fn encoding() -> IdEncoding {
+    let encoding = ENCODING.lock().unwrap_or_else(|error| error.into_inner());
+    encoding.unwrap_or_default()
+}
+
+/// The `DecodeId` struct implements `wd_decode_id`, which turns the numeric
+/// identifiers of the database back into the ones of Wikidata, such as `Q42` or
+/// `L7-F1`, just as `IdEncoding::decode` does. The unused identifiers are turned
+/// into `NULL`.
+struct DecodeId;
+
+impl VScalar for DecodeId {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn StdError>> {
+        let rows = input.len();
+        let ids = input.flat_vector(0);
+        let values = ids.as_slice_with_len::<u64>(rows);
+        let encoding = encoding();
+        let mut output = output.flat_vector();
+        for (row, id) in values.iter().enumerate() {
+            match encoding.decode(*id) {
+                Ok(id) if !ids.row_is_null(row as u64) => {
+                    output.insert(row, id.to_string().as_str())
+                }
+                _ => output.set_null(row),
+            }
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::UBigint.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+/// The `EncodeId` struct implements `wd_encode_id`, which turns the identifiers of
+/// Wikidata, such as `Q42`, into the numeric ones of the database, so those can be
+/// looked up by. Those that cannot be parsed are turned into `NULL`.
+struct EncodeId;
+
+impl VScalar for EncodeId {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn StdError>> {
+        let rows = input.len();
+        let strings = input.flat_vector(0);
+        let values = strings.as_slice_with_len::<duckdb_string_t>(rows);
+        let encoding = encoding();
+        let mut output = output.flat_vector();
+        for (row, value) in values.iter().enumerate() {
+            if strings.row_is_null(row as u64) {
+                output.set_null(row);
+                continue;
+            }
+            // The strings are either inlined or pointed to, which DuckDB itself tells apart :D
+            let mut value = *value;
+            let bytes = std::slice::from_raw_parts(
+                duckdb_string_t_data(&mut value) as *const u8,
+                duckdb_string_t_length(value) as usize,
+            );
+            let string = String::from_utf8_lossy(bytes);
+            match string.trim().parse::<Id>().and_then(|id| encoding.encode(id)) {
+                Ok(id) => output.as_mut_slice_with_len::<u64>(rows)[row] = id,
+                Err(_) => output.set_null(row),
+            }
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Varchar.into()],
+            LogicalTypeId::UBigint.into(),
+        )]
+    }
+}
+
+/// The `Label` struct implements `wd_label`, which returns the label of an entity,
+/// or `NULL` in case it has none. The labels of each batch of rows are looked up at
+/// once, rather than running a subquery per row as the `label` macro does.
+struct Label;
+
+impl VScalar for Label {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn StdError>> {
+        let rows = input.len();
+        let ids = input.flat_vector(0);
+        let values = ids.as_slice_with_len::<u64>(rows);
+        let wanted = (0..rows)
+            .filter(|row| !ids.row_is_null(*row as u64))
+            .map(|row| values[row].to_string())
+            .collect::<Vec<_>>();
+
+        let mut labels: HashMap<u64, String> = HashMap::new();
+        if !wanted.is_empty() {
+            let guard = LABELS.lock().unwrap_or_else(|error| error.into_inner());
+            let connection = match guard.as_ref() {
+                Some(connection) => connection,
+                None => return Err("The labels cannot be looked up".into()),
+            };
+            let mut statement = connection.prepare(&LABELS_SQL.replace("{}", &wanted.join(",")))?;
+            let found = statement.query_map([], |row| {
+                Ok((row.get::<_, u64>(0)?, row.get::<_, Option<String>>(1)?))
+            })?;
+            for entry in found {
+                if let (id, Some(label)) = entry? {
+                    labels.insert(id, label);
+                }
+            }
+        }
+
+        let mut output = output.flat_vector();
+        for (row, id) in values.iter().enumerate() {
+            match labels.get(id) {
+                Some(label) if !ids.row_is_null(row as u64) => output.insert(row, label.as_str()),
+                _ => output.set_null(row),
+            }
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::UBigint.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wikidata::{Fid, Lid, Pid, Qid};
+
+    use super::*;
+
+    fn encoded(id: Id) -> u64 {
+        IdEncoding::default().encode(id).unwrap()
+    }
+
+    #[test]
+    fn decodes_and_encodes_identifiers() {
+        let connection = Connection::open_in_memory().unwrap();
+        register(&connection).unwrap();
+
+        for (id, string) in [
+            (encoded(Id::Qid(Qid(42))), "Q42"),
+            (encoded(Id::Pid(Pid(31))), "P31"),
+            (encoded(Id::Fid(Fid(Lid(7), 2))), "L7-F2"),
+        ] {
+            let (decoded, encoded): (String, u64) = connection
+                .query_row(
+                    &format!(
+                        "SELECT wd_decode_id({}::UBIGINT), wd_encode_id('{}')",
+                        id, string
+                    ),
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(decoded, string);
+            assert_eq!(encoded, id);
+        }
+
+        let (decoded, encoded): (Option<String>, Option<u64>) = connection
+            .query_row(
+                "SELECT wd_decode_id(NULL::UBIGINT), wd_encode_id('X42')",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(decoded, None);
+        assert_eq!(encoded, None);
+    }
+
+    #[test]
+    fn looks_labels_up_by_batches() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE vertex (id UBIGINT, label TEXT, description TEXT); \
+                 INSERT INTO vertex SELECT range, 'Entity ' || range, NULL FROM range(1, 5000);",
+            )
+            .unwrap();
+        register(&connection).unwrap();
+
+        // The rows span several batches, and some of them have no label at all :D
+        let (labels, missing, matching): (i64, i64, i64) = connection
+            .query_row(
+                "SELECT count(label), count(*) - count(label), \
+                 count(*) FILTER (WHERE label = 'Entity ' || id) \
+                 FROM (SELECT id, wd_label(id) AS label \
+                 FROM (SELECT range::UBIGINT AS id FROM range(1, 6000)))",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(labels, 4999);
+        assert_eq!(missing, 1000);
+        assert_eq!(matching, 4999);
+    }
+
+    #[test]
+    fn skips_the_labels_without_texts() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch("CREATE TABLE vertex (id UBIGINT);")
+            .unwrap();
+        register(&connection).unwrap();
+        let functions: i64 = connection
+            .query_row(
+                "SELECT count(*) FROM duckdb_functions() WHERE function_name = 'wd_label'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(functions, 0);
+    }
+}
@@ -0,0 +1,143 @@
+use std::{collections::HashMap, fmt::Display};
+
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use wikidata::EntityError;
+
+/// The `Failure` enum classifies the reasons why a line of the dump could not be
+/// stored in the database. `TruncatedJson` stands for lines that were cut before
+/// the closing bracket of the entity, `InvalidJson` for the rest of the lines that
+/// are not valid JSON, `UnknownDatatype` for entities holding values of datatypes
+/// that are not yet supported, `SchemaDrift` for entities whose JSON does not
+/// follow the expected structure, `IdOutOfRange` for entities whose identifier does
+/// not fit the range of its namespace, `Storage` for errors raised by the database,
+/// and `LineTooLong` for lines longer than the limit, which are not even read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
+pub enum Failure {
+    TruncatedJson,
+    InvalidJson,
+    UnknownDatatype,
+    SchemaDrift,
+    IdOutOfRange,
+    Storage,
+    LineTooLong,
+}
+
+impl Failure {
+    /// The function classifies a line that could not be parsed as JSON. As every
+    /// entity in the dump is a JSON object, a line not ending with a closing bracket
+    /// is considered to be truncated.
+    ///
+    /// Arguments:
+    ///
+    /// * `line`: The line, without the trailing comma, that could not be parsed.
+    ///
+    /// Returns:
+    ///
+    /// The `Failure` the line is classified as.
+    pub fn from_json(line: &str) -> Self {
+        if line.ends_with('}') {
+            Failure::InvalidJson
+        } else {
+            Failure::TruncatedJson
+        }
+    }
+}
+
+/// This code classifies the errors raised while transforming a JSON value into a
+/// Wikidata entity. Those regarding datatypes are caused by values of a datatype
+/// the parser is not aware of, while the rest of them are caused by the JSON not
+/// having the structure the parser expects. Notice that the variants are matched,
+/// rather than their names, so a new one is taken as a schema drift.
+impl From<&EntityError> for Failure {
+    fn from(error: &EntityError) -> Self {
+        match error {
+            EntityError::UnknownDatatype => Failure::UnknownDatatype,
+            _ => Failure::SchemaDrift,
+        }
+    }
+}
+
+/// This implementation of the `Display` trait returns a human readable description
+/// of the failure, which is used in the summary printed at the end of the load.
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Failure::TruncatedJson => "truncated JSON",
+            Failure::InvalidJson => "invalid JSON",
+            Failure::UnknownDatatype => "unknown datatype",
+            Failure::SchemaDrift => "schema drift",
+            Failure::IdOutOfRange => "identifier out of range",
+            Failure::Storage => "storage error",
+            Failure::LineTooLong => "line too long",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// The `LineError` struct contains the classification of the failure together with
+/// the error message that is shown to the user.
+///
+/// Properties:
+///
+/// * `failure`: The `Failure` the error is classified as.
+/// * `message`: A `String` describing the error, including the line number.
+#[derive(Clone, Debug)]
+pub struct LineError {
+    pub failure: Failure,
+    pub message: String,
+}
+
+impl LineError {
+    pub fn new(failure: Failure, message: String) -> Self {
+        Self { failure, message }
+    }
+}
+
+impl Display for LineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.failure, self.message)
+    }
+}
+
+/// The `Report` struct counts the failures found during the load, so those can be
+/// summarized at the end instead of being lost among the per-line messages.
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    failures: HashMap<Failure, usize>,
+}
+
+impl Report {
+    /// The function records a failure in the report.
+    ///
+    /// Arguments:
+    ///
+    /// * `failure`: The `Failure` to be counted.
+    pub fn record(&mut self, failure: Failure) {
+        *self.failures.entry(failure).or_insert(0) += 1;
+    }
+
+    /// The function returns the number of failures of a certain kind.
+    pub fn count(&self, failure: Failure) -> usize {
+        self.failures.get(&failure).copied().unwrap_or(0)
+    }
+
+    /// The function returns the number of failures of any kind.
+    pub fn total(&self) -> usize {
+        self.failures.values().sum()
+    }
+}
+
+/// This implementation of the `Display` trait prints a summary of the failures
+/// found, one line per kind, in the order they are declared.
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} lines could not be stored", self.total())?;
+        for failure in Failure::iter() {
+            if self.count(failure) > 0 {
+                write!(f, "\n  {}: {}", failure, self.count(failure))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,176 @@
 use chrono::{DateTime, Datelike, Utc};
-use duckdb::{params, Appender, Connection, Error, Transaction};
+use duckdb::{Appender, Connection, Error, ToSql, Transaction};
 use lazy_static::lazy_static;
-use std::{collections::HashMap, slice::Iter};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    slice::Iter,
+};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
 use wikidata::ClaimValueData;
 
-use crate::{id::Id, LANG};
+use crate::{
+    columns::SchemaConfig,
+    dtype::{DataType, Target},
+    id::{parse_item, Id, IdEncoding},
+    LANG,
+};
+
+/// The type of the columns storing the exact amounts of the quantities. DuckDB
+/// decimals are at most 38 digits wide, of which 9 are kept for the fractional part,
+/// which leaves room for the integer amounts up to 10^29.
+const DECIMAL_TYPE: &str = "DECIMAL(38, 9)";
+
+/// The number of fractional digits of the `DECIMAL_TYPE`.
+const DECIMAL_SCALE: usize = 9;
+
+/// The number of integer digits of the `DECIMAL_TYPE`.
+const DECIMAL_PRECISION: usize = 38 - DECIMAL_SCALE;
+
+/// The `ExactAmounts` struct contains the amount and the bounds of a quantity as
+/// found in the dump, as the parser of the entities reads them as doubles, which
+/// cannot represent every decimal exactly. Each of them is `None` in case it is
+/// not found, or it does not fit the `DECIMAL_TYPE`.
+///
+/// Properties:
+///
+/// * `amount`: The amount of the quantity.
+/// * `lower_bound`: The lower bound of the quantity.
+/// * `upper_bound`: The upper bound of the quantity.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExactAmounts {
+    pub amount: Option<String>,
+    pub lower_bound: Option<String>,
+    pub upper_bound: Option<String>,
+}
+
+impl ExactAmounts {
+    /// The function retrieves the exact amounts out of the JSON value of a quantity;
+    /// that is, the `value` of its `datavalue`.
+    ///
+    /// Arguments:
+    ///
+    /// * `value`: A reference to the JSON value of the quantity.
+    ///
+    /// Returns:
+    ///
+    /// The `ExactAmounts` of the quantity.
+    pub fn from_json(value: &Value) -> Self {
+        let exact = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .and_then(exact_amount)
+        };
+        Self {
+            amount: exact("amount"),
+            lower_bound: exact("lowerBound"),
+            upper_bound: exact("upperBound"),
+        }
+    }
+}
+
+/// The function normalizes an amount of the dump, such as `+1.50`, so it can be
+/// stored as a decimal: the plus sign, the leading zeros of the integer part and
+/// the trailing zeros of the fractional part are removed.
+///
+/// Arguments:
+///
+/// * `amount`: The amount as found in the dump.
+///
+/// Returns:
+///
+/// An `Option` with the normalized amount, such as `1.5`, or `None` in case it is
+/// not a valid decimal, or it has more digits than the `DECIMAL_TYPE` can hold.
+pub fn exact_amount(amount: &str) -> Option<String> {
+    let (sign, digits) = match amount.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", amount.strip_prefix('+').unwrap_or(amount)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if integer.is_empty()
+        || !integer.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let integer = match integer.trim_start_matches('0') {
+        "" => "0",
+        integer => integer,
+    };
+    let fraction = fraction.trim_end_matches('0');
+    if integer.len() > DECIMAL_PRECISION || fraction.len() > DECIMAL_SCALE {
+        return None;
+    }
+
+    Some(match fraction {
+        "" => format!("{}{}", sign, integer),
+        fraction => format!("{}{}.{}", sign, integer, fraction),
+    })
+}
+
+/// The `ExactDate` struct contains the date of a time as found in the dump, as the
+/// parser of the entities reads it into a `DateTime`, which neither keeps the
+/// calendar it is written in nor the years beyond its range. Years are written as
+/// in the dump, so there is no year 0, and -44 stands for 44 BCE.
+///
+/// Properties:
+///
+/// * `year`: The year of the date, which is negative for the years BCE.
+/// * `month`: The month of the date, which is 0 for the dates less precise than it.
+/// * `day`: The day of the date, which is 0 for the dates less precise than it.
+/// * `calendar_model_id`: The numeric identifier of the item of the calendar the
+/// date is written in, such as the proleptic Gregorian calendar (Q1985727), or
+/// `None` in case it is not known.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExactDate {
+    pub year: i64,
+    pub month: u8,
+    pub day: u8,
+    pub calendar_model_id: Option<u64>,
+}
+
+impl ExactDate {
+    /// The function retrieves the exact date out of the JSON value of a time; that
+    /// is, the `value` of its `datavalue`, such as `+1952-03-11T00:00:00Z`.
+    ///
+    /// Arguments:
+    ///
+    /// * `value`: A reference to the JSON value of the time.
+    /// * `encoding`: The `IdEncoding` the calendar model is stored with.
+    ///
+    /// Returns:
+    ///
+    /// An `Option` with the `ExactDate`, or `None` in case the time cannot be
+    /// understood.
+    pub fn from_json(value: &Value, encoding: &IdEncoding) -> Option<Self> {
+        let time = value.get("time").and_then(Value::as_str)?;
+        let (date, _) = time.split_once('T')?;
+        let (sign, date) = match date.strip_prefix('-') {
+            Some(date) => (-1, date),
+            None => (1, date.strip_prefix('+').unwrap_or(date)),
+        };
+        let mut parts = date.splitn(3, '-');
+        let year = parts.next()?.parse::<i64>().ok()?;
+
+        // The calendar model is referenced by its concept URI, whose last segment is the item :D
+        let calendar_model_id = value
+            .get("calendarmodel")
+            .and_then(Value::as_str)
+            .and_then(|uri| uri.rsplit('/').next())
+            .and_then(|item| parse_item(item).ok())
+            .and_then(|qid| encoding.encode(Id::Qid(qid)).ok());
+
+        Some(Self {
+            year: sign * year,
+            month: parts.next()?.parse().ok()?,
+            day: parts.next()?.parse().ok()?,
+            calendar_model_id,
+        })
+    }
+}
 
 /// The `AppenderHelper` struct contains a hashmap of `Appender` structs with string
 /// keys.
@@ -14,8 +180,18 @@ use crate::{id::Id, LANG};
 /// * `appenders`: `appenders` is a property of type `HashMap<&'a str,
 /// Appender<'a>>` in a struct called `AppenderHelper`. It is a hash map that stores
 /// references to `Appender` objects, with keys of type `&'a str`.
+/// * `placeholders`: The number of placeholder identifiers allocated so far to the
+/// unknown values found.
+/// * `rows`: The number of rows appended so far to each of the tables.
+/// * `skipped`: The tables the user is not interested in, whose rows are dropped.
+/// * `encoding`: The `IdEncoding` the identifiers are stored with, which the
+/// placeholders are allocated from.
 pub struct AppenderHelper<'a> {
     pub appenders: HashMap<&'a str, Appender<'a>>,
+    pub placeholders: u64,
+    pub rows: HashMap<&'static str, u64>,
+    pub skipped: HashSet<&'static str>,
+    pub encoding: IdEncoding,
 }
 
 /// The above code is implementing a new method for the `AppenderHelper` struct in
@@ -27,52 +203,198 @@ pub struct AppenderHelper<'a> {
 /// the appender is successfully obtained, it is inserted into the `appenders`
 /// HashMap with the
 impl<'a> AppenderHelper<'a> {
-    pub fn new(transaction: &'a Transaction) -> Self {
+    pub fn new(transaction: &'a Transaction, encoding: IdEncoding) -> Self {
         let mut appenders = HashMap::new();
         Table::iterator().for_each(|table| {
-            if let Ok(appender) = transaction.appender(table.as_ref()) {
-                appenders.insert(table.as_ref(), appender);
+            for scope in table.scopes() {
+                let table_name = table.table_name(scope);
+                if let Ok(appender) = transaction.appender(table_name) {
+                    appenders.insert(table_name, appender);
+                }
             }
         });
-        Self { appenders }
+        Self {
+            appenders,
+            placeholders: 0,
+            rows: HashMap::new(),
+            skipped: HashSet::new(),
+            encoding,
+        }
+    }
+
+    /// The function appends a row to a table, keeping track of the number of rows
+    /// appended to it. Rows of the skipped tables are just dropped.
+    ///
+    /// Arguments:
+    ///
+    /// * `table_name`: The name of the table the row is appended to.
+    /// * `row`: The values of the columns of the row.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with either an empty `Ok(())` or an `Error` if there is no such
+    /// table or the row could not be appended.
+    pub fn append_row(
+        &mut self,
+        table_name: &'static str,
+        row: &[&dyn ToSql],
+    ) -> Result<(), Error> {
+        if self.skipped.contains(table_name) {
+            return Ok(());
+        }
+        match self.appenders.get_mut(table_name) {
+            Some(appender) => appender.append_row(row)?,
+            None => return Err(Error::AppendError),
+        }
+        *self.rows.entry(table_name).or_insert(0) += 1;
+        Ok(())
+    }
+
+    /// The function returns the tables that are not among the ones selected by the
+    /// user, so those are skipped. Selecting a table selects its qualifier and
+    /// reference counterparts as well; for example, `time` selects `qualifier_time`
+    /// and `reference_time` too.
+    ///
+    /// Arguments:
+    ///
+    /// * `selected`: The names of the tables selected, such as `vertex` or `edge`.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the names of the tables to be skipped, or a `String` with an
+    /// error message in case any of the names selected is not a table.
+    pub fn unselected(selected: &[String]) -> Result<HashSet<&'static str>, String> {
+        let mut tables: Vec<&str> = Vec::new();
+        for table in Table::iterator() {
+            if !tables.contains(&table.table_name(Scope::Statement)) {
+                tables.push(table.table_name(Scope::Statement)); // several values share the table
+            }
+        }
+        if let Some(name) = selected
+            .iter()
+            .find(|name| !tables.contains(&name.as_str()))
+        {
+            return Err(format!(
+                "Not valid table: {}. Expected one of {}",
+                name,
+                tables.join(", ")
+            ));
+        }
+
+        let mut skipped = HashSet::new();
+        for table in Table::iterator() {
+            if !selected
+                .iter()
+                .any(|name| name == table.table_name(Scope::Statement))
+            {
+                skipped.extend(
+                    table
+                        .scopes()
+                        .into_iter()
+                        .map(|scope| table.table_name(scope)),
+                );
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// The function allocates a fresh placeholder identifier. Those are taken from a
+    /// reserved range of the identifiers, so that they never collide with the ones
+    /// of the entities.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the numeric identifier of the placeholder, or an `Error` in
+    /// case the range reserved for the placeholders is exhausted.
+    pub fn next_placeholder(&mut self) -> Result<u64, Error> {
+        let id = self
+            .encoding
+            .encode(Id::Placeholder(self.placeholders))
+            .map_err(|error| Error::ToSqlConversionFailure(error.into()))?;
+        self.placeholders += 1;
+        Ok(id)
+    }
+
+    /// The function flushes the rows buffered by every appender, so those are
+    /// visible to the statements run through the transaction.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
+    /// rows are flushed, or an `Error` object if any of them cannot be stored.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.appenders
+            .values_mut()
+            .try_for_each(|appender| appender.flush())
     }
 }
 
 /// The above code is defining an enum called `Table` in Rust programming language.
 /// The enum has several variants including `Vertex` which has fields `id`, `label`,
-/// and `description`, `Entity` which has a single field `u32`, `String` which has a
-/// single field `String`, `Coordinates` which has fields `latitude`, `longitude`,
+/// and `description`, `Entity` which has the fields `u64` and `Target`, `String` which has a
+/// single field `String`, `Text` which has fields `text` and `lang`, `ExternalId`
+/// and `Url` which have a single field `String` as well, `Coordinates`
+/// which has fields `latitude`, `longitude`,
 /// `precision`, and `globe_id`, `Quantity` which has fields `amount`,
-/// `lower_bound`, `upper_bound`, and `unit_id`, `Time` which has fields `time` and
-/// `precision`, `Unknown`,
+/// `lower_bound`, `upper_bound`, `unit_id` and the `exact` amounts, `Time` which has fields `time`,
+/// `precision` and the exact `date`, `Unknown`,
 pub enum Table {
     Vertex {
-        id: u32,
+        id: u64,
         label: String,
         description: String,
     },
-    Entity(u32),
+    Property {
+        id: u64,
+        datatype: String,
+    },
+    RawClaim {
+        json: String,
+    },
+    Entity(u64, Target),
     String(String),
+    Text {
+        text: String,
+        lang: String,
+    },
+    ExternalId(String),
+    Url(String),
     Coordinates {
         latitude: f64,
         longitude: f64,
         precision: f64,
-        globe_id: u32,
+        globe_id: u64,
     },
     Quantity {
         amount: f64,
         lower_bound: Option<f64>,
         upper_bound: Option<f64>,
-        unit_id: Option<u32>,
+        unit_id: Option<u64>,
+        exact: ExactAmounts,
     },
     Time {
         time: DateTime<Utc>,
         precision: u8,
+        date: ExactDate,
     },
     Unknown,
     None,
 }
 
+/// The `Scope` enum defines where a value is found within an entity. `Statement`
+/// stands for the main value of a claim, `Qualifier` stands for the values of the
+/// qualifiers annotating a claim, and `Reference` stands for the values of the
+/// references backing a claim up; that is, where the claim comes from. Each scope
+/// is stored in its own set of tables, so qualifiers and references are given the
+/// same typed treatment as the main values; that is, `qualifier_time`,
+/// `reference_time`, `qualifier_edge`, `reference_edge`...
+#[derive(Clone, Copy, Debug, PartialEq, EnumIter)]
+pub enum Scope {
+    Statement,
+    Qualifier,
+    Reference,
+}
+
 impl Table {
     /// The function returns an iterator over a static array of tables in Rust.
     ///
@@ -82,14 +404,27 @@ impl Table {
     /// values.
     pub fn iterator() -> Iter<'static, Table> {
         lazy_static! {
-            static ref TABLES: [Table; 8] = [
+            static ref TABLES: [Table; 13] = [
                 Table::Vertex {
                     id: 0,
                     description: String::default(),
                     label: String::default()
                 },
+                Table::Property {
+                    id: 0,
+                    datatype: String::default()
+                },
+                Table::RawClaim {
+                    json: String::default()
+                },
                 Table::String(String::new()),
-                Table::Entity(0),
+                Table::Text {
+                    text: String::new(),
+                    lang: String::new(),
+                },
+                Table::ExternalId(String::new()),
+                Table::Url(String::new()),
+                Table::Entity(0, Target::Item),
                 Table::Coordinates {
                     latitude: 0.0,
                     longitude: 0.0,
@@ -101,10 +436,12 @@ impl Table {
                     lower_bound: None,
                     upper_bound: None,
                     unit_id: None,
+                    exact: ExactAmounts::default(),
                 },
                 Table::Time {
                     time: Default::default(),
                     precision: 0,
+                    date: ExactDate::default(),
                 },
                 Table::None,
                 Table::Unknown,
@@ -124,6 +461,18 @@ impl Table {
     /// However, due to the fact that some datum can possibly reference a yet not parsed value,
     /// we cannot use primary keys. Hence, indices will be created for easier accessing :D
     ///
+    /// Every claim is given a `statement_id` column with the identifier it has in the dump, so
+    /// the statements can be joined with their qualifiers and references, and told apart even
+    /// if the same value is stated twice. Values belonging to qualifiers are stored in tables
+    /// with the exact same structure, where the `statement_id` references the claim they
+    /// annotate. The same goes for the values of the references, which are also given a
+    /// `reference_hash` column, as a claim may be backed up by several references of several
+    /// values each.
+    ///
+    /// Arguments:
+    ///
+    /// * `scope`: The `Scope` of the values stored in the table.
+    ///
     /// Returns:
     ///
     /// A tuple containing the name of the table as a `&str` and a vector of column definitions
@@ -131,36 +480,85 @@ impl Table {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// let table = Table::String("Hello world".to_string());
-    /// let (table_name, columns) = table.table_definition();
+    /// let (table_name, columns) = table.table_definition(Scope::Statement);
     /// println!("Table name: {}", table_name);
     /// println!("Columns: {:?}", columns);
     /// ```
     ///
     /// Output:
-    /// ```
+    /// ```text
     /// Table name: string
-    /// Columns: [("src_id", "UINTEGER NOT NULL"), ("property_id", "UINTEGER NOT NULL"), ("dst_id", "UINTEGER NOT NULL"), ("string", "TEXT NOT NULL")]
+    /// Columns: [("statement_id", "TEXT NOT NULL"), ("src_id", "UBIGINT NOT NULL"), ("property_id", "UBIGINT NOT NULL"), ("dst_id", "UBIGINT NOT NULL"), ("datatype", "UTINYINT"), ("claim_index", "UINTEGER NOT NULL"), ("rank", "UTINYINT NOT NULL"), ("string", "TEXT NOT NULL")]
     /// ```
-    fn table_definition(&self) -> (&str, Vec<(&str, &str)>) {
+    pub(crate) fn table_definition(
+        &self,
+        scope: Scope,
+    ) -> (&'static str, Vec<(&'static str, &'static str)>) {
         if let Table::Vertex { .. } = self {
             // Early return in case we find a Vertex :D
             return (
                 Table::iterator().next().unwrap().as_ref(),
                 vec![
-                    ("id", "UINTEGER NOT NULL"),
+                    ("id", "UBIGINT NOT NULL"),
                     ("label", "TEXT"),
                     ("description", "TEXT"),
+                    ("sitelink_count", "UINTEGER NOT NULL"),
+                    ("lastrevid", "UBIGINT"),
+                    ("entity_type", "TEXT NOT NULL"),
                 ],
             );
         }
 
-        let mut columns: Vec<(&str, &str)> = vec![
-            ("src_id", "UINTEGER NOT NULL"),
-            ("property_id", "UINTEGER NOT NULL"),
-            ("dst_id", "UINTEGER NOT NULL"),
-        ];
+        if let Table::Property { .. } = self {
+            // Properties are vertices as well, but their declared datatype is stored apart :D
+            return (
+                self.table_name(scope),
+                vec![("id", "UBIGINT NOT NULL"), ("datatype", "TEXT NOT NULL")],
+            );
+        }
+
+        if let Table::RawClaim { .. } = self {
+            // Claims that could not be converted are stored as they are found in the dump :(
+            return (
+                self.table_name(scope),
+                vec![
+                    ("src_id", "UBIGINT NOT NULL"),
+                    ("property_id", "UBIGINT NOT NULL"),
+                    ("json", "TEXT NOT NULL"),
+                ],
+            );
+        }
+
+        let mut columns: Vec<(&str, &str)> = match scope {
+            Scope::Statement | Scope::Qualifier => vec![("statement_id", "TEXT NOT NULL")],
+            Scope::Reference => vec![
+                ("statement_id", "TEXT NOT NULL"),
+                ("reference_hash", "TEXT NOT NULL"),
+            ],
+        };
+
+        columns.append(&mut vec![
+            ("src_id", "UBIGINT NOT NULL"),
+            ("property_id", "UBIGINT NOT NULL"),
+            ("dst_id", "UBIGINT NOT NULL"),
+        ]);
+
+        // Every value is given the code of the datatype declared by its property, which tells the
+        // table its payload is stored in without joining with the property table. Claims with no
+        // value, or with an unknown one, are given it too, as their property declares it anyway.
+        // It is NULL only for the datatypes the converter is not aware of :D
+        columns.push(("datatype", "UTINYINT"));
+
+        // Claims keep the position they had among the ones of their property in the dump, so the
+        // first (or primary) value of a property can be told apart, as infobox-like consumers do :D
+        // The rank is kept as well, so the truthy claims can be told apart by the consumers; that
+        // is, the ones whose rank is the greatest among the claims of their property :D
+        if scope == Scope::Statement {
+            columns.push(("claim_index", "UINTEGER NOT NULL"));
+            columns.push(("rank", "UTINYINT NOT NULL"));
+        }
 
         // For the sake of simplicity, those entities that annotate no additional value; that is,
         // Entity, None and Unknown, will be all of those stored in the same table called Edge. Thus,
@@ -169,32 +567,183 @@ impl Table {
         // src_id, as we are annotating additional information to the node itself :D
         let mut value_columns = match self {
             Table::String(_) => vec![("string", "TEXT NOT NULL")],
+            // Texts keep the language they are written in, as a motto or an official name is only
+            // meaningful together with it :D
+            Table::Text { .. } => vec![("text", "TEXT NOT NULL"), ("lang", "TEXT NOT NULL")],
+            // External identifiers and URLs are strings as well, but those are stored apart, so
+            // looking for the entities with an ORCID does not scan every other string :D
+            Table::ExternalId(_) => vec![("external_id", "TEXT NOT NULL")],
+            Table::Url(_) => vec![("url", "TEXT NOT NULL")],
             Table::Coordinates { .. } => vec![
                 ("latitude", "DOUBLE NOT NULL"),
                 ("longitude", "DOUBLE NOT NULL"),
                 ("precision", "DOUBLE NOT NULL"),
-                ("globe_id", "UINTEGER NOT NULL"),
+                ("globe_id", "UBIGINT NOT NULL"),
             ],
             Table::Quantity { .. } => vec![
                 ("amount", "DOUBLE NOT NULL"),
                 ("lower_bound", "DOUBLE"),
                 ("upper_bound", "DOUBLE"),
-                ("unit_id", "UINTEGER"),
+                ("unit_id", "UBIGINT"),
             ],
             Table::Time { .. } => vec![
-                ("time", "DATETIME NOT NULL"),
+                // The times whose year does not fit a DATETIME have none, rather than a made-up one
+                ("time", "DATETIME"),
                 ("precision", "INTEGER NOT NULL"),
+                // The date is kept as found in the dump too, as DATETIME can neither hold the years
+                // of astronomy nor tell the calendars apart :D
+                ("year", "BIGINT NOT NULL"),
+                ("month", "UTINYINT NOT NULL"),
+                ("day", "UTINYINT NOT NULL"),
+                ("calendar_model_id", "UBIGINT"),
             ],
-            _ => vec![], // For Entity, Unknown and None we create only one table...
+            // For Entity, Unknown and None we create only one table, whose rows are told apart by
+            // the target column; that is, the code of what the claim points to :D
+            _ => vec![("target", "UTINYINT NOT NULL")],
         };
 
+        // The doubles are lossy for amounts with many digits, such as population counts, so the
+        // amounts are stored as decimals as well, whenever those fit in them, in every scope :D
+        if let Table::Quantity { .. } = self {
+            value_columns.append(&mut vec![
+                ("amount_decimal", DECIMAL_TYPE),
+                ("lower_bound_decimal", DECIMAL_TYPE),
+                ("upper_bound_decimal", DECIMAL_TYPE),
+            ]);
+        }
+
         // Lastly, we have to extend the common columns with the rest of the body of the entities.
         // In this manner, we can create as many tables as we wish, all of them following the
         // previously described inheritance policy :D
 
         columns.append(&mut value_columns);
 
-        (self.as_ref(), columns)
+        (self.table_name(scope), columns)
+    }
+
+    /// The function returns the name of the table where the values of a certain scope
+    /// are stored. Those belonging to qualifiers are stored in the tables prefixed
+    /// by `qualifier_`, and those belonging to references in the ones prefixed by
+    /// `reference_`.
+    ///
+    /// Arguments:
+    ///
+    /// * `scope`: The `Scope` of the values stored in the table.
+    ///
+    /// Returns:
+    ///
+    /// The name of the table as a `&'static str`.
+    pub fn table_name(&self, scope: Scope) -> &'static str {
+        match scope {
+            Scope::Statement => match self {
+                Table::Vertex { .. } => "vertex",
+                Table::Property { .. } => "property",
+                Table::RawClaim { .. } => "raw_claim",
+                Table::Entity(..) => "edge",
+                Table::String(_) => "string",
+                Table::ExternalId(_) => "external_id",
+                Table::Url(_) => "url",
+                Table::Text { .. } => "text",
+                Table::Coordinates { .. } => "coordinates",
+                Table::Quantity { .. } => "quantity",
+                Table::Time { .. } => "time",
+                Table::Unknown => "edge",
+                Table::None => "edge",
+            },
+            Scope::Qualifier => match self {
+                Table::Vertex { .. } => "vertex",
+                Table::Property { .. } => "property",
+                Table::RawClaim { .. } => "raw_claim",
+                Table::String(_) => "qualifier_string",
+                Table::ExternalId(_) => "qualifier_external_id",
+                Table::Url(_) => "qualifier_url",
+                Table::Text { .. } => "qualifier_text",
+                Table::Coordinates { .. } => "qualifier_coordinates",
+                Table::Quantity { .. } => "qualifier_quantity",
+                Table::Time { .. } => "qualifier_time",
+                Table::Entity(..) | Table::Unknown | Table::None => "qualifier_edge",
+            },
+            Scope::Reference => match self {
+                Table::Vertex { .. } => "vertex",
+                Table::Property { .. } => "property",
+                Table::RawClaim { .. } => "raw_claim",
+                Table::String(_) => "reference_string",
+                Table::ExternalId(_) => "reference_external_id",
+                Table::Url(_) => "reference_url",
+                Table::Text { .. } => "reference_text",
+                Table::Coordinates { .. } => "reference_coordinates",
+                Table::Quantity { .. } => "reference_quantity",
+                Table::Time { .. } => "reference_time",
+                Table::Entity(..) | Table::Unknown | Table::None => "reference_edge",
+            },
+        }
+    }
+
+    /// The function returns the `DataType` of the values stored in this kind of table.
+    ///
+    /// Returns:
+    ///
+    /// An `Option` with the `DataType` of the value, or `None` if there is no value
+    /// at all, as it happens for vertices, NoValue and UnknownValue.
+    pub fn data_type(&self) -> Option<DataType> {
+        match self {
+            Table::Entity(..) => Some(DataType::Entity),
+            Table::String(_) => Some(DataType::String),
+            Table::ExternalId(_) => Some(DataType::ExternalId),
+            Table::Url(_) => Some(DataType::Url),
+            Table::Text { .. } => Some(DataType::Text),
+            Table::Coordinates { .. } => Some(DataType::Coordinate),
+            Table::Quantity { .. } => Some(DataType::Quantity),
+            Table::Time { .. } => Some(DataType::DateTime),
+            Table::Vertex { .. }
+            | Table::Property { .. }
+            | Table::RawClaim { .. }
+            | Table::Unknown
+            | Table::None => None,
+        }
+    }
+
+    /// The function returns the `Target` of the claims stored in this kind of table;
+    /// that is, what those point to.
+    ///
+    /// Returns:
+    ///
+    /// An `Option` with the `Target` of the claim, or `None` for the tables other
+    /// than the `edge` ones.
+    pub fn target(&self) -> Option<Target> {
+        match self {
+            Table::Entity(_, target) => Some(*target),
+            Table::None => Some(Target::NoValue),
+            Table::Unknown => Some(Target::SomeValue),
+            _ => None,
+        }
+    }
+
+    /// The function returns whether the values stored in tables of this kind are
+    /// strings, such as the monolingual texts or the URLs, which are of no use for
+    /// purely structural analyses of the graph.
+    pub fn is_textual(&self) -> bool {
+        matches!(
+            self,
+            Table::String(_) | Table::Text { .. } | Table::ExternalId(_) | Table::Url(_)
+        )
+    }
+
+    /// The function returns the scopes whose values are stored in tables of this
+    /// kind. Notice that neither vertices nor properties are values of any claim,
+    /// so those are only stored once, and neither are the raw claims, whose value
+    /// is not known.
+    ///
+    /// Returns:
+    ///
+    /// A vector with the scopes of the tables to be created.
+    pub fn scopes(&self) -> Vec<Scope> {
+        match self {
+            Table::Vertex { .. } | Table::Property { .. } | Table::RawClaim { .. } => {
+                vec![Scope::Statement]
+            }
+            _ => Scope::iter().collect(),
+        }
     }
 
     /// This function inserts data into a knowledge graph database.
@@ -203,23 +752,161 @@ impl Table {
     ///
     /// * `appender_helper`: A mutable reference to an `AppenderHelper` struct,
     /// which is used to append rows to the various tables in the database.
+    /// * `statement_id`: The identifier of the claim in the dump, such as
+    /// `Q42$8F6B0A4E-...`.
     /// * `src_id`: The ID of the source vertex in the knowledge graph.
-    /// * `label`: An optional reference to a String that represents the label of
-    /// the vertex being inserted into the database.
-    /// * `description`: An optional string parameter that represents the
-    /// description of a vertex in a knowledge graph.
     /// * `property_id`: The ID of the property being inserted into the database.
+    /// * `datatype`: The type of the values declared by the property, if known.
+    /// * `claim_index`: The position of the claim among the ones of its property.
+    /// * `rank`: The code of the rank of the claim, as given by `rank_code`.
+    /// * `extra`: The values of the extra columns declared by the user for the table.
     ///
     /// Returns:
     ///
     /// a `Result` with the `Ok` variant containing an empty tuple `()` if the
     /// function executes successfully, and the `Err` variant containing an `Error`
     /// if there is an error during execution.
+    #[allow(clippy::too_many_arguments)]
     pub fn insert(
         &self,
         appender_helper: &mut AppenderHelper,
-        src_id: u32,
-        property_id: u32,
+        statement_id: &str,
+        src_id: u64,
+        property_id: u64,
+        datatype: Option<&DataType>,
+        claim_index: u32,
+        rank: u8,
+        extra: &[Box<dyn ToSql>],
+    ) -> Result<(), Error> {
+        self.append(
+            appender_helper,
+            Scope::Statement,
+            statement_id,
+            None,
+            src_id,
+            property_id,
+            datatype,
+            Some((claim_index, rank)),
+            extra,
+        )
+    }
+
+    /// This function inserts the value of a qualifier into the database, in the table
+    /// of the same type as the value, but prefixed by `qualifier_`.
+    ///
+    /// Arguments:
+    ///
+    /// * `appender_helper`: A mutable reference to an `AppenderHelper` struct,
+    /// which is used to append rows to the various tables in the database.
+    /// * `statement_id`: The identifier of the claim the qualifier annotates.
+    /// * `src_id`: The ID of the entity the claim belongs to.
+    /// * `property_id`: The ID of the property of the qualifier.
+    /// * `datatype`: The type of the values declared by the property, if known.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Ok` variant containing an empty tuple `()` if the
+    /// function executes successfully, and the `Err` variant containing an `Error`
+    /// if there is an error during execution.
+    pub fn insert_qualifier(
+        &self,
+        appender_helper: &mut AppenderHelper,
+        statement_id: &str,
+        src_id: u64,
+        property_id: u64,
+        datatype: Option<&DataType>,
+    ) -> Result<(), Error> {
+        self.append(
+            appender_helper,
+            Scope::Qualifier,
+            statement_id,
+            None,
+            src_id,
+            property_id,
+            datatype,
+            None,
+            &[],
+        )
+    }
+
+    /// This function inserts the value of a snak of a reference into the database, in
+    /// the table of the same type as the value, but prefixed by `reference_`.
+    ///
+    /// Arguments:
+    ///
+    /// * `appender_helper`: A mutable reference to an `AppenderHelper` struct,
+    /// which is used to append rows to the various tables in the database.
+    /// * `statement_id`: The identifier of the claim the reference backs up.
+    /// * `reference_hash`: The hash of the reference, which tells apart the snaks
+    /// of the several references of a claim.
+    /// * `src_id`: The ID of the entity the claim belongs to.
+    /// * `property_id`: The ID of the property of the snak, such as stated in (P248).
+    /// * `datatype`: The type of the values declared by the property, if known.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Ok` variant containing an empty tuple `()` if the
+    /// function executes successfully, and the `Err` variant containing an `Error`
+    /// if there is an error during execution.
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_reference(
+        &self,
+        appender_helper: &mut AppenderHelper,
+        statement_id: &str,
+        reference_hash: &str,
+        src_id: u64,
+        property_id: u64,
+        datatype: Option<&DataType>,
+    ) -> Result<(), Error> {
+        self.append(
+            appender_helper,
+            Scope::Reference,
+            statement_id,
+            Some(reference_hash),
+            src_id,
+            property_id,
+            datatype,
+            None,
+            &[],
+        )
+    }
+
+    /// This function appends a row to the table of the given scope. The row is built
+    /// out of the common columns, followed by the columns storing the value itself
+    /// and, lastly, the extra columns declared by the user.
+    ///
+    /// Arguments:
+    ///
+    /// * `appender_helper`: A mutable reference to an `AppenderHelper` struct,
+    /// which is used to append rows to the various tables in the database.
+    /// * `scope`: The `Scope` of the value, which determines the table to use.
+    /// * `statement_id`: The identifier of the claim, or the one of the claim
+    /// annotated by the qualifier or the reference.
+    /// * `reference_hash`: The hash of the reference the value belongs to, if any.
+    /// * `src_id`: The ID of the source vertex in the knowledge graph.
+    /// * `property_id`: The ID of the property being inserted into the database.
+    /// * `datatype`: The type of the values declared by the property, if known.
+    /// * `claim`: The position of the claim among the ones of its property and the
+    /// code of its rank, which are only stored for the main values.
+    /// * `extra`: The values of the extra columns declared by the user for the table.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Ok` variant containing an empty tuple `()` if the
+    /// function executes successfully, and the `Err` variant containing an `Error`
+    /// if there is an error during execution.
+    #[allow(clippy::too_many_arguments)]
+    fn append(
+        &self,
+        appender_helper: &mut AppenderHelper,
+        scope: Scope,
+        statement_id: &str,
+        reference_hash: Option<&str>,
+        src_id: u64,
+        property_id: u64,
+        datatype: Option<&DataType>,
+        claim: Option<(u32, u8)>,
+        extra: &[Box<dyn ToSql>],
     ) -> Result<(), Error> {
         // Note the schema of the Database we are working with. In this regard, we have two main
         // entities which include Vertex and Edge; those act as the two pieces that together form
@@ -231,61 +918,123 @@ impl Table {
         // ACK: See https://github.com/angelip2303/wd2duckdb#database-structure for a more detailed
         // description of the data model we are creating with this tool.
 
-        let appender = appender_helper.appenders.get_mut(self.as_ref()).unwrap();
+        // Unknown values (somevalue snaks) are given a fresh placeholder as their destination, so
+        // that two unknown values are never considered to be the same one, nor the entity itself
+        let placeholder = match self {
+            Table::Unknown => appender_helper.next_placeholder()?,
+            _ => src_id,
+        };
+
+        let datatype = datatype.map(u8::from);
+        let target = self.target().map(|target| u8::from(&target));
+
+        // We have to handle years which are greater than the maximum possible value. Those have no
+        // DATETIME at all, as any would be a wrong one, but are kept in the year column :D
+        let stored_time = match self {
+            Table::Time { time, .. } if time.year() < 9999 => Some(*time),
+            _ => None,
+        };
+
+        // Rows are built incrementally: first, the columns that are common to every table of the
+        // scope, and then, the columns storing the value, which depend on its type :D
+        let mut row: Vec<&dyn ToSql> = vec![&statement_id];
+        if let Some(reference_hash) = &reference_hash {
+            row.push(reference_hash);
+        }
+        row.push(&src_id);
+        row.push(&property_id);
+
+        match self {
+            Table::Entity(dst_id, _) => row.push(dst_id),
+            Table::Unknown => row.push(&placeholder),
+            _ => row.push(&src_id),
+        }
+        row.push(&datatype);
+        if let Some((claim_index, rank)) = &claim {
+            row.push(claim_index);
+            row.push(rank);
+        }
 
         match self {
-            Table::Entity(dst_id) => appender.append_row(params![src_id, property_id, dst_id])?,
-            Table::None => appender.append_row(params![src_id, property_id, src_id])?,
-            Table::Unknown => appender.append_row(params![src_id, property_id, src_id])?,
-            Table::String(string) => {
-                appender.append_row(params![src_id, property_id, src_id, string])?
+            Table::Entity(..) | Table::None | Table::Unknown => row.push(&target),
+            Table::String(string) | Table::ExternalId(string) | Table::Url(string) => {
+                row.push(string)
             }
+            Table::Text { text, lang } => row.append(&mut vec![text, lang]),
             Table::Coordinates {
                 latitude,
                 longitude,
                 precision,
                 globe_id,
-            } => appender.append_row(params![
-                src_id,
-                property_id,
-                src_id,
-                latitude,
-                longitude,
-                precision,
-                globe_id
-            ])?,
+            } => row.append(&mut vec![latitude, longitude, precision, globe_id]),
             Table::Quantity {
                 amount,
                 lower_bound,
                 upper_bound,
                 unit_id,
-            } => appender.append_row(params![
-                src_id,
-                property_id,
-                src_id,
-                amount,
-                lower_bound,
-                upper_bound,
-                unit_id
-            ])?,
-            Table::Time { time, precision } => {
-                // We have to handle years wich are greater than the maximum possible value :D
-                if time.year() < 9999 {
-                    appender.append_row(params![src_id, property_id, src_id, time, precision])?
-                } else {
-                    appender.append_row(params![
-                        src_id,
-                        property_id,
-                        src_id,
-                        "infinity",
-                        precision
-                    ])?
-                }
+                exact,
+            } => {
+                row.append(&mut vec![amount, lower_bound, upper_bound, unit_id]);
+                // Decimals are appended as text, which DuckDB casts to the type of the column :D
+                row.append(&mut vec![
+                    &exact.amount,
+                    &exact.lower_bound,
+                    &exact.upper_bound,
+                ]);
+            }
+            Table::Time {
+                precision, date, ..
+            } => {
+                row.append(&mut vec![&stored_time, precision]);
+                row.append(&mut vec![
+                    &date.year,
+                    &date.month,
+                    &date.day,
+                    &date.calendar_model_id,
+                ]);
             }
             _ => return Err(Error::AppendError),
         }
+        row.extend(extra.iter().map(|value| value.as_ref()));
 
-        Ok(())
+        appender_helper.append_row(self.table_name(scope), row.as_slice())
+    }
+
+    /// This function returns the SQL statements creating the table, one per each of
+    /// the scopes it is stored in.
+    ///
+    /// Arguments:
+    ///
+    /// * `config`: The `SchemaConfig` declaring the extra columns of the table, which
+    /// are only added to the tables storing the main values, and the ones left out.
+    ///
+    /// Returns:
+    ///
+    /// A vector with the `CREATE TABLE` statements of the table.
+    pub fn create_table_sql(&self, config: &SchemaConfig) -> Vec<String> {
+        self.scopes()
+            .into_iter()
+            .map(|scope| {
+                let (table_name, columns) = self.table_definition(scope);
+                let mut columns = columns
+                    .iter()
+                    .filter(|(column_name, _)| !config.omits(table_name, column_name))
+                    .map(|(column_name, column_type)| format!("{} {}", column_name, column_type))
+                    .collect::<Vec<_>>();
+                if scope == Scope::Statement {
+                    columns.extend(
+                        config.columns_of(table_name).map(|column| {
+                            format!("{} {}", column.name, column.expression.sql_type())
+                        }),
+                    );
+                }
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {} ({});",
+                    table_name,
+                    columns.join(", "),
+                )
+            })
+            .collect()
     }
 
     /// This function creates a table in a database using the provided transaction and
@@ -297,23 +1046,102 @@ impl Table {
     /// SQL query to create a table in a database. The transaction object is typically
     /// created by starting a transaction on a database connection and then passing it
     /// to this function.
+    /// * `config`: The `SchemaConfig` declaring the extra columns of the table.
     ///
     /// Returns:
     ///
     /// a `Result` object with the `Ok` variant containing an empty tuple `()` if the
     /// table creation is successful, or an `Error` object if there is an error during
     /// the execution of the SQL statement.
-    pub fn create_table(&self, transaction: &Transaction) -> Result<(), Error> {
-        let (table_name, columns) = self.table_definition();
-        transaction.execute_batch(&format!(
-            "CREATE TABLE IF NOT EXISTS {} ({});",
-            table_name,
-            columns
-                .iter()
-                .map(|(column_name, column_type)| format!("{} {}", column_name, column_type))
-                .collect::<Vec<_>>()
-                .join(", "),
-        ))
+    pub fn create_table(
+        &self,
+        transaction: &Transaction,
+        config: &SchemaConfig,
+    ) -> Result<(), Error> {
+        for statement in self.create_table_sql(config) {
+            transaction.execute_batch(&statement)?;
+        }
+
+        // Older databases stored the decimals of the main values only. Hence, those are added to the
+        // tables of the qualifiers and references lacking them, so updating them does not fail :D
+        if let Table::Quantity { .. } = self {
+            for scope in self.scopes() {
+                if scope == Scope::Statement {
+                    continue; // the extra columns follow the decimals there
+                }
+                let (table_name, _) = self.table_definition(scope);
+                let lacking: bool = transaction.query_row(
+                    "SELECT count(*) = 0 FROM duckdb_columns() \
+                     WHERE table_name = ? AND column_name = 'amount_decimal'",
+                    [table_name],
+                    |row| row.get(0),
+                )?;
+                if lacking {
+                    let statements = ["amount_decimal", "lower_bound_decimal", "upper_bound_decimal"]
+                        .map(|column| {
+                            format!(
+                                "ALTER TABLE {} ADD COLUMN {} {};",
+                                table_name, column, DECIMAL_TYPE
+                            )
+                        });
+                    alter_table(transaction, table_name, &statements)?;
+                }
+            }
+        }
+
+        // Older databases made up an infinite time for the years not fitting a DATETIME, and thus
+        // declared it as NOT NULL. We lift that restriction so updating them stores none :D
+        if let Table::Time { .. } = self {
+            for scope in self.scopes() {
+                let (table_name, _) = self.table_definition(scope);
+                let required: bool = transaction.query_row(
+                    "SELECT count(*) > 0 FROM duckdb_columns() \
+                     WHERE table_name = ? AND column_name = 'time' AND NOT is_nullable",
+                    [table_name],
+                    |row| row.get(0),
+                )?;
+                if required {
+                    let statement =
+                        format!("ALTER TABLE {} ALTER COLUMN time DROP NOT NULL;", table_name);
+                    alter_table(transaction, table_name, &[statement])?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This function returns the SQL statements creating the indices of the table;
+    /// that is, the ones over the columns we join over.
+    ///
+    /// Returns:
+    ///
+    /// A vector with the `CREATE INDEX` statements of the table.
+    pub fn create_indices_sql(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+
+        for scope in self.scopes() {
+            let (table_name, columns) = self.table_definition(scope);
+
+            for (column_name, _) in columns {
+                // We are interested in creating indices only for the columns we join over: src_id,
+                // dst_id and statement_id. Hence, we check if the column_name is any of those. In
+                // the previous version loads of clutter was created by creating indices for all the
+                // columns. Notice that we are not interested in querying over columns that just
+                // annotate the node with additional information, such as the description :(
+                if column_name == "src_id"
+                    || column_name == "dst_id"
+                    || column_name == "statement_id"
+                {
+                    statements.push(format!(
+                        "CREATE INDEX IF NOT EXISTS {}_{}_index ON {} ({});",
+                        table_name, column_name, table_name, column_name,
+                    ));
+                }
+            }
+        }
+
+        statements
     }
 
     /// The function creates indices for specific columns in a table using SQL
@@ -330,26 +1158,53 @@ impl Table {
     /// successfully created, or an `Err` value containing an `Error` object if an error
     /// occurred during the execution of the function.
     pub fn create_indices(&self, connection: &Connection) -> Result<(), Error> {
-        let (table_name, columns) = self.table_definition();
-
-        for (column_name, _) in columns {
-            // We are interested in creating indices only for two columns: src_id and dst_id. Hence,
-            // we check if the column_name is any of those. In the previous version loads of clutter
-            // was created by creating indices for all the columns. Notice that we are not interested
-            // in querying over columns that just annotate the node with additional information, such
-            // as the description, or the label in a certain language :(
-            if column_name == "src_id" || column_name == "dst_id" {
-                connection.execute_batch(&format!(
-                    "CREATE INDEX IF NOT EXISTS {}_{}_index ON {} ({});",
-                    table_name, column_name, table_name, column_name,
-                ))?;
-            }
+        for statement in self.create_indices_sql() {
+            connection.execute_batch(&statement)?;
         }
 
         Ok(())
     }
 }
 
+/// The function `alter_table` runs the given `ALTER TABLE` statements on a table,
+/// dropping its indices beforehand and recreating them afterwards, as DuckDB refuses
+/// to alter the tables other entries depend on.
+///
+/// Arguments:
+///
+/// * `transaction`: The transaction the statements are run in.
+/// * `table_name`: The name of the table to be altered.
+/// * `statements`: The `ALTER TABLE` statements to be run.
+///
+/// Returns:
+///
+/// a `Result` which is `Ok(())` once the table has been altered, or the `Error` of
+/// the first statement failing.
+fn alter_table<S: AsRef<str>>(
+    transaction: &Transaction,
+    table_name: &str,
+    statements: &[S],
+) -> Result<(), Error> {
+    let indices = transaction
+        .prepare("SELECT index_name, sql FROM duckdb_indexes() WHERE table_name = ?")?
+        .query_map([table_name], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (index_name, _) in &indices {
+        transaction.execute_batch(&format!("DROP INDEX {};", index_name))?;
+    }
+    for statement in statements {
+        transaction.execute_batch(statement.as_ref())?;
+    }
+    for (_, sql) in &indices {
+        transaction.execute_batch(sql)?;
+    }
+
+    Ok(())
+}
+
 /// The above code is implementing the `AsRef` trait for the `Table` enum in Rust.
 /// This trait allows a type to be borrowed as a reference to another type. In this
 /// implementation, the `as_ref` method returns a string slice that represents the
@@ -357,24 +1212,33 @@ impl Table {
 /// and returns a string slice that corresponds to the variant.
 impl AsRef<str> for Table {
     fn as_ref(&self) -> &str {
-        match self {
-            Table::Vertex { .. } => "vertex",
-            Table::Entity(_) => "edge",
-            Table::String(_) => "string",
-            Table::Coordinates { .. } => "coordinates",
-            Table::Quantity { .. } => "quantity",
-            Table::Time { .. } => "time",
-            Table::Unknown => "edge",
-            Table::None => "edge",
-        }
+        self.table_name(Scope::Statement)
     }
 }
 
-impl From<ClaimValueData> for Table {
-    fn from(claim_value_data: ClaimValueData) -> Self {
+impl Table {
+    /// The function converts the value of a claim into the `Table` it is stored in,
+    /// along with the columns of its row. The exact amounts of the quantities are not
+    /// known by the parser, so those are left empty, and so is the calendar model of
+    /// the times, whose date is the one parsed.
+    ///
+    /// Arguments:
+    ///
+    /// * `claim_value_data`: The value of the claim, as parsed.
+    /// * `encoding`: The `IdEncoding` the entities pointed to are stored with.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Table`, or a `String` with an error message in case any
+    /// of the entities the value points to has an identifier out of the range of its
+    /// namespace, so it is never stored as if it pointed to another entity.
+    pub fn from_claim(
+        claim_value_data: ClaimValueData,
+        encoding: &IdEncoding,
+    ) -> Result<Self, String> {
         use ClaimValueData::*;
 
-        match claim_value_data {
+        Ok(match claim_value_data {
             CommonsMedia(string) => Self::String(string),
             GlobeCoordinate {
                 lat,
@@ -385,21 +1249,28 @@ impl From<ClaimValueData> for Table {
                 latitude: lat,
                 longitude: lon,
                 precision,
-                globe_id: u32::from(Id::Qid(globe)),
+                globe_id: encoding.encode(Id::Qid(globe))?,
             },
-            Item(id) => Self::Entity(u32::from(Id::Qid(id))),
-            Property(id) => Self::Entity(u32::from(Id::Pid(id))),
+            Item(id) => Self::Entity(encoding.encode(Id::Qid(id))?, Target::Item),
+            Property(id) => Self::Entity(encoding.encode(Id::Pid(id))?, Target::Property),
             String(string) => Self::String(string),
-            MonolingualText(text) => Self::String(text.text),
+            MonolingualText(text) => Self::Text {
+                text: text.text,
+                lang: text.lang.0,
+            },
+            // A value holds a single text, so the English one is kept whenever there are several of
+            // them. Otherwise, the first one is kept, rather than dropping the value altogether :D
             MultilingualText(texts) => {
-                for text in texts {
-                    if text.lang.0 == LANG.0 {
-                        return Self::String(text.text);
-                    }
+                let position = texts.iter().position(|text| text.lang.0 == LANG.0);
+                match texts.into_iter().nth(position.unwrap_or_default()) {
+                    Some(text) => Self::Text {
+                        text: text.text,
+                        lang: text.lang.0,
+                    },
+                    None => Self::None,
                 }
-                Self::None
             }
-            ExternalID(string) => Self::String(string),
+            ExternalID(string) => Self::ExternalId(string),
             Quantity {
                 amount,
                 lower_bound,
@@ -409,25 +1280,32 @@ impl From<ClaimValueData> for Table {
                 amount,
                 lower_bound,
                 upper_bound,
-                unit_id: unit.map(|id| u32::from(Id::Qid(id))),
+                unit_id: unit.map(|id| encoding.encode(Id::Qid(id))).transpose()?,
+                exact: ExactAmounts::default(),
             },
             DateTime {
                 date_time,
                 precision,
             } => Self::Time {
+                date: ExactDate {
+                    year: date_time.year() as i64,
+                    month: date_time.month() as u8,
+                    day: date_time.day() as u8,
+                    calendar_model_id: None,
+                },
                 time: date_time,
                 precision,
             },
-            Url(string) => Self::String(string),
+            Url(string) => Self::Url(string),
             MathExpr(string) => Self::String(string),
             GeoShape(string) => Self::String(string),
             MusicNotation(string) => Self::String(string),
             TabularData(string) => Self::String(string),
-            Lexeme(id) => Self::Entity(u32::from(Id::Lid(id))),
-            Form(id) => Self::Entity(u32::from(Id::Fid(id))),
-            Sense(id) => Self::Entity(u32::from(Id::Sid(id))),
+            Lexeme(id) => Self::Entity(encoding.encode(Id::Lid(id))?, Target::Lexeme),
+            Form(id) => Self::Entity(encoding.encode(Id::Fid(id))?, Target::Form),
+            Sense(id) => Self::Entity(encoding.encode(Id::Sid(id))?, Target::Sense),
             NoValue => Self::None,
             UnknownValue => Self::Unknown,
-        }
+        })
     }
 }
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use duckdb::{params, Connection, Error};
+use serde_json::Value;
+
+use crate::api::{api_id, WikidataApi, BATCH_SIZE};
+use crate::id::{IdEncoding, IdKind};
+use crate::partition;
+use crate::schema;
+use crate::LANG;
+
+/// The `Discrepancy` enum defines the differences found between an entity stored in
+/// the database and its current revision in Wikidata. `Missing` stands for those
+/// entities that have been deleted, or merged, since the dump was created; `Label`
+/// for those whose English label has changed; and `Claims` for those properties
+/// whose number of non-deprecated claims is not the same.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discrepancy {
+    Missing,
+    Label {
+        local: Option<String>,
+        remote: Option<String>,
+    },
+    Claims {
+        property: String,
+        local: u64,
+        remote: u64,
+    },
+}
+
+impl Display for Discrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Discrepancy::Missing => write!(f, "missing from Wikidata"),
+            Discrepancy::Label { local, remote } => write!(
+                f,
+                "label {:?} locally, {:?} in Wikidata",
+                local.as_deref().unwrap_or(""),
+                remote.as_deref().unwrap_or("")
+            ),
+            Discrepancy::Claims {
+                property,
+                local,
+                remote,
+            } => write!(
+                f,
+                "{} has {} claims locally, {} in Wikidata",
+                property, local, remote
+            ),
+        }
+    }
+}
+
+/// This function returns the query counting the claims stored for an entity, per
+/// property; that is, those in the tables of the statements, as well as the ones
+/// that could not be converted and were stored as they are. The tables of the
+/// qualifiers and references are told apart by their lack of a `rank`. Deprecated
+/// claims are left out, in case those were kept, as the remote ones are not
+/// counted either. The claims moved to partitions are counted as well.
+fn local_claims_sql(connection: &Connection) -> Result<String, Error> {
+    let mut selects = Vec::new();
+    for table in schema::tables() {
+        if table.column("src_id").is_none()
+            || table.column("property_id").is_none()
+            || (table.column("rank").is_none() && table.column("statement_id").is_some())
+        {
+            continue;
+        }
+        let source = partition::source(connection, table.name)?;
+        selects.push(match table.column("rank") {
+            Some(_) => format!("SELECT src_id, property_id FROM {} WHERE rank > 0", source),
+            None => format!("SELECT src_id, property_id FROM {}", source),
+        });
+    }
+
+    Ok(format!(
+        "SELECT property_id, count(*) FROM ({}) WHERE src_id = ? GROUP BY property_id",
+        selects.join(" UNION ALL ")
+    ))
+}
+
+/// The `Verifier` struct spot-checks a database against the current revision of its
+/// entities, as retrieved from the Wikidata API.
+///
+/// Properties:
+///
+/// * `api`: The client of the Wikidata API.
+pub struct Verifier {
+    api: WikidataApi,
+}
+
+impl Verifier {
+    /// The function creates a verifier sending, at most, the given number of requests
+    /// per second.
+    ///
+    /// Arguments:
+    ///
+    /// * `requests_per_second`: The maximum rate of the requests.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the `Verifier`, or a `String` with an error message in case
+    /// the client of the API cannot be built.
+    pub fn new(requests_per_second: f64) -> Result<Self, String> {
+        Ok(Self {
+            api: WikidataApi::new(requests_per_second)?,
+        })
+    }
+
+    /// The function picks random items and properties out of the database and
+    /// compares their label and the number of their claims, per property, with
+    /// those of their current revision in Wikidata. Notice that discrepancies are
+    /// to be expected whenever the dump is not recent, or the load has dropped some
+    /// of the claims, as the truthy ones or the external identifiers.
+    ///
+    /// Arguments:
+    ///
+    /// * `connection`: A reference to the connection to the database.
+    /// * `sample`: The number of entities verified.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the identifier of each of the entities verified, such as
+    /// `Q42`, and the discrepancies found for it, or a `String` with an error
+    /// message otherwise.
+    pub fn verify(
+        &mut self,
+        connection: &Connection,
+        sample: usize,
+    ) -> Result<Vec<(String, Vec<Discrepancy>)>, String> {
+        // Databases built with --no-text store no labels, so there are none to compare :(
+        let labels = match schema::has_column(connection, "vertex", "label") {
+            Ok(labels) => labels,
+            Err(error) => return Err(format!("Error reading schema. {}", error)),
+        };
+
+        let encoding = match IdEncoding::of(connection) {
+            Ok(encoding) => encoding,
+            Err(error) => return Err(format!("Error reading metadata. {}", error)),
+        };
+
+        // Items and properties are given consecutive ranges, so both are sampled at once :D
+        let entities = match connection
+            .prepare(&format!(
+                "SELECT id, {} FROM vertex WHERE id BETWEEN ? AND ? ORDER BY random() LIMIT ?",
+                match labels {
+                    true => "label",
+                    false => "NULL::TEXT",
+                }
+            ))
+            .and_then(|mut statement| {
+                statement
+                    .query_map(
+                        params![
+                            encoding.range(IdKind::Item).start(),
+                            encoding.range(IdKind::Property).end(),
+                            sample as u64
+                        ],
+                        |row| Ok((row.get::<_, u64>(0)?, row.get::<_, Option<String>>(1)?)),
+                    )?
+                    .collect::<Result<Vec<_>, _>>()
+            }) {
+            Ok(entities) => entities
+                .into_iter()
+                .filter_map(|(id, label)| api_id(&encoding, id).map(|api_id| (id, api_id, label)))
+                .collect::<Vec<_>>(),
+            Err(error) => return Err(format!("Error sampling entities. {}", error)),
+        };
+
+        let mut local_claims = match local_claims_sql(connection)
+            .and_then(|local_claims| connection.prepare(&local_claims))
+        {
+            Ok(local_claims) => local_claims,
+            Err(error) => return Err(format!("Error preparing query. {}", error)),
+        };
+
+        let mut report = Vec::new();
+        for batch in entities.chunks(BATCH_SIZE) {
+            let ids = batch
+                .iter()
+                .map(|(_, api_id, _)| api_id.as_str())
+                .collect::<Vec<_>>();
+            let remote_entities = self.api.get_entities(&ids, "labels|claims")?;
+            for (id, entity_id, local_label) in batch {
+                let entity = match remote_entities.get(entity_id) {
+                    Some(entity) if entity.get("missing").is_none() => entity,
+                    _ => {
+                        report.push((entity_id.to_owned(), vec![Discrepancy::Missing]));
+                        continue;
+                    }
+                };
+
+                let mut discrepancies = Vec::new();
+
+                let remote_label = entity
+                    .get("labels")
+                    .and_then(|labels| labels.get(&LANG.0))
+                    .and_then(|label| label.get("value"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                if labels && *local_label != remote_label {
+                    discrepancies.push(Discrepancy::Label {
+                        local: local_label.to_owned(),
+                        remote: remote_label,
+                    });
+                }
+
+                // Both sides are gathered in the same map, so the properties that are only
+                // found in one of them are reported as well :D
+                let mut claims: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+                let local = match local_claims
+                    .query_map(params![id], |row| {
+                        Ok((row.get::<_, u64>(0)?, row.get::<_, u64>(1)?))
+                    })
+                    .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                {
+                    Ok(local) => local,
+                    Err(error) => {
+                        return Err(format!("Error counting claims of {}. {}", entity_id, error))
+                    }
+                };
+                for (property_id, count) in local {
+                    if let Some(property) = api_id(&encoding, property_id) {
+                        claims.entry(property).or_default().0 += count;
+                    }
+                }
+                if let Some(remote) = entity.get("claims").and_then(Value::as_object) {
+                    for (property, statements) in remote {
+                        // Deprecated claims are not stored by default, so those are not counted :D
+                        let count = statements.as_array().map_or(0, |statements| {
+                            statements
+                                .iter()
+                                .filter(|statement| {
+                                    statement.get("rank").and_then(Value::as_str)
+                                        != Some("deprecated")
+                                })
+                                .count() as u64
+                        });
+                        if count > 0 {
+                            claims.entry(property.to_owned()).or_default().1 += count;
+                        }
+                    }
+                }
+                for (property, (local, remote)) in claims {
+                    if local != remote {
+                        discrepancies.push(Discrepancy::Claims {
+                            property,
+                            local,
+                            remote,
+                        });
+                    }
+                }
+
+                report.push((entity_id.to_owned(), discrepancies));
+            }
+        }
+
+        Ok(report)
+    }
+}
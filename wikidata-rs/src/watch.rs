@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::read_dir;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// The name of the file that marks a watched directory as complete; that is, no
+/// more chunks are going to arrive once it has been created.
+pub const DONE_MARKER: &str = "DONE";
+
+/// The `DirectoryWatcher` struct polls a directory for the chunks of a dump as
+/// those arrive, for example, from a parallel downloader. Hence, the download and
+/// the conversion of the dump overlap. A file is only considered to be complete
+/// once its size has not changed between two polls, and files that are still being
+/// written by most of the downloaders, such as `*.part` or hidden ones, are skipped
+/// altogether. The watch is over once the `DONE` marker is created in the directory
+/// and every chunk has been yielded, or once no new chunk has arrived for a while.
+///
+/// Properties:
+///
+/// * `directory`: The directory being watched.
+/// * `interval`: The time between two polls of the directory.
+/// * `idle_timeout`: The time without any new chunk after which the watch is over.
+/// * `seen`: The files that have been already yielded.
+/// * `growing`: The files found in the last poll, together with their size, which
+/// have not been yielded yet.
+/// * `ready`: The files that are complete, waiting to be yielded.
+/// * `last_arrival`: The instant the last chunk was found.
+pub struct DirectoryWatcher {
+    directory: PathBuf,
+    interval: Duration,
+    idle_timeout: Duration,
+    seen: HashSet<PathBuf>,
+    growing: HashMap<PathBuf, u64>,
+    ready: VecDeque<PathBuf>,
+    last_arrival: Instant,
+}
+
+impl DirectoryWatcher {
+    /// The function creates a watcher over the given directory.
+    ///
+    /// Arguments:
+    ///
+    /// * `directory`: The directory the chunks arrive to.
+    /// * `interval`: The time between two polls of the directory.
+    /// * `idle_timeout`: The time without any new chunk after which the watch is over.
+    ///
+    /// Returns:
+    ///
+    /// The `DirectoryWatcher`.
+    pub fn new(directory: &Path, interval: Duration, idle_timeout: Duration) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+            interval,
+            idle_timeout,
+            seen: HashSet::new(),
+            growing: HashMap::new(),
+            ready: VecDeque::new(),
+            last_arrival: Instant::now(),
+        }
+    }
+
+    /// The function polls the directory once, moving the files whose size has not
+    /// changed since the previous poll to the queue of the complete ones.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with whether the `DONE` marker has been found in the directory,
+    /// or an `Error` if the directory could not be read.
+    fn poll(&mut self) -> Result<bool> {
+        let mut done = false;
+        let mut growing = HashMap::new();
+        let mut complete = Vec::new();
+
+        for entry in read_dir(&self.directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if name == DONE_MARKER {
+                done = true;
+                continue;
+            }
+
+            if self.seen.contains(&path) || !is_chunk(&name) {
+                continue;
+            }
+
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            // A file is complete once its size is the same as in the previous poll. Otherwise, the
+            // downloader may still be writing it, so it is checked again in the next poll :D
+            let size = metadata.len();
+            if size > 0 && self.growing.get(&path) == Some(&size) {
+                complete.push(path);
+            } else {
+                growing.insert(path, size);
+            }
+        }
+
+        if !complete.is_empty() {
+            complete.sort();
+            self.seen.extend(complete.iter().cloned());
+            self.ready.extend(complete);
+            self.last_arrival = Instant::now();
+        }
+        if !growing.is_empty() && growing != self.growing {
+            self.last_arrival = Instant::now(); // chunks are still arriving, so we keep waiting
+        }
+        self.growing = growing;
+
+        Ok(done)
+    }
+}
+
+impl Iterator for DirectoryWatcher {
+    type Item = Result<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(path) = self.ready.pop_front() {
+                return Some(Ok(path));
+            }
+
+            let done = match self.poll() {
+                Ok(done) => done,
+                Err(error) => return Some(Err(error)),
+            };
+
+            if !self.ready.is_empty() {
+                continue;
+            }
+
+            // Once the marker has been created, the files still growing are the last ones, so we
+            // only wait for them to be complete. Otherwise, the watch is over :D
+            if (done && self.growing.is_empty()) || self.last_arrival.elapsed() >= self.idle_timeout
+            {
+                return None;
+            }
+
+            sleep(self.interval);
+        }
+    }
+}
+
+/// The function returns whether a file found in the watched directory is a chunk
+/// of the dump, as opposed to the temporary files the downloaders write to, which
+/// are renamed once those are complete.
+fn is_chunk(name: &str) -> bool {
+    !name.starts_with('.')
+        && ![".part", ".tmp", ".crdownload", ".download", ".aria2"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+}
@@ -0,0 +1,171 @@
+use std::io::{BufRead, Result};
+
+use strum_macros::EnumString;
+
+/// The `InputFormat` enum defines the formats of the dumps that can be processed.
+/// `Json` stands for the JSON entity dumps, with one entity per line, while `Xml`
+/// stands for the pages-articles XML dumps, which embed the JSON of each entity in
+/// the text of its latest revision. The latter are the only ones published by some
+/// Wikibase instances. `StreamJson` stands for JSON entities that are not one per
+/// line, such as pretty-printed or concatenated ones. Lastly, `Ntriples` stands for
+/// the RDF dumps in N-Triples, such as the truthy ones, whose triples are grouped
+/// into the entities they describe.
+#[derive(Clone, Debug, Default, PartialEq, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum InputFormat {
+    #[default]
+    Json,
+    Xml,
+    StreamJson,
+    Ntriples,
+}
+
+/// The `XmlEntities` struct extracts the JSON of the entities embedded in an XML
+/// dump, so those can be fed to the very same pipeline as the lines of a JSON dump.
+/// Only the pages whose content model is one of the Wikibase entities are
+/// considered; that is, the rest of the pages of the wiki are skipped.
+///
+/// Properties:
+///
+/// * `reader`: The reader over the XML dump.
+/// * `model`: The content model of the page being read, if already found.
+pub struct XmlEntities<R> {
+    reader: R,
+    model: Option<String>,
+}
+
+impl<R: BufRead> XmlEntities<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            model: None,
+        }
+    }
+
+    /// The function reads the text of a revision, which may span several lines,
+    /// starting with the line where the `<text>` tag is opened.
+    ///
+    /// Arguments:
+    ///
+    /// * `line`: The line where the `<text>` tag is opened.
+    ///
+    /// Returns:
+    ///
+    /// a `Result` with the escaped text of the revision, or an `Error` if the XML
+    /// could not be read.
+    fn read_text(&mut self, line: &str) -> Result<String> {
+        let start = line.find("<text").unwrap_or_default();
+        let tag = &line[start..];
+        let open = match tag.find('>') {
+            Some(open) => open,
+            None => return Ok(String::new()),
+        };
+        if tag[..open].ends_with('/') {
+            return Ok(String::new()); // the tag is self-closing, so the text is empty
+        }
+
+        let mut text = tag[open + 1..].to_string();
+        loop {
+            if let Some(close) = text.find("</text>") {
+                text.truncate(close);
+                return Ok(text);
+            }
+            if self.reader.read_line(&mut text)? == 0 {
+                return Ok(text); // the dump was truncated, so the text is returned as it is
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for XmlEntities<R> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => (),
+                Err(error) => return Some(Err(error)),
+            }
+
+            let trimmed = line.trim();
+            if let Some(model) = trimmed
+                .strip_prefix("<model>")
+                .and_then(|model| model.strip_suffix("</model>"))
+            {
+                self.model = Some(model.to_string());
+            } else if trimmed.starts_with("<text") {
+                let text = match self.read_text(trimmed) {
+                    Ok(text) => text,
+                    Err(error) => return Some(Err(error)),
+                };
+                // Only the revisions of the Wikibase entities are considered; that is, those whose
+                // content model is wikibase-item, wikibase-property or wikibase-lexeme :D
+                if let Some(model) = self.model.take() {
+                    if model.starts_with("wikibase-") && !text.is_empty() {
+                        return Some(Ok(unescape(&text)));
+                    }
+                }
+            } else if trimmed == "</page>" {
+                self.model = None;
+            }
+        }
+    }
+}
+
+/// The function replaces the XML entities of a text by the characters they stand
+/// for; that is, the predefined entities, such as `&quot;`, and the numeric
+/// character references, such as `&#34;` or `&#x22;`.
+///
+/// Arguments:
+///
+/// * `text`: A string slice with the escaped text.
+///
+/// Returns:
+///
+/// A `String` with the unescaped text.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+
+        let character = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity => match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|decimal| decimal.parse::<u32>().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+
+        match character {
+            Some(character) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&'); // it is not an entity, so the ampersand is kept as it is
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+    unescaped
+}
@@ -0,0 +1,789 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::{BufReader, ErrorKind, Read};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use duckdb::{params, Connection};
+use serde_json::{json, Value};
+use strum::IntoEnumIterator;
+use wikidata::{Lang, Pid};
+use wikidata_rs::checkpoint::Checkpoint;
+use wikidata_rs::converter::{Converter, ConverterConfig};
+use wikidata_rs::dedup::{Deduplicator, OnConflict};
+use wikidata_rs::dtype::DataType;
+use wikidata_rs::export::JsonExporter;
+use wikidata_rs::generate::Generator;
+use wikidata_rs::id::{Id, IdEncoding};
+use wikidata_rs::partition::Partition;
+use wikidata_rs::xml::InputFormat;
+
+/// The `TempDatabase` struct is a database file in the temporary directory, which
+/// is removed once the test is done with it.
+struct TempDatabase {
+    path: PathBuf,
+}
+
+impl TempDatabase {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("wd2duckdb-{}-{}.duckdb", name, std::process::id()));
+        let database = Self { path };
+        database.remove();
+        database
+    }
+
+    fn open(&self) -> Connection {
+        Connection::open(&self.path).unwrap()
+    }
+
+    fn remove(&self) {
+        let _ = std::fs::remove_file(&self.path);
+        let _ = std::fs::remove_file(self.path.with_extension("duckdb.wal"));
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+/// The function generates a sample dump with the given number of entities, and
+/// returns it both as it is written and as the JSON of each of its entities.
+fn sample(entities: u64, seed: u64) -> (Vec<u8>, Vec<Value>) {
+    let mut dump = Vec::new();
+    Generator::new(entities, seed)
+        .write_dump(&mut dump)
+        .expect("the sample is written to memory");
+    let parsed = String::from_utf8(dump.clone())
+        .unwrap()
+        .lines()
+        .map(|line| line.trim_end_matches(','))
+        .filter(|line| !matches!(*line, "[" | "]"))
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    (dump, parsed)
+}
+
+/// The function loads a dump into a database file, with the given converter.
+fn import(dump: &[u8], mut converter: Converter, database: &TempDatabase) -> Connection {
+    let mut connection = database.open();
+    let report = converter
+        .run(BufReader::new(dump), &mut connection)
+        .unwrap();
+    assert_eq!(report.total(), 0, "{}", report);
+    connection
+}
+
+/// The function returns the number of rows of a table, or of a query.
+/// A dump with a single item, whose only statement has the given snaks as its main
+/// one, as its qualifier and as the snak of its reference.
+fn single_statement(main: Value, qualifier: Value, reference: Value) -> String {
+    let property = main["property"].clone();
+    let entity = json!({
+        "type": "item",
+        "id": "Q1",
+        "labels": {},
+        "descriptions": {},
+        "aliases": {},
+        "sitelinks": {},
+        "claims": {
+            property.as_str().unwrap(): [{
+                "mainsnak": main,
+                "type": "statement",
+                "qualifiers": { property.as_str().unwrap(): [qualifier] },
+                "qualifiers-order": [property],
+                "id": "Q1$00000000-0000-0000-0000-000000000000",
+                "rank": "normal",
+                "references": [{
+                    "hash": "0000000000000000000000000000000000000000",
+                    "snaks": { property.as_str().unwrap(): [reference] },
+                    "snaks-order": [property],
+                }],
+            }],
+        },
+    });
+
+    format!("[\n{}\n]\n", entity)
+}
+
+fn count(connection: &Connection, from: &str) -> usize {
+    connection
+        .query_row(&format!("SELECT count(*) FROM {}", from), [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .unwrap() as usize
+}
+
+/// The function encodes an identifier as the database stores it.
+fn encode(connection: &Connection, id: Id) -> u64 {
+    IdEncoding::of(connection).unwrap().encode(id).unwrap()
+}
+
+/// The function exports an entity of the database back into the JSON of the
+/// dumps, as the `export` subcommand does.
+fn export(connection: &Connection, entity_id: &str) -> Option<Value> {
+    let id = encode(connection, entity_id.parse().unwrap());
+    let (label, description) = connection
+        .query_row(
+            "SELECT label, description FROM vertex WHERE id = ?",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok()?;
+    JsonExporter::new(connection)
+        .unwrap()
+        .entity(id, label, description)
+        .unwrap()
+}
+
+/// The function summarizes the value of a snak, so the exported one can be compared
+/// with the generated one. Quantities are left out, as their amounts are written
+/// back out of the decimals, which may format them otherwise.
+fn summary(snak: &Value) -> String {
+    let value = &snak["datavalue"]["value"];
+    match (
+        snak["snaktype"].as_str(),
+        snak["datavalue"]["type"].as_str(),
+    ) {
+        (Some("value"), Some("wikibase-entityid")) => value["id"].to_string(),
+        (Some("value"), Some("string")) => value.to_string(),
+        (Some("value"), Some("monolingualtext")) => {
+            format!("{}@{}", value["text"], value["language"])
+        }
+        (Some("value"), Some("time")) => format!("{}/{}", value["time"], value["precision"]),
+        // The coordinates are parsed by simd-json, whose last digit may differ from the one of
+        // serde, so those are compared well below the precision of the dump :D
+        (Some("value"), Some("globecoordinate")) => format!(
+            "{:.6},{:.6}@{}",
+            value["latitude"].as_f64().unwrap(),
+            value["longitude"].as_f64().unwrap(),
+            value["globe"]
+        ),
+        (snaktype, datatype) => format!("{:?}:{:?}", snaktype, datatype),
+    }
+}
+
+/// The function returns the claims of an entity by their identifier, together with
+/// their property, their rank and the summary of their value.
+fn claims(entity: &Value) -> HashMap<String, (String, String, String)> {
+    let mut claims = HashMap::new();
+    for (property, statements) in entity["claims"].as_object().unwrap() {
+        for statement in statements.as_array().unwrap() {
+            claims.insert(
+                statement["id"].as_str().unwrap().to_string(),
+                (
+                    property.to_string(),
+                    statement["rank"].as_str().unwrap().to_string(),
+                    summary(&statement["mainsnak"]),
+                ),
+            );
+        }
+    }
+    claims
+}
+
+/// The function returns the main snaks of the claims stored by default; that is,
+/// all of them but the deprecated ones.
+fn stored_snaks(entities: &[Value]) -> Vec<&Value> {
+    entities
+        .iter()
+        .flat_map(|entity| entity["claims"].as_object().unwrap().values())
+        .flat_map(|statements| statements.as_array().unwrap())
+        .filter(|statement| statement["rank"] != "deprecated")
+        .map(|statement| &statement["mainsnak"])
+        .collect()
+}
+
+/// The function returns the number of rows the claims of the sample are expected to
+/// take in the table of the given datatype. Entities, unknown values and missing
+/// values are all stored in the `edge` table.
+fn expected_rows(snaks: &[&Value], datatype: &DataType) -> usize {
+    snaks
+        .iter()
+        .filter(|snak| {
+            let declared = DataType::from_declared(snak["datatype"].as_str().unwrap());
+            match snak["snaktype"].as_str() {
+                Some("value") => declared.as_ref() == Some(datatype),
+                _ => *datatype == DataType::Entity,
+            }
+        })
+        .count()
+}
+
+/// The function returns the entities of the given type.
+fn of_type<'a>(entities: &'a [Value], entity_type: &'a str) -> impl Iterator<Item = &'a Value> {
+    entities
+        .iter()
+        .filter(move |entity| entity["type"] == entity_type)
+}
+
+#[test]
+fn exports_the_entities_generated() {
+    let (dump, entities) = sample(200, 42);
+    let database = TempDatabase::new("exports");
+    let connection = import(&dump, Converter::new(ConverterConfig::default()), &database);
+
+    for entity in entities.iter().filter(|entity| entity["type"] != "lexeme") {
+        let entity_id = entity["id"].as_str().unwrap();
+        let exported = export(&connection, entity_id)
+            .unwrap_or_else(|| panic!("{} is not exported", entity_id));
+
+        assert_eq!(exported["id"], entity["id"]);
+        assert_eq!(exported["type"], entity["type"]);
+        assert_eq!(
+            exported["labels"]["en"]["value"], entity["labels"]["en"]["value"],
+            "{}",
+            entity_id
+        );
+        assert_eq!(
+            exported["descriptions"]["en"]["value"], entity["descriptions"]["en"]["value"],
+            "{}",
+            entity_id
+        );
+
+        // Deprecated claims are left out unless asked otherwise, while the rest keep their
+        // identifier, their rank and their value, even the dates before the common era :D
+        let mut expected = claims(entity);
+        expected.retain(|_, (_, rank, _)| rank != "deprecated");
+        assert_eq!(claims(&exported), expected, "{}", entity_id);
+    }
+}
+
+#[test]
+fn exports_the_deprecated_claims_when_kept() {
+    let (dump, entities) = sample(100, 7);
+    let config = ConverterConfig {
+        keep_deprecated: true,
+        ..ConverterConfig::default()
+    };
+    let database = TempDatabase::new("deprecated");
+    let connection = import(&dump, Converter::new(config), &database);
+
+    let mut deprecated = 0;
+    for entity in of_type(&entities, "item") {
+        let entity_id = entity["id"].as_str().unwrap();
+        let exported = export(&connection, entity_id).unwrap();
+        let expected = claims(entity);
+        deprecated += expected
+            .values()
+            .filter(|(_, rank, _)| rank == "deprecated")
+            .count();
+        assert_eq!(claims(&exported), expected, "{}", entity_id);
+    }
+    assert!(deprecated > 0, "the sample holds no deprecated claim");
+}
+
+#[test]
+fn stores_a_row_per_claim_in_the_table_of_its_datatype() {
+    let (dump, entities) = sample(300, 3);
+    let config = ConverterConfig {
+        lexemes: true,
+        ..ConverterConfig::default()
+    };
+    let database = TempDatabase::new("counts");
+    let connection = import(&dump, Converter::new(config), &database);
+
+    let properties = of_type(&entities, "property").count();
+    let lexemes = of_type(&entities, "lexeme").count();
+    assert_eq!(count(&connection, "vertex"), entities.len());
+    assert_eq!(count(&connection, "property"), properties);
+
+    let snaks = stored_snaks(&entities);
+    for datatype in DataType::iter() {
+        let expected = expected_rows(&snaks, &datatype);
+        assert!(
+            expected > 0,
+            "the sample holds no {} claim",
+            datatype.as_ref()
+        );
+        assert_eq!(
+            count(&connection, datatype.as_ref()),
+            expected,
+            "{}",
+            datatype.as_ref()
+        );
+    }
+
+    // Every lexeme has a lemma, two forms with a grammatical feature each, and a sense :D
+    assert_eq!(count(&connection, "lexeme"), lexemes);
+    assert_eq!(count(&connection, "lemma"), lexemes);
+    assert_eq!(count(&connection, "form"), 2 * lexemes);
+    assert_eq!(count(&connection, "form_feature"), 2 * lexemes);
+    assert_eq!(count(&connection, "sense"), lexemes);
+}
+
+#[test]
+fn stores_the_edge_cases_of_the_sample() {
+    let (dump, entities) = sample(300, 11);
+    let config = ConverterConfig {
+        lexemes: true,
+        ..ConverterConfig::default()
+    };
+    let database = TempDatabase::new("edge-cases");
+    let connection = import(&dump, Converter::new(config), &database);
+    let snaks = stored_snaks(&entities);
+
+    // Dates before the common era keep their negative year, rather than being shifted :D
+    let bce = snaks
+        .iter()
+        .filter(|snak| {
+            snak["datavalue"]["value"]["time"]
+                .as_str()
+                .is_some_and(|time| time.starts_with('-'))
+        })
+        .count();
+    assert!(bce > 0, "the sample holds no date before the common era");
+    assert_eq!(count(&connection, "time WHERE year < 0"), bce);
+
+    // The dates of the qualifiers are kept as found in the dump too, calendar model included :D
+    let qualifiers = entities
+        .iter()
+        .flat_map(|entity| entity["claims"].as_object().unwrap().values())
+        .flat_map(|statements| statements.as_array().unwrap())
+        .filter(|statement| statement["rank"] != "deprecated")
+        .flat_map(|statement| statement["qualifiers"].as_object().unwrap().values())
+        .flat_map(|snaks| snaks.as_array().unwrap())
+        .filter_map(|snak| snak["datavalue"]["value"]["time"].as_str())
+        .collect::<Vec<_>>();
+    let bce = qualifiers.iter().filter(|time| time.starts_with('-')).count();
+    assert!(bce > 0, "the sample holds no qualifier before the common era");
+    assert_eq!(count(&connection, "qualifier_time"), qualifiers.len());
+    assert_eq!(count(&connection, "qualifier_time WHERE year < 0"), bce);
+    assert_eq!(
+        count(&connection, "qualifier_time WHERE calendar_model_id = 1985727"),
+        qualifiers.len()
+    );
+
+    // Unknown values (somevalue) and missing ones (novalue) are told apart by their target :D
+    for (snaktype, target) in [("novalue", 6), ("somevalue", 7)] {
+        let expected = snaks
+            .iter()
+            .filter(|snak| snak["snaktype"] == snaktype)
+            .count();
+        assert!(expected > 0, "the sample holds no {} snak", snaktype);
+        assert_eq!(
+            count(&connection, &format!("edge WHERE target = {}", target)),
+            expected,
+            "{}",
+            snaktype
+        );
+    }
+
+    // Every datatype is found in its own table, and every property keeps the one it declares :D
+    for datatype in DataType::iter() {
+        assert!(
+            count(
+                &connection,
+                &format!("{} WHERE datatype = {}", datatype.as_ref(), datatype)
+            ) > 0,
+            "{}",
+            datatype.as_ref()
+        );
+    }
+    let declared = of_type(&entities, "property")
+        .map(|property| property["datatype"].as_str().unwrap())
+        .collect::<BTreeSet<_>>();
+    assert_eq!(
+        count(&connection, "(SELECT DISTINCT datatype FROM property)"),
+        declared.len()
+    );
+
+    // The forms and senses of the lexemes are decoded back into the identifiers of the dump :D
+    for (table, key) in [("form", "forms"), ("sense", "senses")] {
+        let expected = of_type(&entities, "lexeme")
+            .flat_map(|lexeme| lexeme[key].as_array().unwrap())
+            .map(|sub| sub["id"].as_str().unwrap().to_string())
+            .collect::<BTreeSet<_>>();
+        let encoding = IdEncoding::of(&connection).unwrap();
+        let mut statement = connection
+            .prepare(&format!("SELECT DISTINCT id FROM {}", table))
+            .unwrap();
+        let stored = statement
+            .query_map([], |row| row.get::<_, u64>(0))
+            .unwrap()
+            .map(|id| encoding.decode(id.unwrap()).unwrap().to_string())
+            .collect::<BTreeSet<_>>();
+        assert!(!expected.is_empty(), "the sample holds no {}", key);
+        assert_eq!(stored, expected, "{}", table);
+    }
+}
+
+#[test]
+fn stores_the_decimals_of_every_scope() {
+    let quantity = |amount: &str| {
+        json!({
+            "snaktype": "value",
+            "property": "P1082",
+            "datatype": "quantity",
+            "datavalue": {
+                "value": { "amount": amount, "unit": "1" },
+                "type": "quantity",
+            },
+        })
+    };
+    let dump = single_statement(
+        quantity("+12345678901234567891"),
+        quantity("+0.123456789"),
+        quantity("-98765432109876543210.5"),
+    );
+    let database = TempDatabase::new("decimals");
+    let connection = import(
+        dump.as_bytes(),
+        Converter::new(ConverterConfig::default()),
+        &database,
+    );
+
+    // The doubles cannot hold these, but the decimals do, no matter where the amount is found :D
+    for (table, amount) in [
+        ("quantity", "12345678901234567891.000000000"),
+        ("qualifier_quantity", "0.123456789"),
+        ("reference_quantity", "-98765432109876543210.500000000"),
+    ] {
+        let stored: String = connection
+            .query_row(
+                &format!("SELECT CAST(amount_decimal AS TEXT) FROM {}", table),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, amount, "{}", table);
+    }
+}
+
+#[test]
+fn stores_no_datetime_for_the_years_beyond_9999() {
+    let time = |time: &str| {
+        json!({
+            "snaktype": "value",
+            "property": "P585",
+            "datatype": "time",
+            "datavalue": {
+                "value": {
+                    "time": time,
+                    "timezone": 0,
+                    "before": 0,
+                    "after": 0,
+                    "precision": 9,
+                    "calendarmodel": "http://www.wikidata.org/entity/Q1985786",
+                },
+                "type": "time",
+            },
+        })
+    };
+    let dump = single_statement(
+        time("+12345-00-00T00:00:00Z"),
+        time("+2001-00-00T00:00:00Z"),
+        time("-0500-00-00T00:00:00Z"),
+    );
+    let database = TempDatabase::new("years");
+    let connection = import(
+        dump.as_bytes(),
+        Converter::new(ConverterConfig::default()),
+        &database,
+    );
+
+    // Every snak keeps its year and its calendar, while those not fitting a DATETIME have none :D
+    for (table, year, missing) in [
+        ("time", 12345, true),
+        ("qualifier_time", 2001, false),
+        ("reference_time", -500, false),
+    ] {
+        let stored: (i64, Option<u64>, bool) = connection
+            .query_row(
+                &format!(
+                    "SELECT year, calendar_model_id, time IS NULL FROM {}",
+                    table
+                ),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(stored, (year, Some(1985786), missing), "{}", table);
+    }
+}
+
+#[test]
+fn fixes_the_datatypes_of_the_triples_read_before_their_property() {
+    let triple = |subject: &str, predicate: &str, object: &str| {
+        format!(
+            "<http://www.wikidata.org/entity/{}> <{}> {} .\n",
+            subject, predicate, object
+        )
+    };
+    let direct = "http://www.wikidata.org/prop/direct/P214";
+    let novalue = "<http://www.wikidata.org/prop/novalue/P214>";
+    let dump = [
+        triple("Q42", direct, "\"113230702\""),
+        triple("Q1", "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", novalue),
+        triple(
+            "P214",
+            "http://wikiba.se/ontology#propertyType",
+            "<http://wikiba.se/ontology#ExternalId>",
+        ),
+        triple("Q2", direct, "\"1\""),
+    ]
+    .concat();
+    let database = TempDatabase::new("ntriples");
+    let connection = import(
+        dump.as_bytes(),
+        Converter::new(ConverterConfig {
+            input_format: InputFormat::Ntriples,
+            ..ConverterConfig::default()
+        }),
+        &database,
+    );
+
+    // Every external identifier ends up in its table, no matter when its property was read :D
+    assert_eq!(count(&connection, "string"), 0);
+    assert_eq!(count(&connection, "external_id"), 2);
+    let external_id = u8::from(&DataType::ExternalId);
+    assert_eq!(
+        count(
+            &connection,
+            &format!("(SELECT * FROM edge WHERE datatype = {})", external_id)
+        ),
+        1
+    );
+}
+
+#[test]
+fn queries_the_sample_through_the_macros() {
+    let (dump, entities) = sample(200, 5);
+    let database = TempDatabase::new("macros");
+    let connection = import(&dump, Converter::new(ConverterConfig::default()), &database);
+
+    // The first five items are the classes, which are subclasses of each other, while the rest of
+    // the items are instances of any of them :D
+    let instances = of_type(&entities, "item").count() - 5;
+    assert_eq!(count(&connection, "instances_of(1)"), instances);
+
+    let p31 = encode(&connection, Id::Pid(Pid(31)));
+    let (id, label): (String, String) = connection
+        .query_row(
+            &format!("SELECT wd_id_to_str({id}), label({id})", id = p31),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(id, "P31");
+    assert_eq!(label, "instance of");
+}
+
+#[test]
+fn records_the_encoding_of_the_identifiers() {
+    let (dump, entities) = sample(50, 5);
+    let config = ConverterConfig {
+        id_encoding: IdEncoding::V1,
+        ..ConverterConfig::default()
+    };
+    let database = TempDatabase::new("encoding");
+    let connection = import(&dump, Converter::new(config), &database);
+    assert_eq!(IdEncoding::read(&connection).unwrap(), Some(IdEncoding::V1));
+
+    // The identifiers are stored, decoded and exported through the encoding of the database,
+    // rather than the default one :D
+    let p31 = encode(&connection, Id::Pid(Pid(31)));
+    assert_ne!(p31, IdEncoding::default().encode(Id::Pid(Pid(31))).unwrap());
+    let id: String = connection
+        .query_row(&format!("SELECT wd_id_to_str({})", p31), [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(id, "P31");
+    let item = of_type(&entities, "item").next().unwrap();
+    let exported = export(&connection, item["id"].as_str().unwrap()).unwrap();
+    assert_eq!(exported["id"], item["id"]);
+
+    // Appending to it with another encoding would mix both of them, so it is refused :(
+    let mut connection = connection;
+    let error = Converter::new(ConverterConfig::default())
+        .run(BufReader::new(dump.as_slice()), &mut connection)
+        .unwrap_err();
+    assert!(error.contains("encodes identifiers as"), "{}", error);
+}
+
+#[test]
+fn moves_the_claims_of_the_partitioned_properties() {
+    let (dump, entities) = sample(200, 5);
+    let config = ConverterConfig {
+        partitions: vec![Partition { property: Pid(31) }],
+        ..ConverterConfig::default()
+    };
+    let database = TempDatabase::new("partitions");
+    let connection = import(&dump, Converter::new(config), &database);
+
+    let p31 = encode(&connection, Id::Pid(Pid(31)));
+    let instances = of_type(&entities, "item").count() - 5;
+    assert_eq!(count(&connection, "edge_p31"), instances);
+    assert_eq!(
+        count(&connection, &format!("edge WHERE property_id = {}", p31)),
+        0
+    );
+    // The macros read the partitions as well, so those keep finding every instance :D
+    assert_eq!(count(&connection, "instances_of(1)"), instances);
+}
+
+#[test]
+fn stores_once_the_entities_found_in_several_inputs() {
+    let (dump, _) = sample(100, 9);
+
+    let single = TempDatabase::new("single");
+    let expected = import(&dump, Converter::new(ConverterConfig::default()), &single);
+
+    let database = TempDatabase::new("dedup");
+    let mut connection = database.open();
+    let mut converter = Converter::new(ConverterConfig::default());
+    converter.deduplicator = Some(Deduplicator::new(OnConflict::Skip));
+    let inputs = ["first", "second"]
+        .into_iter()
+        .map(|name| (Arc::from(name), BufReader::new(dump.as_slice())));
+    let report = converter.run_inputs(inputs, &mut connection).unwrap();
+    assert_eq!(report.total(), 0, "{}", report);
+
+    for table in [
+        "vertex",
+        "edge",
+        "string",
+        "time",
+        "quantity",
+        "qualifier_time",
+    ] {
+        assert_eq!(
+            count(&connection, table),
+            count(&expected, table),
+            "{}",
+            table
+        );
+    }
+}
+
+#[test]
+fn exports_the_qualifiers_references_and_terms_stored() {
+    let string = |value: &str| {
+        json!({
+            "snaktype": "value",
+            "property": "P1545",
+            "datatype": "string",
+            "datavalue": { "value": value, "type": "string" },
+        })
+    };
+    let dump = single_statement(string("1"), string("2"), string("3"));
+    let mut entity: Value = serde_json::from_str(dump.lines().nth(1).unwrap()).unwrap();
+    entity["labels"] = json!({
+        "en": { "language": "en", "value": "one" },
+        "de": { "language": "de", "value": "eins" },
+    });
+    entity["descriptions"] = json!({ "de": { "language": "de", "value": "Zahl" } });
+    entity["aliases"] = json!({ "de": [{ "language": "de", "value": "Eins" }] });
+    let config = ConverterConfig {
+        languages: vec![Lang("de".to_string())],
+        ..ConverterConfig::default()
+    };
+    let database = TempDatabase::new("annotations");
+    let connection = import(
+        format!("[\n{}\n]\n", entity).as_bytes(),
+        Converter::new(config),
+        &database,
+    );
+
+    // The qualifiers and references are exported within the claim they annotate, while the terms
+    // in the languages asked for are exported next to the English ones :D
+    let exported = export(&connection, "Q1").unwrap();
+    let statement = &exported["claims"]["P1545"][0];
+    assert_eq!(summary(&statement["mainsnak"]), "\"1\"");
+    assert_eq!(statement["qualifiers-order"], json!(["P1545"]));
+    assert_eq!(summary(&statement["qualifiers"]["P1545"][0]), "\"2\"");
+    let reference = &statement["references"][0];
+    assert_eq!(reference["hash"], entity["claims"]["P1545"][0]["references"][0]["hash"]);
+    assert_eq!(reference["snaks-order"], json!(["P1545"]));
+    assert_eq!(summary(&reference["snaks"]["P1545"][0]), "\"3\"");
+    assert_eq!(exported["labels"], entity["labels"]);
+    assert_eq!(exported["descriptions"], entity["descriptions"]);
+    assert_eq!(exported["aliases"], entity["aliases"]);
+}
+
+/// The `Interrupted` struct is a reader over a dump that fails once the given
+/// number of bytes have been read, as a crashed load would stop.
+struct Interrupted<'a> {
+    dump: &'a [u8],
+    left: usize,
+}
+
+impl Read for Interrupted<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.left == 0 {
+            return Err(std::io::Error::new(ErrorKind::Other, "interrupted"));
+        }
+        let length = buf.len().min(self.left);
+        let read = self.dump.read(&mut buf[..length])?;
+        self.left -= read;
+        Ok(read)
+    }
+}
+
+/// The function returns the number of rows of every table of a database, but for
+/// the metadata of the load, which differs between loads.
+fn row_counts(connection: &Connection) -> Vec<(String, usize)> {
+    let mut statement = connection
+        .prepare(
+            "SELECT table_name FROM information_schema.tables \
+             WHERE table_name NOT IN ('wd2duckdb_meta', '_load_log') ORDER BY table_name",
+        )
+        .unwrap();
+    let tables = statement
+        .query_map([], |row| row.get::<_, String>(0))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    tables
+        .into_iter()
+        .map(|table| {
+            let rows = count(connection, &table);
+            (table, rows)
+        })
+        .collect()
+}
+
+#[test]
+fn resumes_an_interrupted_load_as_if_it_were_not() {
+    let (dump, _) = sample(200, 13);
+    for input_format in [InputFormat::Json, InputFormat::StreamJson] {
+        let config = || ConverterConfig {
+            input_format: input_format.clone(),
+            checkpoint_every: NonZeroUsize::new(25),
+            ..ConverterConfig::default()
+        };
+        let database = TempDatabase::new("uninterrupted");
+        let expected = row_counts(&import(&dump, Converter::new(config()), &database));
+
+        // The load stops halfway through the dump, so only the batches committed before it are kept,
+        // together with the checkpoint of the last one :D
+        let database = TempDatabase::new("resumed");
+        let mut connection = database.open();
+        let interrupted = Interrupted {
+            dump: &dump,
+            left: dump.len() / 2,
+        };
+        assert!(Converter::new(config())
+            .run(BufReader::new(interrupted), &mut connection)
+            .is_err());
+        let checkpoint = Checkpoint::read(&connection)
+            .unwrap()
+            .expect("the interrupted load records a checkpoint");
+        assert!(checkpoint.line > 0, "{:?}", input_format);
+
+        // Resuming reads the dump right after the line committed last, as the command line does
+        let mut reader = BufReader::new(dump.as_slice());
+        checkpoint.seek(&mut reader).unwrap();
+        let mut converter = Converter::new(config());
+        let input = Arc::from(checkpoint.input.as_str());
+        converter.checkpoint = Some(checkpoint);
+        let report = converter
+            .run_inputs(std::iter::once((input, reader)), &mut connection)
+            .unwrap();
+        assert_eq!(report.total(), 0, "{}", report);
+
+        assert_eq!(row_counts(&connection), expected, "{:?}", input_format);
+        assert_eq!(Checkpoint::read(&connection).unwrap(), None);
+    }
+}